@@ -31,6 +31,10 @@ struct Cli {
     /// The mot animation files.
     #[arg(long)]
     anim: Vec<String>,
+    /// Apply the average vertex color of each mesh to the material base color.
+    /// This is useful for applications that ignore vertex colors.
+    #[arg(long)]
+    bake_vertex_colors: bool,
 }
 
 fn database_path() -> std::io::Result<PathBuf> {
@@ -144,6 +148,7 @@ fn main() -> anyhow::Result<()> {
                 &output,
                 &shader_textures,
                 has_legacy,
+                cli.bake_vertex_colors,
             )?;
         } else {
             export_model_gltf(
@@ -153,6 +158,7 @@ fn main() -> anyhow::Result<()> {
                 &output,
                 &shader_textures,
                 has_legacy,
+                cli.bake_vertex_colors,
             )?;
         }
     } else if !map_roots.is_empty() {
@@ -204,11 +210,19 @@ fn export_model_gltf(
     output: &Path,
     shader_textures: &ShaderTextures,
     flip_images_uvs: bool,
+    bake_vertex_colors: bool,
 ) -> anyhow::Result<()> {
-    GltfFile::from_model(name, roots, &animations, shader_textures, flip_images_uvs)
-        .with_context(|| "failed to create glTF file")?
-        .save(output)
-        .with_context(|| format!("failed to save glTF file to {:?}", output))
+    GltfFile::from_model(
+        name,
+        roots,
+        &animations,
+        shader_textures,
+        flip_images_uvs,
+        bake_vertex_colors,
+    )
+    .with_context(|| "failed to create glTF file")?
+    .save(output)
+    .with_context(|| format!("failed to save glTF file to {:?}", output))
 }
 
 fn export_model_glb(
@@ -218,9 +232,17 @@ fn export_model_glb(
     output: &Path,
     shader_textures: &ShaderTextures,
     flip_images_uvs: bool,
+    bake_vertex_colors: bool,
 ) -> anyhow::Result<()> {
-    GlbFile::from_model(name, roots, &animations, shader_textures, flip_images_uvs)
-        .with_context(|| "failed to create glb file")?
-        .save(output)
-        .with_context(|| format!("failed to save glb file to {:?}", output))
+    GlbFile::from_model(
+        name,
+        roots,
+        &animations,
+        shader_textures,
+        flip_images_uvs,
+        bake_vertex_colors,
+    )
+    .with_context(|| "failed to create glb file")?
+    .save(output)
+    .with_context(|| format!("failed to save glb file to {:?}", output))
 }
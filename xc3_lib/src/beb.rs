@@ -7,7 +7,9 @@
 //! | Xenoblade Chronicles 1 DE | |  |
 //! | Xenoblade Chronicles 2 |  | |
 //! | Xenoblade Chronicles 3 |  | `event/**/*.beb` |
-use crate::{xbc1::Xbc1, Offset32};
+use std::io::Cursor;
+
+use crate::{error::DecompressStreamError, get_bytes, xbc1::Xbc1, Offset32};
 use binrw::BinRead;
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
@@ -59,3 +61,108 @@ pub struct BebData {
     #[br(count = count)]
     pub lengths: Vec<u32>,
 }
+
+/// A decompressed item from one of the archives in a [Beb].
+#[derive(Debug, PartialEq, Clone)]
+pub struct BebEntry {
+    /// The index of the archive in [Beb::xbc1_offsets].
+    pub archive_index: usize,
+    pub entry_type: BebEntryType,
+    /// The entry bytes excluding the 4 floats at the start of each entry.
+    pub data: Vec<u8>,
+}
+
+/// The detected file type for a [BebEntry].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BebEntryType {
+    /// Animation data for [Bc](crate::bc::Bc).
+    Bc,
+    Unknown,
+}
+
+impl Beb {
+    /// Decompress all archives and extract the entries from each [BebData].
+    pub fn extract_entries(&self) -> Result<Vec<BebEntry>, DecompressStreamError> {
+        let mut entries = Vec::new();
+        for (archive_index, offset) in self.xbc1_offsets.iter().enumerate() {
+            let bytes = offset.value.decompress()?;
+            let data = BebData::read_le(&mut Cursor::new(&bytes))?;
+            for (offset, length) in data.offsets.iter().zip(&data.lengths) {
+                // Skip the 4 floats at the start of each entry.
+                let start = offset.checked_add(16).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("entry offset {offset} out of range"),
+                    )
+                })?;
+                let data = get_bytes(&bytes, start, Some(*length))?.to_vec();
+                let entry_type = if data.get(..4) == Some(b"BC\x00\x00") {
+                    BebEntryType::Bc
+                } else {
+                    BebEntryType::Unknown
+                };
+                entries.push(BebEntry {
+                    archive_index,
+                    entry_type,
+                    data,
+                });
+            }
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xbc1::CompressionType;
+
+    fn beb(data: &BebData) -> Beb {
+        let mut writer = Cursor::new(Vec::new());
+        xc3_write::write_full(data, &mut writer, 0, &mut 0, xc3_write::Endian::Little, ()).unwrap();
+        let mut bytes = writer.into_inner();
+        bytes.extend_from_slice(&[0u8; 16]);
+        bytes.extend_from_slice(b"BC\x00\x00");
+        Beb {
+            xbc1_count: 1,
+            xbc1_offsets: vec![Offset32 {
+                value: Xbc1::from_decompressed("".to_string(), &bytes, CompressionType::Zlib)
+                    .unwrap(),
+            }],
+        }
+    }
+
+    #[test]
+    fn extract_entries_bc() {
+        let beb = beb(&BebData {
+            header_size: 20,
+            count: 1,
+            unk1: vec![0],
+            offsets: vec![20],
+            lengths: vec![4],
+        });
+        assert_eq!(
+            vec![BebEntry {
+                archive_index: 0,
+                entry_type: BebEntryType::Bc,
+                data: b"BC\x00\x00".to_vec()
+            }],
+            beb.extract_entries().unwrap()
+        );
+    }
+
+    #[test]
+    fn extract_entries_offset_overflow() {
+        let beb = beb(&BebData {
+            header_size: 20,
+            count: 1,
+            unk1: vec![0],
+            offsets: vec![u32::MAX],
+            lengths: vec![4],
+        });
+        assert!(matches!(
+            beb.extract_entries(),
+            Err(DecompressStreamError::Io(_))
+        ));
+    }
+}
@@ -79,3 +79,107 @@ impl Xc3WriteOffsets for BehOffsets<'_> {
         Ok(())
     }
 }
+
+/// A single item in an [Unk4] block of a [Beh].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BehEntry {
+    /// The index of the block in [Beh::offsets].
+    pub block_index: usize,
+    /// The index of the item in [Unk4::items].
+    pub item_index: usize,
+    /// Hash using [hash_str_crc](crate::hash::hash_str_crc).
+    /// Known names can be recovered using [build_lookup_str_crc](crate::hash::build_lookup_str_crc).
+    pub hash: u32,
+    /// The offset in bytes relative to the start of the block.
+    pub offset: u32,
+    pub count: u32,
+    /// The number of bytes until the item in the block with the next largest offset
+    /// or `None` for the item with the largest offset.
+    pub size: Option<u32>,
+}
+
+impl Beh {
+    /// List the items for all blocks in order.
+    ///
+    /// The data type for each item is unknown but may depend on the [hash](BehEntry::hash).
+    /// This is intended for reverse engineering the contents of `.beh` files.
+    pub fn entries(&self) -> Vec<BehEntry> {
+        self.offsets
+            .iter()
+            .enumerate()
+            .flat_map(|(block_index, block)| {
+                let items = &block.value.items;
+                items
+                    .iter()
+                    .enumerate()
+                    .map(move |(item_index, item)| BehEntry {
+                        block_index,
+                        item_index,
+                        hash: item.hash,
+                        offset: item.offset,
+                        count: item.count,
+                        size: items
+                            .iter()
+                            .map(|i| i.offset)
+                            .filter(|o| *o > item.offset)
+                            .min()
+                            .map(|next| next - item.offset),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(hash: u32, offset: u32) -> Unk4Item {
+        Unk4Item {
+            hash,
+            offset,
+            count: 1,
+        }
+    }
+
+    #[test]
+    fn entries_unordered_offsets() {
+        let beh = Beh {
+            count: 2,
+            unk2: 1,
+            data_sheet: None,
+            offsets: vec![
+                Offset32 {
+                    value: Unk4 {
+                        count: 3,
+                        items: vec![item(1, 48), item(2, 16), item(3, 40)],
+                    },
+                },
+                Offset32 {
+                    value: Unk4 {
+                        count: 1,
+                        items: vec![item(4, 8)],
+                    },
+                },
+            ],
+        };
+
+        let entry = |block_index, item_index, hash, offset, size| BehEntry {
+            block_index,
+            item_index,
+            hash,
+            offset,
+            count: 1,
+            size,
+        };
+        assert_eq!(
+            vec![
+                entry(0, 0, 1, 48, None),
+                entry(0, 1, 2, 16, Some(24)),
+                entry(0, 2, 3, 40, Some(8)),
+                entry(1, 0, 4, 8, None),
+            ],
+            beh.entries()
+        );
+    }
+}
@@ -8,6 +8,9 @@
 use crate::{parse_offset32_count32, parse_opt_ptr32};
 use binrw::{binread, BinRead};
 
+// The magic and all header fields.
+const HEADER_SIZE: u32 = 76;
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, PartialEq, Clone)]
 #[br(magic(b"BMN\x20"))]
@@ -51,3 +54,61 @@ pub struct Unk16Texture {
     #[br(parse_with = parse_offset32_count32)]
     pub mtxt_data: Vec<u8>,
 }
+
+/// A region of the file starting at an offset from the header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Section {
+    /// The name of the header field like "unk5".
+    pub name: &'static str,
+    pub offset: u32,
+    pub size: u32,
+}
+
+impl Bmn {
+    /// Find regions of the file for header values that appear to be offsets.
+    ///
+    /// The purpose of most header fields is unknown.
+    /// Each section extends to the start of the next section or `file_size`.
+    /// This is intended for reverse engineering and may include values that are not offsets.
+    pub fn sections(&self, file_size: u32) -> Vec<Section> {
+        let values = [
+            ("unk1", self.unk1),
+            ("unk2", self.unk2),
+            ("unk3", self.unk3),
+            ("unk4", self.unk4),
+            ("unk5", self.unk5),
+            ("unk6", self.unk6),
+            ("unk7", self.unk7),
+            ("unk8", self.unk8),
+            ("unk9", self.unk9),
+            ("unk10", self.unk10),
+            ("unk11", self.unk11),
+            ("unk12", self.unk12),
+            ("unk13", self.unk13),
+            ("unk14", self.unk14),
+            ("unk15", self.unk15),
+            ("unk17", self.unk17),
+            ("unk18", self.unk18),
+        ];
+
+        let mut offsets: Vec<_> = values
+            .into_iter()
+            .filter(|(_, v)| *v >= HEADER_SIZE && *v < file_size && *v % 4 == 0)
+            .collect();
+        offsets.sort_by_key(|(_, v)| *v);
+        offsets.dedup_by_key(|(_, v)| *v);
+
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(i, (name, offset))| {
+                let end = offsets.get(i + 1).map(|(_, o)| *o).unwrap_or(file_size);
+                Section {
+                    name,
+                    offset: *offset,
+                    size: end - offset,
+                }
+            })
+            .collect()
+    }
+}
@@ -7,19 +7,105 @@
 //! | Xenoblade Chronicles 1 DE | |  |
 //! | Xenoblade Chronicles 2 |  | |
 //! | Xenoblade Chronicles 3 |  | `datasheet/*.bin` |
+//!
+//! The magic is stored as an integer, so [DataSheet::read_endian] and [DataSheet::write_endian]
+//! support reading and writing sheets in either byte order.
 use bilge::prelude::*;
-use binrw::{BinRead, BinWrite, NullString};
-use std::io::{Read, Seek};
-use xc3_write::{Xc3Write, Xc3WriteOffsets};
+use binrw::{BinRead, BinResult, BinWrite, NullString};
+use std::{
+    io::{Cursor, Read, Seek, Write},
+    path::Path,
+};
+use xc3_write::{write_full, Xc3Result, Xc3Write, Xc3WriteOffsets};
+
+use crate::ReadFileError;
+
+const MAGIC: u32 = 0x00123456;
+const HEADER: [u8; 5] = [0, 0, 0, 0, 0x0f];
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
-#[brw(magic(b"\x56\x34\x12\x00\x00\x00\x00\x00\x0f"))]
-#[xc3(magic(b"\x56\x34\x12\x00\x00\x00\x00\x00\x0f"))]
+#[derive(Debug, PartialEq, Clone)]
 pub struct DataSheet {
     pub key_values: KeyValues,
 }
 
+impl DataSheet {
+    /// Read a sheet with the specified byte order.
+    /// Use [DataSheet::read] for little endian sheets.
+    pub fn read_endian<R: Read + Seek>(reader: &mut R, endian: binrw::Endian) -> BinResult<Self> {
+        Self::read_options(reader, endian, ())
+    }
+
+    /// Read a sheet from `bytes` with the specified byte order.
+    /// Use [DataSheet::from_bytes] for little endian sheets.
+    pub fn from_bytes_endian<T: AsRef<[u8]>>(bytes: T, endian: binrw::Endian) -> BinResult<Self> {
+        Self::read_endian(&mut Cursor::new(bytes), endian)
+    }
+
+    /// Read a sheet from `path` with the specified byte order.
+    /// Use [DataSheet::from_file] for little endian sheets.
+    pub fn from_file_endian<P: AsRef<Path>>(
+        path: P,
+        endian: binrw::Endian,
+    ) -> Result<Self, ReadFileError> {
+        let path = path.as_ref();
+        std::fs::read(path)
+            .map_err(Into::into)
+            .and_then(|bytes| Self::from_bytes_endian(bytes, endian))
+            .map_err(|e| ReadFileError {
+                path: path.to_owned(),
+                source: e,
+            })
+    }
+
+    /// Write a sheet with the specified byte order.
+    /// Use [DataSheet::write] for little endian sheets.
+    pub fn write_endian<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: xc3_write::Endian,
+    ) -> Xc3Result<()> {
+        write_full(self, writer, 0, &mut 0, endian, ())
+    }
+}
+
+impl BinRead for DataSheet {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: binrw::Endian,
+        args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
+        let magic = u32::read_options(reader, endian, args)?;
+        let header = <[u8; 5]>::read_options(reader, endian, args)?;
+        if magic != MAGIC || header != HEADER {
+            return Err(binrw::Error::BadMagic {
+                pos,
+                found: Box::new((magic, header)),
+            });
+        }
+
+        let key_values = KeyValues::read_options(reader, endian, args)?;
+        Ok(Self { key_values })
+    }
+}
+
+impl Xc3Write for DataSheet {
+    type Offsets<'a> = KeyValuesOffsets<'a>;
+
+    fn xc3_write<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: xc3_write::Endian,
+    ) -> Xc3Result<Self::Offsets<'_>> {
+        MAGIC.xc3_write(writer, endian)?;
+        HEADER.xc3_write(writer, endian)?;
+        self.key_values.xc3_write(writer, endian)
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct KeyValues {
@@ -231,3 +317,55 @@ fn read_var_int<R: Read + Seek>(
         _ => todo!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hexlit::hex;
+
+    #[test]
+    fn read_little_endian() {
+        let sheet = DataSheet::from_bytes_endian(
+            hex!(56341200 00000000 0f 210100 6100 58),
+            binrw::Endian::Little,
+        )
+        .unwrap();
+        assert_eq!(
+            DataSheet {
+                key_values: KeyValues {
+                    keys: vec!["a".to_string()],
+                    value: Value::Integer(5)
+                }
+            },
+            sheet
+        );
+    }
+
+    #[test]
+    fn read_big_endian() {
+        let sheet = DataSheet::from_bytes_endian(
+            hex!(00123456 00000000 0f 210001 6100 58),
+            binrw::Endian::Big,
+        )
+        .unwrap();
+        assert_eq!(
+            DataSheet {
+                key_values: KeyValues {
+                    keys: vec!["a".to_string()],
+                    value: Value::Integer(5)
+                }
+            },
+            sheet
+        );
+    }
+
+    #[test]
+    fn read_wrong_endian() {
+        assert!(DataSheet::from_bytes_endian(
+            hex!(00123456 00000000 0f 210001 6100 58),
+            binrw::Endian::Little,
+        )
+        .is_err());
+    }
+}
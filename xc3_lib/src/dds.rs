@@ -1,7 +1,7 @@
 //! Utilities for [Dds] image files.
 use std::{io::Cursor, path::Path};
 
-use image_dds::ddsfile::Dds;
+use image_dds::{ddsfile::Dds, Surface};
 use thiserror::Error;
 
 pub trait DdsExt: Sized {
@@ -31,6 +31,26 @@ impl DdsExt for Dds {
     }
 }
 
+/// Create a DDS file with the same image data and format as `surface`.
+///
+/// Unlike [Surface::to_dds], cube maps use an array size of 1 in the DX10 header
+/// and 3D textures set the volume flag for compatibility with other applications.
+pub fn dds_from_surface<T: AsRef<[u8]>>(
+    surface: &Surface<T>,
+) -> Result<Dds, image_dds::CreateDdsError> {
+    let mut dds = surface.to_dds()?;
+    if surface.depth > 1 {
+        dds.header.caps2 |= image_dds::ddsfile::Caps2::VOLUME;
+    }
+    if let Some(header10) = &mut dds.header10 {
+        // The array size counts cubes instead of faces.
+        if header10.misc_flag == image_dds::ddsfile::MiscFlag::TEXTURECUBE {
+            header10.array_size = header10.array_size.max(1);
+        }
+    }
+    Ok(dds)
+}
+
 #[derive(Debug, Error)]
 pub enum CreateDdsError {
     #[error("error deswizzling surface")]
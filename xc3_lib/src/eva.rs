@@ -1,28 +1,39 @@
 //! Camera animations in `.eva` files or embedded in `.mot` files.
+use std::io::{Seek, SeekFrom};
+
 use crate::parse_ptr32;
-use binrw::{binread, BinRead};
+use binrw::{binread, helpers::args_iter, BinRead};
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
+#[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
+#[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(magic(b"eva\x00"))]
 #[xc3(magic(b"eva\x00"))]
+#[br(stream = r)]
 pub struct Eva {
     pub unk1: u32,
     pub item_count: u32,
     pub frame_count: u32, // frame count?
 
-    #[br(count = item_count)]
+    #[br(temp, restore_position, count = item_count)]
+    item_offsets: Vec<(u32, u32)>,
+
+    #[br(temp, try_calc = stream_end(r))]
+    end_offset: u64,
+
+    #[br(parse_with = args_iter(item_data_sizes(&item_offsets, end_offset)))]
     pub items: Vec<EvaItem1>,
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
+#[br(import_raw(data_size: usize))]
 pub struct EvaItem1 {
     // TODO: flags?
     pub unk1: u32,
 
-    #[br(parse_with = parse_ptr32)]
+    #[br(parse_with = parse_ptr32, args { inner: data_size })]
     #[xc3(offset(u32))]
     pub item2: EvaItem2,
 }
@@ -31,21 +42,120 @@ pub struct EvaItem1 {
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(stream = r)]
+#[br(import_raw(data_size: usize))]
 #[xc3(base_offset)]
 pub struct EvaItem2 {
     #[br(temp, try_calc = r.stream_position())]
     _base_offset: u64,
 
-    // TODO: The float array isn't always present?
-    // TODO: type?
-    // #[br(parse_with = parse_offset32_count32, offset = base_offset)]
-    // #[xc3(offset_count(u32, u32))]
-    // pub items: Vec<u8>,
     pub unk1: u32, // TODO: offset to next EvaItem2?
     pub unk2: u32,
 
     pub frame_count: u32,
+
     // TODO: What controls if there is a float array here?
-    // #[br(args { count: float_count as usize })]
-    // pub floats: Vec<f32>,
+    /// The remaining bytes up to the next [EvaItem2] or the end of the file.
+    #[br(count = data_size)]
+    pub data: Vec<u8>,
+}
+
+/// A single item in an [Eva] with its timing information.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct EvaEntry<'a> {
+    /// The index of the item in [Eva::items].
+    pub index: usize,
+    // TODO: Does this determine the type of the animated value?
+    pub flags: u32,
+    // TODO: Is this always the start frame?
+    /// The start time in frames for this item.
+    pub time: u32,
+    /// The number of frames for this item.
+    pub frame_count: u32,
+    /// The unparsed data for this item.
+    pub data: &'a [u8],
+    pub item: &'a EvaItem2,
+}
+
+impl Eva {
+    /// List the animated items in order with their flags, timing, and data.
+    pub fn entries(&self) -> impl Iterator<Item = EvaEntry<'_>> {
+        self.items.iter().enumerate().map(|(index, item)| EvaEntry {
+            index,
+            flags: item.unk1,
+            time: item.item2.unk2,
+            frame_count: item.item2.frame_count,
+            data: &item.item2.data,
+            item: &item.item2,
+        })
+    }
+}
+
+fn stream_end<R: Seek>(reader: &mut R) -> std::io::Result<u64> {
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+    Ok(end)
+}
+
+fn item_data_sizes(
+    item_offsets: &[(u32, u32)],
+    end_offset: u64,
+) -> impl Iterator<Item = usize> + '_ {
+    // Assume each item's data extends to the start of the next item.
+    item_offsets.iter().map(move |(_, offset)| {
+        let start = *offset as u64 + 12;
+        let end = item_offsets
+            .iter()
+            .map(|(_, o)| *o as u64)
+            .filter(|o| *o > *offset as u64)
+            .min()
+            .unwrap_or(end_offset);
+        end.saturating_sub(start) as usize
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eva_entries() {
+        // Two items with 4 and 8 bytes of data.
+        let mut bytes = b"eva\x00".to_vec();
+        for value in [0u32, 2, 60, 1, 32, 2, 48] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [16u32, 5, 30, 0x3f800000] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [0u32, 10, 20, 1, 2] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let eva = Eva::from_bytes(&bytes).unwrap();
+        let entries: Vec<_> = eva.entries().collect();
+        assert_eq!(2, entries.len());
+        assert_eq!(
+            (1, 5, 30, &1.0f32.to_le_bytes()[..]),
+            (
+                entries[0].flags,
+                entries[0].time,
+                entries[0].frame_count,
+                entries[0].data
+            )
+        );
+        assert_eq!(
+            (2, 10, 20, &[1, 0, 0, 0, 2, 0, 0, 0][..]),
+            (
+                entries[1].flags,
+                entries[1].time,
+                entries[1].frame_count,
+                entries[1].data
+            )
+        );
+
+        let mut writer = std::io::Cursor::new(Vec::new());
+        eva.write(&mut writer).unwrap();
+        assert_eq!(bytes, writer.into_inner());
+    }
 }
@@ -1,4 +1,5 @@
 //! A collection of non-cryptographic hash functions used in game.
+use std::collections::HashMap;
 
 // Data for `mm::mtl::HashStrCrc()` in the Xenoblade 2 binary from ghidra.
 const CRC: [u32; 256] = [
@@ -76,6 +77,34 @@ pub fn murmur3(bytes: &[u8]) -> u32 {
     murmur3::murmur3_32(&mut std::io::Cursor::new(bytes), 0).unwrap()
 }
 
+/// Create a reverse lookup from [hash_str_crc] values to the original strings in `names`.
+///
+/// Names with colliding hashes keep the first name in `names`.
+pub fn build_lookup_str_crc(names: &[&str]) -> HashMap<u32, String> {
+    build_lookup(names, hash_str_crc)
+}
+
+/// Create a reverse lookup from [murmur3] values to the original strings in `names`.
+///
+/// Names with colliding hashes keep the first name in `names`.
+///
+/// # Examples
+/// ```rust
+/// let lookup = xc3_lib::hash::build_lookup_murmur3(&["J_thumb_A_R", "J_hip"]);
+/// assert_eq!(Some("J_hip"), lookup.get(&0xfd011736).map(|s| s.as_str()));
+/// ```
+pub fn build_lookup_murmur3(names: &[&str]) -> HashMap<u32, String> {
+    build_lookup(names, |name| murmur3(name.as_bytes()))
+}
+
+fn build_lookup(names: &[&str], hash: impl Fn(&str) -> u32) -> HashMap<u32, String> {
+    let mut lookup = HashMap::new();
+    for name in names {
+        lookup.entry(hash(name)).or_insert_with(|| name.to_string());
+    }
+    lookup
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,4 +162,20 @@ mod tests {
         assert_eq!(0x47df19d5, murmur3("J_thumb_A_R".as_bytes()));
         assert_eq!(0xfd011736, murmur3("J_hip".as_bytes()));
     }
+
+    #[test]
+    fn build_lookup_str_crc_sar1_names() {
+        let lookup = build_lookup_str_crc(&["break.anm", "smash.anm", "break.anm"]);
+        assert_eq!(2, lookup.len());
+        assert_eq!("break.anm", lookup[&0x41f7dce]);
+        assert_eq!("smash.anm", lookup[&0x47a5352]);
+    }
+
+    #[test]
+    fn build_lookup_murmur3_bones() {
+        let lookup = build_lookup_murmur3(&["J_thumb_A_R", "J_hip"]);
+        assert_eq!(2, lookup.len());
+        assert_eq!("J_thumb_A_R", lookup[&0x47df19d5]);
+        assert_eq!("J_hip", lookup[&0xfd011736]);
+    }
 }
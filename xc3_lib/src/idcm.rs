@@ -195,6 +195,8 @@ pub struct FaceGroup {
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(count: u8))]
 pub struct Faces {
+    // TODO: surface type or material flags?
+    /// A value for each triangle in the fan.
     #[br(count = count)]
     pub unk1: Vec<u16>,
 
@@ -39,6 +39,23 @@ pub struct Last {
     pub unks: Vec<u32>,
 }
 
+impl Last {
+    /// The names of all [FontStyle] in [Self::styles] in order.
+    pub fn style_names(&self) -> impl Iterator<Item = &str> {
+        self.styles.iter().map(|s| s.style_name.as_str())
+    }
+
+    /// Find the first [FontStyle] with the given `style_name`.
+    pub fn style(&self, style_name: &str) -> Option<&FontStyle> {
+        self.styles.iter().find(|s| s.style_name == style_name)
+    }
+
+    /// Find the first [FontStyle] with the given `style_name` for editing.
+    pub fn style_mut(&mut self, style_name: &str) -> Option<&mut FontStyle> {
+        self.styles.iter_mut().find(|s| s.style_name == style_name)
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct FontStyle {
@@ -87,6 +104,52 @@ pub struct FontStyle {
     pub unk2: u32,
 }
 
+// Styles don't define any colors, so only expose sizes and font references.
+impl FontStyle {
+    /// The name of the font referenced by this style or [None] for the default font.
+    pub fn font(&self) -> Option<&str> {
+        self.font_name.as_deref()
+    }
+
+    /// Set the font referenced by this style or [None] for the default font.
+    pub fn set_font(&mut self, font_name: Option<&str>) {
+        self.font_name = font_name.map(Into::into);
+    }
+
+    /// The horizontal and vertical glyph scale.
+    pub fn scale(&self) -> [f32; 2] {
+        [self.scale_x, self.scale_y]
+    }
+
+    /// Set the horizontal and vertical glyph scale.
+    pub fn set_scale(&mut self, [x, y]: [f32; 2]) {
+        self.scale_x = x;
+        self.scale_y = y;
+    }
+
+    /// The maximum line width and the maximum number of lines.
+    pub fn text_limits(&self) -> [u16; 2] {
+        [self.max_width, self.max_lines]
+    }
+
+    /// Set the maximum line width and the maximum number of lines.
+    pub fn set_text_limits(&mut self, [max_width, max_lines]: [u16; 2]) {
+        self.max_width = max_width;
+        self.max_lines = max_lines;
+    }
+
+    /// The extra space added between lines and between characters.
+    pub fn spacing(&self) -> [u16; 2] {
+        [self.add_line_space, self.add_char_space]
+    }
+
+    /// Set the extra space added between lines and between characters.
+    pub fn set_spacing(&mut self, [line_space, char_space]: [u16; 2]) {
+        self.add_line_space = line_space;
+        self.add_char_space = char_space;
+    }
+}
+
 #[bitsize(32)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(DebugBits, FromBits, BinRead, BinWrite, PartialEq, Clone, Copy)]
@@ -102,3 +165,38 @@ pub struct StyleFlags {
 }
 
 xc3_write_binwrite_impl!(StyleFlags);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_style_values() {
+        let mut style = FontStyle {
+            flags: StyleFlags::new(true, false, u30::new(0)),
+            style_name: "style".to_string(),
+            font_name: None,
+            scale_x: 1.0,
+            scale_y: 2.0,
+            unk1: 100.0,
+            max_width: 640,
+            max_lines: 2,
+            add_line_space: 3,
+            add_char_space: 4,
+            unk2: 4,
+        };
+        assert_eq!(None, style.font());
+        assert_eq!([1.0, 2.0], style.scale());
+        assert_eq!([640, 2], style.text_limits());
+        assert_eq!([3, 4], style.spacing());
+
+        style.set_font(Some("font"));
+        style.set_scale([0.5, 0.75]);
+        style.set_text_limits([320, 1]);
+        style.set_spacing([5, 6]);
+        assert_eq!(Some("font"), style.font());
+        assert_eq!([0.5, 0.75], style.scale());
+        assert_eq!([320, 1], style.text_limits());
+        assert_eq!([5, 6], style.spacing());
+    }
+}
@@ -79,6 +79,8 @@ pub mod mtxt;
 pub mod mxmd;
 pub mod sar1;
 pub mod spch;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod vertex;
 pub mod wipac;
 pub mod xbc1;
@@ -597,7 +599,7 @@ file_write_full_impl!(
     last::Last
 );
 
-file_write_full_impl!(xc3_write::Endian::Big, fnt::Fnt);
+file_write_full_impl!(xc3_write::Endian::Big, fnt::Fnt, mxmd::legacy::MxmdLegacy);
 
 #[derive(Debug, Error)]
 #[error("error reading {path:?}")]
@@ -7,9 +7,9 @@
 //! | Xenoblade Chronicles 1 DE | |  |
 //! | Xenoblade Chronicles 2 |  |  |
 //! | Xenoblade Chronicles 3 |  | `monolib/shader/filterlut.wiltp` |
-use binrw::BinRead;
+use binrw::{BinRead, BinResult};
 
-use crate::{parse_count32_offset32, parse_offset32_count32};
+use crate::{mibl::Mibl, parse_count32_offset32, parse_offset32_count32};
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
 /// `monolib/shader/filterlut.wiltp` for Xenoblade 3.
@@ -40,3 +40,10 @@ pub struct Texture {
     // TODO: padding?
     pub unks: [u32; 4],
 }
+
+impl Texture {
+    /// Read the [Mibl] image data for this texture.
+    pub fn to_mibl(&self) -> BinResult<Mibl> {
+        Mibl::from_bytes(&self.mibl_data)
+    }
+}
@@ -33,7 +33,7 @@ use xc3_write::Xc3Write;
 
 pub use tegra_swizzle::SwizzleError;
 
-use crate::xc3_write_binwrite_impl;
+use crate::{mtxt::Mtxt, xc3_write_binwrite_impl};
 
 /// A swizzled image texture surface.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
@@ -211,9 +211,47 @@ pub enum CreateMiblError {
 
     #[error("image format {0:?} is not supported by Mibl")]
     UnsupportedImageFormat(image_dds::ImageFormat),
+
+    #[error("error deswizzling Mtxt surface")]
+    MtxtSwizzleError(#[from] wiiu_swizzle::SwizzleError),
+
+    #[error("surfaces do not have matching dimensions, mipmaps, and format")]
+    MismatchedSurfaces,
+
+    /// Mibl only supports a single layer or 6 layers for cube maps.
+    #[error("layer count {0} is not supported by Mibl")]
+    UnsupportedLayerCount(u32),
+}
+
+#[cfg(feature = "image")]
+#[derive(Debug, Error)]
+pub enum ConvertMiblError {
+    #[error("error deswizzling surface")]
+    SwizzleError(#[from] tegra_swizzle::SwizzleError),
+
+    #[error("error decoding or encoding surface")]
+    SurfaceError(#[from] image_dds::error::SurfaceError),
+
+    #[error("error creating Mibl")]
+    CreateMibl(#[from] CreateMiblError),
 }
 
 impl Mibl {
+    /// The `(width, height, depth)` of the base mip level in pixels.
+    pub fn dimensions(&self) -> (u32, u32, u32) {
+        (self.footer.width, self.footer.height, self.footer.depth)
+    }
+
+    /// The image format of the [image_data](#structfield.image_data).
+    pub fn format(&self) -> ImageFormat {
+        self.footer.image_format
+    }
+
+    /// The number of mip levels or 1 if there are no mipmaps.
+    pub fn mip_count(&self) -> u32 {
+        self.footer.mipmap_count
+    }
+
     /// Deswizzles all layers and mipmaps to a standard row-major memory layout.
     pub fn deswizzled_image_data(&self) -> Result<Vec<u8>, SwizzleError> {
         if self
@@ -245,6 +283,31 @@ impl Mibl {
         )
     }
 
+    /// Deswizzles the image data and splits it into one buffer for each mipmap.
+    ///
+    /// Each buffer contains the data for all layers and depth slices at that mipmap level.
+    pub fn deswizzled_mips(&self) -> Result<Vec<Vec<u8>>, SwizzleError> {
+        let surface = self.to_surface()?;
+
+        (0..surface.mipmaps)
+            .map(|mipmap| {
+                let mut data = Vec::new();
+                for layer in 0..surface.layers {
+                    for depth_level in 0..image_dds::mip_dimension(surface.depth, mipmap) {
+                        let mip = surface.get(layer, depth_level, mipmap).ok_or(
+                            SwizzleError::NotEnoughData {
+                                expected_size: self.footer.swizzled_surface_size(),
+                                actual_size: self.image_data.len(),
+                            },
+                        )?;
+                        data.extend_from_slice(mip);
+                    }
+                }
+                Ok(data)
+            })
+            .collect()
+    }
+
     /// Similar to [Self::to_surface] but adds the swizzled `base_mip_level` with the existing mipmaps.
     /// The base mip should have twice current width and height.
     pub fn to_surface_with_base_mip(
@@ -311,6 +374,42 @@ impl Mibl {
         )
     }
 
+    /// Create a texture with only the base mip level for each layer.
+    ///
+    /// The image data is reswizzled and [MiblFooter::image_size] is updated to match.
+    pub fn without_mipmaps(&self) -> Result<Self, CreateMiblError> {
+        let surface = self.to_surface()?;
+
+        let mut data = Vec::new();
+        for layer in 0..surface.layers {
+            for depth_level in 0..surface.depth {
+                let mip =
+                    surface
+                        .get(layer, depth_level, 0)
+                        .ok_or(SwizzleError::NotEnoughData {
+                            expected_size: self.footer.swizzled_base_mip_size(),
+                            actual_size: surface.data.len(),
+                        })?;
+                data.extend_from_slice(mip);
+            }
+        }
+
+        let mibl = Self::from_surface(Surface {
+            mipmaps: 1,
+            data,
+            ..surface
+        })?;
+
+        Ok(Self {
+            image_data: mibl.image_data,
+            footer: MiblFooter {
+                image_size: mibl.footer.image_size,
+                mipmap_count: 1,
+                ..self.footer
+            },
+        })
+    }
+
     /// Deswizzles all layers and mipmaps to a compatible surface for easier conversions.
     pub fn to_surface(&self) -> Result<Surface<Vec<u8>>, SwizzleError> {
         Ok(Surface {
@@ -379,9 +478,75 @@ impl Mibl {
         })
     }
 
+    /// Swizzles the layers from each surface in `surfaces` in order to an equivalent [Mibl].
+    ///
+    /// All surfaces must have the same dimensions, mipmap count, and format.
+    ///
+    /// The [MiblFooter] has no layer count, and [ViewDimension::Cube] is the only
+    /// view dimension with more than one layer.
+    /// 2D array textures can't be represented, so this returns
+    /// [CreateMiblError::UnsupportedLayerCount] if the total layer count is not 1 or 6.
+    pub fn from_surfaces<T: AsRef<[u8]>>(surfaces: &[Surface<T>]) -> Result<Self, CreateMiblError> {
+        let first = surfaces
+            .first()
+            .ok_or(CreateMiblError::UnsupportedLayerCount(0))?;
+
+        if surfaces.iter().any(|s| {
+            s.width != first.width
+                || s.height != first.height
+                || s.depth != first.depth
+                || s.mipmaps != first.mipmaps
+                || s.image_format != first.image_format
+        }) {
+            return Err(CreateMiblError::MismatchedSurfaces);
+        }
+
+        let layers = surfaces.iter().map(|s| s.layers).sum();
+        if layers != 1 && layers != 6 {
+            return Err(CreateMiblError::UnsupportedLayerCount(layers));
+        }
+
+        // The data for each surface is already ordered by layer and then mipmap.
+        let data = surfaces
+            .iter()
+            .flat_map(|s| s.data.as_ref().iter().copied())
+            .collect::<Vec<_>>();
+
+        Self::from_surface(Surface {
+            width: first.width,
+            height: first.height,
+            depth: first.depth,
+            layers,
+            mipmaps: first.mipmaps,
+            image_format: first.image_format,
+            data,
+        })
+    }
+
+    /// Returns `true` if both textures have the same dimensions, format, and deswizzled image data.
+    ///
+    /// Unlike `==`, this ignores differences in padding and [MiblFooter::image_size].
+    /// Textures that fail to deswizzle are never equal.
+    pub fn image_eq(&self, other: &Mibl) -> bool {
+        let a = &self.footer;
+        let b = &other.footer;
+        let same_surface = a.width == b.width
+            && a.height == b.height
+            && a.depth == b.depth
+            && a.view_dimension == b.view_dimension
+            && a.image_format == b.image_format
+            && a.mipmap_count == b.mipmap_count;
+
+        same_surface
+            && match (self.deswizzled_image_data(), other.deswizzled_image_data()) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => false,
+            }
+    }
+
     /// Deswizzles all layers and mipmaps to a Direct Draw Surface (DDS).
     pub fn to_dds(&self) -> Result<Dds, crate::dds::CreateDdsError> {
-        self.to_surface()?.to_dds().map_err(Into::into)
+        crate::dds::dds_from_surface(&self.to_surface()?).map_err(Into::into)
     }
 
     /// Swizzles all layers and mipmaps in `dds` to an equivalent [Mibl].
@@ -391,6 +556,47 @@ impl Mibl {
         let surface = image_dds::Surface::from_dds(dds)?;
         Self::from_surface(surface)
     }
+
+    /// Deswizzles all layers and mipmaps in the Wii U `mtxt` and swizzles to an equivalent [Mibl].
+    ///
+    /// Returns an error if the conversion fails or the image format is not supported.
+    pub fn from_mtxt(mtxt: &Mtxt) -> Result<Self, CreateMiblError> {
+        let surface = mtxt.to_surface()?;
+        Self::from_surface(surface)
+    }
+
+    /// Decode and reencode all layers and mipmaps to `image_format`.
+    ///
+    /// Floating point formats like [ImageFormat::BC6UFloat] and [ImageFormat::R16G16B16A16Float]
+    /// are decoded and encoded as RGBAF32 to preserve values outside the range `0.0` to `1.0`.
+    /// The existing mipmaps are reused, so the mipmap count does not change.
+    #[cfg(feature = "image")]
+    pub fn convert_format(
+        &self,
+        image_format: ImageFormat,
+        quality: image_dds::Quality,
+    ) -> Result<Self, ConvertMiblError> {
+        let surface = self.to_surface()?;
+        let format = image_dds::ImageFormat::from(image_format);
+        let mipmaps = image_dds::Mipmaps::FromSurface;
+
+        let encoded = if is_float(self.footer.image_format) || is_float(image_format) {
+            surface.decode_rgbaf32()?.encode(format, quality, mipmaps)?
+        } else {
+            surface.decode_rgba8()?.encode(format, quality, mipmaps)?
+        };
+
+        let mibl = Self::from_surface(encoded)?;
+
+        Ok(Self {
+            image_data: mibl.image_data,
+            footer: MiblFooter {
+                image_size: mibl.footer.image_size,
+                image_format,
+                ..self.footer
+            },
+        })
+    }
 }
 
 impl MiblFooter {
@@ -429,6 +635,14 @@ impl MiblFooter {
     }
 }
 
+#[cfg(feature = "image")]
+fn is_float(image_format: ImageFormat) -> bool {
+    matches!(
+        image_format,
+        ImageFormat::R16G16B16A16Float | ImageFormat::BC6UFloat
+    )
+}
+
 impl From<ImageFormat> for image_dds::ImageFormat {
     fn from(value: ImageFormat) -> Self {
         match value {
@@ -469,3 +683,202 @@ impl TryFrom<image_dds::ImageFormat> for ImageFormat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_channel_surface(image_format: image_dds::ImageFormat) -> Surface<Vec<u8>> {
+        // 64x64 with 7 mipmaps using 1 byte per pixel for R8 or 8 bytes per block for BC4.
+        let size = match image_format {
+            image_dds::ImageFormat::R8Unorm => 5461,
+            _ => 2744,
+        };
+        Surface {
+            width: 64,
+            height: 64,
+            depth: 1,
+            layers: 1,
+            mipmaps: 7,
+            image_format,
+            data: (0..size).map(|i| i as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn r8_surface_round_trip() {
+        let surface = single_channel_surface(image_dds::ImageFormat::R8Unorm);
+        let mibl = Mibl::from_surface(surface.clone()).unwrap();
+        assert_eq!(ImageFormat::R8Unorm, mibl.footer.image_format);
+        assert_eq!(surface, mibl.to_surface().unwrap());
+    }
+
+    #[test]
+    fn r8_surface_accessors() {
+        let surface = single_channel_surface(image_dds::ImageFormat::R8Unorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+        assert_eq!((64, 64, 1), mibl.dimensions());
+        assert_eq!(ImageFormat::R8Unorm, mibl.format());
+        assert_eq!(7, mibl.mip_count());
+    }
+
+    #[test]
+    fn r8_dds_round_trip() {
+        let surface = single_channel_surface(image_dds::ImageFormat::R8Unorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+        assert_eq!(mibl, Mibl::from_dds(&mibl.to_dds().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn r8_deswizzled_mips() {
+        let surface = single_channel_surface(image_dds::ImageFormat::R8Unorm);
+        let mibl = Mibl::from_surface(surface.clone()).unwrap();
+        let mips = mibl.deswizzled_mips().unwrap();
+        assert_eq!(
+            vec![4096, 1024, 256, 64, 16, 4, 1],
+            mips.iter().map(|m| m.len()).collect::<Vec<_>>()
+        );
+        assert_eq!(surface.data, mips.concat());
+    }
+
+    #[test]
+    fn bc4_surface_round_trip() {
+        let surface = single_channel_surface(image_dds::ImageFormat::BC4RUnorm);
+        let mibl = Mibl::from_surface(surface.clone()).unwrap();
+        assert_eq!(ImageFormat::BC4Unorm, mibl.footer.image_format);
+        assert_eq!(surface, mibl.to_surface().unwrap());
+    }
+
+    #[test]
+    fn bc4_dds_round_trip() {
+        let surface = single_channel_surface(image_dds::ImageFormat::BC4RUnorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+        assert_eq!(mibl, Mibl::from_dds(&mibl.to_dds().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn bc4_image_eq_padding() {
+        let surface = single_channel_surface(image_dds::ImageFormat::BC4RUnorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+
+        let mut padded = mibl.clone();
+        padded.image_data.extend_from_slice(&[0u8; 4096]);
+        padded.footer.image_size += 4096;
+
+        assert_ne!(mibl, padded);
+        assert!(mibl.image_eq(&padded));
+    }
+
+    #[test]
+    fn bc4_image_eq_different_data() {
+        let surface = single_channel_surface(image_dds::ImageFormat::BC4RUnorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+
+        let mut other = mibl.clone();
+        other.image_data[0] = other.image_data[0].wrapping_add(1);
+
+        assert!(!mibl.image_eq(&other));
+    }
+
+    #[test]
+    fn r8_from_surfaces_cube() {
+        let surfaces = vec![single_channel_surface(image_dds::ImageFormat::R8Unorm); 6];
+        let mibl = Mibl::from_surfaces(&surfaces).unwrap();
+        assert_eq!(ViewDimension::Cube, mibl.footer.view_dimension);
+
+        let surface = mibl.to_surface().unwrap();
+        assert_eq!(6, surface.layers);
+        assert_eq!(
+            surfaces
+                .iter()
+                .map(|s| &s.data[..])
+                .collect::<Vec<_>>()
+                .concat(),
+            surface.data
+        );
+    }
+
+    #[test]
+    fn r8_cube_dds_round_trip() {
+        let surfaces = vec![single_channel_surface(image_dds::ImageFormat::R8Unorm); 6];
+        let mibl = Mibl::from_surfaces(&surfaces).unwrap();
+
+        let dds = mibl.to_dds().unwrap();
+        assert_eq!(1, dds.header10.as_ref().unwrap().array_size);
+        assert_eq!(mibl, Mibl::from_dds(&dds).unwrap());
+    }
+
+    #[test]
+    fn from_surfaces_mismatched_format() {
+        let surfaces = [
+            single_channel_surface(image_dds::ImageFormat::R8Unorm),
+            single_channel_surface(image_dds::ImageFormat::BC4RUnorm),
+        ];
+        assert!(matches!(
+            Mibl::from_surfaces(&surfaces),
+            Err(CreateMiblError::MismatchedSurfaces)
+        ));
+    }
+
+    #[test]
+    fn from_surfaces_unsupported_layer_count() {
+        let surfaces = vec![single_channel_surface(image_dds::ImageFormat::R8Unorm); 2];
+        assert!(matches!(
+            Mibl::from_surfaces(&surfaces),
+            Err(CreateMiblError::UnsupportedLayerCount(2))
+        ));
+        assert!(matches!(
+            Mibl::from_surfaces::<Vec<u8>>(&[]),
+            Err(CreateMiblError::UnsupportedLayerCount(0))
+        ));
+    }
+
+    #[test]
+    fn rgba8_from_mtxt() {
+        // 32x32 with 1 mipmap using 4 bytes per pixel.
+        let mtxt = Mtxt {
+            image_data: (0..4096).map(|i| i as u8).collect(),
+            footer: crate::mtxt::MtxtFooter {
+                swizzle: 0,
+                surface_dim: crate::mtxt::SurfaceDim::D2,
+                width: 32,
+                height: 32,
+                depth_or_array_layers: 1,
+                mipmap_count: 1,
+                surface_format: crate::mtxt::SurfaceFormat::R8G8B8A8Unorm,
+                size: 4096,
+                unk_mip_offset: 0,
+                tile_mode: crate::mtxt::TileMode::D2TiledThin1,
+                unk1: 0,
+                alignment: 2048,
+                pitch: 32,
+                mipmap_offsets: [0; 13],
+                version: 10002,
+            },
+        };
+
+        let mibl = Mibl::from_mtxt(&mtxt).unwrap();
+        assert_eq!(ImageFormat::R8G8B8A8Unorm, mibl.footer.image_format);
+        assert_eq!(ViewDimension::D2, mibl.footer.view_dimension);
+        assert_eq!(mtxt.to_surface().unwrap(), mibl.to_surface().unwrap());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn r8_convert_format_bc4() {
+        let surface = single_channel_surface(image_dds::ImageFormat::R8Unorm);
+        let mibl = Mibl::from_surface(surface).unwrap();
+
+        let converted = mibl
+            .convert_format(ImageFormat::BC4Unorm, image_dds::Quality::Fast)
+            .unwrap();
+        assert_eq!(ImageFormat::BC4Unorm, converted.footer.image_format);
+        assert_eq!(7, converted.footer.mipmap_count);
+        assert_eq!(
+            single_channel_surface(image_dds::ImageFormat::BC4RUnorm)
+                .data
+                .len(),
+            converted.deswizzled_image_data().unwrap().len()
+        );
+    }
+}
@@ -7,8 +7,10 @@
 //! | Xenoblade Chronicles 2 | `map/*.wismhd` |
 //! | Xenoblade Chronicles 3 | `map/*.wismhd` |
 use std::{
-    io::{Cursor, Read, Seek, SeekFrom},
+    fs::File,
+    io::{BufReader, Cursor, Read, Seek, SeekFrom},
     marker::PhantomData,
+    path::Path,
 };
 
 use binrw::{binread, BinRead, BinWrite};
@@ -26,7 +28,7 @@ use crate::{
     parse_string_ptr32,
     vertex::VertexData,
     xbc1::Xbc1,
-    xc3_write_binwrite_impl,
+    xc3_write_binwrite_impl, ReadFileError,
 };
 
 /// The main map data for a `.wismhd` file.
@@ -599,6 +601,194 @@ pub struct MapPart {
     pub unk: u16,
 }
 
+/// A `.wismhd` file with a reader for the corresponding `.wismda` file.
+///
+/// This avoids needing to pass the `.wismda` reader and compression flag to [StreamEntry::extract].
+pub struct MsmdFile<R = BufReader<File>> {
+    pub msmd: Msmd,
+    wismda: R,
+    compressed: bool,
+}
+
+impl Msmd {
+    /// Read the `.wismhd` file at `wismhd_path`
+    /// and open the `.wismda` file in the same directory.
+    pub fn open<P: AsRef<Path>>(wismhd_path: P) -> Result<MsmdFile, ReadFileError> {
+        let wismhd_path = wismhd_path.as_ref();
+        let msmd = Msmd::from_file(wismhd_path)?;
+
+        let wismda_path = wismhd_path.with_extension("wismda");
+        let wismda = File::open(&wismda_path).map_err(|e| ReadFileError {
+            path: wismda_path,
+            source: e.into(),
+        })?;
+
+        Ok(MsmdFile::new(msmd, BufReader::new(wismda)))
+    }
+}
+
+impl<R: Read + Seek> MsmdFile<R> {
+    /// Use `wismda` as the reader for the `.wismda` data for `msmd`.
+    pub fn new(msmd: Msmd, wismda: R) -> Self {
+        // Some maps don't use XBC1 compressed archives in the .wismda file.
+        let compressed = msmd.wismda_info.compressed_length != msmd.wismda_info.decompressed_length;
+        Self {
+            msmd,
+            wismda,
+            compressed,
+        }
+    }
+
+    /// Decompress the data for `entry` from the `.wismda`.
+    pub fn decompress<T>(
+        &mut self,
+        entry: &StreamEntry<T>,
+    ) -> Result<Vec<u8>, DecompressStreamError> {
+        entry.decompress(&mut self.wismda, self.compressed)
+    }
+
+    /// Decompress and read the data for `entry` from the `.wismda`.
+    pub fn extract<T>(&mut self, entry: &StreamEntry<T>) -> Result<T, DecompressStreamError>
+    where
+        for<'a> T: BinRead<Args<'a> = ()>,
+    {
+        entry.extract(&mut self.wismda, self.compressed)
+    }
+
+    /// Extract the data for each of [Msmd::map_models].
+    pub fn map_models(
+        &mut self,
+    ) -> impl Iterator<Item = Result<MapModelData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.map_models.iter().map(|m| &m.entry),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::prop_models].
+    pub fn prop_models(
+        &mut self,
+    ) -> impl Iterator<Item = Result<PropModelData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.prop_models.iter().map(|m| &m.entry),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::env_models].
+    pub fn env_models(
+        &mut self,
+    ) -> impl Iterator<Item = Result<EnvModelData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.env_models.iter().map(|m| &m.entry),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::foliage_models].
+    pub fn foliage_models(
+        &mut self,
+    ) -> impl Iterator<Item = Result<FoliageModelData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.foliage_models.iter().map(|m| &m.entry),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::low_models].
+    pub fn low_models(
+        &mut self,
+    ) -> impl Iterator<Item = Result<MapLowModelData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.low_models.iter().map(|m| &m.entry),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::prop_vertex_data].
+    pub fn prop_vertex_data(
+        &mut self,
+    ) -> impl Iterator<Item = Result<VertexData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.prop_vertex_data.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::map_vertex_data].
+    pub fn map_vertex_data(
+        &mut self,
+    ) -> impl Iterator<Item = Result<VertexData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.map_vertex_data.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::prop_positions].
+    pub fn prop_positions(
+        &mut self,
+    ) -> impl Iterator<Item = Result<PropPositions, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.prop_positions.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::foliage_data].
+    pub fn foliage_data(
+        &mut self,
+    ) -> impl Iterator<Item = Result<FoliageVertexData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.foliage_data.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::unk_foliage_data].
+    pub fn unk_foliage_data(
+        &mut self,
+    ) -> impl Iterator<Item = Result<FoliageUnkData, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.unk_foliage_data.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+
+    /// Extract the data for each of [Msmd::low_textures].
+    pub fn low_textures(
+        &mut self,
+    ) -> impl Iterator<Item = Result<LowTextures, DecompressStreamError>> + '_ {
+        extract_entries(
+            self.msmd.low_textures.iter(),
+            &mut self.wismda,
+            self.compressed,
+        )
+    }
+}
+
+fn extract_entries<'a, T, R>(
+    entries: impl Iterator<Item = &'a StreamEntry<T>> + 'a,
+    wismda: &'a mut R,
+    compressed: bool,
+) -> impl Iterator<Item = Result<T, DecompressStreamError>> + 'a
+where
+    for<'b> T: BinRead<Args<'b> = ()> + 'a,
+    R: Read + Seek,
+{
+    entries.map(move |e| e.extract(wismda, compressed))
+}
+
 /// A reference to an [Xbc1] in the `.wismda` file.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, BinWrite, PartialEq, Clone)]
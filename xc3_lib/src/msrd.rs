@@ -8,7 +8,7 @@
 //! Some legacy files do not use [Msrd], so the [Mxmd](crate::mxmd::Mxmd) streaming is the only
 //! way to determine how to read the `.wismt` file.
 //!
-//! For editing streaming data, see [Msrd::extract_files] and [Msrd::from_extracted_files].
+//! For editing streaming data, see [Msrd::extract_files], [Msrd::from_extracted_files], and [Msrd::from_files].
 //!
 //! # Streams Layout
 //! All 3 games store exactly the same data despite some differences in how the data is organized.
@@ -120,7 +120,6 @@ pub struct StreamingDataLegacy {
     pub texture_data_compressed_size: u32,
 }
 
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct StreamingDataLegacyInner<U>
@@ -155,6 +154,38 @@ where
     pub texture_indices: Option<Vec<u16>>,
 }
 
+// The index counts are inferred from the textures.
+#[cfg(feature = "arbitrary")]
+impl<'a, U> arbitrary::Arbitrary<'a> for StreamingDataLegacyInner<U>
+where
+    U: Xc3Write + arbitrary::Arbitrary<'a> + 'static,
+    for<'b> U: BinRead<Args<'b> = ()>,
+    for<'b> U::Offsets<'b>: Xc3WriteOffsets<Args = ()>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let low_textures: PackedExternalTextures<U> = u.arbitrary()?;
+        let textures: Option<PackedExternalTextures<U>> = u.arbitrary()?;
+        let low_texture_indices = (0..low_textures.textures.len())
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?;
+        let texture_indices = textures
+            .as_ref()
+            .map(|t| {
+                (0..t.textures.len())
+                    .map(|_| u.arbitrary())
+                    .collect::<arbitrary::Result<_>>()
+            })
+            .transpose()?;
+
+        Ok(Self {
+            low_textures,
+            textures,
+            low_texture_indices,
+            texture_indices,
+        })
+    }
+}
+
 /// Flags indicating the way data is stored in the model's `.wismt` file.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, BinWrite, Clone, Copy, PartialEq, Eq, Hash)]
@@ -363,6 +394,103 @@ impl Stream {
     }
 }
 
+/// A summary of the [StreamEntry] items and [StreamFlags] for a [StreamingData].
+#[derive(Debug, PartialEq, Clone)]
+pub struct StreamInfo {
+    pub flags: StreamFlags,
+    /// Information for each item in [stream_entries](struct.StreamingData.html#structfield.stream_entries).
+    pub entries: Vec<StreamEntryInfo>,
+    /// The number of high resolution textures stored in `chr/tex/nx` instead of the `.wismt`.
+    pub chr_texture_count: usize,
+}
+
+/// Information for a single [StreamEntry].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StreamEntryInfo {
+    pub entry_type: EntryType,
+    /// The index into [streams](struct.StreamingData.html#structfield.streams) containing this entry.
+    pub stream_index: usize,
+    /// The offset in bytes for the decompressed data range in the stream.
+    pub offset: u32,
+    /// The size in bytes of the decompressed data range in the stream.
+    pub size: u32,
+    /// The index into [streams](struct.StreamingData.html#structfield.streams)
+    /// for the base mip level of a [EntryType::Texture].
+    pub base_mip_stream_index: Option<usize>,
+    /// The index into the low textures replaced by this [EntryType::Texture].
+    pub low_texture_index: Option<usize>,
+}
+
+impl Streaming {
+    /// Describe the stream entries and flags or `None` for [StreamingInner::StreamingLegacy].
+    ///
+    /// This does not decompress any of the streams.
+    pub fn stream_info(&self) -> Option<StreamInfo> {
+        match &self.inner {
+            StreamingInner::StreamingLegacy(_) => None,
+            StreamingInner::Streaming(data) => Some(data.stream_info()),
+        }
+    }
+}
+
+impl StreamingData {
+    fn stream_info(&self) -> StreamInfo {
+        let textures_start = self.textures_stream_entry_start_index as usize;
+        let textures_end = textures_start + self.textures_stream_entry_count as usize;
+
+        let entries = self
+            .stream_entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                // High resolution textures are in their own stream.
+                let (stream_index, low_texture_index) =
+                    if (textures_start..textures_end).contains(&i) {
+                        (
+                            self.textures_stream_index as usize,
+                            self.texture_resources
+                                .texture_indices
+                                .get(i - textures_start)
+                                .map(|i| *i as usize),
+                        )
+                    } else if entry.entry_type == EntryType::LowTextures {
+                        (self.low_textures_stream_index as usize, None)
+                    } else {
+                        (0, None)
+                    };
+
+                // Indices start from 1 for the base mip level.
+                let base_mip_stream_index = match entry.texture_base_mip_stream_index {
+                    index @ 2.. if entry.entry_type == EntryType::Texture => {
+                        Some(index as usize - 1)
+                    }
+                    _ => None,
+                };
+
+                StreamEntryInfo {
+                    entry_type: entry.entry_type,
+                    stream_index,
+                    offset: entry.offset,
+                    size: entry.size,
+                    base_mip_stream_index,
+                    low_texture_index,
+                }
+            })
+            .collect();
+
+        StreamInfo {
+            flags: self.flags,
+            entries,
+            chr_texture_count: self
+                .texture_resources
+                .chr_textures
+                .as_ref()
+                .map(|c| c.chr_textures.len())
+                .unwrap_or_default(),
+        }
+    }
+}
+
 impl StreamingInner {
     /// Return `true` if the streaming data has `chr/tex/nx` texture data even if empty.
     /// This will be `true` for most Xenoblade 3 models and `false` for all other games.
@@ -444,3 +572,71 @@ impl Xc3WriteOffsets for TextureResourcesOffsets<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entry_type: EntryType, texture_base_mip_stream_index: u16) -> StreamEntry {
+        StreamEntry {
+            offset: 0,
+            size: 16,
+            texture_base_mip_stream_index,
+            entry_type,
+            unk: [0; 2],
+        }
+    }
+
+    #[test]
+    fn stream_info_high_textures() {
+        let streaming = Streaming {
+            inner: StreamingInner::Streaming(StreamingData {
+                flags: StreamFlags::new(true, true, true, true, false, false, false, u25::new(0)),
+                stream_entries: vec![
+                    entry(EntryType::Vertex, 0),
+                    entry(EntryType::Shader, 0),
+                    entry(EntryType::LowTextures, 0),
+                    entry(EntryType::Texture, 3),
+                    entry(EntryType::Texture, 0),
+                ],
+                streams: Vec::new(),
+                vertex_data_entry_index: 0,
+                shader_entry_index: 1,
+                low_textures_entry_index: 2,
+                low_textures_stream_index: 1,
+                textures_stream_index: 2,
+                textures_stream_entry_start_index: 3,
+                textures_stream_entry_count: 2,
+                texture_resources: TextureResources {
+                    texture_indices: vec![4, 7],
+                    low_textures: None,
+                    unk1: 0,
+                    chr_textures: None,
+                    unk: [0; 2],
+                },
+            }),
+        };
+
+        let info = streaming.stream_info().unwrap();
+        assert!(info.flags.has_textures());
+        assert_eq!(0, info.chr_texture_count);
+        assert_eq!(
+            vec![
+                (EntryType::Vertex, 0, None, None),
+                (EntryType::Shader, 0, None, None),
+                (EntryType::LowTextures, 1, None, None),
+                (EntryType::Texture, 2, Some(2), Some(4)),
+                (EntryType::Texture, 2, None, Some(7)),
+            ],
+            info.entries
+                .iter()
+                .map(|e| (
+                    e.entry_type,
+                    e.stream_index,
+                    e.base_mip_stream_index,
+                    e.low_texture_index
+                ))
+                .collect::<Vec<_>>()
+        );
+    }
+}
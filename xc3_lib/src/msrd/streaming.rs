@@ -35,10 +35,25 @@ pub enum ExtractFilesError {
     LegacyStream,
 }
 
+#[derive(Debug, Error)]
+pub enum CreateChrTexturesError {
+    #[error("error compressing texture data")]
+    Xbc1(#[from] CreateXbc1Error),
+
+    #[error("error writing chr/tex/nx texture")]
+    Io(#[from] std::io::Error),
+
+    #[error("texture name {0:?} is not a hexadecimal texture hash")]
+    InvalidHash(String),
+
+    #[error("high resolution texture {0:?} has no base mip level required for chr/tex/nx")]
+    MissingBaseMip(String),
+}
+
 // TODO: Add a function to create an extractedtexture from a surface?
 /// All the mip levels and metadata for an [Mibl] (Switch) or [Dds] (PC) texture.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct ExtractedTexture<T, U> {
     pub name: String,
     pub usage: U,
@@ -48,7 +63,7 @@ pub struct ExtractedTexture<T, U> {
 
 /// An additional texture that replaces the low resolution texture.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct HighTexture<T> {
     pub mid: T,
     pub base_mip: Option<Vec<u8>>,
@@ -127,7 +142,7 @@ fn low_texture(mibl: &Mibl) -> Mibl {
                     height: 4,
                     depth: 1,
                     view_dimension: crate::mibl::ViewDimension::D2,
-                    image_format: mibl.footer.image_format,
+                    image_format: mibl.format(),
                     mipmap_count: 1,
                     version: 10001,
                 },
@@ -183,31 +198,35 @@ impl ChrTextureStreams {
     pub fn save<P: AsRef<Path>>(&self, chr_tex_nx: P) -> Xc3Result<()> {
         let folder = chr_tex_nx.as_ref();
         self.mid
-            .save(folder.join("m").join(format!("{:x}.wismt", self.hash)))?;
+            .save(folder.join("m").join(format!("{:08x}.wismt", self.hash)))?;
         self.base_mip
-            .save(folder.join("h").join(format!("{:x}.wismt", self.hash)))?;
+            .save(folder.join("h").join(format!("{:08x}.wismt", self.hash)))?;
         Ok(())
     }
 }
 
 /// Compress the high resolution and base mip levels for `textures`
 /// to Xenoblade 3 `chr/tex/nx` folder data.
+///
+/// Texture names must be the texture hash in hexadecimal like `"0a1b2c3d"`,
+/// and each high resolution texture must have a base mip level.
 pub fn pack_chr_textures(
     textures: &[ExtractedTexture<Mibl, TextureUsage>],
-) -> Result<(ChrTexTextures, Vec<ChrTextureStreams>), CreateXbc1Error> {
+) -> Result<(ChrTexTextures, Vec<ChrTextureStreams>), CreateChrTexturesError> {
     let streams = textures
         .iter()
-        .filter_map(|t| {
-            let high = t.high.as_ref()?;
-            Some((&high.mid, high.base_mip.as_ref()?, &t.name))
-        })
-        .map(|(mid, base_mip, name)| {
-            // TODO: Always assume the name is already a hash?
-            // TODO: How to handle missing high resolution textures?
+        .filter_map(|t| Some((t.high.as_ref()?, &t.name)))
+        .map(|(high, name)| {
             // TODO: Stream names?
-            let mid = Xbc1::new("0000".to_string(), mid, CompressionType::Zlib)?;
+            let base_mip = high
+                .base_mip
+                .as_ref()
+                .ok_or_else(|| CreateChrTexturesError::MissingBaseMip(name.clone()))?;
+            let hash = u32::from_str_radix(name, 16)
+                .map_err(|_| CreateChrTexturesError::InvalidHash(name.clone()))?;
+
+            let mid = Xbc1::new("0000".to_string(), &high.mid, CompressionType::Zlib)?;
             let base_mip = Xbc1::new("0000".to_string(), base_mip, CompressionType::Zlib)?;
-            let hash = u32::from_str_radix(name, 16).expect(name);
 
             Ok(ChrTextureStreams {
                 hash,
@@ -215,7 +234,7 @@ pub fn pack_chr_textures(
                 base_mip,
             })
         })
-        .collect::<Result<Vec<_>, CreateXbc1Error>>()?;
+        .collect::<Result<Vec<_>, CreateChrTexturesError>>()?;
 
     let chr_textures = streams
         .iter()
@@ -309,8 +328,85 @@ impl Msrd {
         use_chr_textures: bool,
     ) -> Result<Self, CreateXbc1Error> {
         // TODO: This should actually be checking if the game is xenoblade 3.
-        let (mut streaming, data) = pack_files(vertex, spch, textures, use_chr_textures)?;
+        // Replacing chr/tex/nx textures is problematic since texture wismts are shared.
+        // We can avoid conflicts by embedding the high resolution textures in the model wismt.
+        // Xenoblade 3 still requires dummy data even if the chr/tex/nx textures aren't used.
+        let chr_textures = use_chr_textures.then_some(ChrTexTextures {
+            chr_textures: Vec::new(),
+            unk: [0; 2],
+        });
+        let (streaming, data) = pack_files(vertex, spch, textures, chr_textures, true)?;
+        Self::from_streaming_data(streaming, data)
+    }
 
+    /// Pack and compress the files into new archive data
+    /// and optionally save high resolution textures to `chr_tex_nx`.
+    ///
+    /// If `chr_tex_nx` is `None`, high resolution textures are embedded
+    /// like [Msrd::from_extracted_files] with `use_chr_textures` set to `false`.
+    ///
+    /// If `chr_tex_nx` is `Some`, the high resolution textures and base mip levels
+    /// are saved to the `m` and `h` folders using the Xenoblade 3 `chr/tex/nx` layout.
+    /// Existing `chr/tex/nx` files for the same hash will be overwritten.
+    /// See [Msrd::from_files_chr_textures] for the requirements
+    /// or for saving the textures separately.
+    ///
+    /// # Examples
+    /// ```rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xc3_lib::msrd::Msrd;
+    /// # let msrd = Msrd::from_file("ch01011013.wismt")?;
+    /// # let (vertex, spch, textures) = msrd.extract_files(None)?;
+    ///
+    /// let chr_tex_nx = Some(std::path::Path::new("chr/tex/nx"));
+    /// let new_msrd = Msrd::from_files(&vertex, &spch, &textures, chr_tex_nx)?;
+    /// new_msrd.save("ch01011013.wismt")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_files(
+        vertex: &VertexData,
+        spch: &Spch,
+        textures: &[ExtractedTexture<Mibl, TextureUsage>],
+        chr_tex_nx: Option<&Path>,
+    ) -> Result<Self, CreateChrTexturesError> {
+        match chr_tex_nx {
+            Some(chr_tex_nx) => {
+                let (msrd, streams) = Self::from_files_chr_textures(vertex, spch, textures)?;
+
+                std::fs::create_dir_all(chr_tex_nx.join("m"))?;
+                std::fs::create_dir_all(chr_tex_nx.join("h"))?;
+                for stream in &streams {
+                    stream.save(chr_tex_nx)?;
+                }
+
+                Ok(msrd)
+            }
+            None => Ok(Self::from_extracted_files(vertex, spch, textures, false)?),
+        }
+    }
+
+    /// Pack and compress the files into new archive data
+    /// with high resolution textures in the Xenoblade 3 `chr/tex/nx` layout.
+    ///
+    /// The high resolution textures and base mip levels are returned as [ChrTextureStreams]
+    /// and must be saved with [ChrTextureStreams::save] for the textures to load.
+    /// Texture names must be the texture hash in hexadecimal like `"0a1b2c3d"`,
+    /// and each high resolution texture must have a base mip level.
+    pub fn from_files_chr_textures(
+        vertex: &VertexData,
+        spch: &Spch,
+        textures: &[ExtractedTexture<Mibl, TextureUsage>],
+    ) -> Result<(Self, Vec<ChrTextureStreams>), CreateChrTexturesError> {
+        let (chr_textures, streams) = pack_chr_textures(textures)?;
+        let (streaming, data) = pack_files(vertex, spch, textures, Some(chr_textures), false)?;
+        Ok((Self::from_streaming_data(streaming, data)?, streams))
+    }
+
+    fn from_streaming_data(
+        mut streaming: StreamingData,
+        data: Vec<u8>,
+    ) -> Result<Self, CreateXbc1Error> {
         // HACK: We won't know the first xbc1 offset until writing the header.
         let mut writer = Cursor::new(Vec::new());
         let mut data_ptr = 0;
@@ -351,8 +447,10 @@ impl Texture for Mibl {
 
 impl Texture for Dds {
     fn from_bytes<T: AsRef<[u8]>>(bytes: T) -> binrw::BinResult<Self> {
-        // TODO: Avoid unwrap by creating another error type?
-        Ok(<Dds as DdsExt>::from_bytes(bytes).unwrap())
+        <Dds as DdsExt>::from_bytes(bytes).map_err(|e| binrw::Error::Custom {
+            pos: 0,
+            err: Box::new(e),
+        })
     }
 }
 
@@ -549,14 +647,15 @@ fn pack_files(
     vertex: &VertexData,
     spch: &Spch,
     textures: &[ExtractedTexture<Mibl, TextureUsage>],
-    use_chr_textures: bool,
+    chr_textures: Option<ChrTexTextures>,
+    embed_high_textures: bool,
 ) -> Result<(StreamingData, Vec<u8>), CreateXbc1Error> {
     let Streams {
         stream_entries,
         streams,
         low_textures,
         data,
-    } = create_streams(vertex, spch, textures)?;
+    } = create_streams(vertex, spch, textures, embed_high_textures)?;
 
     let vertex_data_entry_index = stream_entry_index(&stream_entries, EntryType::Vertex);
     let shader_entry_index = stream_entry_index(&stream_entries, EntryType::Shader);
@@ -568,13 +667,12 @@ fn pack_files(
         .filter(|e| e.entry_type == EntryType::Texture)
         .count() as u32;
 
-    // Replacing chr/tex/nx textures is problematic since texture wismts are shared.
-    // We can avoid conflicts by embedding the high resolution textures in the model wismt.
-    // Xenoblade 3 still requires dummy data even if the chr/tex/nx textures aren't used.
-    let chr_textures = use_chr_textures.then_some(ChrTexTextures {
-        chr_textures: Vec::new(),
-        unk: [0; 2],
-    });
+    // The indices apply to the embedded textures or chr/tex/nx textures.
+    let texture_indices = textures
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| t.high.as_ref().map(|_| i as u16))
+        .collect();
 
     Ok((
         StreamingData {
@@ -602,11 +700,7 @@ fn pack_files(
             textures_stream_entry_start_index,
             textures_stream_entry_count,
             texture_resources: TextureResources {
-                texture_indices: textures
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, t)| t.high.as_ref().map(|_| i as u16))
-                    .collect(),
+                texture_indices,
                 low_textures: (!low_textures.is_empty()).then_some(PackedExternalTextures {
                     textures: low_textures,
                     unk2: 0,
@@ -639,6 +733,7 @@ fn create_streams(
     vertex: &VertexData,
     spch: &Spch,
     textures: &[ExtractedTexture<Mibl, TextureUsage>],
+    embed_high_textures: bool,
 ) -> Result<Streams, CreateXbc1Error> {
     // Entries are in ascending order by offset and stream.
     // Data order is Vertex, Shader, LowTextures, Textures.
@@ -646,10 +741,11 @@ fn create_streams(
 
     let (low_textures, stream0_data) = write_stream0(&mut stream_entries, vertex, spch, textures)?;
 
-    // Always write high resolution textures to wismt for compatibility.
+    // Write high resolution textures to wismt by default for compatibility.
     // This works across all switch games and doesn't interfere with chr/tex/nx textures.
+    let high_textures = if embed_high_textures { textures } else { &[] };
     let entry_start_index = stream_entries.len();
-    let stream1_data = write_stream1(&mut stream_entries, textures);
+    let stream1_data = write_stream1(&mut stream_entries, high_textures);
 
     // Ignore unused empty streams.
     let mut streams_data = vec![&stream0_data];
@@ -659,7 +755,7 @@ fn create_streams(
 
     let base_mips = write_base_mip_streams(
         &mut stream_entries,
-        textures,
+        high_textures,
         streams_data.len() as u16,
         entry_start_index,
     );
@@ -944,4 +1040,104 @@ mod tests {
             chr_tex_nx_folder("xeno2/extracted/model/bl/bl000101.wimdo")
         );
     }
+
+    fn vertex_data() -> VertexData {
+        VertexData {
+            vertex_buffers: Vec::new(),
+            index_buffers: Vec::new(),
+            unk0: 0,
+            unk1: 0,
+            unk2: 0,
+            vertex_buffer_info: Vec::new(),
+            outline_buffers: Vec::new(),
+            vertex_morphs: None,
+            buffer: vec![1, 2, 3, 4],
+            unk_data: None,
+            weights: None,
+            unk7: None,
+            unks: [0; 5],
+        }
+    }
+
+    fn spch() -> Spch {
+        Spch {
+            version: 0,
+            slct_offsets: Vec::new(),
+            unk4s: Vec::new(),
+            slct_section: Vec::new(),
+            xv4_section: Vec::new(),
+            unk_section: Vec::new(),
+            string_section: None,
+            unk7: 0,
+            padding: [0; 4],
+        }
+    }
+
+    fn textures() -> Vec<ExtractedTexture<Mibl, TextureUsage>> {
+        ["0a1b2c3d", "00000001"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let mibl = Mibl::from_surface(Surface {
+                    width: 64,
+                    height: 64,
+                    depth: 1,
+                    layers: 1,
+                    mipmaps: 2,
+                    image_format: image_dds::ImageFormat::Rgba8Unorm,
+                    data: vec![i as u8; (64 * 64 + 32 * 32) * 4],
+                })
+                .unwrap();
+                ExtractedTexture::from_mibl(&mibl, name.to_string(), TextureUsage::Col)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn from_files_extract_files_embedded() {
+        let textures = textures();
+        let msrd = Msrd::from_files(&vertex_data(), &spch(), &textures, None).unwrap();
+
+        let (new_vertex, new_spch, new_textures) = msrd.extract_files(None).unwrap();
+        assert_eq!(vertex_data(), new_vertex);
+        assert_eq!(spch(), new_spch);
+        assert_eq!(textures, new_textures);
+    }
+
+    #[test]
+    fn from_files_extract_files_chr_tex_nx() {
+        let chr_tex_nx = std::env::temp_dir().join(format!(
+            "xc3_lib_from_files_chr_tex_nx_{}",
+            std::process::id()
+        ));
+
+        let textures = textures();
+        let msrd = Msrd::from_files(&vertex_data(), &spch(), &textures, Some(&chr_tex_nx)).unwrap();
+        assert!(chr_tex_nx.join("m").join("0a1b2c3d.wismt").exists());
+        assert!(chr_tex_nx.join("h").join("00000001.wismt").exists());
+
+        let (new_vertex, new_spch, new_textures) = msrd.extract_files(Some(&chr_tex_nx)).unwrap();
+        std::fs::remove_dir_all(&chr_tex_nx).unwrap();
+
+        assert_eq!(vertex_data(), new_vertex);
+        assert_eq!(spch(), new_spch);
+        assert_eq!(textures, new_textures);
+    }
+
+    #[test]
+    fn from_files_chr_textures_invalid() {
+        let mut textures = textures();
+        textures[0].name = "texture".to_string();
+        assert!(matches!(
+            Msrd::from_files_chr_textures(&vertex_data(), &spch(), &textures),
+            Err(CreateChrTexturesError::InvalidHash(name)) if name == "texture"
+        ));
+
+        let mut textures = self::textures();
+        textures[1].high.as_mut().unwrap().base_mip = None;
+        assert!(matches!(
+            Msrd::from_files_chr_textures(&vertex_data(), &spch(), &textures),
+            Err(CreateChrTexturesError::MissingBaseMip(name)) if name == "00000001"
+        ));
+    }
 }
@@ -14,6 +14,8 @@ use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
 use crate::{parse_count32_offset32_unchecked, parse_string_ptr32_unchecked};
 
+pub mod latte;
+
 const HEADER_SIZE: u32 = 48;
 
 // TODO: list.cashd is an SHPC file.
@@ -209,4 +211,13 @@ impl Mths {
             },
         )
     }
+
+    /// Disassemble the vertex and fragment shader programs to Latte assembly text.
+    ///
+    /// The output can be parsed by `xc3_shader` for analyzing shader code.
+    pub fn disassemble(&self) -> BinResult<(String, String)> {
+        let vertex = latte::disassemble(&self.vertex_shader()?.inner.program_binary);
+        let fragment = latte::disassemble(&self.fragment_shader()?.program_binary);
+        Ok((vertex, fragment))
+    }
 }
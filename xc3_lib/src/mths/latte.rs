@@ -0,0 +1,917 @@
+//! Disassembly for Latte shader binaries.
+//!
+//! The output uses the same assembly syntax as the `disassemble` command in decaf-emu's gfd-tool.
+//! Latte uses a variant of the R600/R700 instruction set.
+//! See the "R600-Family Instruction Set Architecture" and "R700-Family Instruction Set Architecture"
+//! documents from AMD for details on the binary encodings.
+//!
+//! The output can be parsed by the Latte assembly grammar in xc3_shader.
+//! Instructions and sources that can't be represented like unknown opcodes, memory exports,
+//! or reserved source selects are written as `UNKNOWN_CF_<inst>`, `UNKNOWN_OP2_<inst>`,
+//! `UNKNOWN_OP3_<inst>`, or `UNKNOWN_SRC_<sel>` with the raw value in decimal.
+use std::fmt::Write;
+
+/// Disassemble the control flow, ALU, and TEX instructions in `binary`.
+pub fn disassemble(binary: &[u8]) -> String {
+    let words: Vec<u32> = binary
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+        .collect();
+    let word = |i: usize| words.get(i).copied().unwrap_or_default();
+
+    let mut text = String::new();
+
+    // Instruction counts are shared between ALU groups and TEX instructions.
+    let mut inst_count = 0;
+
+    // Control flow instructions are stored in 64-bit words starting from the beginning.
+    for cf_index in 0..words.len() / 2 {
+        let word0 = word(cf_index * 2);
+        let word1 = word(cf_index * 2 + 1);
+
+        match bits(word1, 28, 2) {
+            0 => {
+                let cf_inst = bits(word1, 23, 7);
+                match cf_inst {
+                    1 | 2 | 3 | 27 | 28 | 29 => {
+                        fetch_clause(&mut text, cf_index, word0, word1, &word, &mut inst_count)
+                    }
+                    _ => cf_inst_text(&mut text, cf_index, word0, word1),
+                }
+            }
+            1 => export_text(&mut text, cf_index, word0, word1),
+            _ => alu_clause(&mut text, cf_index, word0, word1, &word, &mut inst_count),
+        }
+        text.push('\n');
+
+        // ALU clauses don't have an end of program bit.
+        let is_alu = bits(word1, 28, 2) >= 2;
+        if !is_alu && bits(word1, 21, 1) == 1 {
+            break;
+        }
+    }
+    text.push_str("END_OF_PROGRAM\n");
+
+    text
+}
+
+fn bits(value: u32, start: u32, count: u32) -> u32 {
+    (value >> start) & ((1 << count) - 1)
+}
+
+fn cf_properties(text: &mut String, word1: u32) {
+    if bits(word1, 22, 1) == 1 {
+        text.push_str(" VALID_PIX");
+    }
+    if bits(word1, 30, 1) == 1 {
+        text.push_str(" WHOLE_QUAD_MODE");
+    }
+    if bits(word1, 31, 1) == 0 {
+        text.push_str(" NO_BARRIER");
+    }
+}
+
+fn cf_count(word1: u32) -> u32 {
+    // The high bit of the count is stored separately.
+    (bits(word1, 10, 3) | (bits(word1, 19, 1) << 3)) + 1
+}
+
+fn cf_inst_text(text: &mut String, cf_index: usize, word0: u32, word1: u32) {
+    let cf_inst = bits(word1, 23, 7);
+    let name = match cf_inst {
+        0 => "NOP",
+        4 => "LOOP_START",
+        5 => "LOOP_END",
+        6 => "LOOP_START_DX10",
+        7 => "LOOP_START_NO_AL",
+        8 => "LOOP_CONTINUE",
+        9 => "LOOP_BREAK",
+        10 => "JUMP",
+        11 => "PUSH",
+        12 => "PUSH_ELSE",
+        13 => "ELSE",
+        14 => "POP",
+        15 => "POP_JUMP",
+        16 => "POP_PUSH",
+        17 => "POP_PUSH_ELSE",
+        18 => "CALL",
+        19 => "CALL_FS",
+        20 => "RETURN",
+        21 => "EMIT_VERTEX",
+        22 => "EMIT_CUT_VERTEX",
+        23 => "CUT_VERTEX",
+        24 => "KILL",
+        26 => "WAIT_ACK",
+        _ => {
+            write!(text, "{cf_index:02} UNKNOWN_CF_{cf_inst}").unwrap();
+            cf_properties(text, word1);
+            return;
+        }
+    };
+    write!(text, "{cf_index:02} {name}").unwrap();
+
+    let pop_count = bits(word1, 0, 3);
+    if pop_count != 0 {
+        write!(text, " POP_CNT({pop_count})").unwrap();
+    }
+
+    // Only flow control instructions use the address.
+    if matches!(cf_inst, 4..=18) {
+        write!(text, " ADDR({word0})").unwrap();
+    }
+
+    let cf_const = bits(word1, 3, 5);
+    if cf_const != 0 {
+        write!(text, " CF_CONST({cf_const})").unwrap();
+    }
+
+    cf_properties(text, word1);
+}
+
+fn export_text(text: &mut String, cf_index: usize, word0: u32, word1: u32) {
+    let array_base = bits(word0, 0, 13);
+    let rw_gpr = bits(word0, 15, 7);
+    let rw_rel = bits(word0, 22, 1);
+
+    let cf_inst = bits(word1, 23, 7);
+    let name = match cf_inst {
+        39 => "EXP",
+        40 => "EXP_DONE",
+        _ => "",
+    };
+
+    let target = match bits(word0, 13, 2) {
+        0 => format!("PIX{array_base}"),
+        1 => format!("POS{}", array_base.saturating_sub(60)),
+        2 => format!("PARAM{array_base}"),
+        _ => String::new(),
+    };
+
+    // Memory exports have no equivalent assembly syntax.
+    if name.is_empty() || target.is_empty() {
+        write!(text, "{cf_index:02} UNKNOWN_CF_{cf_inst}").unwrap();
+        cf_properties(text, word1);
+        return;
+    }
+
+    let gpr = if rw_rel == 1 {
+        format!("R[AL+{rw_gpr}]")
+    } else {
+        format!("R{rw_gpr}")
+    };
+
+    let swizzle: String = [0, 3, 6, 9]
+        .into_iter()
+        .map(|start| sel_char(bits(word1, start, 3)))
+        .collect();
+
+    write!(text, "{cf_index:02} {name}: {target}, {gpr}.{swizzle}").unwrap();
+
+    let burst_count = bits(word1, 17, 4);
+    if burst_count != 0 {
+        write!(text, " BURSTCNT({burst_count})").unwrap();
+    }
+
+    let elem_size = bits(word0, 30, 2);
+    if elem_size != 0 {
+        write!(text, " ELEM_SIZE({elem_size})").unwrap();
+    }
+
+    cf_properties(text, word1);
+}
+
+fn sel_char(sel: u32) -> char {
+    match sel {
+        0 => 'x',
+        1 => 'y',
+        2 => 'z',
+        3 => 'w',
+        4 => '0',
+        5 => '1',
+        _ => '_',
+    }
+}
+
+fn fetch_clause(
+    text: &mut String,
+    cf_index: usize,
+    word0: u32,
+    word1: u32,
+    word: &impl Fn(usize) -> u32,
+    inst_count: &mut usize,
+) {
+    let cf_inst = bits(word1, 23, 7);
+    let name = match cf_inst {
+        1 => "TEX",
+        2 => "VTX",
+        3 => "VTX_TC",
+        27 => "TEX_ACK",
+        28 => "VTX_ACK",
+        _ => "VTX_TC_ACK",
+    };
+    let count = cf_count(word1);
+
+    write!(text, "{cf_index:02} {name}: ADDR({word0}) CNT({count})").unwrap();
+    cf_properties(text, word1);
+    text.push('\n');
+
+    // Each fetch instruction is 128 bits.
+    let start = word0 as usize * 2;
+    for i in 0..count as usize {
+        let words = [
+            word(start + i * 4),
+            word(start + i * 4 + 1),
+            word(start + i * 4 + 2),
+        ];
+        text.push('\n');
+        if matches!(cf_inst, 1 | 27) {
+            tex_inst_text(text, *inst_count, words);
+        } else {
+            vtx_inst_text(text, *inst_count, words);
+        }
+        text.push('\n');
+        *inst_count += 1;
+    }
+}
+
+fn tex_inst_text(text: &mut String, inst_count: usize, [word0, word1, word2]: [u32; 3]) {
+    let name = match bits(word0, 0, 5) {
+        0 => "VTX_FETCH",
+        1 => "VTX_SEMANTIC",
+        2 => "MEM",
+        3 => "LD",
+        4 => "GET_TEXTURE_INFO",
+        5 => "GET_SAMPLE_INFO",
+        6 => "GET_COMP_TEX_LOD",
+        7 => "GET_GRADIENTS_H",
+        8 => "GET_GRADIENTS_V",
+        9 => "GET_LERP",
+        10 => "KEEP_GRADIENTS",
+        11 => "SET_GRADIENTS_H",
+        12 => "SET_GRADIENTS_V",
+        13 => "PASS",
+        14 => "SET_CUBEMAP_INDEX",
+        15 => "FETCH4",
+        16 => "SAMPLE",
+        17 => "SAMPLE_L",
+        18 => "SAMPLE_LB",
+        19 => "SAMPLE_LZ",
+        20 => "SAMPLE_G",
+        21 => "SAMPLE_G_L",
+        22 => "SAMPLE_G_LB",
+        23 => "SAMPLE_G_LZ",
+        24 => "SAMPLE_C",
+        25 => "SAMPLE_C_L",
+        26 => "SAMPLE_C_LB",
+        27 => "SAMPLE_C_LZ",
+        28 => "SAMPLE_C_G",
+        29 => "SAMPLE_C_G_L",
+        30 => "SAMPLE_C_G_LB",
+        _ => "SAMPLE_C_G_LZ",
+    };
+
+    let resource_id = bits(word0, 8, 8);
+    let src_gpr = bits(word0, 16, 7);
+    let src_rel = if bits(word0, 23, 1) == 1 { "[AL]" } else { "" };
+
+    let dst_gpr = bits(word1, 0, 7);
+    let dst_rel = if bits(word1, 7, 1) == 1 { "[AL]" } else { "" };
+    let dst_swizzle: String = [9, 12, 15, 18]
+        .into_iter()
+        .map(|start| sel_char(bits(word1, start, 3)))
+        .collect();
+
+    let sampler_id = bits(word2, 15, 5);
+    let src_swizzle: String = [20, 23, 26, 29]
+        .into_iter()
+        .map(|start| sel_char(bits(word2, start, 3)))
+        .collect();
+
+    write!(
+        text,
+        "  {inst_count:<7}{name:<15} R{dst_gpr}{dst_rel}.{dst_swizzle}, R{src_gpr}{src_rel}.{src_swizzle}, t{resource_id}, s{sampler_id}"
+    )
+    .unwrap();
+
+    // TODO: LOD bias, offsets, and coordinate types.
+    if bits(word0, 5, 1) == 1 {
+        text.push_str(" BC_FRAC_MODE");
+    }
+    if bits(word0, 24, 1) == 1 {
+        text.push_str(" ALT_CONST");
+    }
+    if bits(word0, 7, 1) == 1 {
+        text.push_str(" WHOLE_QUAD_MODE");
+    }
+}
+
+fn vtx_inst_text(text: &mut String, inst_count: usize, [word0, word1, _]: [u32; 3]) {
+    let buffer_id = bits(word0, 8, 8);
+    let src_gpr = bits(word0, 16, 7);
+    let src_rel = if bits(word0, 23, 1) == 1 { "[AL]" } else { "" };
+    let src_channel = sel_char(bits(word0, 24, 2));
+
+    let dst_gpr = bits(word1, 0, 7);
+    let dst_rel = if bits(word1, 7, 1) == 1 { "[AL]" } else { "" };
+    let dst_swizzle: String = [9, 12, 15, 18]
+        .into_iter()
+        .map(|start| sel_char(bits(word1, start, 3)))
+        .collect();
+
+    // TODO: Data formats and fetch types.
+    write!(
+        text,
+        "  {inst_count:<7}{:<15} R{dst_gpr}{dst_rel}.{dst_swizzle}, R{src_gpr}{src_rel}.{src_channel}, b{buffer_id}",
+        "VTX_FETCH"
+    )
+    .unwrap();
+}
+
+fn alu_clause(
+    text: &mut String,
+    cf_index: usize,
+    word0: u32,
+    word1: u32,
+    word: &impl Fn(usize) -> u32,
+    inst_count: &mut usize,
+) {
+    let addr = bits(word0, 0, 22) as usize;
+    let count = bits(word1, 18, 7) as usize + 1;
+
+    let name = match bits(word1, 26, 4) {
+        8 => "ALU",
+        9 => "ALU_PUSH_BEFORE",
+        10 => "ALU_POP_AFTER",
+        11 => "ALU_POP2_AFTER",
+        12 => "ALU_EXT",
+        13 => "ALU_CONTINUE",
+        14 => "ALU_BREAK",
+        _ => "ALU_ELSE_AFTER",
+    };
+    write!(text, "{cf_index:02} {name}: ADDR({addr}) CNT({count})").unwrap();
+
+    let kcache = [
+        (bits(word0, 22, 4), bits(word0, 30, 2), bits(word1, 2, 8)),
+        (bits(word0, 26, 4), bits(word1, 0, 2), bits(word1, 10, 8)),
+    ];
+    for (i, (bank, mode, kcache_addr)) in kcache.into_iter().enumerate() {
+        if mode != 0 {
+            // Each locked cache line contains 16 constants.
+            let start = kcache_addr * 16;
+            let end = if mode == 2 { start + 31 } else { start + 15 };
+            write!(text, " KCACHE{i}(CB{bank}:{start}-{end})").unwrap();
+        }
+    }
+
+    if bits(word1, 25, 1) == 1 {
+        text.push_str(" ALT_CONST");
+    }
+    if bits(word1, 30, 1) == 1 {
+        text.push_str(" WHOLE_QUAD_MODE");
+    }
+    if bits(word1, 31, 1) == 0 {
+        text.push_str(" NO_BARRIER");
+    }
+    text.push('\n');
+
+    // Each ALU instruction is 64 bits with groups of up to 5 instructions.
+    let slot_words = |slot: usize| (word((addr + slot) * 2), word((addr + slot) * 2 + 1));
+
+    let mut slot = 0;
+    while slot < count {
+        let mut group = Vec::new();
+        while slot < count {
+            let (word0, word1) = slot_words(slot);
+            group.push((word0, word1));
+            slot += 1;
+            if bits(word0, 31, 1) == 1 {
+                break;
+            }
+        }
+
+        // Literal constants are stored in 64-bit pairs after the group.
+        let literal_count = group
+            .iter()
+            .flat_map(|(word0, word1)| alu_sources(*word0, *word1))
+            .filter(|s| s.sel == ALU_SRC_LITERAL)
+            .map(|s| s.chan as usize + 1)
+            .max()
+            .unwrap_or_default();
+        let literals: Vec<_> = (0..literal_count)
+            .map(|i| word((addr + slot) * 2 + i))
+            .collect();
+        slot += literal_count.div_ceil(2);
+
+        alu_group_text(text, &group, &literals, *inst_count);
+        *inst_count += 1;
+    }
+}
+
+const ALU_SRC_LITERAL: u32 = 253;
+
+struct AluSource {
+    sel: u32,
+    rel: bool,
+    chan: u32,
+    neg: bool,
+    abs: bool,
+}
+
+fn is_op3(word1: u32) -> bool {
+    bits(word1, 15, 3) != 0
+}
+
+fn alu_sources(word0: u32, word1: u32) -> Vec<AluSource> {
+    let src0 = AluSource {
+        sel: bits(word0, 0, 9),
+        rel: bits(word0, 9, 1) == 1,
+        chan: bits(word0, 10, 2),
+        neg: bits(word0, 12, 1) == 1,
+        abs: false,
+    };
+    let src1 = AluSource {
+        sel: bits(word0, 13, 9),
+        rel: bits(word0, 22, 1) == 1,
+        chan: bits(word0, 23, 2),
+        neg: bits(word0, 25, 1) == 1,
+        abs: false,
+    };
+
+    if is_op3(word1) {
+        let src2 = AluSource {
+            sel: bits(word1, 0, 9),
+            rel: bits(word1, 9, 1) == 1,
+            chan: bits(word1, 10, 2),
+            neg: bits(word1, 12, 1) == 1,
+            abs: false,
+        };
+        vec![src0, src1, src2]
+    } else {
+        let name = op2_name(bits(word1, 7, 11));
+        let src0 = AluSource {
+            abs: bits(word1, 0, 1) == 1,
+            ..src0
+        };
+        let src1 = AluSource {
+            abs: bits(word1, 1, 1) == 1,
+            ..src1
+        };
+        // Unknown instructions include all sources to preserve the raw encoding.
+        match name.map(op2_source_count).unwrap_or(2) {
+            0 => Vec::new(),
+            1 => vec![src0],
+            _ => vec![src0, src1],
+        }
+    }
+}
+
+fn alu_group_text(text: &mut String, group: &[(u32, u32)], literals: &[u32], inst_count: usize) {
+    let mut used_units = [false; 5];
+
+    for (i, (word0, word1)) in group.iter().enumerate() {
+        let base_name = if is_op3(*word1) {
+            let inst = bits(*word1, 13, 5);
+            op3_name(inst).map_or_else(|| format!("UNKNOWN_OP3_{inst}"), str::to_string)
+        } else {
+            let inst = bits(*word1, 7, 11);
+            op2_name(inst).map_or_else(|| format!("UNKNOWN_OP2_{inst}"), str::to_string)
+        };
+
+        let (name, properties) = if is_op3(*word1) {
+            (base_name.clone(), String::new())
+        } else {
+            let mut name = base_name.clone();
+            match bits(*word1, 5, 2) {
+                1 => name.push_str("*2"),
+                2 => name.push_str("*4"),
+                3 => name.push_str("/2"),
+                _ => (),
+            }
+
+            let mut properties = String::new();
+            if bits(*word1, 2, 1) == 1 {
+                properties.push_str(" UPDATE_EXEC_MASK");
+            }
+            if bits(*word1, 3, 1) == 1 {
+                properties.push_str(" UPDATE_PRED");
+            }
+            (name, properties)
+        };
+
+        // Instructions use the vector unit for their output channel if possible.
+        let dst_chan = bits(*word1, 29, 2) as usize;
+        let unit = if is_trans_only(&base_name) || used_units[dst_chan] {
+            4
+        } else {
+            dst_chan
+        };
+        used_units[unit] = true;
+        let unit_char = ['x', 'y', 'z', 'w', 't'][unit];
+
+        let index_mode = bits(*word0, 26, 3);
+
+        // OP3 instructions always write their output.
+        let write_mask = is_op3(*word1) || bits(*word1, 4, 1) == 1;
+        let dst = if write_mask {
+            let rel = if bits(*word1, 28, 1) == 1 {
+                rel_text(index_mode)
+            } else {
+                ""
+            };
+            format!(
+                "R{}{rel}.{}",
+                bits(*word1, 21, 7),
+                sel_char(dst_chan as u32)
+            )
+        } else {
+            "____".to_string()
+        };
+
+        let mut operands = dst;
+        for source in alu_sources(*word0, *word1) {
+            operands.push_str(", ");
+            operands.push_str(&alu_src_text(&source, index_mode, literals, inst_count));
+        }
+
+        if i == 0 {
+            write!(text, "  {inst_count:<4}").unwrap();
+        } else {
+            text.push_str("      ");
+        }
+        write!(text, "{unit_char}: {name:<15} {operands}").unwrap();
+
+        let bank_swizzle = bits(*word1, 18, 3);
+        if bank_swizzle != 0 {
+            let names = if unit == 4 {
+                ["SCL_210", "SCL_122", "SCL_212", "SCL_221", "", "", "", ""]
+            } else {
+                [
+                    "VEC_012", "VEC_021", "VEC_120", "VEC_102", "VEC_201", "VEC_210", "", "",
+                ]
+            };
+            let name = names[bank_swizzle as usize];
+            if !name.is_empty() {
+                write!(text, " {name}").unwrap();
+            }
+        }
+
+        text.push_str(&properties);
+
+        match bits(*word0, 29, 2) {
+            1 => text.push_str(" PRED_SEL_ZERO"),
+            2 => text.push_str(" PRED_SEL_ONE"),
+            _ => (),
+        }
+
+        if bits(*word1, 31, 1) == 1 {
+            text.push_str(" CLAMP");
+        }
+
+        text.push('\n');
+    }
+}
+
+fn rel_text(index_mode: u32) -> &'static str {
+    match index_mode {
+        0 => "[AR.x]",
+        1 => "[AR.y]",
+        2 => "[AR.z]",
+        3 => "[AR.w]",
+        _ => "[AL]",
+    }
+}
+
+fn alu_src_text(
+    source: &AluSource,
+    index_mode: u32,
+    literals: &[u32],
+    inst_count: usize,
+) -> String {
+    let chan = sel_char(source.chan);
+    let rel = if source.rel { rel_text(index_mode) } else { "" };
+    let previous = inst_count.saturating_sub(1);
+
+    // Registers and constants use a relative index and channel.
+    let (value, indexed) = match source.sel {
+        0..=127 => (format!("R{}", source.sel), true),
+        128..=159 => (format!("KC0[{}]", source.sel - 128), true),
+        160..=191 => (format!("KC1[{}]", source.sel - 160), true),
+        248 => ("0.0f".to_string(), false),
+        249 => ("1.0f".to_string(), false),
+        250 => ("1".to_string(), false),
+        251 => ("-1".to_string(), false),
+        252 => ("0.5f".to_string(), false),
+        ALU_SRC_LITERAL => {
+            let value = literals
+                .get(source.chan as usize)
+                .copied()
+                .unwrap_or_default();
+            let float = f32::from_bits(value);
+            if float.is_finite() {
+                (format!("(0x{value:08X}, {float})"), false)
+            } else {
+                // NaN and infinity only have a hex representation.
+                (format!("0x{value:08X}"), false)
+            }
+        }
+        254 => (format!("PV{previous}.{chan}"), false),
+        255 => (format!("PS{previous}"), false),
+        256..=511 => (format!("C{}", source.sel - 256), true),
+        // Reserved selects aren't used by valid shaders.
+        _ => (format!("UNKNOWN_SRC_{}", source.sel), false),
+    };
+
+    let value = match (indexed, source.abs) {
+        (true, true) if source.rel => format!("|{value}|{rel}.{chan}"),
+        (true, true) => format!("|{value}.{chan}|"),
+        (true, false) => format!("{value}{rel}.{chan}"),
+        (false, true) => format!("|{value}|"),
+        (false, false) => value,
+    };
+
+    if source.neg {
+        format!("-{value}")
+    } else {
+        value
+    }
+}
+
+fn is_trans_only(name: &str) -> bool {
+    matches!(
+        name,
+        "EXP_IEEE"
+            | "LOG_CLAMPED"
+            | "LOG_IEEE"
+            | "RECIP_CLAMPED"
+            | "RECIP_FF"
+            | "RECIP_IEEE"
+            | "RECIPSQRT_CLAMPED"
+            | "RECIPSQRT_FF"
+            | "RECIPSQRT_IEEE"
+            | "SQRT_IEEE"
+            | "FLT_TO_INT"
+            | "INT_TO_FLT"
+            | "UINT_TO_FLT"
+            | "FLT_TO_UINT"
+            | "SIN"
+            | "COS"
+            | "MULLO_INT"
+            | "MULHI_INT"
+            | "MULLO_UINT"
+            | "MULHI_UINT"
+            | "RECIP_INT"
+            | "RECIP_UINT"
+    )
+}
+
+fn op2_source_count(name: &str) -> usize {
+    match name {
+        "NOP" => 0,
+        "FLT64_TO_FLT32" | "FLT32_TO_FLT64" | "FRACT" | "TRUNC" | "CEIL" | "RNDNE" | "FLOOR"
+        | "MOVA_FLOOR" | "MOVA_INT" | "MOVA" | "MOV" | "EXP_IEEE" | "LOG_CLAMPED" | "LOG_IEEE"
+        | "RECIP_CLAMPED" | "RECIP_IEEE" | "RECIP_UINT" | "RECIP_INT" | "RECIP_FF"
+        | "RECIPSQRT_CLAMPED" | "RECIPSQRT_IEEE" | "RECIPSQRT_FF" | "SQRT_IEEE" | "FLT_TO_INT"
+        | "INT_TO_FLT" | "UINT_TO_FLT" | "FLT_TO_UINT" | "SIN" | "COS" | "FRACT_64" => 1,
+        _ => 2,
+    }
+}
+
+fn op2_name(inst: u32) -> Option<&'static str> {
+    let name = match inst {
+        0x00 => "ADD",
+        0x01 => "MUL",
+        0x02 => "MUL_IEEE",
+        0x03 => "MAX",
+        0x04 => "MIN",
+        0x05 => "MAX_DX10",
+        0x06 => "MIN_DX10",
+        0x08 => "SETE",
+        0x09 => "SETGT",
+        0x0A => "SETGE",
+        0x0B => "SETNE",
+        0x0C => "SETE_DX10",
+        0x0D => "SETGT_DX10",
+        0x0E => "SETGE_DX10",
+        0x0F => "SETNE_DX10",
+        0x10 => "FRACT",
+        0x11 => "TRUNC",
+        0x12 => "CEIL",
+        0x13 => "RNDNE",
+        0x14 => "FLOOR",
+        0x15 => "MOVA",
+        0x16 => "MOVA_FLOOR",
+        0x17 => "ADD_64",
+        0x18 => "MOVA_INT",
+        0x19 => "MOV",
+        0x1A => "NOP",
+        0x1B => "MUL_64",
+        0x1C => "FLT64_TO_FLT32",
+        0x1D => "FLT32_TO_FLT64",
+        0x1E => "PRED_SETGT_UINT",
+        0x1F => "PRED_SETGE_UINT",
+        0x20 => "PRED_SETE",
+        0x21 => "PRED_SETGT",
+        0x22 => "PRED_SETGE",
+        0x23 => "PRED_SETNE",
+        0x24 => "PRED_SET_INV",
+        0x25 => "PRED_SET_POP",
+        0x26 => "PRED_SET_CLR",
+        0x27 => "PRED_SET_RESTORE",
+        0x28 => "PRED_SETE_PUSH",
+        0x29 => "PRED_SETGT_PUSH",
+        0x2A => "PRED_SETGE_PUSH",
+        0x2B => "PRED_SETNE_PUSH",
+        0x2C => "KILLE",
+        0x2D => "KILLGT",
+        0x2E => "KILLGE",
+        0x2F => "KILLNE",
+        0x30 => "AND_INT",
+        0x31 => "OR_INT",
+        0x32 => "XOR_INT",
+        0x33 => "NOT_INT",
+        0x34 => "ADD_INT",
+        0x35 => "SUB_INT",
+        0x36 => "MAX_INT",
+        0x37 => "MIN_INT",
+        0x38 => "MAX_UINT",
+        0x39 => "MIN_UINT",
+        0x3A => "SETE_INT",
+        0x3B => "SETGT_INT",
+        0x3C => "SETGE_INT",
+        0x3D => "SETNE_INT",
+        0x3E => "SETGT_UINT",
+        0x3F => "SETGE_UINT",
+        0x40 => "KILLGT_UINT",
+        0x41 => "KILLGE_UINT",
+        0x42 => "PRED_SETE_INT",
+        0x43 => "PRED_SETGT_INT",
+        0x44 => "PRED_SETGE_INT",
+        0x45 => "PRED_SETNE_INT",
+        0x46 => "KILLE_INT",
+        0x47 => "KILLGT_INT",
+        0x48 => "KILLGE_INT",
+        0x49 => "KILLNE_INT",
+        0x4A => "PRED_SETE_PUSH_INT",
+        0x4B => "PRED_SETGT_PUSH_INT",
+        0x4C => "PRED_SETGE_PUSH_INT",
+        0x4D => "PRED_SETNE_PUSH_INT",
+        0x4E => "PRED_SETLT_PUSH_INT",
+        0x4F => "PRED_SETLE_PUSH_INT",
+        0x50 => "DOT4",
+        0x51 => "DOT4_IEEE",
+        0x52 => "CUBE",
+        0x53 => "MAX4",
+        0x60 => "MOVA_GPR_INT",
+        0x61 => "EXP_IEEE",
+        0x62 => "LOG_CLAMPED",
+        0x63 => "LOG_IEEE",
+        0x64 => "RECIP_CLAMPED",
+        0x65 => "RECIP_FF",
+        0x66 => "RECIP_IEEE",
+        0x67 => "RECIPSQRT_CLAMPED",
+        0x68 => "RECIPSQRT_FF",
+        0x69 => "RECIPSQRT_IEEE",
+        0x6A => "SQRT_IEEE",
+        0x6B => "FLT_TO_INT",
+        0x6C => "INT_TO_FLT",
+        0x6D => "UINT_TO_FLT",
+        0x6E => "SIN",
+        0x6F => "COS",
+        0x70 => "ASHR_INT",
+        0x71 => "LSHR_INT",
+        0x72 => "LSHL_INT",
+        0x73 => "MULLO_INT",
+        0x74 => "MULHI_INT",
+        0x75 => "MULLO_UINT",
+        0x76 => "MULHI_UINT",
+        0x77 => "RECIP_INT",
+        0x78 => "RECIP_UINT",
+        0x79 => "FLT_TO_UINT",
+        0x7A => "LDEXP_64",
+        0x7B => "FRACT_64",
+        0x7C => "PRED_SETGT_64",
+        0x7D => "PRED_SETE_64",
+        0x7E => "PRED_SETGE_64",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn op3_name(inst: u32) -> Option<&'static str> {
+    let name = match inst {
+        0x08 => "MULADD_64",
+        0x09 => "MULADD_64_M2",
+        0x0A => "MULADD_64_M4",
+        0x0B => "MULADD_64_D2",
+        0x0C => "MUL_LIT",
+        0x0D => "MUL_LIT_M2",
+        0x0E => "MUL_LIT_M4",
+        0x0F => "MUL_LIT_D2",
+        0x10 => "MULADD",
+        0x11 => "MULADD_M2",
+        0x12 => "MULADD_M4",
+        0x13 => "MULADD_D2",
+        0x14 => "MULADD_IEEE",
+        0x15 => "MULADD_IEEE_M2",
+        0x16 => "MULADD_IEEE_M4",
+        0x17 => "MULADD_IEEE_D2",
+        0x18 => "CNDE",
+        0x19 => "CNDGT",
+        0x1A => "CNDGE",
+        0x1C => "CNDE_INT",
+        0x1D => "CNDGT_INT",
+        0x1E => "CNDGE_INT",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn program_bytes(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn disassemble_tex_alu_exp() {
+        let binary = program_bytes(&[
+            // 00 TEX: ADDR(8) CNT(1)
+            8,
+            (1 << 23) | (1 << 31),
+            // 01 ALU: ADDR(4) CNT(3) KCACHE0(CB1:0-15)
+            4 | (1 << 22) | (1 << 30),
+            (2 << 18) | (8 << 26) | (1 << 31),
+            // 02 EXP_DONE: PIX0, R1.xyzw
+            1 << 15,
+            (1 << 3) | (2 << 6) | (3 << 9) | (1 << 21) | (40 << 23) | (1 << 31),
+            0,
+            0,
+            // MUL R1.x, R0.x, KC0[0].x
+            128 << 13,
+            (1 << 4) | (0x01 << 7) | (1 << 21),
+            // MOV R1.y, (0x40000000, 2)
+            ALU_SRC_LITERAL | (1 << 31),
+            (1 << 4) | (0x19 << 7) | (1 << 21) | (1 << 29),
+            0x40000000,
+            0,
+            0,
+            0,
+            // SAMPLE R0.xyzw, R0.xy00, t0, s0
+            16,
+            (1 << 12) | (2 << 15) | (3 << 18) | (0xF << 28),
+            (1 << 23) | (4 << 26) | (4 << 29),
+            0,
+        ]);
+
+        let expected = [
+            "00 TEX: ADDR(8) CNT(1)",
+            "",
+            "  0      SAMPLE          R0.xyzw, R0.xy00, t0, s0",
+            "",
+            "01 ALU: ADDR(4) CNT(3) KCACHE0(CB1:0-15)",
+            "  1   x: MUL             R1.x, R0.x, KC0[0].x",
+            "      y: MOV             R1.y, (0x40000000, 2)",
+            "",
+            "02 EXP_DONE: PIX0, R1.xyzw",
+            "END_OF_PROGRAM",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(expected, disassemble(&binary));
+    }
+    #[test]
+    fn disassemble_unknown() {
+        let binary = program_bytes(&[
+            // 00 UNKNOWN_CF_25
+            0,
+            25 << 23,
+            // 01 ALU: ADDR(3) CNT(2)
+            3,
+            (1 << 18) | (8 << 26) | (1 << 31),
+            // 02 UNKNOWN_CF_41 for a memory export
+            3 << 13,
+            (1 << 28) | (1 << 21) | (41 << 23),
+            // UNKNOWN_OP2_7 R1.x, R0.x, R2.y
+            (2 << 13) | (1 << 23),
+            (1 << 4) | (0x07 << 7) | (1 << 21),
+            // UNKNOWN_OP3_31 R1.y, R0.x, R0.x, UNKNOWN_SRC_200
+            1 << 31,
+            200 | (0x1F << 13) | (1 << 21) | (1 << 29),
+        ]);
+
+        let expected = [
+            "00 UNKNOWN_CF_25 NO_BARRIER",
+            "01 ALU: ADDR(3) CNT(2)",
+            "  0   x: UNKNOWN_OP2_7   R1.x, R0.x, R2.y",
+            "      y: UNKNOWN_OP3_31  R1.y, R0.x, R0.x, UNKNOWN_SRC_200",
+            "",
+            "02 UNKNOWN_CF_41 NO_BARRIER",
+            "END_OF_PROGRAM",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(expected, disassemble(&binary));
+    }
+}
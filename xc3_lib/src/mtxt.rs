@@ -118,12 +118,12 @@ pub enum TileMode {
 #[derive(Debug, Error)]
 pub enum CreateMtxtError {
     #[error("error swizzling surface")]
-    SwizzleError(#[from] tegra_swizzle::SwizzleError),
+    SwizzleError(#[from] SwizzleError),
 
     #[error("error creating surface from DDS")]
     DdsError(#[from] image_dds::error::SurfaceError),
 
-    #[error("image format {0:?} is not supported by Mibl")]
+    #[error("image format {0:?} is not supported by Mtxt")]
     UnsupportedImageFormat(image_dds::ImageFormat),
 }
 
@@ -210,33 +210,98 @@ impl Mtxt {
     ///
     /// Returns an error if the conversion fails or the image format is not supported.
     pub fn from_surface<T: AsRef<[u8]>>(surface: Surface<T>) -> Result<Self, CreateMtxtError> {
-        let surface_format = surface.image_format.try_into()?;
+        let surface_format: SurfaceFormat = surface.image_format.try_into()?;
+        let (block_width, block_height) = surface_format.block_dim();
+        let bytes_per_pixel = surface_format.bytes_per_pixel();
+        let alignment = surface_format.bytes_per_pixel() * 512;
 
-        // TODO: How to set these values?
         // Assume either depth or layers are used but not both.
+        let surface_dim = if surface.layers == 6 {
+            SurfaceDim::Cube
+        } else if surface.depth > 1 {
+            SurfaceDim::D3
+        } else {
+            SurfaceDim::D2
+        };
+        let depth_or_array_layers = surface.depth.max(surface.layers);
+
+        // Micro tiling has simpler pitch and alignment requirements than macro tiling.
+        let tile_mode = TileMode::D1TiledThin1;
+
+        let mut image_data = Vec::new();
+        let mut mip_offsets = Vec::new();
+        let mut pitch = 0;
+        for mip in 0..surface.mipmaps {
+            let width = (surface.width >> mip).div_ceil(block_width);
+            let height = (surface.height >> mip).div_ceil(block_height);
+
+            // Match the pitch calculated by addrlib for micro tiled surfaces.
+            let mip_pitch = if mip == 0 {
+                width
+            } else {
+                width.next_power_of_two()
+            }
+            .next_multiple_of(8);
+            if mip == 0 {
+                pitch = mip_pitch;
+            }
+
+            // Swizzle all layers for each mipmap together.
+            // TODO: Handle mipmaps for 3D textures.
+            let mut mip_data = Vec::new();
+            for layer in 0..surface.layers {
+                for depth_level in 0..(surface.depth >> mip).max(1) {
+                    if let Some(data) = surface.get(layer, depth_level, mip) {
+                        mip_data.extend_from_slice(data);
+                    }
+                }
+            }
+
+            let swizzled = swizzle_mipmap(
+                width,
+                height,
+                depth_or_array_layers,
+                &mip_data,
+                mip_pitch.min(pitch),
+                tile_mode,
+                bytes_per_pixel,
+            )?;
+
+            mip_offsets.push(image_data.len() as u32);
+            image_data.extend_from_slice(&swizzled);
+            image_data.resize(image_data.len().next_multiple_of(alignment as usize), 0);
+        }
+
+        // The base level size is also the offset for mip 1.
+        let size = mip_offsets
+            .get(1)
+            .copied()
+            .unwrap_or(image_data.len() as u32);
+
+        // Offsets after mip 1 are relative to the mip 1 offset.
+        let mut mipmap_offsets = [0; 13];
+        for (i, offset) in mip_offsets.iter().skip(1).enumerate().take(13) {
+            mipmap_offsets[i] = if i == 0 { *offset } else { *offset - size };
+        }
+
+        // TODO: How to set the remaining values?
         Ok(Self {
-            image_data: Vec::new(),
+            image_data,
             footer: MtxtFooter {
                 swizzle: 0,
-                surface_dim: if surface.layers == 6 {
-                    SurfaceDim::Cube
-                } else if surface.depth > 1 {
-                    SurfaceDim::D3
-                } else {
-                    SurfaceDim::D2
-                },
+                surface_dim,
                 width: surface.width,
                 height: surface.height,
-                depth_or_array_layers: surface.depth.max(surface.layers),
+                depth_or_array_layers,
                 mipmap_count: surface.mipmaps,
                 surface_format,
-                size: 0,
+                size,
                 unk_mip_offset: 0,
-                tile_mode: TileMode::D2TiledThin1,
+                tile_mode,
                 unk1: 0,
-                alignment: surface_format.bytes_per_pixel() * 512,
-                pitch: 0,
-                mipmap_offsets: [0; 13],
+                alignment,
+                pitch,
+                mipmap_offsets,
                 version: 10002,
             },
         })
@@ -244,8 +309,60 @@ impl Mtxt {
 
     /// Deswizzles all layers and mipmaps to a Direct Draw Surface (DDS).
     pub fn to_dds(&self) -> Result<Dds, crate::dds::CreateDdsError> {
-        self.to_surface()?.to_dds().map_err(Into::into)
+        crate::dds::dds_from_surface(&self.to_surface()?).map_err(Into::into)
+    }
+}
+
+// TODO: Use wiiu_swizzle::swizzle_mipmap once it calculates the correct output size.
+fn swizzle_mipmap(
+    width: u32,
+    height: u32,
+    depth_or_array_layers: u32,
+    source: &[u8],
+    pitch: u32,
+    tile_mode: TileMode,
+    bytes_per_pixel: u32,
+) -> Result<Vec<u8>, SwizzleError> {
+    let bpp = bytes_per_pixel as usize;
+    let expected_size = (width * height * depth_or_array_layers) as usize * bpp;
+    if source.len() < expected_size {
+        return Err(SwizzleError::NotEnoughData {
+            expected_size,
+            actual_size: source.len(),
+        });
     }
+
+    // Micro tiled surfaces use 8x8 tiles for each slice.
+    let swizzled_size = (pitch * height.next_multiple_of(8) * depth_or_array_layers) as usize * bpp;
+
+    // Deswizzle the index of each block to find the swizzled position for each linear block.
+    // This ensures swizzling is always the inverse of deswizzling.
+    // All supported formats have at least 4 bytes per block to store the index.
+    let mut indices = vec![0u8; swizzled_size];
+    for (i, block) in indices.chunks_exact_mut(bpp).enumerate() {
+        block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+    }
+    let linear_indices = wiiu_swizzle::deswizzle_mipmap(
+        width,
+        height,
+        depth_or_array_layers,
+        &indices,
+        0,
+        pitch,
+        tile_mode.into(),
+        bytes_per_pixel,
+        wiiu_swizzle::AaMode::X1,
+    )?;
+
+    let mut swizzled = vec![0u8; swizzled_size];
+    for (index, block) in linear_indices
+        .chunks_exact(bpp)
+        .zip(source.chunks_exact(bpp))
+    {
+        let i = u32::from_le_bytes(index[..4].try_into().unwrap()) as usize;
+        swizzled[i * bpp..(i + 1) * bpp].copy_from_slice(block);
+    }
+    Ok(swizzled)
 }
 
 impl From<SurfaceFormat> for image_dds::ImageFormat {
@@ -323,3 +440,84 @@ impl SurfaceFormat {
 }
 
 xc3_write_binwrite_impl!(Mtxt);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba8_surface(size: u32, layers: u32, mipmaps: u32) -> Surface<Vec<u8>> {
+        let data_size: u32 = (0..mipmaps).map(|m| (size >> m).pow(2) * 4).sum::<u32>() * layers;
+        Surface {
+            width: size,
+            height: size,
+            depth: 1,
+            layers,
+            mipmaps,
+            image_format: image_dds::ImageFormat::Rgba8Unorm,
+            data: (0..data_size).map(|i| i as u8).collect(),
+        }
+    }
+
+    #[test]
+    fn rgba8_from_surface() {
+        let surface = rgba8_surface(4, 1, 1);
+        let mtxt = Mtxt::from_surface(surface.as_ref()).unwrap();
+        assert_eq!(SurfaceDim::D2, mtxt.footer.surface_dim);
+        assert_eq!(surface, mtxt.to_surface().unwrap());
+    }
+
+    #[test]
+    fn rgba8_from_surface_mipmaps() {
+        let surface = rgba8_surface(64, 1, 4);
+        let mtxt = Mtxt::from_surface(surface.as_ref()).unwrap();
+        assert_eq!(4, mtxt.footer.mipmap_count);
+        assert_eq!(surface, mtxt.to_surface().unwrap());
+    }
+
+    #[test]
+    fn rgba8_from_surface_cube() {
+        let surface = rgba8_surface(16, 6, 2);
+        let mtxt = Mtxt::from_surface(surface.as_ref()).unwrap();
+        assert_eq!(SurfaceDim::Cube, mtxt.footer.surface_dim);
+        assert_eq!(surface, mtxt.to_surface().unwrap());
+    }
+
+    #[test]
+    fn bc1_from_surface_write_read() {
+        let surface = Surface {
+            width: 16,
+            height: 16,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::BC1RgbaUnorm,
+            data: (0..128).map(|i| i as u8).collect::<Vec<_>>(),
+        };
+        let mtxt = Mtxt::from_surface(surface.as_ref()).unwrap();
+
+        let mut writer = std::io::Cursor::new(Vec::new());
+        mtxt.write(&mut writer).unwrap();
+        let new_mtxt = Mtxt::from_bytes(writer.into_inner()).unwrap();
+        assert_eq!(mtxt, new_mtxt);
+        assert_eq!(surface, new_mtxt.to_surface().unwrap());
+    }
+
+    #[test]
+    fn from_surface_unsupported_format() {
+        let surface = Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::R8Unorm,
+            data: vec![0u8; 16],
+        };
+        assert!(matches!(
+            Mtxt::from_surface(surface),
+            Err(CreateMtxtError::UnsupportedImageFormat(
+                image_dds::ImageFormat::R8Unorm
+            ))
+        ));
+    }
+}
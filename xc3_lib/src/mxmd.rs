@@ -9,7 +9,10 @@
 //! | Xenoblade Chronicles 1 DE | `chr/{en,np,obj,pc,wp}/*.wimdo`, `monolib/shader/*.wimdo` |
 //! | Xenoblade Chronicles 2 | `model/{bl,en,np,oj,pc,we,wp}/*.wimdo`, `monolib/shader/*.wimdo` |
 //! | Xenoblade Chronicles 3 | `chr/{bt,ch,en,oj,wp}/*.wimdo`, `map/*.wimdo`, `monolib/shader/*.wimdo` |
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
 use crate::{
+    mibl::{ImageFormat, Mibl},
     msrd::Streaming,
     parse_count32_offset32, parse_offset32_count32, parse_opt_ptr32, parse_ptr32,
     parse_string_opt_ptr32, parse_string_ptr32,
@@ -18,12 +21,12 @@ use crate::{
     xc3_write_binwrite_impl, StringOffset32,
 };
 use bilge::prelude::*;
-use binrw::{args, binread, BinRead, BinWrite};
+use binrw::{args, binread, BinRead, BinReaderExt, BinResult, BinWrite};
+use thiserror::Error;
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
 pub mod legacy;
 
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, PartialEq, Clone)]
 #[br(magic(b"DMXM"))]
 #[xc3(magic(b"DMXM"))]
@@ -82,6 +85,240 @@ pub struct Mxmd {
     pub unk: [u32; 6],
 }
 
+/// The model format version for a `.wimdo` or `.camdo` file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MxmdVersion {
+    /// Xenoblade X models using [MxmdLegacy](legacy::MxmdLegacy).
+    V40,
+    /// Xenoblade 2 models.
+    V111,
+    /// Xenoblade 1 DE and Xenoblade 3 models.
+    V112,
+}
+
+/// The version and container information from [Mxmd::peek_version].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MxmdVersionInfo {
+    pub version: MxmdVersion,
+    /// `true` if the model is the [Mxmd] entry in an [Apmd](crate::apmd::Apmd).
+    pub is_apmd: bool,
+}
+
+impl Mxmd {
+    /// Read only the header data needed to determine the model version.
+    /// This is much faster than reading the entire file.
+    ///
+    /// # Examples
+    /// ```rust no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use xc3_lib::mxmd::{Mxmd, MxmdVersion};
+    ///
+    /// let mut reader = std::io::BufReader::new(std::fs::File::open("ch01011013.wimdo")?);
+    /// let info = Mxmd::peek_version(&mut reader)?;
+    /// assert_eq!(MxmdVersion::V112, info.version);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn peek_version<R: Read + Seek>(reader: &mut R) -> BinResult<MxmdVersionInfo> {
+        let start = reader.stream_position()?;
+        let magic: [u8; 4] = reader.read_le()?;
+        if &magic == b"DMPA" {
+            // Only read the entry table to avoid reading the entry data.
+            let _version: u32 = reader.read_le()?;
+            let entries_offset: u32 = reader.read_le()?;
+            let entry_count: u32 = reader.read_le()?;
+
+            reader.seek(SeekFrom::Start(start + entries_offset as u64))?;
+            for _ in 0..entry_count {
+                let entry_type: crate::apmd::EntryType = reader.read_le()?;
+                let offset: u32 = reader.read_le()?;
+                let _size: u32 = reader.read_le()?;
+                if entry_type == crate::apmd::EntryType::Mxmd {
+                    reader.seek(SeekFrom::Start(start + offset as u64))?;
+                    return Ok(MxmdVersionInfo {
+                        version: read_mxmd_version(reader)?,
+                        is_apmd: true,
+                    });
+                }
+            }
+
+            Err(binrw::Error::AssertFail {
+                pos: start,
+                message: "missing Mxmd entry in Apmd".to_string(),
+            })
+        } else {
+            reader.seek(SeekFrom::Start(start))?;
+            Ok(MxmdVersionInfo {
+                version: read_mxmd_version(reader)?,
+                is_apmd: false,
+            })
+        }
+    }
+
+    /// The index into the programs in the embedded [spch](#structfield.spch)
+    /// used by the material at `material_index`.
+    ///
+    /// Returns [None] if the material does not exist,
+    /// the material has no techniques, or the program index is out of range.
+    /// Models with a .wismt file store the [Spch] in the [Msrd](crate::msrd::Msrd) instead.
+    pub fn material_program_index(&self, material_index: usize) -> Option<usize> {
+        let spch = self.spch.as_ref()?;
+        let material = self.materials.materials.get(material_index)?;
+        let program_index = material.techniques.first()?.technique_index as usize;
+        (program_index < spch.slct_offsets.len()).then_some(program_index)
+    }
+
+    /// Replace the image data for the texture in [packed_textures](#structfield.packed_textures)
+    /// with the given `name`.
+    ///
+    /// Returns an error if no packed texture has the given `name`
+    /// or `mibl` does not have the same image format as the original texture.
+    ///
+    /// Models with a .wismt file typically have no packed textures
+    /// and store their textures in the [Msrd](crate::msrd::Msrd) instead.
+    /// This returns [ReplacePackedTextureError::NoPackedTextures] for these models,
+    /// so textures should be replaced in the extracted files from the [Msrd](crate::msrd::Msrd).
+    pub fn replace_packed_texture(
+        &mut self,
+        name: &str,
+        mibl: &Mibl,
+    ) -> Result<(), ReplacePackedTextureError> {
+        self.packed_textures
+            .as_mut()
+            .ok_or(ReplacePackedTextureError::NoPackedTextures)?
+            .replace_texture(name, mibl)
+    }
+}
+
+impl PackedTextures {
+    fn replace_texture(
+        &mut self,
+        name: &str,
+        mibl: &Mibl,
+    ) -> Result<(), ReplacePackedTextureError> {
+        let texture = self
+            .textures
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| ReplacePackedTextureError::MissingTexture(name.to_string()))?;
+
+        let format = Mibl::from_bytes(&texture.mibl_data)?.footer.image_format;
+        if mibl.footer.image_format != format {
+            return Err(ReplacePackedTextureError::MismatchedFormat {
+                expected: format,
+                actual: mibl.footer.image_format,
+            });
+        }
+
+        let mut writer = Cursor::new(Vec::new());
+        mibl.write(&mut writer)?;
+        texture.mibl_data = writer.into_inner();
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ReplacePackedTextureError {
+    #[error("model has no packed textures")]
+    NoPackedTextures,
+
+    #[error("no packed texture with name {0:?}")]
+    MissingTexture(String),
+
+    #[error("image format {actual:?} does not match the original format {expected:?}")]
+    MismatchedFormat {
+        expected: ImageFormat,
+        actual: ImageFormat,
+    },
+
+    #[error("error reading original texture")]
+    Binrw(#[from] binrw::Error),
+
+    #[error("error writing texture")]
+    Io(#[from] std::io::Error),
+}
+
+fn read_mxmd_version<R: Read + Seek>(reader: &mut R) -> BinResult<MxmdVersion> {
+    let pos = reader.stream_position()?;
+    let magic: [u8; 4] = reader.read_le()?;
+    let version: u32 = match &magic {
+        b"DMXM" => reader.read_le()?,
+        // Xenoblade X files are big endian.
+        b"MXMD" => reader.read_be()?,
+        _ => {
+            return Err(binrw::Error::BadMagic {
+                pos,
+                found: Box::new(magic),
+            })
+        }
+    };
+
+    match version {
+        10040 => Ok(MxmdVersion::V40),
+        10111 => Ok(MxmdVersion::V111),
+        10112 => Ok(MxmdVersion::V112),
+        _ => Err(binrw::Error::AssertFail {
+            pos,
+            message: format!("unsupported Mxmd version {version}"),
+        }),
+    }
+}
+
+// The extra model data depends on the version,
+// so generate both together to produce values that can be written and read again.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Mxmd {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let version = *u.choose(&[10111, 10112])?;
+
+        let mut models: Models = u.arbitrary()?;
+        if version == 10111 {
+            models.models_flags = None;
+            models.extra = None;
+        } else {
+            if models.models_flags.is_none() {
+                models.models_flags = Some(u.arbitrary()?);
+            }
+            // The header size determines the variant, so the padding must be present.
+            match &mut models.extra {
+                None => models.extra = Some(ModelsExtraData::Unk1),
+                Some(ModelsExtraData::Unk4(extra)) if extra.unk.is_none() => {
+                    extra.unk = Some(u.arbitrary()?)
+                }
+                Some(ModelsExtraData::Unk5(extra)) if extra.unk.is_none() => {
+                    extra.unk = Some(u.arbitrary()?)
+                }
+                _ => (),
+            }
+        }
+
+        // The fur shell indices are inferred from the material count.
+        let mut materials: Materials = u.arbitrary()?;
+        if let Some(fur_shells) = &mut materials.fur_shells {
+            fur_shells.material_param_indices = (0..materials.materials.len())
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?;
+        }
+
+        Ok(Self {
+            version,
+            models,
+            materials,
+            unk1: u.arbitrary()?,
+            vertex_data: u.arbitrary()?,
+            spch: u.arbitrary()?,
+            packed_textures: u.arbitrary()?,
+            unk5: u.arbitrary()?,
+            streaming: u.arbitrary()?,
+            unk6: u.arbitrary()?,
+            unk7: u.arbitrary()?,
+            unk8: u.arbitrary()?,
+            unk: u.arbitrary()?,
+        })
+    }
+}
+
 // TODO: more strict alignment for xc3?
 // TODO: 108 bytes for xc2 and 112 bytes for xc3?
 /// A collection of [Material], [Sampler], and material parameters.
@@ -950,7 +1187,6 @@ pub struct Mesh {
 // TODO: remaining bits affect skinning?
 /// Flags to determine how to draw a [Mesh].
 #[bitsize(32)]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(DebugBits, TryFromBits, BinRead, BinWrite, PartialEq, Clone, Copy)]
 #[br(try_map = |x: u32| x.try_into().map_err(|e| format!("{e:?}")))]
 #[bw(map = |&x| u32::from(x))]
@@ -960,6 +1196,16 @@ pub struct MeshRenderFlags2 {
     pub unk5: u28,
 }
 
+// Generate the fields separately to avoid invalid render pass bits.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for MeshRenderFlags2 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let render_pass = u.arbitrary()?;
+        let unk5 = u28::new(u.int_in_range(0..=(1 << 28) - 1)?);
+        Ok(Self::new(render_pass, unk5))
+    }
+}
+
 // TODO: 16 also draws in the first pass but earlier?
 // TODO: Also depends on technique type?
 /// The render pass for this draw call.
@@ -1130,7 +1376,6 @@ pub struct ModelUnk3Item {
 }
 
 #[binread]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
@@ -1154,6 +1399,22 @@ pub struct Unk8 {
     pub unk: [u32; 4],
 }
 
+// The unk3 count is inferred from unk2.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Unk8 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let unk2: Vec<Unk8Item> = u.arbitrary()?;
+        Ok(Self {
+            unk1: u.arbitrary()?,
+            unk3: (0..unk2.len())
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?,
+            unk2,
+            unk: u.arbitrary()?,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
@@ -1187,6 +1448,40 @@ pub struct AlphaTable {
     pub unks: [u32; 4],
 }
 
+impl AlphaTable {
+    /// The `(ext_mesh_index, lod_item1_index)` for the item at `index`.
+    /// Values of `0` in [items](#structfield.items) are converted to [None].
+    pub fn get(&self, index: usize) -> Option<(Option<usize>, Option<usize>)> {
+        let index_value = |i: u16| i.checked_sub(1).map(usize::from);
+        self.items
+            .get(index)
+            .map(|(ext_mesh, lod_item)| (index_value(*ext_mesh), index_value(*lod_item)))
+    }
+
+    /// The index of the first item for `ext_mesh_index` and `lod_item_index`.
+    /// Adds a new item if no item matches.
+    ///
+    /// Returns an error if either index does not fit in the table's 1-based `u16` values.
+    pub fn insert(
+        &mut self,
+        ext_mesh_index: Option<usize>,
+        lod_item_index: Option<usize>,
+    ) -> Result<usize, std::num::TryFromIntError> {
+        let index_value = |i: Option<usize>| match i {
+            Some(i) => u16::try_from(i + 1),
+            None => Ok(0),
+        };
+        let item = (index_value(ext_mesh_index)?, index_value(lod_item_index)?);
+        match self.items.iter().position(|i| *i == item) {
+            Some(index) => Ok(index),
+            None => {
+                self.items.push(item);
+                Ok(self.items.len() - 1)
+            }
+        }
+    }
+}
+
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
@@ -1322,7 +1617,6 @@ pub struct ModelUnk11 {
 
 // TODO: Some sort of float animation for eyes, morphs, etc?
 #[binread]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
 #[br(stream = r)]
 #[br(import_raw(next_offset: u32))]
@@ -1365,6 +1659,45 @@ pub struct ModelUnk1 {
     pub extra: Option<ModelUnk1Extra>,
 }
 
+// The item counts and presence of extra data depend on other fields,
+// so generate all fields together to produce values that can be written and read again.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ModelUnk1 {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let items1: Vec<ModelUnk1Item1> = u.arbitrary()?;
+        let items3 = (0..items1.len())
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?;
+        let items2 = u.arbitrary()?;
+        let unk1 = u.arbitrary()?;
+        let items4 = u.arbitrary()?;
+
+        // The inner items2 count is inferred from the offset of the following data,
+        // which isn't known until writing, so skip generating the inner table.
+        let extra = u
+            .arbitrary::<Option<[u32; 4]>>()?
+            .map(|unk| ModelUnk1Extra {
+                unk_inner: None,
+                unk,
+            });
+        let (unk4, unk5) = match extra {
+            Some(_) => (u.int_in_range(1..=u32::MAX)?, u.arbitrary()?),
+            None => (0, 0),
+        };
+
+        Ok(Self {
+            items1,
+            items2,
+            items3,
+            unk1,
+            items4,
+            unk4,
+            unk5,
+            extra,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import { base_offset: u64, next_offset: u32 })]
@@ -1617,7 +1950,6 @@ pub enum TextureUsage {
 // xc3: 52, 60 bytes
 /// Information for the skinned bones used by this model.
 #[binread]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
@@ -1686,6 +2018,75 @@ pub struct Skinning {
     pub unk: Option<[u32; 4]>,
 }
 
+// The counts and optional fields depend on the bones,
+// so generate all fields together to produce values that can be written and read again.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Skinning {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut bones: Vec<Bone> = u.arbitrary()?;
+        // Limit the indices to avoid generating huge constraint and bounds lists.
+        for bone in &mut bones {
+            bone.constraint_index %= 16;
+            bone.bounds_index %= 16;
+        }
+
+        let inverse_bind_transforms = (0..bones.len())
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?;
+
+        let constraints = match count_constraints(&bones) {
+            0 => None,
+            count => Some(
+                (0..count)
+                    .map(|_| u.arbitrary())
+                    .collect::<arbitrary::Result<_>>()?,
+            ),
+        };
+        let bounds = match count_bounds(&bones) {
+            0 => None,
+            count => Some(
+                (0..count)
+                    .map(|_| u.arbitrary())
+                    .collect::<arbitrary::Result<_>>()?,
+            ),
+        };
+        let bone_indices: Vec<u16> = u.arbitrary()?;
+
+        let unk_offset4 = match constraints {
+            Some(_) => Some(u.arbitrary()?),
+            None => None,
+        };
+        let unk_offset5 = match bounds {
+            Some(_) => Some(u.arbitrary()?),
+            None => None,
+        };
+        let as_bone_data = match bone_indices.is_empty() {
+            false => Some(u.arbitrary()?),
+            true => None,
+        };
+
+        // The padding is only present if the header has every optional field.
+        let unk = match (&unk_offset4, &unk_offset5, &as_bone_data) {
+            (Some(_), Some(_), Some(_)) => u.arbitrary()?,
+            _ => None,
+        };
+
+        Ok(Self {
+            render_bone_count: u.arbitrary()?,
+            bone_count: bones.len() as u32,
+            bones,
+            inverse_bind_transforms,
+            constraints,
+            bounds,
+            bone_indices,
+            unk_offset4,
+            unk_offset5,
+            as_bone_data,
+            unk,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
@@ -1764,7 +2165,6 @@ pub struct BoneFlags {
     pub unk: u12,
 }
 
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct UnkBones {
@@ -1779,6 +2179,20 @@ pub struct UnkBones {
     // TODO: no padding?
 }
 
+// The unk_offset count is inferred from the bones.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for UnkBones {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bones: Vec<UnkBone> = u.arbitrary()?;
+        Ok(Self {
+            unk_offset: (0..bones.len())
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?,
+            bones,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct UnkBone {
@@ -1842,7 +2256,6 @@ pub struct SkeletonUnk5Unk1Unk3 {
 }
 
 // TODO: Data for AS_ bones?
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct AsBoneData {
@@ -1865,6 +2278,23 @@ pub struct AsBoneData {
     pub unk: [u32; 2],
 }
 
+// The transforms count is inferred from the bones.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for AsBoneData {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let bones: Vec<AsBone> = u.arbitrary()?;
+        Ok(Self {
+            values: u.arbitrary()?,
+            transforms: (0..bones.len())
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?,
+            bones,
+            unk3: u.arbitrary()?,
+            unk: u.arbitrary()?,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct AsBone {
@@ -2260,6 +2690,8 @@ impl Xc3WriteOffsets for MxmdOffsets<'_> {
             .write_full(writer, base_offset, data_ptr, endian, ())?;
 
         // Apply padding even if this is the end of the file.
+        // The writer may still be positioned at an earlier offset field.
+        writer.seek(std::io::SeekFrom::Start(*data_ptr))?;
         vec![0u8; (data_ptr.next_multiple_of(16) - *data_ptr) as usize]
             .xc3_write(writer, endian)?;
         *data_ptr = (*data_ptr).max(writer.stream_position()?);
@@ -2493,3 +2925,193 @@ fn count_bounds(bones: &[Bone]) -> usize {
         .max()
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packed_texture(name: &str, format: image_dds::ImageFormat) -> PackedTexture {
+        let mibl = Mibl::from_surface(image_dds::Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: format,
+            // A single 4x4 block.
+            data: vec![
+                0u8;
+                if format == image_dds::ImageFormat::BC1RgbaUnorm {
+                    8
+                } else {
+                    16
+                }
+            ],
+        })
+        .unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        mibl.write(&mut writer).unwrap();
+
+        PackedTexture {
+            usage: TextureUsage::Col,
+            mibl_data: writer.into_inner(),
+            name: name.to_string(),
+        }
+    }
+
+    fn packed_textures() -> PackedTextures {
+        PackedTextures {
+            textures: vec![
+                packed_texture("a", image_dds::ImageFormat::BC7RgbaUnorm),
+                packed_texture("b", image_dds::ImageFormat::BC1RgbaUnorm),
+            ],
+            unk2: 0,
+            strings_offset: 0,
+        }
+    }
+
+    #[test]
+    fn replace_packed_texture() {
+        let mut textures = packed_textures();
+        let mibl = Mibl::from_surface(image_dds::Surface {
+            width: 8,
+            height: 8,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::BC1RgbaUnorm,
+            data: vec![1u8; 32],
+        })
+        .unwrap();
+        textures.replace_texture("b", &mibl).unwrap();
+
+        let mut writer = Cursor::new(Vec::new());
+        mibl.write(&mut writer).unwrap();
+
+        let mut expected = packed_textures();
+        expected.textures[1].mibl_data = writer.into_inner();
+        assert_eq!(expected, textures);
+        assert_ne!(packed_textures(), textures);
+    }
+
+    #[test]
+    fn replace_packed_texture_missing_name() {
+        let mut textures = packed_textures();
+        let mibl = Mibl::from_bytes(&textures.textures[0].mibl_data).unwrap();
+        assert!(matches!(
+            textures.replace_texture("c", &mibl),
+            Err(ReplacePackedTextureError::MissingTexture(name)) if name == "c"
+        ));
+        assert_eq!(packed_textures(), textures);
+    }
+
+    #[test]
+    fn replace_packed_texture_mismatched_format() {
+        let mut textures = packed_textures();
+        let mibl = Mibl::from_bytes(&textures.textures[1].mibl_data).unwrap();
+        assert!(matches!(
+            textures.replace_texture("a", &mibl),
+            Err(ReplacePackedTextureError::MismatchedFormat {
+                expected: ImageFormat::BC7Unorm,
+                actual: ImageFormat::BC1Unorm
+            })
+        ));
+        assert_eq!(packed_textures(), textures);
+    }
+
+    #[test]
+    fn alpha_table_get() {
+        let table = AlphaTable {
+            items: vec![(0, 0), (1, 0), (3, 2)],
+            unks: [0; 4],
+        };
+        assert_eq!(Some((None, None)), table.get(0));
+        assert_eq!(Some((Some(0), None)), table.get(1));
+        assert_eq!(Some((Some(2), Some(1))), table.get(2));
+        assert_eq!(None, table.get(3));
+    }
+
+    #[test]
+    fn alpha_table_insert() {
+        let mut table = AlphaTable {
+            items: vec![(0, 0), (1, 0), (1, 0)],
+            unks: [1, 2, 3, 4],
+        };
+        assert_eq!(Ok(0), table.insert(None, None));
+        assert_eq!(Ok(1), table.insert(Some(0), None));
+        assert_eq!(Ok(3), table.insert(Some(2), Some(1)));
+        assert_eq!(Ok(3), table.insert(Some(2), Some(1)));
+        assert_eq!(
+            AlphaTable {
+                items: vec![(0, 0), (1, 0), (1, 0), (3, 2)],
+                unks: [1, 2, 3, 4],
+            },
+            table
+        );
+    }
+
+    #[test]
+    fn alpha_table_insert_out_of_range() {
+        let mut table = AlphaTable {
+            items: Vec::new(),
+            unks: [0; 4],
+        };
+        assert!(table.insert(Some(u16::MAX as usize), None).is_err());
+        assert!(table.insert(None, Some(u16::MAX as usize)).is_err());
+        assert_eq!(Ok(0), table.insert(Some(u16::MAX as usize - 1), None));
+        assert_eq!(vec![(u16::MAX, 0)], table.items);
+    }
+
+    // Deterministic pseudorandom bytes to drive arbitrary without extra dependencies.
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E3779B97F4A7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 56) as u8
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "arbitrary")]
+    macro_rules! arbitrary_write_read_test {
+        ($test_name:ident, $type_name:ty) => {
+            #[test]
+            fn $test_name() {
+                for seed in 0..32 {
+                    let bytes = arbitrary_bytes(seed, 4096);
+                    let mut u = arbitrary::Unstructured::new(&bytes);
+                    let value: $type_name = u.arbitrary().unwrap();
+
+                    // Null terminated strings can't contain null bytes.
+                    if format!("{value:?}").contains("\\0") {
+                        continue;
+                    }
+
+                    let mut writer = std::io::Cursor::new(Vec::new());
+                    value.write(&mut writer).unwrap();
+                    let written = writer.into_inner();
+
+                    // Compare bytes since generated floats may be NaN.
+                    let new_value = <$type_name>::from_bytes(&written)
+                        .unwrap_or_else(|e| panic!("seed {seed}: {e}"));
+                    let mut writer = std::io::Cursor::new(Vec::new());
+                    new_value.write(&mut writer).unwrap();
+                    assert!(written == writer.into_inner(), "seed {seed}");
+                }
+            }
+        };
+    }
+
+    #[cfg(feature = "arbitrary")]
+    arbitrary_write_read_test!(arbitrary_write_read_mxmd, Mxmd);
+
+    #[cfg(feature = "arbitrary")]
+    arbitrary_write_read_test!(arbitrary_write_read_mxmd_legacy, legacy::MxmdLegacy);
+
+    #[cfg(feature = "arbitrary")]
+    arbitrary_write_read_test!(arbitrary_write_read_vertex_data, crate::vertex::VertexData);
+}
@@ -1,4 +1,4 @@
-use std::io::SeekFrom;
+use std::io::{Seek, SeekFrom, Write};
 
 use crate::{
     msrd::StreamingDataLegacyInner, parse_count32_offset32, parse_count32_offset32_unchecked,
@@ -6,13 +6,12 @@ use crate::{
     parse_string_ptr32, vertex::VertexAttribute, xc3_write_binwrite_impl, StringOffset32,
 };
 use binrw::{binread, BinRead, BinWrite};
-use xc3_write::{Xc3Write, Xc3WriteOffsets};
+use xc3_write::{Offset, Xc3Result, Xc3Write, Xc3WriteOffsets};
 
 use super::{MaterialFlags, SamplerFlags, StateFlags};
 
 // TODO: How much code can be shared with non legacy types?
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, PartialEq, Clone)]
+#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(magic(b"MXMD"))]
 #[xc3(magic(b"MXMD"))]
 pub struct MxmdLegacy {
@@ -54,6 +53,25 @@ pub struct MxmdLegacy {
     pub unk: [u32; 7],
 }
 
+// The version is checked when reading.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for MxmdLegacy {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            version: 10040,
+            models: u.arbitrary()?,
+            materials: u.arbitrary()?,
+            unk1: u.arbitrary()?,
+            vertex: u.arbitrary()?,
+            shaders: u.arbitrary()?,
+            packed_textures: u.arbitrary()?,
+            unk3: u.arbitrary()?,
+            streaming: u.arbitrary()?,
+            unk: u.arbitrary()?,
+        })
+    }
+}
+
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
@@ -110,6 +128,7 @@ pub struct Models {
 #[br(import_raw(base_offset: u64))]
 pub struct Bone {
     #[br(parse_with = parse_string_ptr32, offset = base_offset)]
+    #[xc3(offset(u32))]
     pub name: String,
     /// The index in [bones](struct.Models.html#structfield.bones) of the parent bone.
     pub parent_index: i32,
@@ -182,7 +201,7 @@ pub struct Skinning {
 
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, Xc3Write, PartialEq, Clone)]
+#[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
 pub struct Materials {
@@ -352,7 +371,6 @@ pub struct Texture {
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
-#[xc3(base_offset)]
 pub struct MaterialsUnk2 {
     #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
@@ -372,7 +390,6 @@ pub struct MaterialsUnk2 {
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
-#[xc3(base_offset)]
 pub struct MaterialsUnk3 {
     #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
@@ -403,7 +420,6 @@ pub struct MaterialsUnk3Unk3 {
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
-#[xc3(base_offset)]
 pub struct MaterialsUnk4 {
     #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
@@ -414,7 +430,7 @@ pub struct MaterialsUnk4 {
 
 // TODO: compare with decompiled shader data.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, PartialEq, Clone)]
+#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct Technique {
     #[br(parse_with = parse_offset32_count32, offset = base_offset)]
@@ -471,18 +487,18 @@ pub struct VertexData {
     pub unk: [u32; 5],
 }
 
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
+#[binread]
+#[derive(Debug, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct VertexBufferDescriptor {
-    pub data_offset: u32,
+    #[br(temp)]
+    data_offset: u32,
     pub vertex_count: u32,
     /// The size or stride of the vertex in bytes.
     pub vertex_size: u32,
 
     /// A tightly packed list of attributes for the data for this buffer.
     #[br(parse_with = parse_offset32_count32, offset = base_offset)]
-    #[xc3(offset_count(u32, u32))]
     pub attributes: Vec<VertexAttribute>,
 
     pub unk1: u32,
@@ -494,11 +510,12 @@ pub struct VertexBufferDescriptor {
     pub data: Vec<u8>,
 }
 
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
+#[binread]
+#[derive(Debug, PartialEq, Clone)]
 #[br(import_raw(base_offset: u64))]
 pub struct IndexBufferDescriptor {
-    pub data_offset: u32,
+    #[br(temp)]
+    data_offset: u32,
     pub index_count: u32,
     pub unk1: u16, // TODO: primitive type?
     pub unk2: u16, // TODO: index format?
@@ -510,6 +527,40 @@ pub struct IndexBufferDescriptor {
     pub data: Vec<u8>,
 }
 
+// The data length is inferred from the counts.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VertexBufferDescriptor {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let vertex_count = u.int_in_range(0..=8)?;
+        let vertex_size = u.int_in_range(0..=32)?;
+        Ok(Self {
+            vertex_count,
+            vertex_size,
+            attributes: u.arbitrary()?,
+            unk1: u.arbitrary()?,
+            data: (0..vertex_count * vertex_size)
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?,
+        })
+    }
+}
+
+// The data length is inferred from the counts.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for IndexBufferDescriptor {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let index_count = u.int_in_range(0..=32)?;
+        Ok(Self {
+            index_count,
+            unk1: u.arbitrary()?,
+            unk2: u.arbitrary()?,
+            data: (0..index_count * 2)
+                .map(|_| u.arbitrary())
+                .collect::<arbitrary::Result<_>>()?,
+        })
+    }
+}
+
 /// A collection of [Mtxt](crate::mtxt::Mtxt) textures embedded in the current file.
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
@@ -583,7 +634,7 @@ pub enum TextureUsage {
 // TODO: Nearly identical to legacy wimdo but not compressed?
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, Xc3Write, PartialEq, Clone)]
+#[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
 pub struct Streaming {
@@ -604,7 +655,7 @@ pub struct Streaming {
 
 #[binread]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
-#[derive(Debug, Xc3Write, PartialEq, Clone)]
+#[derive(Debug, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
 pub struct Shaders {
@@ -635,3 +686,190 @@ pub struct Shader {
 }
 
 xc3_write_binwrite_impl!(TextureUsage, UnkPassType);
+
+pub struct VertexBufferDescriptorOffsets<'a> {
+    pub data: Offset<'a, u32, Vec<u8>>,
+    pub attributes: Offset<'a, u32, Vec<VertexAttribute>>,
+}
+
+pub struct IndexBufferDescriptorOffsets<'a> {
+    pub data: Offset<'a, u32, Vec<u8>>,
+}
+
+// The data offset isn't stored since the data needs to be written after the descriptors.
+impl Xc3Write for VertexBufferDescriptor {
+    type Offsets<'a> = VertexBufferDescriptorOffsets<'a>;
+
+    fn xc3_write<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: xc3_write::Endian,
+    ) -> Xc3Result<Self::Offsets<'_>> {
+        // TODO: Find the alignment used in game.
+        // Use the GX2 vertex buffer alignment for now.
+        let data = Offset::new(writer.stream_position()?, &self.data, Some(64), 0u8);
+        0u32.xc3_write(writer, endian)?;
+        self.vertex_count.xc3_write(writer, endian)?;
+        self.vertex_size.xc3_write(writer, endian)?;
+
+        let attributes = Offset::new(writer.stream_position()?, &self.attributes, None, 0u8);
+        0u32.xc3_write(writer, endian)?;
+        (self.attributes.len() as u32).xc3_write(writer, endian)?;
+
+        self.unk1.xc3_write(writer, endian)?;
+
+        Ok(VertexBufferDescriptorOffsets { data, attributes })
+    }
+}
+
+impl Xc3Write for IndexBufferDescriptor {
+    type Offsets<'a> = IndexBufferDescriptorOffsets<'a>;
+
+    fn xc3_write<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: xc3_write::Endian,
+    ) -> Xc3Result<Self::Offsets<'_>> {
+        // TODO: Find the alignment used in game.
+        // Use the GX2 index buffer alignment for now.
+        let data = Offset::new(writer.stream_position()?, &self.data, Some(32), 0u8);
+        0u32.xc3_write(writer, endian)?;
+        self.index_count.xc3_write(writer, endian)?;
+        self.unk1.xc3_write(writer, endian)?;
+        self.unk2.xc3_write(writer, endian)?;
+
+        Ok(IndexBufferDescriptorOffsets { data })
+    }
+}
+
+impl Xc3WriteOffsets for ModelsOffsets<'_> {
+    type Args = ();
+
+    fn write_offsets<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _base_offset: u64,
+        data_ptr: &mut u64,
+        endian: xc3_write::Endian,
+        _args: Self::Args,
+    ) -> Xc3Result<()> {
+        let base_offset = self.base_offset;
+
+        self.models
+            .write_full(writer, base_offset, data_ptr, endian, ())?;
+        self.skins
+            .write_full(writer, base_offset, data_ptr, endian, ())?;
+
+        // Name offsets are relative to the start of the bones.
+        write_relative_offsets(&self.bones, writer, base_offset, data_ptr, endian)?;
+
+        self.floats
+            .write_full(writer, base_offset, data_ptr, endian, ())?;
+
+        // Name offsets are relative to the start of the names.
+        write_relative_offsets(&self.bone_names, writer, base_offset, data_ptr, endian)?;
+
+        Ok(())
+    }
+}
+
+fn write_relative_offsets<'a, T, W>(
+    offset: &Offset<'a, u32, Vec<T>>,
+    writer: &mut W,
+    base_offset: u64,
+    data_ptr: &mut u64,
+    endian: xc3_write::Endian,
+) -> Xc3Result<()>
+where
+    W: Write + Seek,
+    T: Xc3Write + 'static,
+    T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
+{
+    if offset.data.is_empty() {
+        return offset.write_full(writer, base_offset, data_ptr, endian, ());
+    }
+
+    // Write the items first to find their position before writing their offsets.
+    let items_position = writer.stream_position()?.max(*data_ptr).next_multiple_of(4);
+    offset.set_offset(writer, items_position - base_offset, endian)?;
+
+    writer.seek(SeekFrom::Start(items_position))?;
+    let items = offset.data.xc3_write(writer, endian)?;
+    *data_ptr = (*data_ptr).max(writer.stream_position()?);
+
+    items.write_offsets(writer, items_position, data_ptr, endian, ())
+}
+
+impl Xc3WriteOffsets for VertexDataOffsets<'_> {
+    type Args = ();
+
+    fn write_offsets<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _base_offset: u64,
+        data_ptr: &mut u64,
+        endian: xc3_write::Endian,
+        _args: Self::Args,
+    ) -> Xc3Result<()> {
+        let base_offset = self.base_offset;
+
+        let vertex_buffers = self
+            .vertex_buffers
+            .write(writer, base_offset, data_ptr, endian)?;
+        let index_buffers = self
+            .index_buffers
+            .write(writer, base_offset, data_ptr, endian)?;
+
+        for buffer in &vertex_buffers.0 {
+            buffer
+                .attributes
+                .write_full(writer, base_offset, data_ptr, endian, ())?;
+        }
+
+        // Buffer data is written after all of the descriptors.
+        for buffer in &vertex_buffers.0 {
+            buffer
+                .data
+                .write_full(writer, base_offset, data_ptr, endian, ())?;
+        }
+        for buffer in &index_buffers.0 {
+            buffer
+                .data
+                .write_full(writer, base_offset, data_ptr, endian, ())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Xc3WriteOffsets for PackedTexturesOffsets<'_> {
+    type Args = ();
+
+    fn write_offsets<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        _base_offset: u64,
+        data_ptr: &mut u64,
+        endian: xc3_write::Endian,
+        _args: Self::Args,
+    ) -> Xc3Result<()> {
+        let base_offset = self.base_offset;
+
+        // Names and data need to be written at the end.
+        let textures = self.textures.write(writer, base_offset, data_ptr, endian)?;
+
+        self.strings_offset
+            .write_full(writer, base_offset, data_ptr, endian, ())?;
+        for texture in &textures.0 {
+            texture
+                .name
+                .write_full(writer, base_offset, data_ptr, endian, ())?;
+        }
+        for texture in &textures.0 {
+            texture
+                .mtxt_data
+                .write_full(writer, base_offset, data_ptr, endian, ())?;
+        }
+        Ok(())
+    }
+}
@@ -8,11 +8,12 @@
 //! | Xenoblade Chronicles 1 DE | `chr/{en,np,obj,pc,wp}/*.{chr,mot}` |
 //! | Xenoblade Chronicles 2 | `event/mot/{bl,en,np,oj,pc,we,wp}/*.mot`, `model/{bl,en,np,oj,pc,we,wp}/*.{arc,mot}` |
 //! | Xenoblade Chronicles 3 | `chr/{bt,ch,en,oj,wp}/*.{chr,mot}` |
-use std::io::Cursor;
+use std::{io::Cursor, path::Path};
 
 use crate::{
-    hash::hash_str_crc, idcm::Idcm, parse_count32_offset32, parse_offset32_count32,
-    parse_opt_offset32_inner_count32, parse_ptr32, parse_string_ptr32,
+    error::DecompressStreamError, hash::hash_str_crc, idcm::Idcm, parse_count32_offset32,
+    parse_offset32_count32, parse_opt_offset32_inner_count32, parse_ptr32, parse_string_ptr32,
+    xbc1::MaybeXbc1,
 };
 use binrw::{BinRead, BinReaderExt, BinResult, NullString};
 use xc3_write::{write_full, Xc3Write, Xc3WriteOffsets};
@@ -43,6 +44,30 @@ pub struct Sar1 {
     pub name: String,
 }
 
+impl Sar1 {
+    /// Read from `path` and decompress the archive if it is wrapped in an [Xbc1](crate::xbc1::Xbc1).
+    ///
+    /// Xenoblade 1 DE compresses some `.chr` and `.mot` files.
+    /// Use [Sar1::from_file] to only read uncompressed archives.
+    pub fn from_file_maybe_compressed<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<Self, DecompressStreamError> {
+        Self::from_bytes_maybe_compressed(std::fs::read(path)?)
+    }
+
+    /// Read from `bytes` and decompress the archive if it is wrapped in an [Xbc1](crate::xbc1::Xbc1).
+    ///
+    /// Use [Sar1::from_bytes] to only read uncompressed archives.
+    pub fn from_bytes_maybe_compressed<B: AsRef<[u8]>>(
+        bytes: B,
+    ) -> Result<Self, DecompressStreamError> {
+        match MaybeXbc1::<Sar1>::from_bytes(bytes)? {
+            MaybeXbc1::Uncompressed(sar1) => Ok(sar1),
+            MaybeXbc1::Xbc1(xbc1) => xbc1.extract(),
+        }
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 pub struct Entry {
@@ -284,3 +309,41 @@ impl Xc3WriteOffsets for Sar1Offsets<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::xbc1::{CompressionType, Xbc1};
+
+    fn sar1() -> Sar1 {
+        Sar1 {
+            file_size: 0,
+            version: 256,
+            entries: vec![Entry::from_entry_data("a.bc".to_string(), vec![1, 2, 3, 4])],
+            data_offset: 0,
+            unk4: 0,
+            unk5: 0,
+            name: "a".to_string(),
+        }
+    }
+
+    #[test]
+    fn read_maybe_compressed_uncompressed() {
+        let mut writer = Cursor::new(Vec::new());
+        sar1().write(&mut writer).unwrap();
+
+        let sar1 = Sar1::from_bytes_maybe_compressed(writer.into_inner()).unwrap();
+        assert_eq!(vec![1, 2, 3, 4], sar1.entries[0].entry_data);
+    }
+
+    #[test]
+    fn read_maybe_compressed_xbc1() {
+        let xbc1 = Xbc1::new("a.mot".to_string(), &sar1(), CompressionType::Zlib).unwrap();
+        let mut writer = Cursor::new(Vec::new());
+        xbc1.write(&mut writer).unwrap();
+
+        let sar1 = Sar1::from_bytes_maybe_compressed(writer.into_inner()).unwrap();
+        assert_eq!(vec![1, 2, 3, 4], sar1.entries[0].entry_data);
+    }
+}
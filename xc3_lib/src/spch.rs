@@ -18,7 +18,6 @@ use xc3_write::{Xc3Write, Xc3WriteOffsets};
 // TODO: Add example code for extracting shaders.
 /// .wishp, embedded in .wismt and .wimdo
 #[binread]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
 #[br(magic(b"HCPS"))]
 #[xc3(magic(b"HCPS"))]
@@ -74,6 +73,34 @@ pub struct Spch {
     pub padding: [u32; 4],
 }
 
+// The program name count is inferred from the slct offsets.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Spch {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let slct_offsets: Vec<SlctOffset> = u.arbitrary()?;
+        let string_section = match u.arbitrary()? {
+            true => Some(StringSection {
+                program_names: (0..slct_offsets.len())
+                    .map(|_| u.arbitrary())
+                    .collect::<arbitrary::Result<_>>()?,
+            }),
+            false => None,
+        };
+
+        Ok(Self {
+            version: u.arbitrary()?,
+            slct_offsets,
+            unk4s: u.arbitrary()?,
+            slct_section: u.arbitrary()?,
+            xv4_section: u.arbitrary()?,
+            unk_section: u.arbitrary()?,
+            string_section,
+            unk7: u.arbitrary()?,
+            padding: u.arbitrary()?,
+        })
+    }
+}
+
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
 #[br(import { base_offset: u64, count: usize })]
@@ -0,0 +1,250 @@
+//! Utilities for testing that formats read and write binary identical files.
+//!
+//! This module requires the `testing` feature.
+//!
+//! # Examples
+//! ```rust no_run
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use xc3_lib::{mibl::Mibl, testing::{roundtrip_eq, RoundtripResult}};
+//!
+//! // Files in chr/tex/nx/m are compressed, so use an uncompressed texture.
+//! let bytes = std::fs::read("xeno3/monolib/shader/toon_grad.witex")?;
+//! match roundtrip_eq::<Mibl>(&bytes) {
+//!     RoundtripResult::Equal => (),
+//!     RoundtripResult::Mismatch(mismatch) => println!("{mismatch}"),
+//!     result => println!("{result:?}"),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{fmt::Display, io::Cursor};
+
+use binrw::{BinRead, Endian};
+use xc3_write::{write_full, Xc3Write, Xc3WriteOffsets};
+
+/// The number of bytes before and after the mismatched byte in [ByteMismatch].
+pub const CONTEXT_SIZE: usize = 16;
+
+/// The result of reading and writing with [roundtrip_eq] or [write_eq].
+#[derive(Debug)]
+pub enum RoundtripResult {
+    /// The written bytes are identical to the original bytes.
+    Equal,
+    /// The written bytes differ from the original bytes.
+    Mismatch(ByteMismatch),
+    /// The original bytes could not be read.
+    ReadError(binrw::Error),
+    /// The value could not be written.
+    WriteError(std::io::Error),
+}
+
+impl RoundtripResult {
+    /// Returns `true` if the written bytes are identical to the original bytes.
+    pub fn is_equal(&self) -> bool {
+        matches!(self, Self::Equal)
+    }
+}
+
+/// The location of the first difference between the original and written bytes.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ByteMismatch {
+    /// The offset of the first differing byte.
+    /// This is the length of the shorter buffer if one buffer starts with the other.
+    pub offset: usize,
+    /// The length of the original bytes.
+    pub expected_len: usize,
+    /// The length of the written bytes.
+    pub actual_len: usize,
+    /// The offset of the first byte in [expected](#structfield.expected)
+    /// and [actual](#structfield.actual).
+    pub context_offset: usize,
+    /// The original bytes surrounding [offset](#structfield.offset).
+    pub expected: Vec<u8>,
+    /// The written bytes surrounding [offset](#structfield.offset).
+    pub actual: Vec<u8>,
+}
+
+impl Display for ByteMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "first mismatch at offset 0x{:X} with {} original bytes and {} written bytes",
+            self.offset, self.expected_len, self.actual_len
+        )?;
+        writeln!(
+            f,
+            "expected 0x{:X}: {}",
+            self.context_offset,
+            hex(&self.expected)
+        )?;
+        write!(
+            f,
+            "actual   0x{:X}: {}",
+            self.context_offset,
+            hex(&self.actual)
+        )
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Read `bytes` as little endian, write the value again,
+/// and compare the written bytes with `bytes`.
+pub fn roundtrip_eq<T>(bytes: &[u8]) -> RoundtripResult
+where
+    for<'a> T: BinRead<Args<'a> = ()> + Xc3Write + 'static,
+    for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
+{
+    roundtrip_eq_endian::<T>(bytes, Endian::Little)
+}
+
+/// Read `bytes` as big endian, write the value again,
+/// and compare the written bytes with `bytes`.
+pub fn roundtrip_eq_be<T>(bytes: &[u8]) -> RoundtripResult
+where
+    for<'a> T: BinRead<Args<'a> = ()> + Xc3Write + 'static,
+    for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
+{
+    roundtrip_eq_endian::<T>(bytes, Endian::Big)
+}
+
+fn roundtrip_eq_endian<T>(bytes: &[u8], endian: Endian) -> RoundtripResult
+where
+    for<'a> T: BinRead<Args<'a> = ()> + Xc3Write + 'static,
+    for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
+{
+    match T::read_options(&mut Cursor::new(bytes), endian, ()) {
+        Ok(value) => write_eq(&value, bytes, endian),
+        Err(e) => RoundtripResult::ReadError(e),
+    }
+}
+
+/// Write `value` and compare the written bytes with `original_bytes`.
+pub fn write_eq<T>(value: &T, original_bytes: &[u8], endian: Endian) -> RoundtripResult
+where
+    T: Xc3Write + 'static,
+    for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
+{
+    let mut writer = Cursor::new(Vec::new());
+    let endian = match endian {
+        Endian::Big => xc3_write::Endian::Big,
+        Endian::Little => xc3_write::Endian::Little,
+    };
+    match write_full(value, &mut writer, 0, &mut 0, endian, ()) {
+        Ok(()) => match first_mismatch(original_bytes, &writer.into_inner()) {
+            Some(mismatch) => RoundtripResult::Mismatch(mismatch),
+            None => RoundtripResult::Equal,
+        },
+        Err(e) => RoundtripResult::WriteError(e),
+    }
+}
+
+/// Find the first differing byte between `expected` and `actual`
+/// or [None] if the bytes are identical.
+pub fn first_mismatch(expected: &[u8], actual: &[u8]) -> Option<ByteMismatch> {
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .or_else(|| (expected.len() != actual.len()).then(|| expected.len().min(actual.len())))?;
+
+    let context_offset = offset.saturating_sub(CONTEXT_SIZE);
+    let context = |bytes: &[u8]| {
+        bytes
+            .get(context_offset..bytes.len().min(offset + CONTEXT_SIZE + 1))
+            .unwrap_or_default()
+            .to_vec()
+    };
+
+    Some(ByteMismatch {
+        offset,
+        expected_len: expected.len(),
+        actual_len: actual.len(),
+        context_offset,
+        expected: context(expected),
+        actual: context(actual),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_mismatch_equal() {
+        assert_eq!(None, first_mismatch(&[1, 2, 3], &[1, 2, 3]));
+    }
+
+    #[test]
+    fn first_mismatch_different_byte() {
+        let expected: Vec<u8> = (0..64).collect();
+        let mut actual = expected.clone();
+        actual[40] = 0xFF;
+
+        let mismatch = first_mismatch(&expected, &actual).unwrap();
+        assert_eq!(40, mismatch.offset);
+        assert_eq!(24, mismatch.context_offset);
+        assert_eq!((24..57).collect::<Vec<u8>>(), mismatch.expected);
+        assert_eq!(0xFF, mismatch.actual[16]);
+    }
+
+    #[test]
+    fn first_mismatch_shorter_output() {
+        assert_eq!(
+            Some(ByteMismatch {
+                offset: 2,
+                expected_len: 4,
+                actual_len: 2,
+                context_offset: 0,
+                expected: vec![1, 2, 3, 4],
+                actual: vec![1, 2],
+            }),
+            first_mismatch(&[1, 2, 3, 4], &[1, 2])
+        );
+    }
+
+    #[test]
+    fn roundtrip_eq_mibl() {
+        let surface = image_dds::Surface {
+            width: 4,
+            height: 4,
+            depth: 1,
+            layers: 1,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::R8Unorm,
+            data: vec![0u8; 16],
+        };
+        let mibl = crate::mibl::Mibl::from_surface(surface).unwrap();
+
+        let mut bytes = Cursor::new(Vec::new());
+        mibl.write(&mut bytes).unwrap();
+        let bytes = bytes.into_inner();
+        assert!(roundtrip_eq::<crate::mibl::Mibl>(&bytes).is_equal());
+    }
+
+    #[test]
+    fn roundtrip_eq_skipped_byte() {
+        // The padding byte is skipped when reading and not written.
+        #[derive(BinRead, xc3_write::Xc3Write, xc3_write::Xc3WriteOffsets)]
+        struct A {
+            #[br(pad_after = 1)]
+            a: u16,
+            b: u8,
+        }
+
+        match roundtrip_eq_be::<A>(&[0, 1, 0, 2]) {
+            RoundtripResult::Mismatch(mismatch) => {
+                assert_eq!(2, mismatch.offset);
+                assert_eq!(vec![0, 1, 0, 2], mismatch.expected);
+                assert_eq!(vec![0, 1, 2], mismatch.actual);
+            }
+            result => panic!("unexpected result {result:?}"),
+        }
+    }
+}
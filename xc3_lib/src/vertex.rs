@@ -53,11 +53,11 @@ use crate::{
 };
 use bilge::prelude::*;
 use binrw::{args, binread, BinRead, BinWrite};
+use thiserror::Error;
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
 
 /// Vertex and vertex index buffer data used by a [Model](crate::mxmd::Model).
 #[binread]
-#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, Xc3Write, PartialEq, Clone)]
 #[br(stream = r)]
 #[xc3(base_offset)]
@@ -89,7 +89,8 @@ pub struct VertexData {
     #[xc3(offset_count(u32, u32))]
     pub outline_buffers: Vec<OutlineBufferDescriptor>,
 
-    #[br(parse_with = parse_opt_ptr32, offset = base_offset)]
+    #[br(parse_with = parse_opt_ptr32)]
+    #[br(args { offset: base_offset, inner: base_offset })]
     #[xc3(offset(u32))]
     pub vertex_morphs: Option<VertexMorphs>,
 
@@ -109,7 +110,8 @@ pub struct VertexData {
     #[xc3(offset(u32))]
     pub unk_data: Option<UnkData>,
 
-    #[br(parse_with = parse_opt_ptr32, offset = base_offset)]
+    #[br(parse_with = parse_opt_ptr32)]
+    #[br(args { offset: base_offset, inner: base_offset })]
     #[xc3(offset(u32))]
     pub weights: Option<Weights>,
 
@@ -283,12 +285,14 @@ pub enum IndexFormat {
 /// Vertex animation data often called "vertex morphs", "shape keys", or "blend shapes".
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, PartialEq, Clone)]
+#[br(import_raw(base_offset: u64))]
 pub struct VertexMorphs {
     #[br(parse_with = parse_count32_offset32)]
+    #[br(args { offset: base_offset, inner: base_offset })]
     #[xc3(count_offset(u32, u32))]
     pub descriptors: Vec<MorphDescriptor>,
 
-    #[br(parse_with = parse_count32_offset32)]
+    #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
     pub targets: Vec<MorphTarget>,
 
@@ -303,12 +307,13 @@ pub struct VertexMorphs {
 /// the length of [param_indices](#structfield.param_indices).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, PartialEq, Clone)]
+#[br(import_raw(base_offset: u64))]
 pub struct MorphDescriptor {
     pub vertex_buffer_index: u32,
     pub target_start_index: u32,
 
     /// Indices into [controllers](../mxmd/struct.MorphControllers.html#structfield.controllers).
-    #[br(parse_with = parse_count32_offset32)]
+    #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
     pub param_indices: Vec<u16>,
 
@@ -351,9 +356,10 @@ pub struct MorphTargetFlags {
 /// based on a mesh's level of detail (LOD) and [RenderPassType](crate::mxmd::RenderPassType).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, BinRead, Xc3Write, Xc3WriteOffsets, PartialEq, Clone)]
+#[br(import_raw(base_offset: u64))]
 pub struct Weights {
     /// Selected based on the associated [WeightLod] for a [Mesh](crate::mxmd::Mesh).
-    #[br(parse_with = parse_count32_offset32)]
+    #[br(parse_with = parse_count32_offset32, offset = base_offset)]
     #[xc3(count_offset(u32, u32))]
     pub groups: Vec<WeightGroup>,
 
@@ -362,7 +368,7 @@ pub struct Weights {
     pub vertex_buffer_index: u16,
 
     /// Selected based on the LOD of the [Mesh](crate::mxmd::Mesh).
-    #[br(parse_with = parse_count16_offset32)]
+    #[br(parse_with = parse_count16_offset32, offset = base_offset)]
     #[xc3(count_offset(u16, u32))]
     pub weight_lods: Vec<WeightLod>,
 
@@ -562,6 +568,181 @@ impl From<DataType> for VertexAttribute {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum EditAttributeError {
+    #[error("vertex buffer index {0} is out of range")]
+    BufferIndexOutOfRange(usize),
+
+    #[error("attribute index {0} is out of range")]
+    AttributeIndexOutOfRange(usize),
+
+    #[error(
+        "attribute size {data_size} does not match the expected size {expected} for {data_type:?}"
+    )]
+    InvalidAttributeSize {
+        data_type: DataType,
+        data_size: u16,
+        expected: usize,
+    },
+
+    #[error("attribute data has {actual} bytes but expected {expected} bytes")]
+    InvalidDataLength { expected: usize, actual: usize },
+
+    #[error("vertex buffer data is out of range for the combined buffer")]
+    BufferDataOutOfRange,
+}
+
+impl VertexData {
+    /// Insert `attribute` at `attribute_index` in the vertex buffer at `buffer_index`.
+    ///
+    /// The `data` should contain the tightly packed values for each vertex.
+    /// The buffer is reinterleaved and the
+    /// [vertex_size](struct.VertexBufferDescriptor.html#structfield.vertex_size) is updated.
+    /// Buffers that no longer fit in their original location are moved to the end of
+    /// [buffer](#structfield.buffer).
+    pub fn insert_attribute(
+        &mut self,
+        buffer_index: usize,
+        attribute_index: usize,
+        attribute: VertexAttribute,
+        data: &[u8],
+    ) -> Result<(), EditAttributeError> {
+        let expected = attribute.data_type.size_in_bytes();
+        if attribute.data_size as usize != expected {
+            return Err(EditAttributeError::InvalidAttributeSize {
+                data_type: attribute.data_type,
+                data_size: attribute.data_size,
+                expected,
+            });
+        }
+
+        let descriptor = self
+            .vertex_buffers
+            .get(buffer_index)
+            .ok_or(EditAttributeError::BufferIndexOutOfRange(buffer_index))?;
+        if attribute_index > descriptor.attributes.len() {
+            return Err(EditAttributeError::AttributeIndexOutOfRange(
+                attribute_index,
+            ));
+        }
+
+        let vertex_count = descriptor.vertex_count as usize;
+        let expected = vertex_count * attribute.data_size as usize;
+        if data.len() != expected {
+            return Err(EditAttributeError::InvalidDataLength {
+                expected,
+                actual: data.len(),
+            });
+        }
+
+        let mut attributes = descriptor.attributes.clone();
+        let mut attribute_data = self.deinterleave(buffer_index)?;
+        attributes.insert(attribute_index, attribute);
+        attribute_data.insert(attribute_index, data.to_vec());
+
+        self.interleave(buffer_index, attributes, &attribute_data);
+        Ok(())
+    }
+
+    /// Remove and return the attribute at `attribute_index` in the vertex buffer at `buffer_index`.
+    ///
+    /// The buffer is reinterleaved in place and the
+    /// [vertex_size](struct.VertexBufferDescriptor.html#structfield.vertex_size) is updated.
+    pub fn remove_attribute(
+        &mut self,
+        buffer_index: usize,
+        attribute_index: usize,
+    ) -> Result<VertexAttribute, EditAttributeError> {
+        let descriptor = self
+            .vertex_buffers
+            .get(buffer_index)
+            .ok_or(EditAttributeError::BufferIndexOutOfRange(buffer_index))?;
+        if attribute_index >= descriptor.attributes.len() {
+            return Err(EditAttributeError::AttributeIndexOutOfRange(
+                attribute_index,
+            ));
+        }
+
+        let mut attributes = descriptor.attributes.clone();
+        let mut attribute_data = self.deinterleave(buffer_index)?;
+        let attribute = attributes.remove(attribute_index);
+        attribute_data.remove(attribute_index);
+
+        self.interleave(buffer_index, attributes, &attribute_data);
+        Ok(attribute)
+    }
+
+    fn deinterleave(&self, buffer_index: usize) -> Result<Vec<Vec<u8>>, EditAttributeError> {
+        let descriptor = &self.vertex_buffers[buffer_index];
+        let stride = descriptor.vertex_size as usize;
+        let attributes_size: usize = descriptor
+            .attributes
+            .iter()
+            .map(|a| a.data_size as usize)
+            .sum();
+        if attributes_size > stride {
+            return Err(EditAttributeError::BufferDataOutOfRange);
+        }
+
+        let start = descriptor.data_offset as usize;
+        let end = start + descriptor.vertex_count as usize * stride;
+        let bytes = self
+            .buffer
+            .get(start..end)
+            .ok_or(EditAttributeError::BufferDataOutOfRange)?;
+
+        let mut attribute_offset = 0;
+        let attribute_data = descriptor
+            .attributes
+            .iter()
+            .map(|a| {
+                let size = a.data_size as usize;
+                let data = bytes
+                    .chunks_exact(stride.max(1))
+                    .flat_map(|v| &v[attribute_offset..attribute_offset + size])
+                    .copied()
+                    .collect();
+                attribute_offset += size;
+                data
+            })
+            .collect();
+        Ok(attribute_data)
+    }
+
+    fn interleave(
+        &mut self,
+        buffer_index: usize,
+        attributes: Vec<VertexAttribute>,
+        attribute_data: &[Vec<u8>],
+    ) {
+        let descriptor = &mut self.vertex_buffers[buffer_index];
+        let old_size = descriptor.vertex_count as usize * descriptor.vertex_size as usize;
+        let vertex_size: usize = attributes.iter().map(|a| a.data_size as usize).sum();
+
+        let mut bytes = Vec::with_capacity(descriptor.vertex_count as usize * vertex_size);
+        for i in 0..descriptor.vertex_count as usize {
+            for (a, data) in attributes.iter().zip(attribute_data) {
+                let size = a.data_size as usize;
+                bytes.extend_from_slice(&data[i * size..(i + 1) * size]);
+            }
+        }
+
+        // Other data in the buffer may use the bytes after the original data.
+        if bytes.len() > old_size {
+            let offset = self.buffer.len().next_multiple_of(16);
+            self.buffer.resize(offset, 0);
+            self.buffer.extend_from_slice(&bytes);
+            descriptor.data_offset = offset as u32;
+        } else {
+            let start = descriptor.data_offset as usize;
+            self.buffer[start..start + bytes.len()].copy_from_slice(&bytes);
+        }
+
+        descriptor.vertex_size = vertex_size as u32;
+        descriptor.attributes = attributes;
+    }
+}
+
 xc3_write_binwrite_impl!(
     DataType,
     PrimitiveType,
@@ -570,6 +751,34 @@ xc3_write_binwrite_impl!(
     VertexBufferExtInfoFlags
 );
 
+// The buffer info count depends on the vertex buffers,
+// so generate both together to produce values that can be written and read again.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for VertexData {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let vertex_buffers: Vec<VertexBufferDescriptor> = u.arbitrary()?;
+        let vertex_buffer_info = (0..buffer_info_count(&vertex_buffers))
+            .map(|_| u.arbitrary())
+            .collect::<arbitrary::Result<_>>()?;
+
+        Ok(Self {
+            vertex_buffers,
+            index_buffers: u.arbitrary()?,
+            unk0: u.arbitrary()?,
+            unk1: u.arbitrary()?,
+            unk2: u.arbitrary()?,
+            vertex_buffer_info,
+            outline_buffers: u.arbitrary()?,
+            vertex_morphs: u.arbitrary()?,
+            buffer: u.arbitrary()?,
+            unk_data: u.arbitrary()?,
+            weights: u.arbitrary()?,
+            unk7: u.arbitrary()?,
+            unks: u.arbitrary()?,
+        })
+    }
+}
+
 fn buffer_info_count(vertex_buffers: &[VertexBufferDescriptor]) -> usize {
     // TODO: Extra data for every buffer except the single weights buffer?
     vertex_buffers
@@ -650,3 +859,95 @@ impl Xc3WriteOffsets for VertexDataOffsets<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex_data(buffer: Vec<u8>) -> VertexData {
+        VertexData {
+            vertex_buffers: vec![VertexBufferDescriptor {
+                data_offset: 0,
+                vertex_count: 2,
+                vertex_size: 8,
+                attributes: vec![DataType::VertexColor.into(), DataType::Normal.into()],
+                unk1: 0,
+                unk2: 0,
+                unk3: 0,
+            }],
+            index_buffers: Vec::new(),
+            unk0: 0,
+            unk1: 0,
+            unk2: 0,
+            vertex_buffer_info: Vec::new(),
+            outline_buffers: Vec::new(),
+            vertex_morphs: None,
+            buffer,
+            unk_data: None,
+            weights: None,
+            unk7: None,
+            unks: [0; 5],
+        }
+    }
+
+    #[test]
+    fn insert_remove_attribute() {
+        let mut data = vertex_data(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]);
+
+        data.insert_attribute(
+            0,
+            1,
+            DataType::TexCoord1.into(),
+            &[
+                21, 22, 23, 24, 25, 26, 27, 28, 31, 32, 33, 34, 35, 36, 37, 38,
+            ],
+        )
+        .unwrap();
+
+        let descriptor = &data.vertex_buffers[0];
+        assert_eq!(16, descriptor.vertex_size);
+        assert_eq!(16, descriptor.data_offset);
+        assert_eq!(
+            vec![
+                VertexAttribute::from(DataType::VertexColor),
+                VertexAttribute::from(DataType::TexCoord1),
+                VertexAttribute::from(DataType::Normal)
+            ],
+            descriptor.attributes
+        );
+        assert_eq!(
+            &[
+                1, 2, 3, 4, 21, 22, 23, 24, 25, 26, 27, 28, 5, 6, 7, 8, 9, 10, 11, 12, 31, 32, 33,
+                34, 35, 36, 37, 38, 13, 14, 15, 16
+            ],
+            &data.buffer[16..]
+        );
+
+        assert_eq!(
+            VertexAttribute::from(DataType::VertexColor),
+            data.remove_attribute(0, 0).unwrap()
+        );
+
+        let descriptor = &data.vertex_buffers[0];
+        assert_eq!(12, descriptor.vertex_size);
+        assert_eq!(
+            &[
+                21, 22, 23, 24, 25, 26, 27, 28, 5, 6, 7, 8, 31, 32, 33, 34, 35, 36, 37, 38, 13, 14,
+                15, 16
+            ],
+            &data.buffer[16..40]
+        );
+    }
+
+    #[test]
+    fn insert_attribute_invalid_length() {
+        let mut data = vertex_data(vec![0; 16]);
+        assert!(matches!(
+            data.insert_attribute(0, 0, DataType::TexCoord1.into(), &[0; 4]),
+            Err(EditAttributeError::InvalidDataLength {
+                expected: 16,
+                actual: 4
+            })
+        ));
+    }
+}
@@ -7,9 +7,10 @@
 //! | Xenoblade Chronicles 1 DE | |  |
 //! | Xenoblade Chronicles 2 |  |  |
 //! | Xenoblade Chronicles 3 |  | `effect/**/*.wipac`  |
-use crate::{parse_ptr32, xc3_write_binwrite_impl, Offset32};
+use crate::{error::DecompressStreamError, parse_ptr32, xc3_write_binwrite_impl, Offset32};
 use binrw::{binread, BinRead, BinWrite, NullString};
 use xc3_write::{Xc3Write, Xc3WriteOffsets};
+use zune_inflate::{DeflateDecoder, DeflateOptions};
 
 // TODO: Come up with a better name
 // TODO: implement proper write support
@@ -88,4 +89,30 @@ pub enum CmpType {
     Zlib,
 }
 
+impl Wipac {
+    /// The name and compressed data for each entry.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Cmp)> {
+        // TODO: Are names always in the same order as the data?
+        self.unk6
+            .iter()
+            .zip(&self.unk5)
+            .map(|(efxa, unk)| (efxa.unk5.as_str(), &unk.value.cmp))
+    }
+}
+
+impl Cmp {
+    /// Decompress the bytes for [compressed_stream](#structfield.compressed_stream).
+    pub fn decompress(&self) -> Result<Vec<u8>, DecompressStreamError> {
+        match self.cmp_type {
+            CmpType::Zlib => {
+                let mut decoder = DeflateDecoder::new_with_options(
+                    &self.compressed_stream,
+                    DeflateOptions::default().set_size_hint(self.decompressed_size as usize),
+                );
+                decoder.decode_zlib().map_err(Into::into)
+            }
+        }
+    }
+}
+
 xc3_write_binwrite_impl!(CmpType);
@@ -20,12 +20,23 @@ pub struct Animation {
     pub frames_per_second: f32,
     pub frame_count: u32,
     pub tracks: Vec<Track>,
-    // TODO: make this a vec instead?
-    pub morph_tracks: Option<MorphTracks>,
+    /// Morph target weight tracks keyed by the [ExtraTrackAnimation](xc3_lib::bc::anim::ExtraTrackAnimation) name.
+    pub morph_tracks: BTreeMap<String, MorphTracks>,
     /// Translation at each frame for the skeleton root.
     pub root_translation: Option<Vec<Vec3>>,
 }
 
+/// Summary information for an [Animation] without any track data.
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnimationInfo {
+    pub name: String,
+    pub frames_per_second: f32,
+    pub frame_count: u32,
+    /// The length of the animation in seconds based on
+    /// [frame_count](#structfield.frame_count) and [frames_per_second](#structfield.frames_per_second).
+    pub duration_seconds: f32,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Track {
     // TODO: Are fractional keyframes used in practice?
@@ -63,6 +74,9 @@ pub struct Keyframe {
 #[derive(Debug, PartialEq, Clone)]
 pub struct MorphTracks {
     pub track_indices: Vec<i16>,
+    /// The weight at each frame for each track.
+    /// The values for each track are stored contiguously with one value per frame.
+    /// Cubic tracks are sampled at each frame to use the same layout as uncompressed tracks.
     pub track_values: Vec<f32>,
 }
 
@@ -92,6 +106,11 @@ impl Animation {
         }
     }
 
+    /// The summary information for this animation.
+    pub fn info(&self) -> AnimationInfo {
+        AnimationInfo::new(&self.name, self.frames_per_second, self.frame_count)
+    }
+
     /// Convert `current_time_seconds` to frames based on the animation parameters.
     pub fn current_frame(&self, current_time_seconds: f32) -> f32 {
         // TODO: add option to force looping?
@@ -103,6 +122,84 @@ impl Animation {
         }
     }
 
+    /// Create a new animation with only the frames from `start_frame` to `end_frame` inclusive.
+    /// The first frame of the new animation is `start_frame`.
+    ///
+    /// Keyframes before `start_frame` are replaced with a keyframe at `start_frame`
+    /// with the same interpolated values.
+    /// The `end_frame` is clamped to the last frame of the animation.
+    /// The new animation has no frames or keyframes if `start_frame` is after the clamped `end_frame`.
+    ///
+    /// Morph track values are assumed to store a value at each frame for each track.
+    pub fn trim(&self, start_frame: u32, end_frame: u32) -> Animation {
+        let end_frame = end_frame.min(self.frame_count.saturating_sub(1));
+        let frame_count = if start_frame <= end_frame {
+            end_frame - start_frame + 1
+        } else {
+            0
+        };
+
+        Self {
+            name: self.name.clone(),
+            space_mode: self.space_mode,
+            play_mode: self.play_mode,
+            blend_mode: self.blend_mode,
+            frames_per_second: self.frames_per_second,
+            frame_count,
+            tracks: self
+                .tracks
+                .iter()
+                .map(|t| Track {
+                    translation_keyframes: trim_keyframes(
+                        &t.translation_keyframes,
+                        start_frame,
+                        end_frame,
+                        self.frame_count,
+                    ),
+                    rotation_keyframes: trim_keyframes(
+                        &t.rotation_keyframes,
+                        start_frame,
+                        end_frame,
+                        self.frame_count,
+                    ),
+                    scale_keyframes: trim_keyframes(
+                        &t.scale_keyframes,
+                        start_frame,
+                        end_frame,
+                        self.frame_count,
+                    ),
+                    bone_index: t.bone_index.clone(),
+                })
+                .collect(),
+            morph_tracks: self
+                .morph_tracks
+                .iter()
+                .map(|(name, morphs)| {
+                    (
+                        name.clone(),
+                        MorphTracks {
+                            track_indices: morphs.track_indices.clone(),
+                            track_values: trim_morph_values(
+                                &morphs.track_values,
+                                start_frame,
+                                frame_count,
+                                self.frame_count,
+                            ),
+                        },
+                    )
+                })
+                .collect(),
+            root_translation: self.root_translation.as_ref().map(|translations| {
+                translations
+                    .iter()
+                    .skip(start_frame as usize)
+                    .take(frame_count as usize)
+                    .copied()
+                    .collect()
+            }),
+        }
+    }
+
     // TODO: Tests for this.
     /// Compute the matrix for each bone in `skeleton`
     /// that transforms a vertex in model space to its animated position in model space.
@@ -143,6 +240,61 @@ impl Animation {
         anim_model_space
     }
 
+    /// Identical to [Self::model_space_transforms] but without applying the extra translations to the root bone.
+    ///
+    /// This keeps the skeleton in place for applying [Self::root_motion] separately.
+    pub fn model_space_transforms_in_place(
+        &self,
+        skeleton: &Skeleton,
+        frame: f32,
+    ) -> Vec<Transform> {
+        let animated_transforms = self.animated_transforms(skeleton, frame);
+
+        let mut anim_model_space = skeleton.model_space_transforms();
+        self.apply_animation_transforms(&mut anim_model_space, skeleton, &animated_transforms);
+
+        anim_model_space
+    }
+
+    /// The change in the extra root bone translation from the previous frame for each frame
+    /// in [root_translation](#structfield.root_translation).
+    ///
+    /// The root bone is the first bone in `skeleton` with no parent,
+    /// which is the same bone that [Self::model_space_transforms] applies the translations to.
+    ///
+    /// The first frame has no change from the previous frame.
+    /// This returns an empty list if the animation has no root motion
+    /// or `skeleton` does not start with a root bone.
+    /// Use [Self::model_space_transforms_in_place] for the pose without root motion.
+    pub fn root_motion(&self, skeleton: &Skeleton) -> Vec<Transform> {
+        // Root motion is always applied to the first bone.
+        if skeleton
+            .bones
+            .first()
+            .is_none_or(|b| b.parent_index.is_some())
+        {
+            return Vec::new();
+        }
+
+        self.root_translation
+            .as_ref()
+            .map(|translations| {
+                let mut previous = translations.first().copied().unwrap_or(Vec3::ZERO);
+                translations
+                    .iter()
+                    .map(|translation| {
+                        let delta = *translation - previous;
+                        previous = *translation;
+                        Transform {
+                            translation: delta,
+                            ..Transform::IDENTITY
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
     fn apply_animation_transforms(
         &self,
         anim_model_space: &mut [Transform],
@@ -268,7 +420,7 @@ impl Animation {
         // Default to the basis values if no morph animation is present.
         let mut weights = vec![0.0f32; morph_controller_names.len()];
 
-        if let Some(morphs) = &self.morph_tracks {
+        for morphs in self.morph_tracks.values() {
             for (i, track_index) in morphs.track_indices.iter().enumerate() {
                 // TODO: The counts and indices match up but don't select the right names?
                 let name = &animation_morph_names[i];
@@ -283,11 +435,11 @@ impl Animation {
                     let len = weights.len();
                     if let Some(weight) = weights.get_mut(target_index % len) {
                         if let Ok(track_index) = usize::try_from(*track_index) {
-                            // TODO: Is this how to handle multiple frames?
-                            let frame_value = morphs.track_values.get(track_index * frame_index);
-
+                            // The values for each track are stored contiguously.
+                            let start = track_index * self.frame_count as usize;
+                            let frame_value = morphs.track_values.get(start + frame_index);
                             let next_frame_value =
-                                morphs.track_values.get(track_index * next_frame_index);
+                                morphs.track_values.get(start + next_frame_index);
                             if let Some(value) = frame_value {
                                 *weight = match next_frame_value {
                                     Some(next_value) => {
@@ -621,33 +773,94 @@ fn anim_tracks(anim: &xc3_lib::bc::anim::Anim) -> Vec<Track> {
     }
 }
 
-fn morph_tracks(anim: &xc3_lib::bc::anim::Anim) -> Option<MorphTracks> {
-    match &anim.binding.inner {
-        xc3_lib::bc::anim::AnimationBindingInner::Unk1(unk1) => {
-            // TODO: Does this ever have more than 1 element?
-            let extra = unk1.extra_track_bindings.first()?;
-
-            Some(MorphTracks {
-                track_indices: match &extra.track_indices {
-                    xc3_lib::bc::BcListCount::List(list) => list.clone(),
-                    xc3_lib::bc::BcListCount::NullOffsetCount(_) => Vec::new(),
+fn morph_tracks(anim: &xc3_lib::bc::anim::Anim) -> BTreeMap<String, MorphTracks> {
+    let bindings = match &anim.binding.inner {
+        xc3_lib::bc::anim::AnimationBindingInner::Unk1(unk1) => &unk1.extra_track_bindings,
+        // TODO: Does this also contain morph animations?
+        xc3_lib::bc::anim::AnimationBindingInner::Unk2(_) => &[][..],
+        // Xenoblade 3 facial animations store morph tracks with the extra track data.
+        xc3_lib::bc::anim::AnimationBindingInner::Unk3(inner) => {
+            extra_track_bindings(&inner.extra_track_data)
+        }
+        xc3_lib::bc::anim::AnimationBindingInner::Unk4(inner) => {
+            extra_track_bindings(&inner.extra_track_data)
+        }
+    };
+
+    morph_binding_tracks(bindings, anim.binding.animation.frame_count)
+}
+
+fn morph_binding_tracks(
+    bindings: &[xc3_lib::bc::anim::ExtraTrackAnimationBinding],
+    frame_count: u32,
+) -> BTreeMap<String, MorphTracks> {
+    // Bindings without an animation have no values to apply.
+    bindings
+        .iter()
+        .filter_map(|binding| {
+            let extra = binding.extra_track_animation.as_ref()?;
+            Some((
+                extra.name.clone(),
+                MorphTracks {
+                    track_indices: match &binding.track_indices {
+                        xc3_lib::bc::BcListCount::List(list) => list.clone(),
+                        xc3_lib::bc::BcListCount::NullOffsetCount(_) => Vec::new(),
+                    },
+                    track_values: morph_track_values(&extra.data, frame_count),
                 },
-                track_values: extra
-                    .extra_track_animation
-                    .as_ref()
-                    .map(|extra| match &extra.data {
-                        xc3_lib::bc::anim::ExtraAnimationData::Uncompressed(values) => {
-                            values.elements.clone()
-                        }
-                        xc3_lib::bc::anim::ExtraAnimationData::Cubic(_cubic) => Vec::new(),
+            ))
+        })
+        .collect()
+}
+
+fn morph_track_values(data: &xc3_lib::bc::anim::ExtraAnimationData, frame_count: u32) -> Vec<f32> {
+    match data {
+        xc3_lib::bc::anim::ExtraAnimationData::Uncompressed(values) => values.elements.clone(),
+        xc3_lib::bc::anim::ExtraAnimationData::Cubic(tracks) => {
+            // Each keyframe is a frame followed by coefficients [a,b,c,d].
+            tracks
+                .elements
+                .iter()
+                .flat_map(|track| {
+                    let keyframes: BTreeMap<_, _> = track
+                        .elements
+                        .iter()
+                        .map(|[frame, a, b, c, d]| {
+                            (
+                                OrderedFloat(*frame),
+                                Keyframe {
+                                    x_coeffs: vec4(*a, *b, *c, *d),
+                                    y_coeffs: Vec4::ZERO,
+                                    z_coeffs: Vec4::ZERO,
+                                    w_coeffs: Vec4::ZERO,
+                                },
+                            )
+                        })
+                        .collect();
+                    (0..frame_count).map(move |frame| {
+                        sample_keyframe_cubic(&keyframes, frame as f32, frame_count)
+                            .map(|v| v.x)
+                            .unwrap_or_default()
                     })
-                    .unwrap_or_default(),
-            })
+                })
+                .collect()
+        }
+    }
+}
+
+fn extra_track_bindings(
+    data: &xc3_lib::bc::anim::ExtraTrackData,
+) -> &[xc3_lib::bc::anim::ExtraTrackAnimationBinding] {
+    match data {
+        xc3_lib::bc::anim::ExtraTrackData::Uncompressed(extra) => {
+            &extra.extra_track_bindings.elements
+        }
+        // TODO: Cubic extra track data has no known track bindings.
+        xc3_lib::bc::anim::ExtraTrackData::Cubic(_) => &[],
+        xc3_lib::bc::anim::ExtraTrackData::Empty => &[],
+        xc3_lib::bc::anim::ExtraTrackData::PackedCubic(extra) => {
+            &extra.extra_track_bindings.elements
         }
-        // TODO: Does these also contain morph animations?
-        xc3_lib::bc::anim::AnimationBindingInner::Unk2(_) => None,
-        xc3_lib::bc::anim::AnimationBindingInner::Unk3(_) => None,
-        xc3_lib::bc::anim::AnimationBindingInner::Unk4(_) => None,
     }
 }
 
@@ -774,6 +987,32 @@ fn packed_cubic_vec4_keyframes(
         .collect()
 }
 
+impl AnimationInfo {
+    /// Read the summary information for `anim` without decoding any track data.
+    pub fn from_anim(anim: &xc3_lib::bc::anim::Anim) -> Self {
+        let animation = &anim.binding.animation;
+        Self::new(
+            &animation.name,
+            animation.frames_per_second,
+            animation.frame_count,
+        )
+    }
+
+    fn new(name: &str, frames_per_second: f32, frame_count: u32) -> Self {
+        let duration_seconds = if frames_per_second > 0.0 {
+            frame_count as f32 / frames_per_second
+        } else {
+            0.0
+        };
+        Self {
+            name: name.to_string(),
+            frames_per_second,
+            frame_count,
+            duration_seconds,
+        }
+    }
+}
+
 impl Track {
     /// Sample the translation at `frame` using the appropriate interpolation between frames.
     /// Returns `None` if the animation is empty.
@@ -852,6 +1091,77 @@ fn interpolate_cubic(coeffs: Vec4, x: f32) -> f32 {
     coeffs.x * (x * x * x) + coeffs.y * (x * x) + coeffs.z * x + coeffs.w
 }
 
+fn trim_keyframes(
+    keyframes: &BTreeMap<OrderedFloat<f32>, Keyframe>,
+    start_frame: u32,
+    end_frame: u32,
+    frame_count: u32,
+) -> BTreeMap<OrderedFloat<f32>, Keyframe> {
+    if start_frame > end_frame {
+        return BTreeMap::new();
+    }
+
+    let start = start_frame as f32;
+    let end = end_frame as f32;
+
+    // Shift the keyframe containing the start to begin at the first frame.
+    let first_keyframe = keyframe_position(keyframes, start, frame_count).map(|(keyframe, x)| {
+        (
+            OrderedFloat(0.0),
+            Keyframe {
+                x_coeffs: shift_cubic(keyframe.x_coeffs, x),
+                y_coeffs: shift_cubic(keyframe.y_coeffs, x),
+                z_coeffs: shift_cubic(keyframe.z_coeffs, x),
+                w_coeffs: shift_cubic(keyframe.w_coeffs, x),
+            },
+        )
+    });
+
+    let key = OrderedFloat::<f32>::from(start);
+    first_keyframe
+        .into_iter()
+        .chain(
+            keyframes
+                .range((Excluded(key), Included(OrderedFloat(end))))
+                .map(|(frame, keyframe)| (OrderedFloat(frame.0 - start), keyframe.clone())),
+        )
+        .collect()
+}
+
+fn trim_morph_values(
+    values: &[f32],
+    start_frame: u32,
+    trimmed_frame_count: u32,
+    frame_count: u32,
+) -> Vec<f32> {
+    // Assume the values for each track are stored contiguously.
+    if frame_count == 0 || !values.len().is_multiple_of(frame_count as usize) {
+        return values.to_vec();
+    }
+    values
+        .chunks_exact(frame_count as usize)
+        .flat_map(|track| {
+            track
+                .iter()
+                .skip(start_frame as usize)
+                .take(trimmed_frame_count as usize)
+                .copied()
+        })
+        .collect()
+}
+
+/// Find the coefficients for evaluating `coeffs` at `x + offset`.
+fn shift_cubic(coeffs: Vec4, offset: f32) -> Vec4 {
+    let [a, b, c, _] = coeffs.to_array();
+    let s = offset;
+    vec4(
+        a,
+        3.0 * a * s + b,
+        3.0 * a * s * s + 2.0 * b * s + c,
+        interpolate_cubic(coeffs, s),
+    )
+}
+
 fn apply_transform(target: Transform, source: Transform, blend_mode: BlendMode) -> Transform {
     // TODO: Is this the correct way to implement additive blending?
     match blend_mode {
@@ -941,6 +1251,210 @@ mod tests {
         assert_eq!(58.0, interpolate_cubic(coeffs, 3.0));
     }
 
+    #[test]
+    fn shift_cubic_values() {
+        let coeffs = vec4(1.0, 2.0, 3.0, 4.0);
+        let shifted = shift_cubic(coeffs, 2.0);
+        for x in [0.0, 0.5, 1.0, 3.0] {
+            assert_eq!(
+                interpolate_cubic(coeffs, x + 2.0),
+                interpolate_cubic(shifted, x)
+            );
+        }
+    }
+
+    #[test]
+    fn trim_animation() {
+        let animation = trim_test_animation();
+
+        let trimmed = animation.trim(3, 7);
+        assert_eq!(5, trimmed.frame_count);
+        assert_eq!(
+            vec![0.0, 2.0],
+            trimmed.tracks[0]
+                .translation_keyframes
+                .keys()
+                .map(|k| k.0)
+                .collect::<Vec<_>>()
+        );
+        for frame in [0.0, 1.5, 2.0, 4.0] {
+            assert_eq!(
+                animation.tracks[0].sample_translation(frame + 3.0, 11),
+                trimmed.tracks[0].sample_translation(frame, 5)
+            );
+        }
+        assert_eq!(
+            Some((3..8).map(|i| Vec3::splat(i as f32)).collect()),
+            trimmed.root_translation
+        );
+    }
+
+    #[test]
+    fn trim_animation_end_out_of_range() {
+        assert_eq!(3, trim_test_animation().trim(8, 20).frame_count);
+    }
+
+    fn trim_test_animation() -> Animation {
+        let keyframes = keys(&[0.0, 5.0, 9.0]);
+        Animation {
+            name: "a".to_string(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Loop,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 11,
+            tracks: vec![Track {
+                translation_keyframes: keyframes.clone(),
+                rotation_keyframes: keyframes.clone(),
+                scale_keyframes: keyframes,
+                bone_index: BoneIndex::Index(0),
+            }],
+            morph_tracks: [(
+                "a".to_string(),
+                MorphTracks {
+                    track_indices: vec![0, 1],
+                    track_values: (0..22).map(|i| i as f32).collect(),
+                },
+            )]
+            .into(),
+            root_translation: Some((0..11).map(|i| Vec3::splat(i as f32)).collect()),
+        }
+    }
+
+    #[test]
+    fn trim_animation_start_out_of_range() {
+        let trimmed = trim_test_animation().trim(20, 30);
+        assert_eq!(0, trimmed.frame_count);
+        assert!(trimmed.tracks[0].translation_keyframes.is_empty());
+        assert!(trimmed.tracks[0].rotation_keyframes.is_empty());
+        assert!(trimmed.tracks[0].scale_keyframes.is_empty());
+        assert!(trimmed.morph_tracks["a"].track_values.is_empty());
+        assert_eq!(Some(Vec::new()), trimmed.root_translation);
+    }
+
+    #[test]
+    fn trim_animation_start_after_end() {
+        let trimmed = trim_test_animation().trim(5, 3);
+        assert_eq!(0, trimmed.frame_count);
+        assert!(trimmed.tracks[0].translation_keyframes.is_empty());
+        assert!(trimmed.morph_tracks["a"].track_values.is_empty());
+        assert_eq!(Some(Vec::new()), trimmed.root_translation);
+    }
+
+    #[test]
+    fn trim_animation_morph_tracks() {
+        let trimmed = trim_test_animation().trim(3, 7);
+        let morphs = &trimmed.morph_tracks["a"];
+        assert_eq!(vec![0, 1], morphs.track_indices);
+        assert_eq!(
+            (3..8).chain(14..19).map(|i| i as f32).collect::<Vec<_>>(),
+            morphs.track_values
+        );
+    }
+
+    #[test]
+    fn trim_animation_morph_weights() {
+        let controller_names = ["m1".to_string(), "m0".to_string()];
+        let animation_names = ["m0".to_string(), "m1".to_string()];
+        let weights = |animation: &Animation, frame| {
+            animation.morph_weights(&controller_names, &animation_names, &[0, 1], frame)
+        };
+
+        let animation = trim_test_animation();
+        assert_eq!(vec![15.0, 4.0], weights(&animation, 4.0));
+        assert_eq!(vec![15.0, 4.0], weights(&animation.trim(3, 7), 1.0));
+    }
+
+    fn extra_binding(
+        name: &str,
+        track_indices: Vec<i16>,
+        data: Option<xc3_lib::bc::anim::ExtraAnimationData>,
+    ) -> xc3_lib::bc::anim::ExtraTrackAnimationBinding {
+        xc3_lib::bc::anim::ExtraTrackAnimationBinding {
+            extra_track_animation: data.map(|data| xc3_lib::bc::anim::ExtraTrackAnimation {
+                unk1: 0,
+                name: name.to_string(),
+                animation_type: match data {
+                    xc3_lib::bc::anim::ExtraAnimationData::Uncompressed(_) => {
+                        xc3_lib::bc::anim::AnimationType::Uncompressed
+                    }
+                    xc3_lib::bc::anim::ExtraAnimationData::Cubic(_) => {
+                        xc3_lib::bc::anim::AnimationType::Cubic
+                    }
+                },
+                blend_mode: BlendMode::Blend,
+                unk2: 0,
+                unk3: 0,
+                unk4: -1,
+                data,
+            }),
+            track_indices: xc3_lib::bc::BcListCount::List(track_indices),
+        }
+    }
+
+    fn bc_list<T>(elements: Vec<T>) -> xc3_lib::bc::BcList8<T>
+    where
+        for<'a> T: binrw::BinRead<Args<'a> = ()>,
+    {
+        xc3_lib::bc::BcList8 { elements, unk1: -1 }
+    }
+
+    #[test]
+    fn morph_tracks_uncompressed_cubic() {
+        let bindings = [
+            extra_binding(
+                "face_a",
+                vec![0, -1],
+                Some(xc3_lib::bc::anim::ExtraAnimationData::Uncompressed(
+                    bc_list(vec![0.0, 0.5, 1.0]),
+                )),
+            ),
+            extra_binding("face_none", vec![0], None),
+            extra_binding(
+                "face_b",
+                vec![1, 0],
+                Some(xc3_lib::bc::anim::ExtraAnimationData::Cubic(bc_list(vec![
+                    // x
+                    bc_list(vec![[0.0, 0.0, 0.0, 1.0, 0.0]]),
+                    // 1 then 5 starting at frame 2
+                    bc_list(vec![[0.0, 0.0, 0.0, 0.0, 1.0], [2.0, 0.0, 0.0, 0.0, 5.0]]),
+                ]))),
+            ),
+        ];
+
+        assert_eq!(
+            BTreeMap::from([
+                (
+                    "face_a".to_string(),
+                    MorphTracks {
+                        track_indices: vec![0, -1],
+                        track_values: vec![0.0, 0.5, 1.0]
+                    }
+                ),
+                (
+                    "face_b".to_string(),
+                    MorphTracks {
+                        track_indices: vec![1, 0],
+                        track_values: vec![0.0, 1.0, 2.0, 1.0, 1.0, 5.0]
+                    }
+                )
+            ]),
+            morph_binding_tracks(&bindings, 3)
+        );
+    }
+
+    #[test]
+    fn animation_info_duration() {
+        let info = AnimationInfo::new("a", 30.0, 45);
+        assert_eq!(1.5, info.duration_seconds);
+    }
+
+    #[test]
+    fn animation_info_duration_zero_fps() {
+        let info = AnimationInfo::new("a", 0.0, 45);
+        assert_eq!(0.0, info.duration_seconds);
+    }
+
     #[test]
     fn index_position_no_keyframes() {
         let keyframes = keys(&[]);
@@ -1012,7 +1526,7 @@ mod tests {
             frames_per_second: 30.0,
             frame_count: 1,
             tracks: Vec::new(),
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: None,
         };
 
@@ -1031,7 +1545,7 @@ mod tests {
             frames_per_second: 30.0,
             frame_count: 1,
             tracks: Vec::new(),
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: Some(vec![Vec3::ONE]),
         };
 
@@ -1040,6 +1554,127 @@ mod tests {
             .is_empty());
     }
 
+    #[test]
+    fn root_motion_translation_deltas() {
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 3,
+            tracks: Vec::new(),
+            morph_tracks: BTreeMap::new(),
+            root_translation: Some(vec![
+                vec3(1.0, 0.0, 0.0),
+                vec3(1.5, 0.0, 1.0),
+                vec3(3.0, 0.0, 2.0),
+            ]),
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![Bone {
+                name: "a".to_string(),
+                transform: Transform::IDENTITY,
+                parent_index: None,
+            }],
+        };
+
+        assert_eq!(
+            vec![
+                Transform::IDENTITY,
+                Transform {
+                    translation: vec3(0.5, 0.0, 1.0),
+                    ..Transform::IDENTITY
+                },
+                Transform {
+                    translation: vec3(1.5, 0.0, 1.0),
+                    ..Transform::IDENTITY
+                }
+            ],
+            animation.root_motion(&skeleton)
+        );
+    }
+
+    #[test]
+    fn root_motion_no_root_bone() {
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 2,
+            tracks: Vec::new(),
+            morph_tracks: BTreeMap::new(),
+            root_translation: Some(vec![vec3(1.0, 0.0, 0.0), vec3(2.0, 0.0, 0.0)]),
+        };
+
+        assert!(animation
+            .root_motion(&Skeleton { bones: Vec::new() })
+            .is_empty());
+    }
+
+    #[test]
+    fn root_motion_empty() {
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: Vec::new(),
+            morph_tracks: BTreeMap::new(),
+            root_translation: None,
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![Bone {
+                name: "a".to_string(),
+                transform: Transform::IDENTITY,
+                parent_index: None,
+            }],
+        };
+
+        assert!(animation.root_motion(&skeleton).is_empty());
+    }
+
+    #[test]
+    fn model_space_transforms_in_place_root_motion() {
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: Vec::new(),
+            morph_tracks: BTreeMap::new(),
+            root_translation: Some(vec![vec3(1.0, 2.0, 3.0)]),
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![Bone {
+                name: "a".to_string(),
+                transform: Transform::IDENTITY,
+                parent_index: None,
+            }],
+        };
+
+        assert_eq!(
+            vec![Transform::IDENTITY],
+            animation.model_space_transforms_in_place(&skeleton, 0.0)
+        );
+        assert_eq!(
+            vec![Transform {
+                translation: vec3(1.0, 2.0, 3.0),
+                ..Transform::IDENTITY
+            }],
+            animation.model_space_transforms(&skeleton, 0.0)
+        );
+    }
+
     fn keyframe(x: f32, y: f32, z: f32, w: f32) -> (OrderedFloat<f32>, Keyframe) {
         // Crate a keyframe with a constant value.
         (
@@ -1077,7 +1712,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: None,
         };
 
@@ -1154,7 +1789,7 @@ mod tests {
                     bone_index: BoneIndex::Name("b_R".to_string()),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: None,
         };
 
@@ -1264,7 +1899,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: None,
         };
 
@@ -1329,7 +1964,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: Some(vec![vec3(0.25, 0.5, 0.75)]),
         };
 
@@ -1394,7 +2029,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: None,
         };
 
@@ -1459,7 +2094,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: Some(vec![vec3(0.25, 0.5, 0.75)]),
         };
 
@@ -1538,7 +2173,7 @@ mod tests {
                     bone_index: BoneIndex::Index(1),
                 },
             ],
-            morph_tracks: None,
+            morph_tracks: BTreeMap::new(),
             root_translation: Some(vec![vec3(0.25, 0.5, 0.75)]),
         };
 
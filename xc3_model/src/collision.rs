@@ -1,6 +1,6 @@
-use std::path::Path;
+use std::{collections::BTreeMap, path::Path};
 
-use glam::{Mat4, Vec4};
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
 use thiserror::Error;
 use xc3_lib::{idcm::Idcm, ReadFileError};
 
@@ -20,6 +20,334 @@ pub struct CollisionMesh {
 
     /// Triangle list vertex indices.
     pub indices: Vec<u32>,
+
+    /// The index into [face_groups](xc3_lib::idcm::Idcm::face_groups) for each triangle in
+    /// [indices](#structfield.indices).
+    pub face_group_indices: Vec<usize>,
+
+    /// The raw surface value from [unk1](xc3_lib::idcm::Faces::unk1) for each triangle in
+    /// [indices](#structfield.indices).
+    ///
+    /// The files do not store names for these values,
+    /// so the mapping to surface types like ground, water, or walls is not yet known.
+    pub surface_ids: Vec<u16>,
+
+    /// Bounding volume hierarchy for accelerating ray queries
+    /// or `None` to test every triangle in [indices](#structfield.indices).
+    ///
+    /// This must be rebuilt with [CollisionMeshes::build_bvhs] after modifying the triangles.
+    pub bvh: Option<CollisionBvh>,
+}
+
+/// A bounding volume hierarchy over the triangles of a [CollisionMesh].
+///
+/// The IDCM format's own acceleration data is not yet understood,
+/// so the hierarchy is created from the triangles when loading.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CollisionBvh {
+    nodes: Vec<BvhNode>,
+    triangle_indices: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct BvhNode {
+    min: Vec3,
+    max: Vec3,
+    /// The first child node index for interior nodes or the first triangle for leaves.
+    start: usize,
+    /// The number of triangles for leaves or 0 for interior nodes.
+    count: usize,
+}
+
+// Larger leaves reduce the tree size at the cost of more triangle tests.
+const BVH_LEAF_SIZE: usize = 4;
+
+/// The closest intersection found by [CollisionMeshes::raycast].
+#[derive(Debug, PartialEq, Clone)]
+pub struct CollisionHit {
+    /// The intersection point in world space.
+    pub position: Vec3,
+    /// The distance from the ray origin to [position](#structfield.position).
+    pub distance: f32,
+    /// The index into [meshes](struct.CollisionMeshes.html#structfield.meshes).
+    pub mesh_index: usize,
+    /// The index into [instances](struct.CollisionMesh.html#structfield.instances)
+    /// or `None` if the mesh has only a single instance.
+    pub instance_index: Option<usize>,
+    /// The triangle index for [indices](struct.CollisionMesh.html#structfield.indices).
+    pub triangle_index: usize,
+    /// The index into [face_groups](xc3_lib::idcm::Idcm::face_groups) for the hit triangle.
+    pub face_group_index: usize,
+    /// The value in [surface_ids](struct.CollisionMesh.html#structfield.surface_ids)
+    /// for the hit triangle.
+    pub surface_id: u16,
+}
+
+impl CollisionMeshes {
+    /// Build the [bvh](struct.CollisionMesh.html#structfield.bvh) for each mesh.
+    ///
+    /// This is done automatically by [load_collisions](crate::load_collisions).
+    pub fn build_bvhs(&mut self) {
+        for mesh in &mut self.meshes {
+            mesh.bvh = Some(CollisionBvh::from_triangles(&self.vertices, &mesh.indices));
+        }
+    }
+
+    /// Find the closest intersection of the ray from `origin` in the direction `dir`
+    /// with any mesh instance.
+    ///
+    /// Meshes without a [bvh](struct.CollisionMesh.html#structfield.bvh) test every triangle.
+    pub fn raycast(&self, origin: Vec3, dir: Vec3) -> Option<CollisionHit> {
+        let dir = dir.normalize_or_zero();
+        if dir == Vec3::ZERO {
+            return None;
+        }
+
+        let mut closest: Option<CollisionHit> = None;
+        self.intersect_ray(origin, dir, |hit| {
+            if closest
+                .as_ref()
+                .map(|c| hit.distance < c.distance)
+                .unwrap_or(true)
+            {
+                closest = Some(hit);
+            }
+        });
+        closest
+    }
+
+    /// Returns `true` if `point` is inside any of the mesh instances.
+    ///
+    /// This counts ray intersections and assumes each mesh is closed.
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        // Avoid axis aligned directions that are more likely to hit edges.
+        let dir = Vec3::new(0.5773, 0.5774, 0.5773).normalize();
+
+        // Count intersections per instance to handle overlapping meshes.
+        let mut hit_counts = BTreeMap::new();
+        self.intersect_ray(point, dir, |hit| {
+            *hit_counts
+                .entry((hit.mesh_index, hit.instance_index))
+                .or_insert(0usize) += 1;
+        });
+        hit_counts.values().any(|count| count % 2 == 1)
+    }
+
+    fn intersect_ray<F: FnMut(CollisionHit)>(&self, origin: Vec3, dir: Vec3, mut f: F) {
+        for (mesh_index, mesh) in self.meshes.iter().enumerate() {
+            if mesh.instances.is_empty() {
+                self.intersect_mesh(mesh_index, None, Mat4::IDENTITY, origin, dir, &mut f);
+            } else {
+                for (i, transform) in mesh.instances.iter().enumerate() {
+                    self.intersect_mesh(mesh_index, Some(i), *transform, origin, dir, &mut f);
+                }
+            }
+        }
+    }
+
+    fn intersect_mesh<F: FnMut(CollisionHit)>(
+        &self,
+        mesh_index: usize,
+        instance_index: Option<usize>,
+        transform: Mat4,
+        origin: Vec3,
+        dir: Vec3,
+        f: &mut F,
+    ) {
+        let mesh = &self.meshes[mesh_index];
+
+        // Transforming the ray preserves the distance along the ray.
+        let inverse = transform.inverse();
+        let local_origin = inverse.transform_point3(origin);
+        let local_dir = inverse.transform_vector3(dir);
+
+        let intersect = |triangle_index: usize| {
+            let Some(face) = mesh.indices.get(triangle_index * 3..triangle_index * 3 + 3) else {
+                return;
+            };
+            let vertex = |i: u32| self.vertices.get(i as usize).map(|v| v.xyz());
+            let (Some(v0), Some(v1), Some(v2)) =
+                (vertex(face[0]), vertex(face[1]), vertex(face[2]))
+            else {
+                return;
+            };
+
+            if let Some(distance) = intersect_triangle(local_origin, local_dir, v0, v1, v2) {
+                f(CollisionHit {
+                    position: origin + dir * distance,
+                    distance,
+                    mesh_index,
+                    instance_index,
+                    triangle_index,
+                    face_group_index: mesh
+                        .face_group_indices
+                        .get(triangle_index)
+                        .copied()
+                        .unwrap_or_default(),
+                    surface_id: mesh
+                        .surface_ids
+                        .get(triangle_index)
+                        .copied()
+                        .unwrap_or_default(),
+                });
+            }
+        };
+
+        match &mesh.bvh {
+            Some(bvh) => bvh.intersect_ray(local_origin, local_dir, intersect),
+            None => (0..mesh.indices.len() / 3).for_each(intersect),
+        }
+    }
+}
+
+impl CollisionBvh {
+    /// Build a hierarchy for the triangle list `indices` into `vertices`.
+    pub fn from_triangles(vertices: &[Vec4], indices: &[u32]) -> Self {
+        let vertex = |i: u32| vertices.get(i as usize).map(|v| v.xyz());
+
+        // Skip triangles with out of range indices since they can never be hit.
+        let bounds: Vec<_> = indices
+            .chunks_exact(3)
+            .map(|face| {
+                let (v0, v1, v2) = (vertex(face[0])?, vertex(face[1])?, vertex(face[2])?);
+                Some((v0.min(v1).min(v2), v0.max(v1).max(v2)))
+            })
+            .collect();
+        let mut triangle_indices: Vec<_> = bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| b.map(|_| i))
+            .collect();
+        let bounds: Vec<_> = bounds.into_iter().map(Option::unwrap_or_default).collect();
+
+        let mut nodes = vec![BvhNode {
+            min: Vec3::ZERO,
+            max: Vec3::ZERO,
+            start: 0,
+            count: triangle_indices.len(),
+        }];
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let start = nodes[node_index].start;
+            let count = nodes[node_index].count;
+            let triangles = &mut triangle_indices[start..start + count];
+
+            let (min, max) = triangles.iter().fold(
+                (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+                |(min, max), i| (min.min(bounds[*i].0), max.max(bounds[*i].1)),
+            );
+            nodes[node_index].min = min;
+            nodes[node_index].max = max;
+
+            if count <= BVH_LEAF_SIZE {
+                continue;
+            }
+
+            // Split at the median centroid along the longest axis.
+            let size = max - min;
+            let axis = if size.x >= size.y && size.x >= size.z {
+                0
+            } else if size.y >= size.z {
+                1
+            } else {
+                2
+            };
+            let centroid = |i: &usize| (bounds[*i].0[axis] + bounds[*i].1[axis]) * 0.5;
+            let mid = count / 2;
+            triangles.select_nth_unstable_by(mid, |a, b| centroid(a).total_cmp(&centroid(b)));
+
+            let children = nodes.len();
+            nodes.push(BvhNode {
+                min: Vec3::ZERO,
+                max: Vec3::ZERO,
+                start,
+                count: mid,
+            });
+            nodes.push(BvhNode {
+                min: Vec3::ZERO,
+                max: Vec3::ZERO,
+                start: start + mid,
+                count: count - mid,
+            });
+            nodes[node_index].start = children;
+            nodes[node_index].count = 0;
+            stack.extend([children, children + 1]);
+        }
+
+        Self {
+            nodes,
+            triangle_indices,
+        }
+    }
+
+    fn intersect_ray<F: FnMut(usize)>(&self, origin: Vec3, dir: Vec3, mut f: F) {
+        let inv_dir = dir.recip();
+
+        let mut stack = vec![0];
+        while let Some(node_index) = stack.pop() {
+            let Some(node) = self.nodes.get(node_index) else {
+                continue;
+            };
+            if !intersect_aabb(origin, inv_dir, node.min, node.max) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for i in &self.triangle_indices[node.start..node.start + node.count] {
+                    f(*i);
+                }
+            } else {
+                stack.extend([node.start, node.start + 1]);
+            }
+        }
+    }
+}
+
+// Slab test that also accepts rays starting inside the box.
+fn intersect_aabb(origin: Vec3, inv_dir: Vec3, min: Vec3, max: Vec3) -> bool {
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        if inv_dir[axis].is_infinite() {
+            // Rays parallel to the slab only need the origin to be inside.
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return false;
+            }
+        } else {
+            let t0 = (min[axis] - origin[axis]) * inv_dir[axis];
+            let t1 = (max[axis] - origin[axis]) * inv_dir[axis];
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+    }
+    t_min <= t_max
+}
+
+// Möller–Trumbore intersection for both triangle facings.
+fn intersect_triangle(origin: Vec3, dir: Vec3, v0: Vec3, v1: Vec3, v2: Vec3) -> Option<f32> {
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = dir.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = origin - v0;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    (t >= 0.0).then_some(t)
 }
 
 #[derive(Debug, Error)]
@@ -50,6 +378,8 @@ pub fn load_collisions<P: AsRef<Path>>(
         .zip(idcm.mesh_names)
         .map(|(mesh, name)| {
             let mut indices = Vec::new();
+            let mut face_group_indices = Vec::new();
+            let mut surface_ids = Vec::new();
 
             let (start, count) = match mesh {
                 xc3_lib::idcm::MeshVersioned::MeshLegacy(m) => {
@@ -61,9 +391,10 @@ pub fn load_collisions<P: AsRef<Path>>(
             };
 
             // Each fan needs to be handled individually.
-            for group in idcm
+            for (face_group_index, group) in idcm
                 .face_groups
                 .iter()
+                .enumerate()
                 .skip(start as usize)
                 .take(count as usize)
             {
@@ -78,6 +409,8 @@ pub fn load_collisions<P: AsRef<Path>>(
                         group.faces.vertex_indices[i + 2] as u32 + start,
                         group.faces.vertex_indices[0] as u32 + start,
                     ]);
+                    face_group_indices.push(face_group_index);
+                    surface_ids.push(group.faces.unk1.get(i).copied().unwrap_or_default());
                 }
             }
 
@@ -85,6 +418,9 @@ pub fn load_collisions<P: AsRef<Path>>(
                 name: name.name,
                 instances: Vec::new(),
                 indices,
+                face_group_indices,
+                surface_ids,
+                bvh: None,
             }
         })
         .collect();
@@ -101,8 +437,171 @@ pub fn load_collisions<P: AsRef<Path>>(
             .push(Mat4::from_cols_array_2d(&transform.transform).transpose());
     }
 
-    Ok(CollisionMeshes {
+    let mut collisions = CollisionMeshes {
         vertices: idcm.vertices.into_iter().map(Into::into).collect(),
         meshes,
-    })
+    };
+    collisions.build_bvhs();
+    Ok(collisions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{vec3, vec4};
+
+    fn cube() -> CollisionMeshes {
+        let vertices = [
+            [-1.0, -1.0, -1.0],
+            [1.0, -1.0, -1.0],
+            [1.0, 1.0, -1.0],
+            [-1.0, 1.0, -1.0],
+            [-1.0, -1.0, 1.0],
+            [1.0, -1.0, 1.0],
+            [1.0, 1.0, 1.0],
+            [-1.0, 1.0, 1.0],
+        ]
+        .map(|[x, y, z]| vec4(x, y, z, 0.0))
+        .to_vec();
+
+        let indices = vec![
+            0, 2, 1, 0, 3, 2, 4, 5, 6, 4, 6, 7, 0, 1, 5, 0, 5, 4, 3, 7, 6, 3, 6, 2, 0, 4, 7, 0, 7,
+            3, 1, 2, 6, 1, 6, 5,
+        ];
+
+        CollisionMeshes {
+            vertices,
+            meshes: vec![CollisionMesh {
+                name: "cube".to_string(),
+                instances: Vec::new(),
+                indices,
+                face_group_indices: (0..12).collect(),
+                surface_ids: (0..12).map(|i| i / 2).collect(),
+                bvh: None,
+            }],
+        }
+    }
+
+    // Splits each cube face into a grid to produce enough triangles for multiple BVH levels.
+    fn grid_cube(divisions: usize) -> CollisionMeshes {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for axis in 0..3 {
+            for side in [-1.0, 1.0] {
+                let start = vertices.len() as u32;
+                for i in 0..=divisions {
+                    for j in 0..=divisions {
+                        let u = i as f32 / divisions as f32 * 2.0 - 1.0;
+                        let v = j as f32 / divisions as f32 * 2.0 - 1.0;
+                        let mut p = [0.0; 3];
+                        p[axis] = side;
+                        p[(axis + 1) % 3] = u;
+                        p[(axis + 2) % 3] = v;
+                        vertices.push(vec4(p[0], p[1], p[2], 0.0));
+                    }
+                }
+                let row = divisions as u32 + 1;
+                for i in 0..divisions as u32 {
+                    for j in 0..divisions as u32 {
+                        let v0 = start + i * row + j;
+                        indices.extend_from_slice(&[v0, v0 + row, v0 + 1]);
+                        indices.extend_from_slice(&[v0 + 1, v0 + row, v0 + row + 1]);
+                    }
+                }
+            }
+        }
+
+        let triangle_count = indices.len() / 3;
+        CollisionMeshes {
+            vertices,
+            meshes: vec![CollisionMesh {
+                name: "cube".to_string(),
+                instances: vec![Mat4::from_translation(vec3(10.0, 0.0, 0.0))],
+                indices,
+                face_group_indices: (0..triangle_count).collect(),
+                surface_ids: vec![0; triangle_count],
+                bvh: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn raycast_cube() {
+        let hit = cube()
+            .raycast(vec3(0.25, 5.0, 0.5), vec3(0.0, -2.0, 0.0))
+            .unwrap();
+        assert_eq!(vec3(0.25, 1.0, 0.5), hit.position);
+        assert_eq!(4.0, hit.distance);
+        assert_eq!(0, hit.mesh_index);
+        assert_eq!(None, hit.instance_index);
+        assert_eq!(hit.triangle_index, hit.face_group_index);
+        assert_eq!(3, hit.surface_id);
+        assert!((6..8).contains(&hit.triangle_index));
+    }
+
+    #[test]
+    fn raycast_cube_instance_miss() {
+        let mut collisions = cube();
+        collisions.meshes[0].instances = vec![Mat4::from_translation(vec3(10.0, 0.0, 0.0))];
+
+        assert_eq!(
+            None,
+            collisions.raycast(vec3(0.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0))
+        );
+        let hit = collisions
+            .raycast(vec3(10.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0))
+            .unwrap();
+        assert_eq!(Some(0), hit.instance_index);
+        assert_eq!(4.0, hit.distance);
+    }
+
+    #[test]
+    fn contains_point_cube() {
+        let collisions = cube();
+        assert!(collisions.contains_point(vec3(0.1, 0.2, 0.3)));
+        assert!(!collisions.contains_point(vec3(2.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn build_bvhs_cube() {
+        let mut collisions = grid_cube(8);
+        collisions.build_bvhs();
+
+        let bvh = collisions.meshes[0].bvh.as_ref().unwrap();
+        assert!(bvh.nodes.len() > 1);
+        assert_eq!(Vec3::splat(-1.0), bvh.nodes[0].min);
+        assert_eq!(Vec3::splat(1.0), bvh.nodes[0].max);
+
+        // Each triangle is in exactly one leaf.
+        let mut triangles = bvh.triangle_indices.clone();
+        triangles.sort();
+        assert_eq!((0..8 * 8 * 2 * 6).collect::<Vec<_>>(), triangles);
+    }
+
+    #[test]
+    fn raycast_bvh_matches_brute_force() {
+        let brute_force = grid_cube(8);
+        let mut collisions = brute_force.clone();
+        collisions.build_bvhs();
+
+        let rays = [
+            (vec3(10.3, 5.0, 0.55), vec3(0.0, -1.0, 0.0)),
+            (vec3(5.0, 0.3, -0.2), vec3(1.0, 0.0, 0.0)),
+            (vec3(10.1, 0.2, 0.3), vec3(0.3, 0.5, -1.0)),
+            (vec3(14.0, 4.3, 3.1), vec3(-1.1, -1.0, -0.8)),
+            (vec3(0.0, 5.0, 0.0), vec3(0.0, -1.0, 0.0)),
+            (vec3(10.0, 5.0, 0.0), vec3(0.0, 1.0, 0.0)),
+        ];
+        for (origin, dir) in rays {
+            assert_eq!(
+                brute_force.raycast(origin, dir),
+                collisions.raycast(origin, dir)
+            );
+        }
+
+        assert!(collisions.contains_point(vec3(10.1, 0.2, 0.3)));
+        assert!(!collisions.contains_point(vec3(0.1, 0.2, 0.3)));
+        assert!(!collisions.contains_point(vec3(12.0, 0.0, 0.0)));
+    }
 }
@@ -0,0 +1,384 @@
+use glam::{Mat3, Vec2, Vec3, Vec4, Vec4Swizzles};
+use xc3_lib::mxmd::RenderPassType;
+
+use crate::{
+    skinning::SkinWeights,
+    vertex::{AttributeData, ModelBuffers, PrimitiveType, VertexBuffer},
+    Mesh, Models,
+};
+
+/// The vertices and triangles of all meshes combined into a single mesh.
+/// See [Models::flatten].
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FlatMesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec4>,
+    pub texcoords: Vec<Vec2>,
+    /// Triangle list vertex indices into the combined vertex data.
+    pub indices: Vec<u32>,
+    /// The index into [materials](struct.Models.html#structfield.materials)
+    /// for each triangle in [indices](#structfield.indices).
+    pub material_indices: Vec<usize>,
+    /// Per vertex skin weights using a single bone list for all meshes.
+    pub skin_weights: Option<SkinWeights>,
+}
+
+pub(crate) fn flatten_models(models: &Models, buffers: &[ModelBuffers]) -> FlatMesh {
+    let bone_names = unified_bone_names(buffers);
+
+    let mut flat_mesh = FlatMesh::default();
+    let mut bone_indices = Vec::new();
+    let mut weights = Vec::new();
+
+    for model in &models.models {
+        let Some(model_buffers) = buffers.get(model.model_buffers_index) else {
+            continue;
+        };
+
+        for mesh in model.meshes.iter().filter(|m| include_mesh(models, m)) {
+            let (Some(vertex_buffer), Some(index_buffer)) = (
+                model_buffers.vertex_buffers.get(mesh.vertex_buffer_index),
+                model_buffers.index_buffers.get(mesh.index_buffer_index),
+            ) else {
+                continue;
+            };
+            if index_buffer.primitive_type != PrimitiveType::TriangleList {
+                continue;
+            }
+
+            let vertex_count = vertex_buffer.vertex_count();
+            let mesh_weights = bone_names
+                .as_ref()
+                .map(|names| mesh_skin_weights(models, model_buffers, mesh, vertex_buffer, names));
+
+            for instance in &model.instances {
+                let start_vertex = flat_mesh.positions.len() as u32;
+
+                let normal_transform = Mat3::from_mat4(*instance);
+                let (positions, normals, texcoords) = vertex_attributes(vertex_buffer);
+                flat_mesh.positions.extend(
+                    positions
+                        .iter()
+                        .map(|p| instance.transform_point3(*p))
+                        .chain(std::iter::repeat(Vec3::ZERO))
+                        .take(vertex_count),
+                );
+                flat_mesh.normals.extend(
+                    normals
+                        .iter()
+                        .map(|n| (normal_transform * n.xyz()).normalize_or_zero().extend(n.w))
+                        .chain(std::iter::repeat(Vec4::ZERO))
+                        .take(vertex_count),
+                );
+                flat_mesh.texcoords.extend(
+                    texcoords
+                        .iter()
+                        .copied()
+                        .chain(std::iter::repeat(Vec2::ZERO))
+                        .take(vertex_count),
+                );
+
+                for triangle in index_buffer.indices.chunks_exact(3) {
+                    flat_mesh
+                        .indices
+                        .extend(triangle.iter().map(|i| *i as u32 + start_vertex));
+                    flat_mesh.material_indices.push(mesh.material_index);
+                }
+
+                if let Some((mesh_bone_indices, mesh_weights)) = &mesh_weights {
+                    bone_indices.extend_from_slice(mesh_bone_indices);
+                    weights.extend_from_slice(mesh_weights);
+                }
+            }
+        }
+    }
+
+    flat_mesh.skin_weights = bone_names.map(|bone_names| SkinWeights {
+        bone_indices,
+        weights,
+        bone_names,
+    });
+
+    flat_mesh
+}
+
+fn include_mesh(models: &Models, mesh: &Mesh) -> bool {
+    // Only export the base LOD and skip meshes like outlines that don't render normally.
+    let is_base_lod = models
+        .lod_data
+        .as_ref()
+        .map(|d| d.is_base_lod(mesh.lod_item_index))
+        .unwrap_or(true);
+    let is_visible = models
+        .materials
+        .get(mesh.material_index)
+        .map(|m| !m.name.ends_with("_outline") && !m.name.contains("_speff_"))
+        .unwrap_or(true);
+    is_base_lod && is_visible
+}
+
+fn unified_bone_names(buffers: &[ModelBuffers]) -> Option<Vec<String>> {
+    let mut bone_names = Vec::new();
+    let mut has_weights = false;
+    for weights in buffers.iter().filter_map(|b| b.weights.as_ref()) {
+        has_weights = true;
+        for name in weights.weight_buffers.iter().flat_map(|w| &w.bone_names) {
+            if !bone_names.contains(name) {
+                bone_names.push(name.clone());
+            }
+        }
+    }
+    has_weights.then_some(bone_names)
+}
+
+fn vertex_attributes(buffer: &VertexBuffer) -> (&[Vec3], &[Vec4], &[Vec2]) {
+    let mut positions: &[Vec3] = &[];
+    let mut normals: &[Vec4] = &[];
+    let mut texcoords: &[Vec2] = &[];
+    for attribute in &buffer.attributes {
+        match attribute {
+            AttributeData::Position(values) => positions = values,
+            AttributeData::Normal(values) => normals = values,
+            AttributeData::TexCoord0(values) => texcoords = values,
+            _ => (),
+        }
+    }
+    (positions, normals, texcoords)
+}
+
+fn mesh_skin_weights(
+    models: &Models,
+    buffers: &ModelBuffers,
+    mesh: &Mesh,
+    vertex_buffer: &VertexBuffer,
+    bone_names: &[String],
+) -> (Vec<[u8; 4]>, Vec<Vec4>) {
+    let vertex_count = vertex_buffer.vertex_count();
+
+    // Vertices without weights use the first bone with zero weight.
+    let unweighted = || (vec![[0u8; 4]; vertex_count], vec![Vec4::ZERO; vertex_count]);
+
+    let Some(weights) = &buffers.weights else {
+        return unweighted();
+    };
+    let Some(skin_weights) = weights.weight_buffer(mesh.flags2.into()) else {
+        return unweighted();
+    };
+    let weight_indices = vertex_buffer.attributes.iter().find_map(|a| match a {
+        AttributeData::WeightIndex(indices) => Some(indices),
+        AttributeData::WeightIndex2(indices) => Some(indices),
+        _ => None,
+    });
+    let Some(weight_indices) = weight_indices else {
+        return unweighted();
+    };
+
+    let skin_weights = skin_weights.reindex_bones(bone_names.to_vec());
+
+    let pass_type = models
+        .materials
+        .get(mesh.material_index)
+        .map(|m| m.pass_type)
+        .unwrap_or(RenderPassType::Unk0);
+    let start_index = weights.weight_groups.weights_start_index(
+        mesh.flags2.into(),
+        mesh.lod_item_index,
+        pass_type,
+    );
+
+    // Index each vertex individually to keep the weights aligned with the vertices.
+    weight_indices
+        .iter()
+        .map(|i| i[0] as usize + start_index)
+        .map(|i| {
+            match (
+                skin_weights.bone_indices.get(i),
+                skin_weights.weights.get(i),
+            ) {
+                (Some(bone_indices), Some(weights)) => (*bone_indices, *weights),
+                _ => ([0; 4], Vec4::ZERO),
+            }
+        })
+        .chain(std::iter::repeat(([0; 4], Vec4::ZERO)))
+        .take(vertex_count)
+        .unzip()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glam::{vec2, vec3, vec4, Mat4};
+
+    use crate::{
+        skinning::{WeightGroups, Weights},
+        vertex::IndexBuffer,
+        Model,
+    };
+
+    fn mesh(vertex_buffer_index: usize, material_index: usize) -> Mesh {
+        Mesh {
+            flags1: 0,
+            flags2: 0u32.try_into().unwrap(),
+            vertex_buffer_index,
+            index_buffer_index: vertex_buffer_index,
+            index_buffer_index2: 0,
+            material_index,
+            ext_mesh_index: None,
+            lod_item_index: None,
+            base_mesh_index: None,
+        }
+    }
+
+    fn vertex_buffer(attributes: Vec<AttributeData>) -> VertexBuffer {
+        VertexBuffer {
+            attributes,
+            morph_blend_target: Vec::new(),
+            morph_targets: Vec::new(),
+            outline_buffer_index: None,
+        }
+    }
+
+    fn triangle() -> IndexBuffer {
+        IndexBuffer {
+            indices: vec![0, 1, 2],
+            primitive_type: PrimitiveType::TriangleList,
+        }
+    }
+
+    fn models(meshes: Vec<Mesh>, instances: Vec<Mat4>) -> Models {
+        Models {
+            models: vec![Model {
+                meshes,
+                instances,
+                model_buffers_index: 0,
+                max_xyz: Vec3::ZERO,
+                min_xyz: Vec3::ZERO,
+                bounding_radius: 0.0,
+            }],
+            materials: Vec::new(),
+            samplers: Vec::new(),
+            skinning: None,
+            lod_data: None,
+            morph_controller_names: Vec::new(),
+            animation_morph_names: Vec::new(),
+            max_xyz: Vec3::ZERO,
+            min_xyz: Vec3::ZERO,
+        }
+    }
+
+    #[test]
+    fn flatten_meshes_instances() {
+        let models = models(
+            vec![mesh(0, 0), mesh(1, 2)],
+            vec![Mat4::IDENTITY, Mat4::from_translation(vec3(1.0, 0.0, 0.0))],
+        );
+        let buffers = ModelBuffers {
+            vertex_buffers: vec![
+                vertex_buffer(vec![
+                    AttributeData::Position(vec![Vec3::X, Vec3::Y, Vec3::Z]),
+                    AttributeData::Normal(vec![vec4(0.0, 1.0, 0.0, 1.0); 3]),
+                    AttributeData::TexCoord0(vec![vec2(0.5, 0.5); 3]),
+                ]),
+                vertex_buffer(vec![AttributeData::Position(vec![
+                    Vec3::ZERO,
+                    Vec3::ONE,
+                    Vec3::X,
+                ])]),
+            ],
+            outline_buffers: Vec::new(),
+            index_buffers: vec![triangle(), triangle()],
+            unk_buffers: Vec::new(),
+            unk_data: None,
+            weights: None,
+        };
+
+        let flat_mesh = models.flatten(&[buffers]);
+        assert_eq!(
+            vec![
+                Vec3::X,
+                Vec3::Y,
+                Vec3::Z,
+                vec3(2.0, 0.0, 0.0),
+                vec3(1.0, 1.0, 0.0),
+                vec3(1.0, 0.0, 1.0),
+                Vec3::ZERO,
+                Vec3::ONE,
+                Vec3::X,
+                vec3(1.0, 0.0, 0.0),
+                vec3(2.0, 1.0, 1.0),
+                vec3(2.0, 0.0, 0.0),
+            ],
+            flat_mesh.positions
+        );
+        assert_eq!(12, flat_mesh.normals.len());
+        assert_eq!(vec4(0.0, 1.0, 0.0, 1.0), flat_mesh.normals[3]);
+        assert_eq!(Vec4::ZERO, flat_mesh.normals[6]);
+        assert_eq!(12, flat_mesh.texcoords.len());
+        assert_eq!(vec2(0.5, 0.5), flat_mesh.texcoords[5]);
+        assert_eq!(Vec2::ZERO, flat_mesh.texcoords[6]);
+        assert_eq!((0..12).collect::<Vec<u32>>(), flat_mesh.indices);
+        assert_eq!(vec![0, 0, 2, 2], flat_mesh.material_indices);
+        assert_eq!(None, flat_mesh.skin_weights);
+    }
+
+    #[test]
+    fn flatten_unified_bone_names() {
+        let models = models(vec![mesh(0, 0)], vec![Mat4::IDENTITY]);
+        let buffers = [
+            ModelBuffers {
+                vertex_buffers: vec![vertex_buffer(vec![
+                    AttributeData::Position(vec![Vec3::X, Vec3::Y, Vec3::Z]),
+                    AttributeData::WeightIndex(vec![[1, 0], [0, 0], [2, 0]]),
+                ])],
+                outline_buffers: Vec::new(),
+                index_buffers: vec![triangle()],
+                unk_buffers: Vec::new(),
+                unk_data: None,
+                weights: Some(Weights {
+                    weight_buffers: vec![SkinWeights {
+                        bone_indices: vec![[0, 1, 0, 0], [1, 0, 0, 0]],
+                        weights: vec![vec4(0.5, 0.5, 0.0, 0.0), vec4(1.0, 0.0, 0.0, 0.0)],
+                        bone_names: vec!["b".to_string(), "a".to_string()],
+                    }],
+                    weight_groups: WeightGroups::Groups {
+                        weight_groups: Vec::new(),
+                        weight_lods: Vec::new(),
+                    },
+                }),
+            },
+            ModelBuffers {
+                vertex_buffers: Vec::new(),
+                outline_buffers: Vec::new(),
+                index_buffers: Vec::new(),
+                unk_buffers: Vec::new(),
+                unk_data: None,
+                weights: Some(Weights {
+                    weight_buffers: vec![SkinWeights {
+                        bone_indices: Vec::new(),
+                        weights: Vec::new(),
+                        bone_names: vec!["c".to_string(), "a".to_string()],
+                    }],
+                    weight_groups: WeightGroups::Groups {
+                        weight_groups: Vec::new(),
+                        weight_lods: Vec::new(),
+                    },
+                }),
+            },
+        ];
+
+        let flat_mesh = models.flatten(&buffers);
+        assert_eq!(
+            Some(SkinWeights {
+                bone_indices: vec![[1, 0, 0, 0], [0, 1, 0, 0], [0, 0, 0, 0]],
+                weights: vec![
+                    vec4(1.0, 0.0, 0.0, 0.0),
+                    vec4(0.5, 0.5, 0.0, 0.0),
+                    Vec4::ZERO
+                ],
+                bone_names: vec!["b".to_string(), "a".to_string(), "c".to_string()],
+            }),
+            flat_mesh.skin_weights
+        );
+    }
+}
@@ -13,12 +13,12 @@
 //! let root = xc3_model::load_model("xeno3/chr/ch/ch01027000.wimdo", Some(&database))?;
 //! let shader_textures = ShaderTextures::from_folder("xeno3/monolib/shader");
 //! let animations = xc3_model::load_animations("xeno3/chr/ch/ch01027000_event.mot")?;
-//! let gltf = GltfFile::from_model("mio_military", &[root], &animations, &shader_textures, false)?;
+//! let gltf = GltfFile::from_model("mio_military", &[root], &animations, &shader_textures, false, false)?;
 //! gltf.save("mio_military.gltf")?;
 //!
 //! // Xenoblade X models need to have images and UVs flipped.
 //! let root = xc3_model::load_model("xenox/chr_np/np009001.camdo", Some(&database))?;
-//! let gltf = GltfFile::from_model("tatsu", &[root], &animations, &ShaderTextures::default(), true)?;
+//! let gltf = GltfFile::from_model("tatsu", &[root], &animations, &ShaderTextures::default(), true, false)?;
 //! gltf.save("tatsu.gltf")?;
 //!
 //! // Maps have multiple roots.
@@ -36,7 +36,7 @@ use crate::{
     Transform,
 };
 use animation::add_animations;
-use glam::Mat4;
+use glam::{Mat4, Vec3, Vec4Swizzles};
 use gltf::json::validation::Checked::Valid;
 use rayon::prelude::*;
 use thiserror::Error;
@@ -102,6 +102,7 @@ struct GltfData {
     scene_nodes: Vec<gltf::json::Index<gltf::json::Node>>,
     skins: Vec<gltf::json::Skin>,
     animations: Vec<gltf::json::animation::Animation>,
+    bake_vertex_colors: bool,
 }
 
 impl GltfData {
@@ -152,6 +153,7 @@ impl GltfData {
                 name: None,
                 nodes: self.scene_nodes,
             }],
+            extensions_used: self.material_cache.extensions_used(),
             materials: self.material_cache.materials,
             textures: self.material_cache.textures,
             images,
@@ -229,6 +231,7 @@ impl GltfData {
                 name: None,
                 nodes: self.scene_nodes,
             }],
+            extensions_used: self.material_cache.extensions_used(),
             materials: self.material_cache.materials,
             textures: self.material_cache.textures,
             images,
@@ -250,12 +253,14 @@ impl GltfData {
         animations: &[Animation],
         shader_textures: &ShaderTextures,
         flip_images_uvs: bool,
+        bake_vertex_colors: bool,
     ) -> Result<Self, CreateGltfError> {
         let mut data = GltfData {
             texture_cache: TextureCache::new(
                 roots.iter().map(|r| &r.image_textures),
                 shader_textures,
             ),
+            bake_vertex_colors,
             ..Default::default()
         };
 
@@ -386,6 +391,10 @@ impl GltfFile {
     ///
     /// `flip_image_uvs` should only be set to `true` for Xenoblade X models.
     ///
+    /// If `bake_vertex_colors` is `true`, the average vertex color RGB for each mesh
+    /// is applied to the material base color factor for applications that ignore vertex colors.
+    /// The vertex color attribute is not exported for meshes with baked vertex colors.
+    ///
     /// Skeletons from all `roots` will be merged into a single skeleton with all bones.
     /// Each animation in `animations` will apply to this combined skeleton.
     pub fn from_model(
@@ -394,6 +403,7 @@ impl GltfFile {
         animations: &[Animation],
         shader_textures: &ShaderTextures,
         flip_images_uvs: bool,
+        bake_vertex_colors: bool,
     ) -> Result<Self, CreateGltfError> {
         GltfData::from_model(
             model_name,
@@ -401,6 +411,7 @@ impl GltfFile {
             animations,
             shader_textures,
             flip_images_uvs,
+            bake_vertex_colors,
         )?
         .into_gltf(model_name, flip_images_uvs)
     }
@@ -412,6 +423,8 @@ impl GltfFile {
     /// usually match the file name for [save](GltfFile::save) without the `.gltf` extension.
     ///
     /// `flip_images_uvs` should only be set to `true` for Xenoblade X maps.
+    ///
+    /// Identical textures from different `roots` are only converted to images once.
     pub fn from_map(
         model_name: &str,
         roots: &[MapRoot],
@@ -430,7 +443,7 @@ impl GltfFile {
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # use xc3_model::gltf::GltfFile;
     /// # let roots = Vec::new();
-    /// let gltf_file = GltfFile::from_model("model", &roots, &[], &Default::default(), false)?;
+    /// let gltf_file = GltfFile::from_model("model", &roots, &[], &Default::default(), false, false)?;
     /// gltf_file.save("model.gltf")?;
     /// # Ok(())
     /// # }
@@ -460,12 +473,17 @@ impl GlbFile {
     /// usually match the file name for [save](GlbFile::save) without the `.glb` extension.
     ///
     /// `flip_image_uvs` should only be set to `true` for Xenoblade X models.
+    ///
+    /// If `bake_vertex_colors` is `true`, the average vertex color RGB for each mesh
+    /// is applied to the material base color factor for applications that ignore vertex colors.
+    /// The vertex color attribute is not exported for meshes with baked vertex colors.
     pub fn from_model(
         model_name: &str,
         roots: &[ModelRoot],
         animations: &[Animation],
         shader_textures: &ShaderTextures,
         flip_images_uvs: bool,
+        bake_vertex_colors: bool,
     ) -> Result<Self, CreateGltfError> {
         // TODO: Does this need a model name?
         GltfData::from_model(
@@ -474,6 +492,7 @@ impl GlbFile {
             animations,
             shader_textures,
             flip_images_uvs,
+            bake_vertex_colors,
         )?
         .into_glb(model_name, flip_images_uvs)
     }
@@ -485,6 +504,8 @@ impl GlbFile {
     /// usually match the file name for [save](GlbFile::save) without the `.glb` extension.
     ///
     /// `flip_images_uvs` should only be set to `true` for Xenoblade X maps.
+    ///
+    /// Identical textures from different `roots` are only converted to images once.
     pub fn from_map(
         model_name: &str,
         roots: &[MapRoot],
@@ -504,7 +525,7 @@ impl GlbFile {
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # use xc3_model::gltf::GlbFile;
     /// # let roots = Vec::new();
-    /// let glb_file = GlbFile::from_model("model", &roots, &[], &Default::default(), false)?;
+    /// let glb_file = GlbFile::from_model("model", &roots, &[], &Default::default(), false, false)?;
     /// glb_file.save("model.glb")?;
     /// # Ok(())
     /// # }
@@ -532,6 +553,33 @@ impl GlbFile {
     }
 }
 
+fn average_vertex_color(
+    vertex_buffer: &crate::vertex::VertexBuffer,
+    index_buffer: &crate::vertex::IndexBuffer,
+) -> Option<[u8; 3]> {
+    let colors = vertex_buffer.attributes.iter().find_map(|a| match a {
+        crate::vertex::AttributeData::VertexColor(values) => Some(values),
+        _ => None,
+    })?;
+
+    // Only include vertices used by this mesh.
+    let (sum, count) = index_buffer
+        .indices
+        .iter()
+        .filter_map(|i| colors.get(*i as usize))
+        .fold((Vec3::ZERO, 0), |(sum, count), c| {
+            (sum + c.xyz(), count + 1)
+        });
+
+    // Quantize the color to avoid creating too many unique materials.
+    (count > 0).then(|| {
+        (sum / count as f32)
+            .clamp(Vec3::ZERO, Vec3::ONE)
+            .to_array()
+            .map(|v| (v * 255.0).round() as u8)
+    })
+}
+
 #[allow(clippy::too_many_arguments)]
 fn add_models(
     models: &crate::Models,
@@ -618,6 +666,22 @@ fn add_models(
                     mesh.index_buffer_index,
                 )? as u32;
 
+                // Meshes with different vertex colors need different base color factors.
+                let vertex_color = data
+                    .bake_vertex_colors
+                    .then(|| {
+                        average_vertex_color(
+                            &model_buffers.vertex_buffers[mesh.vertex_buffer_index],
+                            &model_buffers.index_buffers[mesh.index_buffer_index],
+                        )
+                    })
+                    .flatten();
+
+                // Avoid applying vertex colors twice in applications that support them.
+                if vertex_color.is_some() {
+                    attributes.remove(&Valid(gltf::Semantic::Extras("VertexColor".to_string())));
+                }
+
                 // We lazy load meshes, so also lazy load materials to save space.
                 let material_index = data.material_cache.insert(
                     material,
@@ -628,6 +692,7 @@ fn add_models(
                         group_index,
                         models_index,
                         material_index: mesh.material_index,
+                        vertex_color,
                     },
                 );
 
@@ -841,3 +906,64 @@ fn align_bytes(bytes: &mut Vec<u8>, align: usize) {
     let aligned = bytes.len().next_multiple_of(align);
     bytes.resize(aligned, 0u8);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::vertex::{AttributeData, IndexBuffer, PrimitiveType, VertexBuffer};
+    use glam::{vec4, Vec4};
+
+    fn vertex_buffer(attributes: Vec<AttributeData>) -> VertexBuffer {
+        VertexBuffer {
+            attributes,
+            morph_blend_target: Vec::new(),
+            morph_targets: Vec::new(),
+            outline_buffer_index: None,
+        }
+    }
+
+    fn index_buffer(indices: Vec<u16>) -> IndexBuffer {
+        IndexBuffer {
+            indices,
+            primitive_type: PrimitiveType::TriangleList,
+        }
+    }
+
+    #[test]
+    fn average_vertex_color_used_vertices() {
+        // The unused green vertex should not affect the result.
+        let buffer = vertex_buffer(vec![AttributeData::VertexColor(vec![
+            vec4(1.0, 0.0, 0.0, 1.0),
+            vec4(0.0, 0.0, 1.0, 0.0),
+            vec4(0.0, 1.0, 0.0, 1.0),
+        ])]);
+        assert_eq!(
+            Some([170, 0, 85]),
+            average_vertex_color(&buffer, &index_buffer(vec![0, 1, 0]))
+        );
+    }
+
+    #[test]
+    fn average_vertex_color_clamp() {
+        let buffer = vertex_buffer(vec![AttributeData::VertexColor(vec![vec4(
+            2.0, -1.0, 0.5, 1.0,
+        )])]);
+        assert_eq!(
+            Some([255, 0, 128]),
+            average_vertex_color(&buffer, &index_buffer(vec![0]))
+        );
+    }
+
+    #[test]
+    fn average_vertex_color_missing() {
+        let buffer = vertex_buffer(vec![AttributeData::Position(vec![Vec3::ZERO])]);
+        assert_eq!(None, average_vertex_color(&buffer, &index_buffer(vec![0])));
+
+        let buffer = vertex_buffer(vec![AttributeData::VertexColor(vec![Vec4::ONE])]);
+        assert_eq!(
+            None,
+            average_vertex_color(&buffer, &index_buffer(Vec::new()))
+        );
+    }
+}
@@ -6,7 +6,7 @@ use crate::gltf::texture::{
 use crate::{AddressMode, ImageTexture, Sampler};
 use gltf::json::validation::Checked::Valid;
 
-use super::texture::{emissive_generated_key, GeneratedImageKey, ImageIndex};
+use super::texture::{emissive_generated_key, emissive_strength, GeneratedImageKey, ImageIndex};
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct MaterialKey {
@@ -14,6 +14,8 @@ pub struct MaterialKey {
     pub group_index: usize,
     pub models_index: usize,
     pub material_index: usize,
+    /// The baked vertex color RGB for the base color factor.
+    pub vertex_color: Option<[u8; 3]>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -35,6 +37,19 @@ pub struct MaterialCache {
 }
 
 impl MaterialCache {
+    /// The names of the glTF extensions used by any material.
+    pub fn extensions_used(&self) -> Vec<String> {
+        let mut extensions = Vec::new();
+        if self.materials.iter().any(|m| {
+            m.extensions
+                .as_ref()
+                .is_some_and(|e| e.emissive_strength.is_some())
+        }) {
+            extensions.push("KHR_materials_emissive_strength".to_string());
+        }
+        extensions
+    }
+
     pub fn insert_samplers(
         &mut self,
         models: &crate::Models,
@@ -83,6 +98,7 @@ impl MaterialCache {
                     key.root_index,
                     sampler_base_index,
                     image_textures,
+                    key.vertex_color,
                 );
                 let new_index = self.materials.len();
                 self.materials.push(material);
@@ -125,6 +141,7 @@ fn create_material(
     root_index: usize,
     sampler_base_index: usize,
     image_textures: &[ImageTexture],
+    vertex_color: Option<[u8; 3]>,
 ) -> gltf::json::Material {
     let assignments = material.output_assignments(image_textures);
 
@@ -140,6 +157,7 @@ fn create_material(
 
     let emissive_key = emissive_generated_key(material, &assignments, root_index);
     let emissive_index = texture_cache.insert(emissive_key.clone());
+    let emissive_strength = emissive_strength(&assignments);
 
     gltf::json::Material {
         name: Some(material.name.clone()),
@@ -154,6 +172,16 @@ fn create_material(
 
                 texture_info(texture_index, &metallic_roughness_key)
             }),
+            base_color_factor: vertex_color
+                .map(|[r, g, b]| {
+                    gltf::json::material::PbrBaseColorFactor([
+                        r as f32 / 255.0,
+                        g as f32 / 255.0,
+                        b as f32 / 255.0,
+                        1.0,
+                    ])
+                })
+                .unwrap_or_default(),
             ..Default::default()
         },
         normal_texture: normal_index.map(|i| {
@@ -187,6 +215,21 @@ fn create_material(
             let texture_index = add_texture(textures, &emissive_key, i, sampler_base_index);
             texture_info(texture_index, &emissive_key)
         }),
+        // The emissive texture has no effect with the default factor of 0.0.
+        emissive_factor: gltf::json::material::EmissiveFactor(if emissive_index.is_some() {
+            [1.0; 3]
+        } else {
+            [0.0; 3]
+        }),
+        extensions: (emissive_index.is_some() && emissive_strength > 1.0).then_some(
+            gltf::json::extensions::material::Material {
+                emissive_strength: Some(gltf::json::extensions::material::EmissiveStrength {
+                    emissive_strength: gltf::json::extensions::material::EmissiveStrengthFactor(
+                        emissive_strength,
+                    ),
+                }),
+            },
+        ),
         alpha_mode: if material.alpha_test.is_some() {
             Valid(gltf::json::material::AlphaMode::Mask)
         } else {
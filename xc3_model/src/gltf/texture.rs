@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::{
     material::{ChannelAssignment, OutputAssignments, TextureAssignment},
     monolib::ShaderTextures,
@@ -10,7 +12,7 @@ use rayon::prelude::*;
 use smol_str::SmolStr;
 
 // TODO: This will eventually need to account for parameters and constants.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct GeneratedImageKey {
     pub root_index: usize,
     pub red_index: Option<ImageIndex>,
@@ -21,7 +23,7 @@ pub struct GeneratedImageKey {
     pub invert_green: bool,
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ImageIndex {
     Image {
         image_texture: usize,
@@ -40,7 +42,7 @@ pub enum ImageIndex {
     Value(OrderedFloat<f32>),
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ImageKey {
     root_index: usize,
     image_index: usize,
@@ -50,6 +52,8 @@ pub struct ImageKey {
 #[derive(Default)]
 pub struct TextureCache {
     original_images: IndexMap<ImageKey, RgbaImage>,
+    // The first identical image for each image across all roots.
+    unique_image_keys: IndexMap<ImageKey, ImageKey>,
     // Use a map that preserves insertion order to get consistent ordering.
     pub generated_texture_indices: IndexMap<GeneratedImageKey, usize>,
 
@@ -61,8 +65,14 @@ impl TextureCache {
         root_textures: impl Iterator<Item = &'a Vec<ImageTexture>>,
         shader_textures: &ShaderTextures,
     ) -> Self {
+        let root_textures: Vec<_> = root_textures.collect();
+
+        // Maps often have identical textures in multiple roots.
+        let unique_image_keys = unique_image_keys(&root_textures);
+
         // Get the base images used for channel reconstruction.
-        let original_images = create_images(root_textures);
+        // Duplicate images don't need to be decoded.
+        let original_images = create_images(&root_textures, &unique_image_keys);
 
         let shader_images = shader_textures
             .textures
@@ -78,6 +88,7 @@ impl TextureCache {
         Self {
             generated_texture_indices: IndexMap::new(),
             original_images,
+            unique_image_keys,
             shader_images,
         }
     }
@@ -90,12 +101,77 @@ impl TextureCache {
             || key.blue_index.is_some()
             || key.alpha_index.is_some()
         {
+            let key = self.unique_key(key);
             Some(self.generated_texture_indices.entry_index(key) as u32)
         } else {
             None
         }
     }
 
+    /// Reference the first identical image for each channel
+    /// to avoid generating duplicate images for different roots.
+    fn unique_key(&self, key: GeneratedImageKey) -> GeneratedImageKey {
+        let indices = [
+            &key.red_index,
+            &key.green_index,
+            &key.blue_index,
+            &key.alpha_index,
+        ];
+        let mut unique_roots = indices.iter().filter_map(|index| match index {
+            Some(ImageIndex::Image { image_texture, .. }) => Some(
+                self.unique_image_key(ImageKey {
+                    root_index: key.root_index,
+                    image_index: *image_texture,
+                })
+                .root_index,
+            ),
+            _ => None,
+        });
+
+        // Keys with only global images and values don't depend on the root.
+        // Channels can only be remapped if all images share the same root.
+        let root_index = unique_roots.next().unwrap_or_default();
+        if unique_roots.any(|r| r != root_index) {
+            return key;
+        }
+
+        let unique_index = |index: Option<ImageIndex>| match index {
+            Some(ImageIndex::Image {
+                image_texture,
+                sampler,
+                channel,
+                texcoord_name,
+                texcoord_scale,
+            }) => Some(ImageIndex::Image {
+                image_texture: self
+                    .unique_image_key(ImageKey {
+                        root_index: key.root_index,
+                        image_index: image_texture,
+                    })
+                    .image_index,
+                sampler,
+                channel,
+                texcoord_name,
+                texcoord_scale,
+            }),
+            index => index,
+        };
+
+        GeneratedImageKey {
+            root_index,
+            red_index: unique_index(key.red_index),
+            green_index: unique_index(key.green_index),
+            blue_index: unique_index(key.blue_index),
+            alpha_index: unique_index(key.alpha_index),
+            recalculate_normal_z: key.recalculate_normal_z,
+            invert_green: key.invert_green,
+        }
+    }
+
+    fn unique_image_key(&self, key: ImageKey) -> ImageKey {
+        self.unique_image_keys.get(&key).copied().unwrap_or(key)
+    }
+
     // TODO: Avoid unwrap?
     pub fn generate_png_images(
         &self,
@@ -108,6 +184,7 @@ impl TextureCache {
                 let image = generate_image(
                     key.clone(),
                     &self.original_images,
+                    &self.unique_image_keys,
                     &self.shader_images,
                     flip_vertical,
                 );
@@ -234,12 +311,16 @@ pub fn emissive_generated_key(
     assignments: &OutputAssignments,
     root_index: usize,
 ) -> GeneratedImageKey {
-    // TODO: Is it correct to assume only toon and hair materials use specular?
-    let has_emission = !matches!(assignments.mat_id(), Some(2 | 5));
-    if has_emission {
-        let red_index = image_index(material, assignments.assignments[5].x.as_ref());
-        let green_index = image_index(material, assignments.assignments[5].y.as_ref());
-        let blue_index = image_index(material, assignments.assignments[5].z.as_ref());
+    if has_emission(assignments) {
+        // Scale constants to fit in the image and apply the scale using the emissive strength.
+        let strength = emissive_strength(assignments);
+        let emissive_index = |a| match image_index(material, a) {
+            Some(ImageIndex::Value(v)) => Some(ImageIndex::Value(v / strength)),
+            index => index,
+        };
+        let red_index = emissive_index(assignments.assignments[5].x.as_ref());
+        let green_index = emissive_index(assignments.assignments[5].y.as_ref());
+        let blue_index = emissive_index(assignments.assignments[5].z.as_ref());
 
         GeneratedImageKey {
             root_index,
@@ -263,33 +344,59 @@ pub fn emissive_generated_key(
     }
 }
 
+/// The largest constant emissive value or `1.0` if all constants are in the range `0.0` to `1.0`.
+pub fn emissive_strength(assignments: &OutputAssignments) -> f32 {
+    if has_emission(assignments) {
+        let emission = &assignments.assignments[5];
+        [&emission.x, &emission.y, &emission.z]
+            .into_iter()
+            .filter_map(|a| match a {
+                Some(ChannelAssignment::Value(v)) => Some(*v),
+                _ => None,
+            })
+            .fold(1.0, f32::max)
+    } else {
+        1.0
+    }
+}
+
+fn has_emission(assignments: &OutputAssignments) -> bool {
+    // TODO: Is it correct to assume only toon and hair materials use specular?
+    !matches!(assignments.mat_id(), Some(2 | 5))
+}
+
 // TODO: how to make this faster?
 fn generate_image(
     key: GeneratedImageKey,
     original_images: &IndexMap<ImageKey, RgbaImage>,
+    unique_image_keys: &IndexMap<ImageKey, ImageKey>,
     shader_images: &IndexMap<String, RgbaImage>,
     flip_vertical: bool,
 ) -> RgbaImage {
     let red_image = find_image_channel(
         original_images,
+        unique_image_keys,
         shader_images,
         &key.red_index,
         key.root_index,
     );
     let green_image = find_image_channel(
         original_images,
+        unique_image_keys,
         shader_images,
         &key.green_index,
         key.root_index,
     );
     let blue_image = find_image_channel(
         original_images,
+        unique_image_keys,
         shader_images,
         &key.blue_index,
         key.root_index,
     );
     let alpha_image = find_image_channel(
         original_images,
+        unique_image_keys,
         shader_images,
         &key.alpha_index,
         key.root_index,
@@ -348,6 +455,7 @@ fn generate_image(
 
 fn find_image_channel<'a>(
     original_images: &'a IndexMap<ImageKey, RgbaImage>,
+    unique_image_keys: &IndexMap<ImageKey, ImageKey>,
     shader_images: &'a IndexMap<String, RgbaImage>,
     index: &Option<ImageIndex>,
     root_index: usize,
@@ -357,12 +465,14 @@ fn find_image_channel<'a>(
             image_texture,
             channel,
             ..
-        } => original_images
-            .get(&ImageKey {
+        } => {
+            let key = ImageKey {
                 root_index,
                 image_index: *image_texture,
-            })
-            .map(|i| (i, *channel)),
+            };
+            let key = unique_image_keys.get(&key).unwrap_or(&key);
+            original_images.get(key).map(|i| (i, *channel))
+        }
         ImageIndex::GlobalImage { name, channel, .. } => {
             shader_images.get(name.as_str()).map(|i| (i, *channel))
         }
@@ -506,21 +616,142 @@ fn material_texture_index(sampler: &str) -> Option<usize> {
     sampler.strip_prefix('s')?.parse().ok()
 }
 
-pub fn create_images<'a>(
-    root_textures: impl Iterator<Item = &'a Vec<ImageTexture>>,
+pub fn create_images(
+    root_textures: &[&Vec<ImageTexture>],
+    unique_image_keys: &IndexMap<ImageKey, ImageKey>,
 ) -> IndexMap<ImageKey, RgbaImage> {
     let mut png_images = IndexMap::new();
-    for (root_index, image_textures) in root_textures.into_iter().enumerate() {
+    for (root_index, image_textures) in root_textures.iter().enumerate() {
         // Decode images in parallel to boost performance.
-        png_images.par_extend(image_textures.par_iter().enumerate().map(|(i, texture)| {
-            // Convert to PNG since DDS is not well supported.
-            let image = texture.to_image().unwrap();
+        png_images.par_extend(
+            image_textures
+                .par_iter()
+                .enumerate()
+                .filter_map(|(i, texture)| {
+                    let key = ImageKey {
+                        root_index,
+                        image_index: i,
+                    };
+                    // Duplicate images use the image of the first identical texture.
+                    (unique_image_keys.get(&key) == Some(&key)).then(|| {
+                        // Convert to PNG since DDS is not well supported.
+                        let image = texture.to_image().unwrap();
+                        (key, image)
+                    })
+                }),
+        );
+    }
+    png_images
+}
+
+fn unique_image_keys(root_textures: &[&Vec<ImageTexture>]) -> IndexMap<ImageKey, ImageKey> {
+    // Compare textures using the same criteria as ModelRoot::dedup_textures.
+    let mut first_keys = HashMap::new();
+    let mut unique_keys = IndexMap::new();
+    for (root_index, image_textures) in root_textures.iter().enumerate() {
+        for (i, t) in image_textures.iter().enumerate() {
             let key = ImageKey {
                 root_index,
                 image_index: i,
             };
-            (key, image)
-        }));
+            let texture_key = (
+                t.width,
+                t.height,
+                t.depth,
+                t.view_dimension as u32,
+                t.image_format as u32,
+                t.mipmap_count,
+                t.image_data.as_slice(),
+            );
+            let first_key = *first_keys.entry(texture_key).or_insert(key);
+            unique_keys.insert(key, first_key);
+        }
+    }
+    unique_keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ImageFormat, ViewDimension};
+
+    fn image_texture(value: u8) -> ImageTexture {
+        ImageTexture {
+            name: None,
+            usage: None,
+            width: 4,
+            height: 4,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format: ImageFormat::R8G8B8A8Unorm,
+            mipmap_count: 1,
+            image_data: vec![value; 4 * 4 * 4],
+        }
+    }
+
+    fn image(image_texture: usize, channel: usize) -> Option<ImageIndex> {
+        Some(ImageIndex::Image {
+            image_texture,
+            sampler: 0,
+            channel,
+            texcoord_name: "in_attr4".into(),
+            texcoord_scale: None,
+        })
+    }
+
+    fn key(
+        root_index: usize,
+        red_index: Option<ImageIndex>,
+        green_index: Option<ImageIndex>,
+    ) -> GeneratedImageKey {
+        GeneratedImageKey {
+            root_index,
+            red_index,
+            green_index,
+            blue_index: None,
+            alpha_index: Some(ImageIndex::Value(1.0.into())),
+            recalculate_normal_z: false,
+            invert_green: false,
+        }
+    }
+
+    #[test]
+    fn texture_cache_shared_root_texture() {
+        // The second root has a copy of the first root's texture at index 1.
+        let roots = [
+            vec![image_texture(1)],
+            vec![image_texture(2), image_texture(1)],
+        ];
+        let mut cache = TextureCache::new(roots.iter(), &ShaderTextures::default());
+
+        // Only the first identical image is decoded.
+        assert_eq!(
+            vec![
+                ImageKey {
+                    root_index: 0,
+                    image_index: 0
+                },
+                ImageKey {
+                    root_index: 1,
+                    image_index: 0
+                }
+            ],
+            cache.original_images.keys().copied().collect::<Vec<_>>()
+        );
+
+        // Channels from the shared texture reference the first root.
+        assert_eq!(Some(0), cache.insert(key(0, image(0, 0), image(0, 1))));
+        assert_eq!(Some(0), cache.insert(key(1, image(1, 0), image(1, 1))));
+        assert_eq!(1, cache.generated_texture_indices.len());
+        assert!(cache
+            .generated_texture_indices
+            .contains_key(&key(0, image(0, 0), image(0, 1))));
+
+        // Keys can't be remapped if channels reference images from different roots.
+        assert_eq!(Some(1), cache.insert(key(1, image(1, 0), image(0, 1))));
+        assert!(cache
+            .generated_texture_indices
+            .contains_key(&key(1, image(1, 0), image(0, 1))));
     }
-    png_images
 }
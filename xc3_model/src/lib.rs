@@ -34,15 +34,16 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use animation::Animation;
+use animation::{Animation, AnimationInfo};
 use binrw::{BinRead, BinReaderExt};
 use glam::{Mat4, Vec3};
 use indexmap::IndexMap;
 use log::error;
 use material::{create_materials, create_materials_samplers_legacy};
+use rayon::prelude::*;
 use shader_database::ShaderDatabase;
 use skinning::{create_skinning, Skinning};
-use texture::{load_textures, load_textures_legacy};
+use texture::{load_textures, load_textures_legacy, packed_textures};
 use thiserror::Error;
 use vertex::ModelBuffers;
 use xc3_lib::{
@@ -50,11 +51,7 @@ use xc3_lib::{
     bc::{skel::Skel, Bc},
     error::DecompressStreamError,
     hkt::Hkt,
-    mibl::Mibl,
-    msrd::{
-        streaming::{chr_tex_nx_folder, ExtractedTexture},
-        Msrd,
-    },
+    msrd::{streaming::chr_tex_nx_folder, Msrd},
     mxmd::{legacy::MxmdLegacy, AlphaTable, Materials, Mxmd},
     sar1::Sar1,
     xbc1::MaybeXbc1,
@@ -62,7 +59,8 @@ use xc3_lib::{
 };
 
 pub use collision::load_collisions;
-pub use map::{load_map, LoadMapError};
+pub use flatten::FlatMesh;
+pub use map::{load_map, load_map_lenient, LoadMapError};
 use material::{Material, Texture};
 pub use sampler::{AddressMode, FilterMode, Sampler};
 pub use skeleton::{Bone, Skeleton};
@@ -75,6 +73,7 @@ pub mod gltf;
 
 pub mod animation;
 pub mod collision;
+mod flatten;
 mod map;
 pub mod material;
 mod model;
@@ -142,9 +141,19 @@ pub struct Models {
 
     // TODO: Use none instead of empty?
     /// The name of the controller for each morph target like "mouth_shout".
+    ///
+    /// Each [MorphTarget](vertex::MorphTarget) in the vertex buffers references this list
+    /// using [morph_controller_index](vertex::MorphTarget::morph_controller_index).
+    /// See [Self::morph_target_index].
     pub morph_controller_names: Vec<String>,
 
     /// The the morph controller names used for animations.
+    ///
+    /// Animation morph tracks index into this list instead of
+    /// [morph_controller_names](#structfield.morph_controller_names).
+    /// The two lists may have different lengths and orderings,
+    /// so tracks are matched to controllers by name.
+    /// See [Animation::morph_weights].
     pub animation_morph_names: Vec<String>,
 
     // TODO: make this a function instead to avoid dependencies?
@@ -283,6 +292,54 @@ impl Models {
             min_xyz: models.min_xyz.into(),
         }
     }
+
+    /// Apply the skinning for the rest pose of `skeleton` to the vertices in `buffers`
+    /// and remove all skinning data.
+    ///
+    /// Each model uses the buffers at its [model_buffers_index](Model::model_buffers_index).
+    /// The result renders the same as the skinned rest pose without any skinning.
+    /// This is useful for exporting static meshes to applications that do not support skinning.
+    pub fn bake_rest_pose(&mut self, buffers: &mut [ModelBuffers], skeleton: &Skeleton) {
+        // Vertices are already in model space for the rest pose.
+        // This means the skinning transforms are all the identity.
+        let skinning_transforms = vec![Mat4::IDENTITY; skeleton.bones.len()];
+        self.bake_pose(buffers, skeleton, &skinning_transforms);
+    }
+
+    /// Similar to [Self::bake_rest_pose] but applies `skinning_transforms`
+    /// from a method like [Animation::skinning_transforms] to pose the vertices.
+    pub fn bake_pose(
+        &mut self,
+        buffers: &mut [ModelBuffers],
+        skeleton: &Skeleton,
+        skinning_transforms: &[Mat4],
+    ) {
+        skinning::bake_skinning(self, buffers, skeleton, skinning_transforms);
+        self.skinning = None;
+    }
+
+    /// The index into [morph_controller_names](#structfield.morph_controller_names)
+    /// for the controller named `controller_name`.
+    ///
+    /// This matches [morph_controller_index](vertex::MorphTarget::morph_controller_index)
+    /// for the targets in each vertex buffer affected by the controller.
+    /// Use [VertexBuffer::morph_target](vertex::VertexBuffer::morph_target) to find the deltas.
+    pub fn morph_target_index(&self, controller_name: &str) -> Option<usize> {
+        self.morph_controller_names
+            .iter()
+            .position(|n| n == controller_name)
+    }
+
+    /// Combine the meshes of all models into a single mesh for simplified exports.
+    ///
+    /// Each model instance is applied to the vertices,
+    /// and indices are rebased to the combined vertex data.
+    /// Only meshes for the base LOD are included,
+    /// and outline and special effect meshes are skipped like for glTF.
+    /// Skin weights are reindexed to use the unique bone names from all `buffers`.
+    pub fn flatten(&self, buffers: &[ModelBuffers]) -> FlatMesh {
+        flatten::flatten_models(self, buffers)
+    }
 }
 
 fn lod_data(data: &xc3_lib::mxmd::LodData) -> LodData {
@@ -320,8 +377,7 @@ impl Model {
             .map(|mesh| {
                 // TODO: Is there also a flag that disables the ext mesh?
                 let ext_mesh_index = if let Some(a) = alpha_table {
-                    // This uses 1-based indexing so 0 is disabled.
-                    if matches!(a.items.get(mesh.alpha_table_index as usize), Some((0, _))) {
+                    if matches!(a.get(mesh.alpha_table_index as usize), Some((None, _))) {
                         None
                     } else {
                         Some(mesh.ext_mesh_index as usize)
@@ -409,6 +465,12 @@ pub enum LoadModelError {
         source: binrw::Error,
     },
 
+    #[error("error extracting DDS texture from pcmdo file")]
+    PcmdoPackedTexture {
+        #[source]
+        source: image_dds::ddsfile::Error,
+    },
+
     #[error("error reading vertex data")]
     VertexData(binrw::Error),
 
@@ -456,6 +518,9 @@ pub enum LoadModelLegacyError {
 pub enum CreateModelError {
     #[error("error extracting stream data")]
     ExtractFiles(#[from] xc3_lib::msrd::streaming::ExtractFilesError),
+
+    #[error("alpha table index out of range")]
+    AlphaTableIndex(#[from] std::num::TryFromIntError),
 }
 
 // TODO: Take an iterator for wimdo paths and merge to support xc1?
@@ -484,34 +549,61 @@ pub enum CreateModelError {
 /// # }
 /// ```
 ///
-/// For models split into multiple files, simply combine the roots.
-/// ```rust no_run
-/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
-/// # use xc3_model::{load_model, shader_database::ShaderDatabase};
-/// let database = ShaderDatabase::from_file("xc1.bin")?;
+/// For models split into multiple files, see [load_models].
+pub fn load_model<P: AsRef<Path>>(
+    wimdo_path: P,
+    shader_database: Option<&ShaderDatabase>,
+) -> Result<ModelRoot, LoadModelError> {
+    let options = LoadOptions {
+        shader_database,
+        ..Default::default()
+    };
+    load_model_with_options(wimdo_path, &options).map(|model| model.root)
+}
+
+/// Options for [load_model_with_options].
+#[derive(Debug, Default, Clone)]
+pub struct LoadOptions<'a> {
+    /// The shader database used to assign material outputs.
+    /// See [load_model] for details.
+    pub shader_database: Option<&'a ShaderDatabase>,
+
+    /// The `.chr` or `.arc` file for the skeleton
+    /// or [None] to search for the skeleton file in the same directory as the model.
+    pub chr_path: Option<PathBuf>,
+}
+
+/// The result of [load_model_with_options].
+#[derive(Debug, Clone)]
+pub struct LoadedModel {
+    pub root: ModelRoot,
+
+    /// The `.chr` or `.arc` file used for the skeleton
+    /// or [None] if no skeleton file could be loaded.
+    pub chr_path: Option<PathBuf>,
+}
+
+/// Load a model from a `.wimdo` or `.pcmdo` file like [load_model]
+/// and report which `.chr` or `.arc` skeleton file was used.
 ///
-/// // Shulk's main outfit.
-/// let paths = [
-///     "xeno1/chr/pc/pc010201.wimdo",
-///     "xeno1/chr/pc/pc010202.wimdo",
-///     "xeno1/chr/pc/pc010203.wimdo",
-///     "xeno1/chr/pc/pc010204.wimdo",
-///     "xeno1/chr/pc/pc010205.wimdo",
-///     "xeno1/chr/pc/pc010109.wimdo",
-/// ];
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::{load_model_with_options, LoadOptions};
 ///
-/// let mut roots = Vec::new();
-/// for path in paths {
-///     let root = xc3_model::load_model(path, Some(&database))?;
-///     roots.push(root);
+/// // Mio military uniform
+/// let model = load_model_with_options("xeno3/chr/ch/ch01027000.wimdo", &LoadOptions::default())?;
+/// match model.chr_path {
+///     Some(path) => println!("skeleton: {path:?}"),
+///     None => println!("no skeleton found"),
 /// }
 /// # Ok(())
 /// # }
 /// ```
-pub fn load_model<P: AsRef<Path>>(
+pub fn load_model_with_options<P: AsRef<Path>>(
     wimdo_path: P,
-    shader_database: Option<&ShaderDatabase>,
-) -> Result<ModelRoot, LoadModelError> {
+    options: &LoadOptions,
+) -> Result<LoadedModel, LoadModelError> {
     let wimdo_path = wimdo_path.as_ref();
 
     let mxmd = load_wimdo(wimdo_path)?;
@@ -527,14 +619,70 @@ pub fn load_model<P: AsRef<Path>>(
     let streaming_data =
         StreamingData::from_files(&mxmd, &wismt_path, is_pc, chr_tex_folder.as_deref())?;
 
-    let model_name = model_name(wimdo_path);
-    let skel = load_skel(wimdo_path, &model_name);
+    let (skel, chr_path) = match &options.chr_path {
+        Some(chr_path) => (
+            Sar1::from_file(chr_path)
+                .ok()
+                .and_then(|chr| find_skel(&chr)),
+            Some(chr_path.clone()),
+        ),
+        None => {
+            let model_name = model_name(wimdo_path);
+            match load_chr(wimdo_path, &model_name) {
+                Some((chr, chr_path)) => (find_skel(&chr), Some(chr_path)),
+                None => (None, None),
+            }
+        }
+    };
+    // A skeleton file without a skeleton was not used.
+    let chr_path = chr_path.filter(|_| skel.is_some());
+
+    let root = ModelRoot::from_mxmd_model(&mxmd, skel, &streaming_data, options.shader_database)?;
+    Ok(LoadedModel { root, chr_path })
+}
 
-    ModelRoot::from_mxmd_model(&mxmd, skel, &streaming_data, shader_database)
+/// Load models split into multiple `.wimdo` or `.pcmdo` files in parallel.
+/// This returns one root for each path in the same order as `wimdo_paths`.
+/// See [load_model] for details on loading each file.
+///
+/// Each root has its own skeleton loaded from the `.chr` or `.arc` files.
+/// Use [merge_skeletons](crate::skeleton::merge_skeletons) to combine the bones from each root.
+///
+/// # Examples
+/// ```rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # use xc3_model::{load_models, shader_database::ShaderDatabase};
+/// let database = ShaderDatabase::from_file("xc1.bin")?;
+///
+/// // Shulk's main outfit.
+/// let paths = [
+///     "xeno1/chr/pc/pc010201.wimdo",
+///     "xeno1/chr/pc/pc010202.wimdo",
+///     "xeno1/chr/pc/pc010203.wimdo",
+///     "xeno1/chr/pc/pc010204.wimdo",
+///     "xeno1/chr/pc/pc010205.wimdo",
+///     "xeno1/chr/pc/pc010109.wimdo",
+/// ];
+/// let roots = load_models(&paths, Some(&database))?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_models<P: AsRef<Path> + Sync>(
+    wimdo_paths: &[P],
+    shader_database: Option<&ShaderDatabase>,
+) -> Result<Vec<ModelRoot>, LoadModelError> {
+    wimdo_paths
+        .par_iter()
+        .map(|path| load_model(path, shader_database))
+        .collect()
 }
 
 pub fn load_skel(wimdo: &Path, model_name: &str) -> Option<xc3_lib::bc::skel::Skel> {
-    let chr = load_chr(wimdo, model_name)?;
+    let (chr, _) = load_chr(wimdo, model_name)?;
+    find_skel(&chr)
+}
+
+fn find_skel(chr: &Sar1) -> Option<xc3_lib::bc::skel::Skel> {
     chr.entries
         .iter()
         .find_map(|e| match e.read_data::<xc3_lib::bc::Bc>() {
@@ -546,7 +694,7 @@ pub fn load_skel(wimdo: &Path, model_name: &str) -> Option<xc3_lib::bc::skel::Sk
         })
 }
 
-fn load_chr(wimdo: &Path, model_name: &str) -> Option<Sar1> {
+fn load_chr(wimdo: &Path, model_name: &str) -> Option<(Sar1, PathBuf)> {
     // TODO: Does every wimdo have a chr file?
     // TODO: Does something control the chr name used?
     // Try to find the base skeleton file first if it exists.
@@ -554,22 +702,26 @@ fn load_chr(wimdo: &Path, model_name: &str) -> Option<Sar1> {
     // XC1: pc010101.wimdo -> pc010000.chr.
     // XC3: ch01012013.wimdo -> ch01012000.chr.
     let base_name = base_chr_name(model_name);
-    Sar1::from_file(wimdo.with_file_name(&base_name).with_extension("chr"))
-        .ok()
-        .or_else(|| Sar1::from_file(wimdo.with_file_name(&base_name).with_extension("arc")).ok())
-        .or_else(|| Sar1::from_file(wimdo.with_extension("chr")).ok())
-        .or_else(|| Sar1::from_file(wimdo.with_extension("arc")).ok())
-        .or_else(|| {
-            // Keep trying with more 0's at the end to match in game naming conventions.
-            // This usually only requires one additional 0.
-            // XC3: ch01056013.wimdo -> ch01056010.chr.
-            (0..model_name.len()).find_map(|i| {
-                let mut chr_name = model_name.to_string();
-                chr_name.replace_range(chr_name.len() - i.., &"0".repeat(i));
-                let chr_path = wimdo.with_file_name(chr_name).with_extension("chr");
-                Sar1::from_file(chr_path).ok()
-            })
-        })
+    let paths = [
+        wimdo.with_file_name(&base_name).with_extension("chr"),
+        wimdo.with_file_name(&base_name).with_extension("arc"),
+        wimdo.with_extension("chr"),
+        wimdo.with_extension("arc"),
+    ];
+
+    // Keep trying with more 0's at the end to match in game naming conventions.
+    // This usually only requires one additional 0.
+    // XC3: ch01056013.wimdo -> ch01056010.chr.
+    let zero_paths = (0..model_name.len()).map(|i| {
+        let mut chr_name = model_name.to_string();
+        chr_name.replace_range(chr_name.len() - i.., &"0".repeat(i));
+        wimdo.with_file_name(chr_name).with_extension("chr")
+    });
+
+    paths
+        .into_iter()
+        .chain(zero_paths)
+        .find_map(|path| Sar1::from_file(&path).ok().map(|chr| (chr, path)))
 }
 
 fn base_chr_name(model_name: &str) -> String {
@@ -683,6 +835,196 @@ impl ModelRoot {
             skeleton,
         })
     }
+
+    /// Remove [ImageTexture] with identical image data and update the texture indices for each [Material].
+    ///
+    /// Textures are compared using the dimensions, mipmap count, and decoded RGBA8 image data,
+    /// so identical images with different names or encoded data are merged.
+    /// Textures that can not be decoded are compared using their format and encoded image data.
+    /// The name and usage are ignored since duplicate textures may have different names.
+    /// The first texture in [Self::image_textures] is kept for each set of duplicates.
+    pub fn dedup_textures(&mut self) {
+        // Decoding is expensive, so decode each texture in parallel.
+        let keys: Vec<_> = self
+            .image_textures
+            .par_iter()
+            .map(|t| {
+                let (format, data) = match t.to_surface().decode_rgba8() {
+                    Ok(surface) => (None, surface.data),
+                    Err(_) => (Some(t.image_format as u32), t.image_data.clone()),
+                };
+                (
+                    t.width,
+                    t.height,
+                    t.depth,
+                    t.view_dimension as u32,
+                    t.mipmap_count,
+                    format,
+                    data,
+                )
+            })
+            .collect();
+
+        // Assign indices to unique textures in the order they first appear.
+        let mut unique_indices = IndexMap::new();
+        let new_indices: Vec<_> = keys
+            .into_iter()
+            .map(|key| unique_indices.entry_index(key))
+            .collect();
+
+        // New indices are assigned in order, so each unique texture is the first with its index.
+        let mut next_index = 0;
+        self.image_textures = std::mem::take(&mut self.image_textures)
+            .into_iter()
+            .zip(&new_indices)
+            .filter_map(|(t, i)| {
+                if *i == next_index {
+                    next_index += 1;
+                    Some(t)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for material in &mut self.models.materials {
+            for texture in &mut material.textures {
+                if let Some(new_index) = new_indices.get(texture.image_texture_index) {
+                    texture.image_texture_index = *new_index;
+                }
+            }
+        }
+    }
+
+    /// Check that buffer, material, and texture indices are in range.
+    ///
+    /// This should be called before rendering or converting edited models
+    /// to avoid panics from invalid indices.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate_models(
+            &self.models,
+            std::slice::from_ref(&self.buffers),
+            self.image_textures.len(),
+        )
+    }
+}
+
+impl MapRoot {
+    /// Check that buffer, material, and texture indices are in range for all groups.
+    ///
+    /// This should be called before rendering or converting edited models
+    /// to avoid panics from invalid indices.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        for group in &self.groups {
+            for models in &group.models {
+                validate_models(models, &group.buffers, self.image_textures.len())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    #[error("model {model_index} has model buffers index {index} but there are only {count} model buffers")]
+    ModelBuffersIndex {
+        model_index: usize,
+        index: usize,
+        count: usize,
+    },
+
+    #[error("mesh {mesh_index} in model {model_index} has vertex buffer index {index} but there are only {count} vertex buffers")]
+    VertexBufferIndex {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+        count: usize,
+    },
+
+    #[error("mesh {mesh_index} in model {model_index} has index buffer index {index} but there are only {count} index buffers")]
+    IndexBufferIndex {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+        count: usize,
+    },
+
+    #[error("mesh {mesh_index} in model {model_index} has material index {index} but there are only {count} materials")]
+    MaterialIndex {
+        model_index: usize,
+        mesh_index: usize,
+        index: usize,
+        count: usize,
+    },
+
+    #[error("texture {texture_index} in material {material_index} has image texture index {index} but there are only {count} image textures")]
+    ImageTextureIndex {
+        material_index: usize,
+        texture_index: usize,
+        index: usize,
+        count: usize,
+    },
+}
+
+fn validate_models(
+    models: &Models,
+    buffers: &[ModelBuffers],
+    image_texture_count: usize,
+) -> Result<(), ValidationError> {
+    for (model_index, model) in models.models.iter().enumerate() {
+        let model_buffers =
+            buffers
+                .get(model.model_buffers_index)
+                .ok_or(ValidationError::ModelBuffersIndex {
+                    model_index,
+                    index: model.model_buffers_index,
+                    count: buffers.len(),
+                })?;
+
+        for (mesh_index, mesh) in model.meshes.iter().enumerate() {
+            if mesh.vertex_buffer_index >= model_buffers.vertex_buffers.len() {
+                return Err(ValidationError::VertexBufferIndex {
+                    model_index,
+                    mesh_index,
+                    index: mesh.vertex_buffer_index,
+                    count: model_buffers.vertex_buffers.len(),
+                });
+            }
+
+            if mesh.index_buffer_index >= model_buffers.index_buffers.len() {
+                return Err(ValidationError::IndexBufferIndex {
+                    model_index,
+                    mesh_index,
+                    index: mesh.index_buffer_index,
+                    count: model_buffers.index_buffers.len(),
+                });
+            }
+
+            if mesh.material_index >= models.materials.len() {
+                return Err(ValidationError::MaterialIndex {
+                    model_index,
+                    mesh_index,
+                    index: mesh.material_index,
+                    count: models.materials.len(),
+                });
+            }
+        }
+    }
+
+    for (material_index, material) in models.materials.iter().enumerate() {
+        for (texture_index, texture) in material.textures.iter().enumerate() {
+            if texture.image_texture_index >= image_texture_count {
+                return Err(ValidationError::ImageTextureIndex {
+                    material_index,
+                    texture_index,
+                    index: texture.image_texture_index,
+                    count: image_texture_count,
+                });
+            }
+        }
+    }
+
+    Ok(())
 }
 
 // TODO: move this to xc3_lib?
@@ -796,21 +1138,8 @@ impl<'a> StreamingData<'a> {
             })
             .unwrap_or_else(|| {
                 let textures = match &mxmd.packed_textures {
-                    Some(textures) => textures
-                        .textures
-                        .iter()
-                        .map(|t| {
-                            Ok(ExtractedTexture {
-                                name: t.name.clone(),
-                                usage: t.usage,
-                                low: Mibl::from_bytes(&t.mibl_data).map_err(|e| {
-                                    LoadModelError::WimdoPackedTexture { source: e }
-                                })?,
-                                high: None,
-                            })
-                        })
-                        .collect::<Result<Vec<_>, LoadModelError>>()?,
-                    None => Vec::new(),
+                    Some(textures) => packed_textures(&textures.textures, is_pc)?,
+                    None => ExtractedTextures::Switch(Vec::new()),
                 };
 
                 Ok(StreamingData {
@@ -824,7 +1153,7 @@ impl<'a> StreamingData<'a> {
                             .as_ref()
                             .ok_or(LoadModelError::MissingMxmdShaderData)?,
                     ),
-                    textures: ExtractedTextures::Switch(textures),
+                    textures,
                     texture_indices: None,
                 })
             })
@@ -859,44 +1188,150 @@ enum AnimFile {
 pub fn load_animations<P: AsRef<Path>>(
     anim_path: P,
 ) -> Result<Vec<Animation>, DecompressStreamError> {
-    let mut reader = Cursor::new(std::fs::read(anim_path)?);
-    let anim_file: AnimFile = reader.read_le()?;
+    let mut animations = Vec::new();
+    visit_anims(anim_path.as_ref(), |anim| {
+        animations.push(Animation::from_anim(anim))
+    })?;
+    Ok(animations)
+}
 
+/// Load the name and length of all animations from a `.anm`, `.mot`, or `.motstm_data` file.
+///
+/// This is faster than [load_animations] since the track data is not decoded.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Mio military uniform
+/// let animations = xc3_model::list_animations("xeno3/chr/ch/ch01027000_event.mot")?;
+/// for info in animations {
+///     println!("{}: {} frames", info.name, info.frame_count);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn list_animations<P: AsRef<Path>>(
+    anim_path: P,
+) -> Result<Vec<AnimationInfo>, DecompressStreamError> {
     let mut animations = Vec::new();
+    visit_anims(anim_path.as_ref(), |anim| {
+        animations.push(AnimationInfo::from_anim(anim))
+    })?;
+    Ok(animations)
+}
 
-    // Most animations are in sar1 archives.
-    // Xenoblade 1 DE compresses the sar1 archive.
-    // Some animations are in standalone BC files.
-    match anim_file {
-        AnimFile::Sar1(sar1) => match sar1 {
-            MaybeXbc1::Uncompressed(sar1) => {
-                for entry in &sar1.entries {
-                    if let Ok(bc) = entry.read_data() {
-                        add_bc_animations(&mut animations, bc);
-                    }
-                }
-            }
-            MaybeXbc1::Xbc1(xbc1) => {
-                let sar1: Sar1 = xbc1.extract()?;
-                for entry in &sar1.entries {
-                    if let Ok(bc) = entry.read_data() {
-                        add_bc_animations(&mut animations, bc);
-                    }
-                }
-            }
-        },
-        AnimFile::Bc(bc) => {
-            add_bc_animations(&mut animations, *bc);
-        }
+fn visit_anims<F>(anim_path: &Path, mut f: F) -> Result<(), DecompressStreamError>
+where
+    F: FnMut(&xc3_lib::bc::anim::Anim),
+{
+    let stream = AnimationStream::from_file(anim_path)?;
+    for i in 0..stream.len() {
+        stream.visit_anim(i, &mut f);
     }
+    Ok(())
+}
 
-    Ok(animations)
+/// A handle for loading individual animations from a `.anm`, `.mot`, or `.motstm_data` file.
+///
+/// Animations are only decoded when requested.
+/// This is faster than [load_animations] for large files when only some animations are needed.
+///
+/// # Examples
+/// ``` rust no_run
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use xc3_model::AnimationStream;
+///
+/// let stream = AnimationStream::from_file("xeno3/chr/ch/ch01027000.motstm_data")?;
+/// for i in 0..stream.len() {
+///     println!("{i}: {:?}", stream.entry_name(i));
+/// }
+/// let animation = stream.animation(0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AnimationStream {
+    inner: AnimationStreamInner,
 }
 
-fn add_bc_animations(animations: &mut Vec<Animation>, bc: Bc) {
-    if let xc3_lib::bc::BcData::Anim(anim) = bc.data {
-        let animation = Animation::from_anim(&anim);
-        animations.push(animation);
+#[derive(Debug)]
+enum AnimationStreamInner {
+    Sar1(Sar1),
+    Bc(Box<Bc>),
+}
+
+impl AnimationStream {
+    /// Read the archive or animation file without decoding any animations.
+    ///
+    /// Xenoblade 1 DE compressed archives are decompressed once when reading.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, DecompressStreamError> {
+        let mut reader = Cursor::new(std::fs::read(path)?);
+        let anim_file: AnimFile = reader.read_le()?;
+
+        // Most animations are in sar1 archives.
+        // Xenoblade 1 DE compresses the sar1 archive.
+        // Some animations are in standalone BC files.
+        let inner = match anim_file {
+            AnimFile::Sar1(MaybeXbc1::Uncompressed(sar1)) => AnimationStreamInner::Sar1(sar1),
+            AnimFile::Sar1(MaybeXbc1::Xbc1(xbc1)) => AnimationStreamInner::Sar1(xbc1.extract()?),
+            AnimFile::Bc(bc) => AnimationStreamInner::Bc(bc),
+        };
+        Ok(Self { inner })
+    }
+
+    /// The number of entries in the file.
+    ///
+    /// Not all entries are guaranteed to contain an animation.
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            AnimationStreamInner::Sar1(sar1) => sar1.entries.len(),
+            AnimationStreamInner::Bc(_) => 1,
+        }
+    }
+
+    /// Returns `true` if the file has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The archive entry name at `index` without decoding the entry
+    /// or [None] if the file is not an archive or the index is out of range.
+    pub fn entry_name(&self, index: usize) -> Option<&str> {
+        match &self.inner {
+            AnimationStreamInner::Sar1(sar1) => sar1.entries.get(index).map(|e| e.name.as_str()),
+            AnimationStreamInner::Bc(_) => None,
+        }
+    }
+
+    /// Decode the animation at `index`
+    /// or [None] if the entry is not a valid animation or the index is out of range.
+    pub fn animation(&self, index: usize) -> Option<Animation> {
+        self.visit_anim(index, Animation::from_anim)
+    }
+
+    /// Load the name and length of the animation at `index` without converting any track data.
+    /// See [Self::animation].
+    pub fn animation_info(&self, index: usize) -> Option<AnimationInfo> {
+        self.visit_anim(index, AnimationInfo::from_anim)
+    }
+
+    fn visit_anim<T, F>(&self, index: usize, f: F) -> Option<T>
+    where
+        F: FnOnce(&xc3_lib::bc::anim::Anim) -> T,
+    {
+        match &self.inner {
+            AnimationStreamInner::Sar1(sar1) => {
+                let bc: Bc = sar1.entries.get(index)?.read_data().ok()?;
+                match bc.data {
+                    xc3_lib::bc::BcData::Anim(anim) => Some(f(&anim)),
+                    _ => None,
+                }
+            }
+            AnimationStreamInner::Bc(bc) => match &bc.data {
+                xc3_lib::bc::BcData::Anim(anim) if index == 0 => Some(f(anim)),
+                _ => None,
+            },
+        }
     }
 }
 
@@ -1044,3 +1479,122 @@ fn get_bytes(bytes: &[u8], offset: u32, size: Option<u32>) -> std::io::Result<&[
         }),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use material::{Material, MaterialParameters, Texture};
+    use xc3_lib::mxmd::{
+        BlendMode, ColorWriteMode, CullMode, DepthFunc, MaterialFlags, MaterialRenderFlags,
+        RenderPassType, StateFlags, StencilMode, StencilValue,
+    };
+
+    fn image_texture(name: &str, image_format: ImageFormat, image_data: Vec<u8>) -> ImageTexture {
+        ImageTexture {
+            name: Some(name.to_string()),
+            usage: None,
+            width: 1,
+            height: 1,
+            depth: 1,
+            view_dimension: ViewDimension::D2,
+            image_format,
+            mipmap_count: 1,
+            image_data,
+        }
+    }
+
+    fn material(image_texture_indices: &[usize]) -> Material {
+        Material {
+            name: String::new(),
+            flags: MaterialFlags::from(0u32),
+            render_flags: MaterialRenderFlags::from(0u32),
+            state_flags: StateFlags {
+                depth_write_mode: 0,
+                blend_mode: BlendMode::Disabled,
+                cull_mode: CullMode::Back,
+                unk4: 0,
+                stencil_value: StencilValue::Unk0,
+                stencil_mode: StencilMode::Unk0,
+                depth_func: DepthFunc::LessEqual,
+                color_write_mode: ColorWriteMode::Unk0,
+            },
+            color: [1.0; 4],
+            textures: image_texture_indices
+                .iter()
+                .map(|i| Texture {
+                    image_texture_index: *i,
+                    sampler_index: 0,
+                })
+                .collect(),
+            alpha_test: None,
+            work_values: Vec::new(),
+            shader_vars: Vec::new(),
+            work_callbacks: Vec::new(),
+            alpha_test_ref: [0; 4],
+            m_unks1_1: 0,
+            m_unks1_2: 0,
+            m_unks1_3: 0,
+            m_unks1_4: 0,
+            shader: None,
+            technique_index: 0,
+            pass_type: RenderPassType::Unk0,
+            parameters: MaterialParameters::default(),
+            m_unks2_2: 0,
+            m_unks3_1: 0,
+            fur_params: None,
+        }
+    }
+
+    #[test]
+    fn dedup_textures_decoded_data() {
+        let mut root = ModelRoot {
+            models: Models {
+                models: Vec::new(),
+                materials: vec![material(&[2, 1, 3]), material(&[0])],
+                samplers: Vec::new(),
+                skinning: None,
+                lod_data: None,
+                morph_controller_names: Vec::new(),
+                animation_morph_names: Vec::new(),
+                max_xyz: Vec3::ZERO,
+                min_xyz: Vec3::ZERO,
+            },
+            buffers: ModelBuffers {
+                vertex_buffers: Vec::new(),
+                outline_buffers: Vec::new(),
+                index_buffers: Vec::new(),
+                unk_buffers: Vec::new(),
+                unk_data: None,
+                weights: None,
+            },
+            image_textures: vec![
+                image_texture("a", ImageFormat::R8G8B8A8Unorm, vec![1, 2, 3, 4]),
+                // The same decoded RGBA with a different name and encoded data.
+                image_texture("b", ImageFormat::B8G8R8A8Unorm, vec![3, 2, 1, 4]),
+                image_texture("c", ImageFormat::R8G8B8A8Unorm, vec![5, 6, 7, 8]),
+                image_texture("d", ImageFormat::R8G8B8A8Unorm, vec![5, 6, 7, 8]),
+            ],
+            skeleton: None,
+        };
+
+        root.dedup_textures();
+
+        assert_eq!(
+            vec![
+                image_texture("a", ImageFormat::R8G8B8A8Unorm, vec![1, 2, 3, 4]),
+                image_texture("c", ImageFormat::R8G8B8A8Unorm, vec![5, 6, 7, 8]),
+            ],
+            root.image_textures
+        );
+
+        let indices = |m: &Material| {
+            m.textures
+                .iter()
+                .map(|t| t.image_texture_index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![1, 0, 1], indices(&root.models.materials[0]));
+        assert_eq!(vec![0], indices(&root.models.materials[1]));
+    }
+}
@@ -70,12 +70,41 @@ pub fn load_map<P: AsRef<Path>>(
     MapRoot::from_msmd(&msmd, &wismda, shader_database)
 }
 
+/// Load a map from a `.wismhd` file similar to [load_map]
+/// but with separate results for each [MapRoot] or group.
+///
+/// Errors for individual env models, foliage models, or groups are returned
+/// without preventing the remaining data from loading.
+pub fn load_map_lenient<P: AsRef<Path>>(
+    wismhd_path: P,
+    shader_database: Option<&ShaderDatabase>,
+) -> Result<Vec<Result<MapRoot, LoadMapError>>, LoadMapError> {
+    let msmd = Msmd::from_file(wismhd_path.as_ref()).map_err(LoadMapError::Wismhd)?;
+    let wismda = std::fs::read(wismhd_path.as_ref().with_extension("wismda"))?;
+
+    Ok(MapRoot::from_msmd_lenient(&msmd, &wismda, shader_database))
+}
+
 impl MapRoot {
     pub fn from_msmd(
         msmd: &Msmd,
         wismda: &[u8],
         shader_database: Option<&ShaderDatabase>,
     ) -> Result<Vec<Self>, LoadMapError> {
+        Self::from_msmd_lenient(msmd, wismda, shader_database)
+            .into_iter()
+            .collect()
+    }
+
+    /// Similar to [Self::from_msmd] but with separate results for each root or group.
+    ///
+    /// The map and prop model groups share a single root.
+    /// A group that fails to load is returned as an error after the root with the remaining groups.
+    pub fn from_msmd_lenient(
+        msmd: &Msmd,
+        wismda: &[u8],
+        shader_database: Option<&ShaderDatabase>,
+    ) -> Vec<Result<Self, LoadMapError>> {
         // Loading is CPU intensive due to decompression and decoding.
         // The .wismda is loaded into memory as &[u8].
         // Extracting can be parallelized without locks by creating multiple readers.
@@ -87,18 +116,22 @@ impl MapRoot {
         let mut roots = Vec::new();
 
         for model in &msmd.env_models {
-            let root = load_env_model(wismda, compressed, model, shader_database)?;
-            roots.push(root);
+            roots.push(load_env_model(wismda, compressed, model, shader_database));
         }
 
         for foliage_model in &msmd.foliage_models {
-            let root = load_foliage_model(wismda, compressed, foliage_model)?;
-            roots.push(root);
+            roots.push(load_foliage_model(wismda, compressed, foliage_model));
         }
 
         // TODO: How much does a mutable cache negatively impact parallelization?
         // TODO: Is there enough reuse for it to be worth caching these?
-        let mut texture_cache = TextureCache::new(msmd, wismda, compressed)?;
+        let mut texture_cache = match TextureCache::new(msmd, wismda, compressed) {
+            Ok(cache) => cache,
+            Err(e) => {
+                roots.push(Err(e));
+                return roots;
+            }
+        };
 
         let map_model_group = map_models_group(
             msmd,
@@ -106,7 +139,7 @@ impl MapRoot {
             compressed,
             &mut texture_cache,
             shader_database,
-        )?;
+        );
 
         let prop_model_group = props_group(
             msmd,
@@ -114,14 +147,32 @@ impl MapRoot {
             compressed,
             &mut texture_cache,
             shader_database,
-        )?;
+        );
 
-        roots.push(MapRoot {
-            groups: vec![map_model_group, prop_model_group],
-            image_textures: texture_cache.image_textures()?,
-        });
+        let mut groups = Vec::new();
+        let mut errors = Vec::new();
+        for group in [map_model_group, prop_model_group] {
+            match group {
+                Ok(group) => groups.push(group),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        // Skip the root if all groups failed to load.
+        if !groups.is_empty() {
+            roots.push(
+                texture_cache
+                    .image_textures()
+                    .map(|image_textures| MapRoot {
+                        groups,
+                        image_textures,
+                    })
+                    .map_err(Into::into),
+            );
+        }
+        roots.extend(errors.into_iter().map(Err));
 
-        Ok(roots)
+        roots
     }
 }
 
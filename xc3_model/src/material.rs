@@ -1,6 +1,7 @@
 use glam::{vec4, Vec4};
 use log::warn;
 use smol_str::{SmolStr, ToSmolStr};
+use thiserror::Error;
 
 pub use xc3_lib::mxmd::{
     BlendMode, ColorWriteMode, CullMode, DepthFunc, FurShellParams, MaterialFlags,
@@ -62,6 +63,154 @@ pub struct Material {
     pub fur_params: Option<FurShellParams>,
 }
 
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SetParameterError {
+    #[error("parameter {0:?} is not a known U_Mate parameter")]
+    UnknownParameter(String),
+
+    #[error("parameter {0:?} is not present for this material")]
+    MissingParameter(String),
+
+    #[error("index {index} out of range for parameter {name:?} with length {count}")]
+    IndexOutOfRange {
+        name: String,
+        index: usize,
+        count: usize,
+    },
+
+    #[error("expected {expected} values for parameter {name:?} but found {actual}")]
+    InvalidLength {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("work values {start}..{end} for parameter {name:?} out of range for length {count}")]
+    WorkValuesOutOfRange {
+        name: String,
+        start: usize,
+        end: usize,
+        count: usize,
+    },
+}
+
+impl Material {
+    /// Assign `value` to the `U_Mate` uniform parameter with field `name` like `"gWrkCol"` or `"gWrkFl4[1]"`.
+    /// The index defaults to `0` if not specified.
+    ///
+    /// The length of `value` must match the size of a single element
+    /// like 4 for `gWrkFl4` and `gWrkCol` or 8 for `gTexMat`.
+    /// This updates both [parameters](#structfield.parameters) and the corresponding
+    /// [work_values](#structfield.work_values) to preserve the edit when saving.
+    pub fn set_parameter(&mut self, name: &str, value: &[f32]) -> Result<(), SetParameterError> {
+        let (field, index) = parse_parameter_name(name)
+            .ok_or_else(|| SetParameterError::UnknownParameter(name.to_string()))?;
+
+        let indices = self.parameters.work_value_indices;
+        let work = WorkValues {
+            values: &mut self.work_values,
+            callbacks: &self.work_callbacks,
+        };
+        let params = &mut self.parameters;
+        match field {
+            "gTexMat" => set_param(
+                &mut params.tex_matrix,
+                indices.tex_matrix,
+                work,
+                name,
+                index,
+                value,
+            ),
+            "gWrkFl4" => set_param(
+                &mut params.work_float4,
+                indices.work_float4,
+                work,
+                name,
+                index,
+                value,
+            ),
+            "gWrkCol" => set_param(
+                &mut params.work_color,
+                indices.work_color,
+                work,
+                name,
+                index,
+                value,
+            ),
+            _ => Err(SetParameterError::UnknownParameter(name.to_string())),
+        }
+    }
+}
+
+struct WorkValues<'a> {
+    values: &'a mut [f32],
+    callbacks: &'a [WorkCallback],
+}
+
+fn parse_parameter_name(name: &str) -> Option<(&str, usize)> {
+    match name.split_once('[') {
+        Some((field, index)) => Some((field, index.strip_suffix(']')?.parse().ok()?)),
+        None => Some((name, 0)),
+    }
+}
+
+fn set_param<const N: usize>(
+    values: &mut Option<Vec<[f32; N]>>,
+    work_value_index: Option<usize>,
+    work_values: WorkValues,
+    name: &str,
+    index: usize,
+    value: &[f32],
+) -> Result<(), SetParameterError> {
+    let (values, work_value_index) = values
+        .as_mut()
+        .zip(work_value_index)
+        .ok_or_else(|| SetParameterError::MissingParameter(name.to_string()))?;
+
+    let count = values.len();
+    let element = values
+        .get_mut(index)
+        .ok_or_else(|| SetParameterError::IndexOutOfRange {
+            name: name.to_string(),
+            index,
+            count,
+        })?;
+
+    let new_element: [f32; N] = value
+        .try_into()
+        .map_err(|_| SetParameterError::InvalidLength {
+            name: name.to_string(),
+            expected: N,
+            actual: value.len(),
+        })?;
+
+    // Each element in the parameter array is stored contiguously.
+    let start = work_value_index + index * N;
+    let end = start + N;
+    let count = work_values.values.len();
+    let slot = work_values.values.get_mut(start..end).ok_or_else(|| {
+        SetParameterError::WorkValuesOutOfRange {
+            name: name.to_string(),
+            start,
+            end,
+            count,
+        }
+    })?;
+
+    slot.copy_from_slice(&new_element);
+    *element = new_element;
+
+    // Callbacks overwrite the referenced value using the next value when loading.
+    for callback in work_values.callbacks {
+        let i = callback.unk2 as usize;
+        if callback.unk1 == 26 && (start..end).contains(&i) && i + 1 < count {
+            work_values.values[i + 1] = work_values.values[i] * 255.0;
+        }
+    }
+
+    Ok(())
+}
+
 /// Information for alpha testing based on sampled texture values.
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug, PartialEq, Clone)]
@@ -80,6 +229,17 @@ pub struct MaterialParameters {
     pub tex_matrix: Option<Vec<[f32; 8]>>, // TODO: mat2x4?
     pub work_float4: Option<Vec<[f32; 4]>>,
     pub work_color: Option<Vec<[f32; 4]>>,
+    /// The start of each parameter in [work_values](struct.Material.html#structfield.work_values).
+    pub work_value_indices: WorkValueIndices,
+}
+
+/// The index of the first work value for each parameter or `None` if not present.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct WorkValueIndices {
+    pub tex_matrix: Option<usize>,
+    pub work_float4: Option<usize>,
+    pub work_color: Option<usize>,
 }
 
 impl MaterialParameters {
@@ -292,11 +452,7 @@ pub(crate) fn create_materials_samplers_legacy(
                     Some(xc3_lib::mxmd::legacy::UnkPassType::Unk8) => RenderPassType::Unk0,
                     None => RenderPassType::Unk0,
                 },
-                parameters: MaterialParameters {
-                    tex_matrix: None,
-                    work_float4: None,
-                    work_color: None,
-                },
+                parameters: MaterialParameters::default(),
                 work_values,
                 shader_vars: materials
                     .shader_vars
@@ -477,11 +633,7 @@ fn assign_parameters(
 
     let work_values = apply_callbacks(work_values, callbacks);
 
-    let mut parameters = MaterialParameters {
-        tex_matrix: None,
-        work_float4: None,
-        work_color: None,
-    };
+    let mut parameters = MaterialParameters::default();
 
     if let Some(technique) = get_technique(material, &materials.techniques) {
         for param in &technique.parameters {
@@ -489,12 +641,18 @@ fn assign_parameters(
                 xc3_lib::mxmd::ParamType::Unk0 => (),
                 xc3_lib::mxmd::ParamType::TexMatrix => {
                     parameters.tex_matrix = Some(read_param(param, &work_values));
+                    parameters.work_value_indices.tex_matrix =
+                        Some(param.work_value_index as usize);
                 }
                 xc3_lib::mxmd::ParamType::WorkFloat4 => {
                     parameters.work_float4 = Some(read_param(param, &work_values));
+                    parameters.work_value_indices.work_float4 =
+                        Some(param.work_value_index as usize);
                 }
                 xc3_lib::mxmd::ParamType::WorkColor => {
                     parameters.work_color = Some(read_param(param, &work_values));
+                    parameters.work_value_indices.work_color =
+                        Some(param.work_value_index as usize);
                 }
                 // TODO: Find the corresponding uniform name.
                 xc3_lib::mxmd::ParamType::Unk4 => (),
@@ -1058,4 +1216,251 @@ mod tests {
             )
         );
     }
+
+    fn material(parameters: MaterialParameters) -> Material {
+        Material {
+            name: String::new(),
+            flags: MaterialFlags::from(0u32),
+            render_flags: MaterialRenderFlags::from(0u32),
+            state_flags: StateFlags {
+                depth_write_mode: 0,
+                blend_mode: BlendMode::Disabled,
+                cull_mode: CullMode::Back,
+                unk4: 0,
+                stencil_value: StencilValue::Unk0,
+                stencil_mode: StencilMode::Unk0,
+                depth_func: DepthFunc::LessEqual,
+                color_write_mode: ColorWriteMode::Unk0,
+            },
+            color: [1.0; 4],
+            textures: Vec::new(),
+            alpha_test: None,
+            work_values: Vec::new(),
+            shader_vars: Vec::new(),
+            work_callbacks: Vec::new(),
+            alpha_test_ref: [0; 4],
+            m_unks1_1: 0,
+            m_unks1_2: 0,
+            m_unks1_3: 0,
+            m_unks1_4: 0,
+            shader: None,
+            technique_index: 0,
+            pass_type: RenderPassType::Unk0,
+            parameters,
+            m_unks2_2: 0,
+            m_unks3_1: 0,
+            fur_params: None,
+        }
+    }
+
+    #[test]
+    fn set_parameter_work_color() {
+        let mut material = material(MaterialParameters {
+            work_color: Some(vec![[0.0; 4]; 2]),
+            work_value_indices: WorkValueIndices {
+                work_color: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        material.work_values = vec![0.0; 10];
+        material.set_parameter("gWrkCol", &[1.0; 4]).unwrap();
+        material
+            .set_parameter("gWrkCol[1]", &[0.5, 0.25, 0.125, 1.0])
+            .unwrap();
+        assert_eq!(
+            Some(vec![[1.0; 4], [0.5, 0.25, 0.125, 1.0]]),
+            material.parameters.work_color
+        );
+        assert_eq!(
+            vec![0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.5, 0.25, 0.125, 1.0],
+            material.work_values
+        );
+    }
+
+    #[test]
+    fn set_parameter_work_callbacks() {
+        let mut material = material(MaterialParameters {
+            work_float4: Some(vec![[0.0; 4]]),
+            work_value_indices: WorkValueIndices {
+                work_float4: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        material.work_values = vec![0.0; 8];
+        material.work_callbacks = vec![WorkCallback { unk1: 26, unk2: 2 }];
+        material
+            .set_parameter("gWrkFl4", &[1.0, 2.0, 0.5, 4.0])
+            .unwrap();
+
+        // The callback should recreate the edited value when loading.
+        assert_eq!(
+            vec![1.0, 2.0, 0.5, 127.5, 0.0, 0.0, 0.0, 0.0],
+            material.work_values
+        );
+        assert_eq!(
+            vec![1.0, 2.0, 0.5, 127.5, 0.0, 0.0, 0.0, 0.0],
+            apply_callbacks(&material.work_values, &material.work_callbacks)
+        );
+    }
+
+    #[test]
+    fn set_parameter_save_reload() {
+        let materials = xc3_lib::mxmd::Materials {
+            materials: vec![xc3_lib::mxmd::Material {
+                name: "a".to_string(),
+                flags: MaterialFlags::from(0u32),
+                render_flags: MaterialRenderFlags::from(0u32),
+                color: [1.0; 4],
+                alpha_test_ref: [0; 4],
+                textures: Vec::new(),
+                state_flags: material(MaterialParameters::default()).state_flags,
+                m_unks1_1: 0,
+                m_unks1_2: 0,
+                m_unks1_3: 0,
+                m_unks1_4: 0,
+                work_value_start_index: 0,
+                shader_var_start_index: 0,
+                shader_var_count: 0,
+                techniques: vec![xc3_lib::mxmd::MaterialTechnique {
+                    technique_index: 0,
+                    pass_type: RenderPassType::Unk0,
+                    material_buffer_index: 0,
+                    flags: 1,
+                }],
+                unk5: 0,
+                callback_start_index: 0,
+                callback_count: 0,
+                m_unks2: [0; 3],
+                alpha_test_texture_index: 0,
+                m_unks3: [0; 8],
+            }],
+            unk1: 0,
+            unk2: 0,
+            work_values: vec![0.0; 8],
+            shader_vars: Vec::new(),
+            callbacks: Some(xc3_lib::mxmd::MaterialCallbacks {
+                work_callbacks: Vec::new(),
+                material_indices: vec![0],
+                unk: [0; 8],
+            }),
+            unk4: 0,
+            techniques: vec![xc3_lib::mxmd::Technique {
+                attributes: Vec::new(),
+                unk3: 0,
+                unk4: 0,
+                parameters: vec![xc3_lib::mxmd::MaterialParameter {
+                    param_type: xc3_lib::mxmd::ParamType::WorkColor,
+                    work_value_index: 4,
+                    unk: 0,
+                    count: 1,
+                }],
+                textures: Vec::new(),
+                uniform_blocks: Vec::new(),
+                material_texture_count: 0,
+                unk12: 0,
+                unk13: 0,
+                padding: [0; 5],
+            }],
+            unks1: [0; 2],
+            alpha_test_textures: Vec::new(),
+            unks3: [0; 3],
+            material_unk2: None,
+            fur_shells: None,
+            unks3_1: [0; 2],
+            samplers: None,
+            unks4: [0; 3],
+            unks5: None,
+        };
+        let spch = xc3_lib::spch::Spch {
+            version: 0,
+            slct_offsets: Vec::new(),
+            unk4s: Vec::new(),
+            slct_section: Vec::new(),
+            xv4_section: Vec::new(),
+            unk_section: Vec::new(),
+            string_section: None,
+            unk7: 0,
+            padding: [0; 4],
+        };
+
+        let mut new_materials = create_materials(&materials, None, &spch, None);
+        new_materials[0]
+            .set_parameter("gWrkCol", &[0.5, 0.25, 0.125, 1.0])
+            .unwrap();
+
+        let mut new_mxmd_materials = materials.clone();
+        crate::model::apply_materials(&mut new_mxmd_materials, &new_materials);
+
+        let mut writer = std::io::Cursor::new(Vec::new());
+        xc3_write::write_full(
+            &new_mxmd_materials,
+            &mut writer,
+            0,
+            &mut 0,
+            xc3_write::Endian::Little,
+            (),
+        )
+        .unwrap();
+        writer.set_position(0);
+        let materials: xc3_lib::mxmd::Materials =
+            binrw::BinReaderExt::read_le(&mut writer).unwrap();
+
+        let new_materials = create_materials(&materials, None, &spch, None);
+        assert_eq!(
+            Some(vec![[0.5, 0.25, 0.125, 1.0]]),
+            new_materials[0].parameters.work_color
+        );
+        assert_eq!(
+            vec![0.0, 0.0, 0.0, 0.0, 0.5, 0.25, 0.125, 1.0],
+            new_materials[0].work_values
+        );
+    }
+
+    #[test]
+    fn set_parameter_invalid() {
+        let mut material = material(MaterialParameters {
+            work_float4: Some(vec![[0.0; 4]]),
+            work_value_indices: WorkValueIndices {
+                work_float4: Some(6),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        material.work_values = vec![0.0; 8];
+        assert_eq!(
+            Err(SetParameterError::UnknownParameter("gUnk".to_string())),
+            material.set_parameter("gUnk", &[0.0; 4])
+        );
+        assert_eq!(
+            Err(SetParameterError::MissingParameter("gWrkCol".to_string())),
+            material.set_parameter("gWrkCol", &[0.0; 4])
+        );
+        assert_eq!(
+            Err(SetParameterError::IndexOutOfRange {
+                name: "gWrkFl4[1]".to_string(),
+                index: 1,
+                count: 1
+            }),
+            material.set_parameter("gWrkFl4[1]", &[0.0; 4])
+        );
+        assert_eq!(
+            Err(SetParameterError::InvalidLength {
+                name: "gWrkFl4".to_string(),
+                expected: 4,
+                actual: 3
+            }),
+            material.set_parameter("gWrkFl4", &[0.0; 3])
+        );
+        assert_eq!(
+            Err(SetParameterError::WorkValuesOutOfRange {
+                name: "gWrkFl4".to_string(),
+                start: 6,
+                end: 10,
+                count: 8
+            }),
+            material.set_parameter("gWrkFl4", &[0.0; 4])
+        );
+    }
 }
@@ -1,5 +1,4 @@
 use glam::{Mat4, Vec2, Vec3, Vec4};
-use indexmap::IndexMap;
 use log::warn;
 use xc3_lib::{
     mibl::Mibl,
@@ -11,7 +10,7 @@ use xc3_lib::{
 use crate::{
     skinning::BoneConstraintType,
     vertex::{AttributeData, ModelBuffers},
-    CreateModelError, ImageTexture, IndexMapExt, ModelRoot,
+    CreateModelError, ImageTexture, Material, ModelRoot,
 };
 
 // TODO: Not possible to make files compatible with all game versions?
@@ -68,7 +67,11 @@ impl ModelRoot {
 
         // TODO: Rebuild materials.
         // TODO: How many of these mesh fields can use a default value?
-        let mut alpha_table = IndexMap::new();
+        // Start with the existing items to preserve the original ordering.
+        let mut alpha_table = mxmd.models.alpha_table.clone().unwrap_or(AlphaTable {
+            items: Vec::new(),
+            unks: [0; 4],
+        });
 
         let has_speff_materials = self
             .models
@@ -86,65 +89,78 @@ impl ModelRoot {
             .models
             .models
             .iter()
-            .map(|model| xc3_lib::mxmd::Model {
-                meshes: model
-                    .meshes
-                    .iter()
-                    .map(|m| {
-                        // Generate the mapping for unique ext mesh and lod values.
-                        // An index of 0 represents no ext mesh.
-                        // TODO: Why is the lod index set to 0 for some xc3 models?
-                        let ext_index = m.ext_mesh_index.map(|i| i + 1).unwrap_or_default() as u16;
-                        let alpha_table_index = alpha_table.entry_index((
-                            ext_index,
-                            m.lod_item_index.map(|i| i as u16 + 1).unwrap_or_default(),
-                        )) as u16;
-
-                        // TODO: How to set these indices in applications?
-                        let base_mesh_index = m
-                            .base_mesh_index
-                            .map(|i| i as i32)
-                            .unwrap_or(default_base_mesh_index);
-
-                        xc3_lib::mxmd::Mesh {
-                            flags1: m.flags1,
-                            flags2: m.flags2,
-                            vertex_buffer_index: m.vertex_buffer_index as u16,
-                            index_buffer_index: m.index_buffer_index as u16,
-                            index_buffer_index2: m.index_buffer_index2 as u16,
-                            material_index: m.material_index as u16,
-                            unk2: 0,
-                            unk3: 0,
-                            ext_mesh_index: m.ext_mesh_index.unwrap_or_default() as u16,
-                            unk4: 0,
-                            unk5: 0, // TODO: flags?
-                            lod_item_index: m
-                                .lod_item_index
-                                .map(|i| i as u8 + 1)
-                                .unwrap_or_default(),
-                            unk_mesh_index2: 0, // TODO: how to set this?
-                            alpha_table_index,
-                            unk6: 0, // TODO: flags?
-                            base_mesh_index,
-                            unk8: 0,
-                            unk9: 0,
-                        }
-                    })
-                    .collect(),
-                unk1: 0,
-                max_xyz: model.max_xyz.to_array(),
-                min_xyz: model.min_xyz.to_array(),
-                bounding_radius: model.bounding_radius,
-                unks1: [0; 3],
-                unk2: mxmd.models.models[0].unk2,
-                unks: [0; 3],
+            .enumerate()
+            .map(|(model_index, model)| {
+                Ok(xc3_lib::mxmd::Model {
+                    meshes: model
+                        .meshes
+                        .iter()
+                        .enumerate()
+                        .map(|(mesh_index, m)| {
+                            // Keep the original index if the mesh's alpha table item is unchanged.
+                            // Multiple items may have the same values, so don't just search the table.
+                            let original_index = mxmd
+                                .models
+                                .models
+                                .get(model_index)
+                                .and_then(|model| model.meshes.get(mesh_index))
+                                .map(|mesh| mesh.alpha_table_index as usize)
+                                .filter(|i| {
+                                    alpha_table.get(*i)
+                                        == Some((m.ext_mesh_index, m.lod_item_index))
+                                });
+
+                            // Generate the mapping for unique ext mesh and lod values.
+                            // TODO: Why is the lod index set to 0 for some xc3 models?
+                            let alpha_table_index = match original_index {
+                                Some(i) => i,
+                                None => alpha_table.insert(m.ext_mesh_index, m.lod_item_index)?,
+                            };
+                            let alpha_table_index = u16::try_from(alpha_table_index)?;
+
+                            // TODO: How to set these indices in applications?
+                            let base_mesh_index = m
+                                .base_mesh_index
+                                .map(|i| i as i32)
+                                .unwrap_or(default_base_mesh_index);
+
+                            Ok(xc3_lib::mxmd::Mesh {
+                                flags1: m.flags1,
+                                flags2: m.flags2,
+                                vertex_buffer_index: m.vertex_buffer_index as u16,
+                                index_buffer_index: m.index_buffer_index as u16,
+                                index_buffer_index2: m.index_buffer_index2 as u16,
+                                material_index: m.material_index as u16,
+                                unk2: 0,
+                                unk3: 0,
+                                ext_mesh_index: m.ext_mesh_index.unwrap_or_default() as u16,
+                                unk4: 0,
+                                unk5: 0, // TODO: flags?
+                                lod_item_index: m
+                                    .lod_item_index
+                                    .map(|i| i as u8 + 1)
+                                    .unwrap_or_default(),
+                                unk_mesh_index2: 0, // TODO: how to set this?
+                                alpha_table_index,
+                                unk6: 0, // TODO: flags?
+                                base_mesh_index,
+                                unk8: 0,
+                                unk9: 0,
+                            })
+                        })
+                        .collect::<Result<_, CreateModelError>>()?,
+                    unk1: 0,
+                    max_xyz: model.max_xyz.to_array(),
+                    min_xyz: model.min_xyz.to_array(),
+                    bounding_radius: model.bounding_radius,
+                    unks1: [0; 3],
+                    unk2: mxmd.models.models[0].unk2,
+                    unks: [0; 3],
+                })
             })
-            .collect();
+            .collect::<Result<_, CreateModelError>>()?;
 
-        new_mxmd.models.alpha_table = Some(AlphaTable {
-            items: alpha_table.keys().copied().collect(),
-            unks: [0; 4],
-        });
+        new_mxmd.models.alpha_table = Some(alpha_table);
 
         new_mxmd.models.lod_data = self.models.lod_data.as_ref().map(|data| LodData {
             unk1: 0,
@@ -210,7 +226,7 @@ impl ModelRoot {
             }
         }
 
-        self.apply_materials(&mut new_mxmd);
+        apply_materials(&mut new_mxmd.materials, &self.models.materials);
 
         new_mxmd.models.min_xyz = new_mxmd
             .models
@@ -233,110 +249,6 @@ impl ModelRoot {
         Ok((new_mxmd, new_vertex, textures))
     }
 
-    fn apply_materials(&self, mxmd: &mut Mxmd) {
-        // Recreate start indices and counts by assuming value ranges don't overlap.
-        mxmd.materials.materials.clear();
-        mxmd.materials.work_values.clear();
-        mxmd.materials.shader_vars.clear();
-
-        // Don't assume callbacks are used.
-        let mut callbacks = mxmd.materials.callbacks.as_mut();
-        if let Some(callbacks) = callbacks.as_mut() {
-            callbacks.work_callbacks.clear();
-            callbacks.material_indices = (0..self.models.materials.len() as u16).collect();
-        }
-
-        let mut fur_params = Vec::new();
-        let mut fur_param_indices = Vec::new();
-
-        // Recreate materials to avoid restrictions with referencing existing ones.
-        for (i, m) in self.models.materials.iter().enumerate() {
-            // TODO: Is it ok to potentially add a new buffer index here?
-            let technique = xc3_lib::mxmd::MaterialTechnique {
-                technique_index: m.technique_index as u32,
-                pass_type: m.pass_type,
-                material_buffer_index: i as u16,
-                flags: 1,
-            };
-
-            // TODO: Also rebuild alpha textures in case we need to add more.
-            let new_material = xc3_lib::mxmd::Material {
-                name: m.name.clone(),
-                flags: m.flags,
-                render_flags: m.render_flags,
-                color: m.color,
-                alpha_test_ref: m.alpha_test_ref,
-                textures: m
-                    .textures
-                    .iter()
-                    .map(|t| xc3_lib::mxmd::Texture {
-                        texture_index: t.image_texture_index as u16,
-                        sampler_index: t.sampler_index as u16,
-                        unk2: 0,
-                        unk3: 0,
-                    })
-                    .collect(),
-                state_flags: m.state_flags,
-                m_unks1_1: m.m_unks1_1,
-                m_unks1_2: m.m_unks1_2,
-                m_unks1_3: m.m_unks1_3,
-                m_unks1_4: m.m_unks1_4,
-                work_value_start_index: mxmd.materials.work_values.len() as u32,
-                shader_var_start_index: mxmd.materials.shader_vars.len() as u32,
-                shader_var_count: m.shader_vars.len() as u32,
-                techniques: vec![technique],
-                unk5: 0,
-                callback_start_index: callbacks
-                    .as_ref()
-                    .map(|c| c.work_callbacks.len() as u16)
-                    .unwrap_or_default(),
-                callback_count: m.work_callbacks.len() as u16,
-                m_unks2: [0, 0, m.m_unks2_2],
-                alpha_test_texture_index: m
-                    .alpha_test
-                    .as_ref()
-                    .and_then(|a| {
-                        let alpha_image_index =
-                            m.textures[a.texture_index].image_texture_index as u16;
-                        // TODO: This won't work since textures can be used more than once.
-                        mxmd.materials
-                            .alpha_test_textures
-                            .iter()
-                            .position(|t| t.texture_index == alpha_image_index)
-                    })
-                    .unwrap_or_default() as u16,
-                m_unks3: [0, m.m_unks3_1, 0, 0, 0, 0, 0, 0],
-            };
-            mxmd.materials.materials.push(new_material);
-
-            mxmd.materials.work_values.extend_from_slice(&m.work_values);
-            mxmd.materials.shader_vars.extend_from_slice(&m.shader_vars);
-            if let Some(callbacks) = callbacks.as_mut() {
-                callbacks
-                    .work_callbacks
-                    .extend_from_slice(&m.work_callbacks);
-            }
-
-            if let Some(params) = &m.fur_params {
-                // Each material uses its own params in practice.
-                fur_param_indices.push(fur_params.len() as u16);
-                fur_params.push(params.clone());
-            } else {
-                fur_param_indices.push(0);
-            }
-        }
-
-        mxmd.materials.fur_shells = if !fur_params.is_empty() {
-            Some(xc3_lib::mxmd::FurShells {
-                material_param_indices: fur_param_indices,
-                params: fur_params,
-                unk: [0; 4],
-            })
-        } else {
-            None
-        };
-    }
-
     fn match_technique_attributes(&self, buffers: &mut ModelBuffers, mxmd: &Mxmd) {
         let attribute_count =
             |attrs: &[VertexAttribute]| attrs.iter().filter(|a| a.buffer_index == 0).count();
@@ -369,6 +281,112 @@ impl ModelRoot {
     }
 }
 
+pub(crate) fn apply_materials(
+    mxmd_materials: &mut xc3_lib::mxmd::Materials,
+    materials: &[Material],
+) {
+    // Recreate start indices and counts by assuming value ranges don't overlap.
+    mxmd_materials.materials.clear();
+    mxmd_materials.work_values.clear();
+    mxmd_materials.shader_vars.clear();
+
+    // Don't assume callbacks are used.
+    let mut callbacks = mxmd_materials.callbacks.as_mut();
+    if let Some(callbacks) = callbacks.as_mut() {
+        callbacks.work_callbacks.clear();
+        callbacks.material_indices = (0..materials.len() as u16).collect();
+    }
+
+    let mut fur_params = Vec::new();
+    let mut fur_param_indices = Vec::new();
+
+    // Recreate materials to avoid restrictions with referencing existing ones.
+    for (i, m) in materials.iter().enumerate() {
+        // TODO: Is it ok to potentially add a new buffer index here?
+        let technique = xc3_lib::mxmd::MaterialTechnique {
+            technique_index: m.technique_index as u32,
+            pass_type: m.pass_type,
+            material_buffer_index: i as u16,
+            flags: 1,
+        };
+
+        // TODO: Also rebuild alpha textures in case we need to add more.
+        let new_material = xc3_lib::mxmd::Material {
+            name: m.name.clone(),
+            flags: m.flags,
+            render_flags: m.render_flags,
+            color: m.color,
+            alpha_test_ref: m.alpha_test_ref,
+            textures: m
+                .textures
+                .iter()
+                .map(|t| xc3_lib::mxmd::Texture {
+                    texture_index: t.image_texture_index as u16,
+                    sampler_index: t.sampler_index as u16,
+                    unk2: 0,
+                    unk3: 0,
+                })
+                .collect(),
+            state_flags: m.state_flags,
+            m_unks1_1: m.m_unks1_1,
+            m_unks1_2: m.m_unks1_2,
+            m_unks1_3: m.m_unks1_3,
+            m_unks1_4: m.m_unks1_4,
+            work_value_start_index: mxmd_materials.work_values.len() as u32,
+            shader_var_start_index: mxmd_materials.shader_vars.len() as u32,
+            shader_var_count: m.shader_vars.len() as u32,
+            techniques: vec![technique],
+            unk5: 0,
+            callback_start_index: callbacks
+                .as_ref()
+                .map(|c| c.work_callbacks.len() as u16)
+                .unwrap_or_default(),
+            callback_count: m.work_callbacks.len() as u16,
+            m_unks2: [0, 0, m.m_unks2_2],
+            alpha_test_texture_index: m
+                .alpha_test
+                .as_ref()
+                .and_then(|a| {
+                    let alpha_image_index = m.textures[a.texture_index].image_texture_index as u16;
+                    // TODO: This won't work since textures can be used more than once.
+                    mxmd_materials
+                        .alpha_test_textures
+                        .iter()
+                        .position(|t| t.texture_index == alpha_image_index)
+                })
+                .unwrap_or_default() as u16,
+            m_unks3: [0, m.m_unks3_1, 0, 0, 0, 0, 0, 0],
+        };
+        mxmd_materials.materials.push(new_material);
+
+        mxmd_materials.work_values.extend_from_slice(&m.work_values);
+        mxmd_materials.shader_vars.extend_from_slice(&m.shader_vars);
+        if let Some(callbacks) = callbacks.as_mut() {
+            callbacks
+                .work_callbacks
+                .extend_from_slice(&m.work_callbacks);
+        }
+
+        if let Some(params) = &m.fur_params {
+            // Each material uses its own params in practice.
+            fur_param_indices.push(fur_params.len() as u16);
+            fur_params.push(params.clone());
+        } else {
+            fur_param_indices.push(0);
+        }
+    }
+
+    mxmd_materials.fur_shells = if !fur_params.is_empty() {
+        Some(xc3_lib::mxmd::FurShells {
+            material_param_indices: fur_param_indices,
+            params: fur_params,
+            unk: [0; 4],
+        })
+    } else {
+        None
+    };
+}
+
 fn apply_skinning(
     new_skinning: &mut xc3_lib::mxmd::Skinning,
     skinning: &crate::skinning::Skinning,
@@ -9,7 +9,12 @@
 //! Applications can parse the data with [ShaderDatabase::from_file]
 //! to avoid needing to generate this data at runtime.
 
-use std::{collections::BTreeMap, path::Path};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    path::Path,
+    sync::{Mutex, MutexGuard},
+};
 
 use indexmap::IndexMap;
 use ordered_float::OrderedFloat;
@@ -32,7 +37,33 @@ pub enum SaveShaderDatabaseError {
 
 /// Metadata for the assigned shaders for all models and maps in a game dump.
 #[derive(Debug, PartialEq, Clone)]
-pub struct ShaderDatabase(io::ShaderDatabaseIndexed);
+pub struct ShaderDatabase(io::ShaderDatabaseIndexed, ProgramCache);
+
+/// Programs converted by [ShaderDatabase::shader_program] to avoid converting
+/// the same program for each material when loading many models.
+#[derive(Debug, Default)]
+struct ProgramCache(Mutex<HashMap<ProgramHash, Option<ShaderProgram>>>);
+
+impl ProgramCache {
+    fn lock(&self) -> MutexGuard<'_, HashMap<ProgramHash, Option<ShaderProgram>>> {
+        // The cached data is still valid even if another thread panicked.
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clone for ProgramCache {
+    fn clone(&self) -> Self {
+        // The cache is recreated as programs are accessed.
+        Self::default()
+    }
+}
+
+impl PartialEq for ProgramCache {
+    fn eq(&self, _other: &Self) -> bool {
+        // The cache does not affect the database contents.
+        true
+    }
+}
 
 impl ShaderDatabase {
     /// Load the database data from `path`.
@@ -40,7 +71,7 @@ impl ShaderDatabase {
         // Avoid converting the indexed database to improve load times.
         // Most uses cases will only need data for a single model or map.
         let indexed = io::ShaderDatabaseIndexed::from_file(path)?;
-        Ok(Self(indexed))
+        Ok(Self(indexed, ProgramCache::default()))
     }
 
     /// Serialize and save the database data to `path`.
@@ -50,18 +81,99 @@ impl ShaderDatabase {
     }
 
     /// The shader information for the specified shader program.
+    ///
+    /// Programs are cached, so repeated calls with the same `hash` are cheap.
     pub fn shader_program(&self, hash: ProgramHash) -> Option<ShaderProgram> {
-        self.0.shader_program(hash)
+        if let Some(program) = self.1.lock().get(&hash) {
+            return program.clone();
+        }
+
+        // Don't hold the lock while converting to allow loading models in parallel.
+        let program = self.0.shader_program(hash);
+        self.1.lock().insert(hash, program.clone());
+        program
+    }
+
+    /// Write the dependencies for each output of all programs as CSV.
+    ///
+    /// Each row contains the program hash, output, channel, and dependency
+    /// for spreadsheet analysis of assignments across an entire game.
+    /// Outputs with multiple dependencies have a row for each dependency.
+    pub fn write_output_dependencies_csv<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writeln!(writer, "program_hash,output,channel,dependency")?;
+        for hash in self.0.program_hashes() {
+            let Some(program) = self.0.shader_program(hash) else {
+                continue;
+            };
+
+            for (name, dependencies) in &program.output_dependencies {
+                let (output, channel) = name.split_once('.').unwrap_or((name, ""));
+                for dependency in &dependencies.dependencies {
+                    writeln!(
+                        writer,
+                        "{:08X},{output},{channel},{}",
+                        hash.0,
+                        dependency_csv(dependency)
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add or replace the data for the program with the given `hash`.
+    ///
+    /// Existing programs are not reindexed, so updating a single program
+    /// and calling [Self::save] avoids regenerating the entire database.
+    /// Data only used by replaced programs is kept until the database is recreated with [Self::merge].
+    pub fn update_program(&mut self, hash: ProgramHash, program: ShaderProgram) {
+        self.0.update_program(hash, program);
+        self.1.lock().remove(&hash);
     }
 
     /// Create the internal database representation from non indexed data.
     pub fn from_programs(programs: BTreeMap<ProgramHash, ShaderProgram>) -> Self {
-        Self(io::ShaderDatabaseIndexed::from_programs(programs))
+        Self(
+            io::ShaderDatabaseIndexed::from_programs(programs),
+            ProgramCache::default(),
+        )
     }
 
     /// Create a new database with combined entries from `other`.
     pub fn merge(&self, other: &Self) -> Self {
-        Self(self.0.merge(&other.0))
+        Self(self.0.merge(&other.0), ProgramCache::default())
+    }
+
+    /// Similar to [Self::merge] but also returns the hashes for programs
+    /// in both databases with different data.
+    ///
+    /// The merged database uses the program from `other` for any conflicts.
+    pub fn merge_checked(&self, other: &Self) -> (Self, Vec<ProgramHash>) {
+        let conflicts = self.0.conflicting_programs(&other.0);
+        (self.merge(other), conflicts)
+    }
+}
+
+fn dependency_csv(dependency: &Dependency) -> String {
+    match dependency {
+        Dependency::Constant(f) => f.to_string(),
+        Dependency::Buffer(b) => buffer_csv(b),
+        Dependency::Texture(t) => with_channel(&t.name, t.channel),
+        Dependency::Attribute(a) => with_channel(&a.name, a.channel),
+    }
+}
+
+fn buffer_csv(b: &BufferDependency) -> String {
+    match b.index {
+        Some(i) => with_channel(&format!("{}.{}[{i}]", b.name, b.field), b.channel),
+        None => with_channel(&format!("{}.{}", b.name, b.field), b.channel),
+    }
+}
+
+fn with_channel(name: &str, channel: Option<char>) -> String {
+    match channel {
+        Some(c) => format!("{name}.{c}"),
+        None => name.to_string(),
     }
 }
 
@@ -101,6 +213,7 @@ impl ProgramHash {
 }
 
 /// A single shader program with a vertex and fragment shader.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone, Default)]
 pub struct ShaderProgram {
     /// The input values used to initialize each fragment output.
@@ -120,6 +233,7 @@ pub struct ShaderProgram {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Clone)]
 pub struct OutputDependencies {
     /// All of the possible dependencies that may affect the output.
@@ -129,6 +243,7 @@ pub struct OutputDependencies {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Dependency {
     Constant(OrderedFloat<f32>),
@@ -138,6 +253,7 @@ pub enum Dependency {
 }
 
 /// A single buffer access like `UniformBuffer.field[0].y` or `UniformBuffer.field.y` in GLSL.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct BufferDependency {
     pub name: SmolStr,
@@ -147,15 +263,39 @@ pub struct BufferDependency {
 }
 
 /// A single texture access like `texture(s0, tex0.xy).rgb` in GLSL.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct TextureDependency {
     pub name: SmolStr,
     pub channel: Option<char>,
     /// Texture coordinate values used for the texture function call.
     pub texcoords: Vec<TexCoord>,
+    /// The layer for array textures like `texture(s0, vec3(tex0.xy, 1.0))`.
+    pub array_layer: Option<TextureArrayLayer>,
+}
+
+impl TextureDependency {
+    /// Returns `true` if any texture coordinate is offset using [TexCoordParams::Parallax].
+    ///
+    /// This distinguishes height offset samples from plain texture samples.
+    pub fn is_parallax(&self) -> bool {
+        self.texcoords
+            .iter()
+            .any(|t| matches!(t.params, Some(TexCoordParams::Parallax { .. })))
+    }
+}
+
+/// The layer index value for an array texture access.
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum TextureArrayLayer {
+    Constant(OrderedFloat<f32>),
+    Buffer(BufferDependency),
 }
 
 /// A texture coordinate attribute with optional transform parameters.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct TexCoord {
     /// The name of the attribute like "in_attr4".
@@ -166,6 +306,7 @@ pub struct TexCoord {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum TexCoordParams {
     // A single scale parameter.
@@ -187,6 +328,7 @@ pub enum TexCoordParams {
 }
 
 /// A single input attribute like `in_attr0.x` in GLSL.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct AttributeDependency {
     pub name: SmolStr,
@@ -194,6 +336,7 @@ pub struct AttributeDependency {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum LayerBlendMode {
     /// `mix(a, b, ratio)`
@@ -215,6 +358,7 @@ impl Default for LayerBlendMode {
 }
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct TextureLayer {
     pub value: Dependency,
@@ -223,7 +367,82 @@ pub struct TextureLayer {
     pub is_fresnel: bool,
 }
 
+/// The textures assigned to each channel of a metallic roughness PBR material.
+/// See [ShaderProgram::pbr_assignments].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct PbrTextures {
+    /// The albedo or base color RGB channels from `o0.xyz`.
+    pub albedo: [Option<TextureDependency>; 3],
+    /// The metalness from `o1.x`.
+    pub metalness: Option<TextureDependency>,
+    /// The glossiness from `o1.y`.
+    /// Roughness is calculated as `1.0 - glossiness`.
+    pub glossiness: Option<TextureDependency>,
+    /// The ambient occlusion from `o2.z`.
+    pub ambient_occlusion: Option<TextureDependency>,
+}
+
+/// The channels of a texture sampled by a [ShaderProgram].
+/// See [ShaderProgram::texture_channels].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct TextureChannels {
+    pub x: bool,
+    pub y: bool,
+    pub z: bool,
+    pub w: bool,
+}
+
+impl TextureChannels {
+    fn insert(&mut self, channel: Option<char>) {
+        match channel {
+            Some('x') => self.x = true,
+            Some('y') => self.y = true,
+            Some('z') => self.z = true,
+            Some('w') => self.w = true,
+            // Assume accesses without a channel use the entire texture.
+            _ => *self = Self::all(),
+        }
+    }
+
+    fn all() -> Self {
+        Self {
+            x: true,
+            y: true,
+            z: true,
+            w: true,
+        }
+    }
+}
+
 impl ShaderProgram {
+    /// Returns the channels used from each texture across all outputs in order of first use.
+    ///
+    /// This includes textures for all [layers](struct.OutputDependencies.html#structfield.layers).
+    /// Channels not listed for a texture do not affect any output.
+    pub fn texture_channels(&self) -> IndexMap<SmolStr, TextureChannels> {
+        let mut channels: IndexMap<SmolStr, TextureChannels> = IndexMap::new();
+
+        for output in self.output_dependencies.values() {
+            let layer_dependencies = output
+                .layers
+                .iter()
+                .flat_map(|l| std::iter::once(&l.value).chain(l.ratio.as_ref()));
+
+            for dependency in output.dependencies.iter().chain(layer_dependencies) {
+                if let Dependency::Texture(t) = dependency {
+                    channels
+                        .entry(t.name.clone())
+                        .or_default()
+                        .insert(t.channel);
+                }
+            }
+        }
+
+        channels
+    }
+
     /// Returns the textures assigned to the output or `None` if the output does not use any texture.
     pub fn textures(&self, output_index: usize, channel: char) -> Vec<&TextureDependency> {
         let output = format!("o{output_index}.{channel}");
@@ -294,6 +513,33 @@ impl ShaderProgram {
             _ => None,
         }
     }
+
+    /// Returns the base layer texture assigned to each PBR material channel.
+    ///
+    /// This uses the G-Buffer output assignments for metalness, glossiness,
+    /// and ambient occlusion that are packed into separate channels of `o1` and `o2`.
+    pub fn pbr_assignments(&self) -> PbrTextures {
+        PbrTextures {
+            albedo: ['x', 'y', 'z'].map(|c| self.base_texture(0, c)),
+            metalness: self.base_texture(1, 'x'),
+            glossiness: self.base_texture(1, 'y'),
+            ambient_occlusion: self.base_texture(2, 'z'),
+        }
+    }
+
+    fn base_texture(&self, output_index: usize, channel: char) -> Option<TextureDependency> {
+        let output = format!("o{output_index}.{channel}");
+        let dependencies = self.output_dependencies.get(&SmolStr::from(output))?;
+
+        // Prefer the base layer if present since later layers are blended on top.
+        match dependencies.layers.first().map(|l| &l.value) {
+            Some(Dependency::Texture(t)) => Some(t.clone()),
+            _ => dependencies.dependencies.iter().find_map(|d| match d {
+                Dependency::Texture(t) => Some(t.clone()),
+                _ => None,
+            }),
+        }
+    }
 }
 
 #[cfg(feature = "arbitrary")]
@@ -325,6 +571,7 @@ impl<'a> arbitrary::Arbitrary<'a> for TextureDependency {
             name: crate::arbitrary_smolstr(u)?,
             channel: u.arbitrary()?,
             texcoords: u.arbitrary()?,
+            array_layer: u.arbitrary()?,
         })
     }
 }
@@ -367,6 +614,70 @@ mod tests {
         assert!(shader.textures(0, 'x').is_empty());
     }
 
+    #[test]
+    fn texture_channels_outputs_layers() {
+        let texture = |name: &str, channel| {
+            Dependency::Texture(TextureDependency {
+                name: name.into(),
+                channel: Some(channel),
+                texcoords: Vec::new(),
+                array_layer: None,
+            })
+        };
+        let shader = ShaderProgram {
+            output_dependencies: [
+                (
+                    "o0.x".into(),
+                    OutputDependencies {
+                        dependencies: vec![texture("s1", 'y'), texture("s0", 'x')],
+                        layers: Vec::new(),
+                    },
+                ),
+                (
+                    "o1.y".into(),
+                    OutputDependencies {
+                        dependencies: vec![Dependency::Constant(1.0.into())],
+                        layers: vec![TextureLayer {
+                            value: texture("s0", 'w'),
+                            ratio: Some(texture("s2", 'z')),
+                            blend_mode: LayerBlendMode::Mix,
+                            is_fresnel: false,
+                        }],
+                    },
+                ),
+            ]
+            .into(),
+            outline_width: None,
+        };
+        assert_eq!(
+            IndexMap::from([
+                (
+                    "s1".into(),
+                    TextureChannels {
+                        y: true,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    "s0".into(),
+                    TextureChannels {
+                        x: true,
+                        w: true,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    "s2".into(),
+                    TextureChannels {
+                        z: true,
+                        ..Default::default()
+                    }
+                ),
+            ]),
+            shader.texture_channels()
+        );
+    }
+
     #[test]
     fn material_channel_assignment_single_output_no_assignment() {
         let shader = ShaderProgram {
@@ -394,6 +705,7 @@ mod tests {
                             name: "s0".into(),
                             channel: Some('y'),
                             texcoords: Vec::new(),
+                            array_layer: None,
                         })],
                         layers: Vec::new(),
                     },
@@ -406,11 +718,13 @@ mod tests {
                                 name: "tex".into(),
                                 channel: Some('y'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                             Dependency::Texture(TextureDependency {
                                 name: "s2".into(),
                                 channel: Some('z'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                         ],
                         layers: Vec::new(),
@@ -423,6 +737,7 @@ mod tests {
                             name: "s3".into(),
                             channel: Some('y'),
                             texcoords: Vec::new(),
+                            array_layer: None,
                         })],
                         layers: Vec::new(),
                     },
@@ -436,12 +751,14 @@ mod tests {
                 &TextureDependency {
                     name: "tex".into(),
                     channel: Some('y'),
-                    texcoords: Vec::new()
+                    texcoords: Vec::new(),
+                    array_layer: None,
                 },
                 &TextureDependency {
                     name: "s2".into(),
                     channel: Some('z'),
-                    texcoords: Vec::new()
+                    texcoords: Vec::new(),
+                    array_layer: None,
                 },
             ],
             shader.textures(0, 'y')
@@ -459,6 +776,7 @@ mod tests {
                             name: "s0".into(),
                             channel: Some('y'),
                             texcoords: Vec::new(),
+                            array_layer: None,
                         })],
                         layers: Vec::new(),
                     },
@@ -471,11 +789,13 @@ mod tests {
                                 name: "tex".into(),
                                 channel: Some('y'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                             Dependency::Texture(TextureDependency {
                                 name: "s2".into(),
                                 channel: Some('z'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                         ],
                         layers: Vec::new(),
@@ -507,6 +827,7 @@ mod tests {
                             name: "s0".into(),
                             channel: Some('y'),
                             texcoords: Vec::new(),
+                            array_layer: None,
                         })],
                         layers: Vec::new(),
                     },
@@ -519,11 +840,13 @@ mod tests {
                                 name: "tex".into(),
                                 channel: Some('y'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                             Dependency::Texture(TextureDependency {
                                 name: "s2".into(),
                                 channel: Some('z'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             }),
                         ],
                         layers: Vec::new(),
@@ -556,4 +879,281 @@ mod tests {
             shader.buffer_parameter(1, 'z')
         );
     }
+
+    fn constant_program(value: f32) -> ShaderProgram {
+        ShaderProgram {
+            output_dependencies: [(
+                "o0.x".into(),
+                OutputDependencies {
+                    dependencies: vec![Dependency::Constant(value.into())],
+                    layers: Vec::new(),
+                },
+            )]
+            .into(),
+            outline_width: None,
+        }
+    }
+
+    #[test]
+    fn merge_checked_conflicts() {
+        let a = ShaderDatabase::from_programs(
+            [
+                (ProgramHash(1), constant_program(1.0)),
+                (ProgramHash(2), constant_program(2.0)),
+            ]
+            .into(),
+        );
+        let b = ShaderDatabase::from_programs(
+            [
+                (ProgramHash(1), constant_program(1.0)),
+                (ProgramHash(2), constant_program(3.0)),
+                (ProgramHash(4), constant_program(4.0)),
+            ]
+            .into(),
+        );
+
+        let (merged, conflicts) = a.merge_checked(&b);
+        assert_eq!(vec![ProgramHash(2)], conflicts);
+        assert_eq!(merged, a.merge(&b));
+        assert_eq!(
+            Some(constant_program(3.0)),
+            merged.shader_program(ProgramHash(2))
+        );
+        assert_eq!(
+            Some(constant_program(4.0)),
+            merged.shader_program(ProgramHash(4))
+        );
+    }
+
+    #[test]
+    fn write_output_dependencies_csv() {
+        let program = ShaderProgram {
+            output_dependencies: [
+                (
+                    "o0.x".into(),
+                    OutputDependencies {
+                        dependencies: vec![
+                            Dependency::Texture(texture("s0", 'x')),
+                            Dependency::Buffer(BufferDependency {
+                                name: "U_Mate".into(),
+                                field: "gWrkFl4".into(),
+                                index: Some(1),
+                                channel: Some('z'),
+                            }),
+                        ],
+                        layers: Vec::new(),
+                    },
+                ),
+                (
+                    "o1.w".into(),
+                    OutputDependencies {
+                        dependencies: vec![
+                            Dependency::Constant(0.5.into()),
+                            Dependency::Attribute(AttributeDependency {
+                                name: "vColor".into(),
+                                channel: Some('w'),
+                            }),
+                        ],
+                        layers: Vec::new(),
+                    },
+                ),
+            ]
+            .into(),
+            outline_width: None,
+        };
+        let database = ShaderDatabase::from_programs([(ProgramHash(0xABCD), program)].into());
+
+        let mut writer = Vec::new();
+        database.write_output_dependencies_csv(&mut writer).unwrap();
+        assert_eq!(
+            "program_hash,output,channel,dependency\n\
+             0000ABCD,o0,x,s0.x\n\
+             0000ABCD,o0,x,U_Mate.gWrkFl4[1].z\n\
+             0000ABCD,o1,w,0.5\n\
+             0000ABCD,o1,w,vColor.w\n",
+            String::from_utf8(writer).unwrap()
+        );
+    }
+
+    #[test]
+    fn update_program_add_replace() {
+        let mut database = ShaderDatabase::from_programs(
+            [
+                (ProgramHash(1), constant_program(1.0)),
+                (ProgramHash(2), constant_program(2.0)),
+            ]
+            .into(),
+        );
+
+        database.update_program(ProgramHash(2), constant_program(3.0));
+        database.update_program(ProgramHash(4), constant_program(1.0));
+
+        assert_eq!(
+            Some(constant_program(1.0)),
+            database.shader_program(ProgramHash(1))
+        );
+        assert_eq!(
+            Some(constant_program(3.0)),
+            database.shader_program(ProgramHash(2))
+        );
+        assert_eq!(
+            Some(constant_program(1.0)),
+            database.shader_program(ProgramHash(4))
+        );
+    }
+
+    #[test]
+    fn update_program_after_cached_lookup() {
+        let mut database =
+            ShaderDatabase::from_programs([(ProgramHash(1), constant_program(1.0))].into());
+
+        assert_eq!(
+            Some(constant_program(1.0)),
+            database.shader_program(ProgramHash(1))
+        );
+        assert_eq!(None, database.shader_program(ProgramHash(2)));
+
+        database.update_program(ProgramHash(1), constant_program(2.0));
+        database.update_program(ProgramHash(2), constant_program(3.0));
+
+        assert_eq!(
+            Some(constant_program(2.0)),
+            database.shader_program(ProgramHash(1))
+        );
+        assert_eq!(
+            Some(constant_program(3.0)),
+            database.shader_program(ProgramHash(2))
+        );
+    }
+
+    fn texture(name: &str, channel: char) -> TextureDependency {
+        TextureDependency {
+            name: name.into(),
+            channel: Some(channel),
+            texcoords: Vec::new(),
+            array_layer: None,
+        }
+    }
+
+    #[test]
+    fn pbr_assignments_empty() {
+        let shader = ShaderProgram {
+            output_dependencies: IndexMap::new(),
+            outline_width: None,
+        };
+        assert_eq!(PbrTextures::default(), shader.pbr_assignments());
+    }
+
+    #[test]
+    fn pbr_assignments_split_channels() {
+        let output = |dependencies, layers| OutputDependencies {
+            dependencies,
+            layers,
+        };
+        let shader = ShaderProgram {
+            output_dependencies: [
+                (
+                    "o0.x".into(),
+                    output(vec![Dependency::Texture(texture("s0", 'x'))], Vec::new()),
+                ),
+                (
+                    "o0.y".into(),
+                    output(vec![Dependency::Texture(texture("s0", 'y'))], Vec::new()),
+                ),
+                (
+                    "o0.z".into(),
+                    output(vec![Dependency::Constant(0.5.into())], Vec::new()),
+                ),
+                (
+                    "o1.x".into(),
+                    output(
+                        vec![
+                            Dependency::Buffer(BufferDependency {
+                                name: "U_Mate".into(),
+                                field: "gWrkFl4".into(),
+                                index: Some(0),
+                                channel: Some('x'),
+                            }),
+                            Dependency::Texture(texture("s1", 'x')),
+                        ],
+                        Vec::new(),
+                    ),
+                ),
+                (
+                    "o1.y".into(),
+                    output(
+                        vec![
+                            Dependency::Texture(texture("s3", 'y')),
+                            Dependency::Texture(texture("s1", 'y')),
+                        ],
+                        vec![
+                            TextureLayer {
+                                value: Dependency::Texture(texture("s1", 'y')),
+                                ratio: None,
+                                blend_mode: LayerBlendMode::Mix,
+                                is_fresnel: false,
+                            },
+                            TextureLayer {
+                                value: Dependency::Texture(texture("s3", 'y')),
+                                ratio: None,
+                                blend_mode: LayerBlendMode::Mix,
+                                is_fresnel: false,
+                            },
+                        ],
+                    ),
+                ),
+                (
+                    "o2.z".into(),
+                    output(vec![Dependency::Texture(texture("s2", 'z'))], Vec::new()),
+                ),
+            ]
+            .into(),
+            outline_width: None,
+        };
+        assert_eq!(
+            PbrTextures {
+                albedo: [Some(texture("s0", 'x')), Some(texture("s0", 'y')), None],
+                metalness: Some(texture("s1", 'x')),
+                glossiness: Some(texture("s1", 'y')),
+                ambient_occlusion: Some(texture("s2", 'z')),
+            },
+            shader.pbr_assignments()
+        );
+    }
+
+    #[test]
+    fn texture_is_parallax() {
+        let mut texture = TextureDependency {
+            name: "s0".into(),
+            channel: Some('x'),
+            texcoords: vec![TexCoord {
+                name: "in_attr4".into(),
+                channel: Some('x'),
+                params: Some(TexCoordParams::Scale(BufferDependency {
+                    name: "U_Mate".into(),
+                    field: "gWrkFl4".into(),
+                    index: Some(0),
+                    channel: Some('x'),
+                })),
+            }],
+            array_layer: None,
+        };
+        assert!(!texture.is_parallax());
+
+        texture.texcoords.push(TexCoord {
+            name: "in_attr4".into(),
+            channel: Some('y'),
+            params: Some(TexCoordParams::Parallax {
+                mask_a: Dependency::Constant(0.0.into()),
+                mask_b: Dependency::Constant(1.0.into()),
+                ratio: BufferDependency {
+                    name: "U_Mate".into(),
+                    field: "gWrkFl4".into(),
+                    index: Some(1),
+                    channel: Some('z'),
+                },
+            }),
+        });
+        assert!(texture.is_parallax());
+    }
 }
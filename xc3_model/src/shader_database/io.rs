@@ -8,7 +8,8 @@ use varint_rs::{VarintReader, VarintWriter};
 
 use super::{
     AttributeDependency, BufferDependency, Dependency, LayerBlendMode, OutputDependencies,
-    ProgramHash, ShaderProgram, TexCoord, TexCoordParams, TextureDependency, TextureLayer,
+    ProgramHash, ShaderProgram, TexCoord, TexCoordParams, TextureArrayLayer, TextureDependency,
+    TextureLayer,
 };
 
 // Create a separate optimized representation for on disk.
@@ -18,8 +19,8 @@ use super::{
 pub struct ShaderDatabaseIndexed {
     // File version numbers should be updated with each release.
     // This improves the error when parsing an incompatible version.
-    #[br(assert(major_version == 2))]
-    #[bw(calc = 2)]
+    #[br(assert(major_version == 3))]
+    #[bw(calc = 3)]
     major_version: u16,
     #[bw(calc = 0)]
     _minor_version: u16,
@@ -211,6 +212,20 @@ struct TextureDependencyIndexed {
     #[br(parse_with = parse_count)]
     #[bw(write_with = write_count)]
     texcoords: Vec<TexCoordIndexed>,
+
+    array_layer: TextureArrayLayerIndexed,
+}
+
+#[derive(Debug, PartialEq, Clone, BinRead, BinWrite)]
+enum TextureArrayLayerIndexed {
+    #[brw(magic(0u8))]
+    None,
+
+    #[brw(magic(1u8))]
+    Constant(f32),
+
+    #[brw(magic(2u8))]
+    Buffer(VarInt),
 }
 
 #[binrw]
@@ -259,6 +274,10 @@ impl ShaderDatabaseIndexed {
         Ok(())
     }
 
+    pub fn program_hashes(&self) -> impl Iterator<Item = ProgramHash> + '_ {
+        self.programs.keys().map(|hash| ProgramHash(*hash))
+    }
+
     pub fn shader_program(&self, hash: ProgramHash) -> Option<ShaderProgram> {
         self.programs
             .get(&hash.0)
@@ -284,6 +303,26 @@ impl ShaderDatabaseIndexed {
         database
     }
 
+    pub fn update_program(&mut self, hash: ProgramHash, program: ShaderProgram) {
+        // Reuse existing entries to avoid reindexing the other programs.
+        let mut dependency_to_index = (0..self.dependencies.len())
+            .map(|i| (self.dependency_from_indexed(VarInt(i)), i))
+            .collect();
+        let mut buffer_dependency_to_index = self
+            .buffer_dependencies
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (buffer_dependency(b.clone(), &self.strings), i))
+            .collect();
+
+        let program = self.program_indexed(
+            program,
+            &mut dependency_to_index,
+            &mut buffer_dependency_to_index,
+        );
+        self.programs.insert(hash.0, program);
+    }
+
     pub fn merge(&self, other: &Self) -> Self {
         let mut dependency_to_index = IndexMap::new();
         let mut buffer_dependency_to_index = IndexMap::new();
@@ -314,6 +353,18 @@ impl ShaderDatabaseIndexed {
         merged
     }
 
+    pub fn conflicting_programs(&self, other: &Self) -> Vec<ProgramHash> {
+        other
+            .programs
+            .iter()
+            .filter_map(|(hash, program)| {
+                let existing = self.programs.get(hash)?;
+                (self.program_from_indexed(existing) != other.program_from_indexed(program))
+                    .then_some(ProgramHash(*hash))
+            })
+            .collect()
+    }
+
     fn program_indexed(
         &mut self,
         p: ShaderProgram,
@@ -515,6 +566,15 @@ impl ShaderDatabaseIndexed {
                         },
                     })
                     .collect(),
+                array_layer: match t.array_layer {
+                    TextureArrayLayerIndexed::None => None,
+                    TextureArrayLayerIndexed::Constant(f) => {
+                        Some(TextureArrayLayer::Constant(f.into()))
+                    }
+                    TextureArrayLayerIndexed::Buffer(b) => Some(TextureArrayLayer::Buffer(
+                        buffer_dependency(self.buffer_dependencies[b.0].clone(), &self.strings),
+                    )),
+                },
             }),
             DependencyIndexed::Attribute(a) => Dependency::Attribute(AttributeDependency {
                 name: self.strings[a.name.0].to_smolstr(),
@@ -576,6 +636,13 @@ impl ShaderDatabaseIndexed {
                             .unwrap_or(TexCoordParamsIndexed::None),
                     })
                     .collect(),
+                array_layer: match t.array_layer {
+                    Some(TextureArrayLayer::Constant(f)) => TextureArrayLayerIndexed::Constant(f.0),
+                    Some(TextureArrayLayer::Buffer(b)) => TextureArrayLayerIndexed::Buffer(
+                        self.add_buffer_dependency(b, buffer_dependency_to_index),
+                    ),
+                    None => TextureArrayLayerIndexed::None,
+                },
             }),
             Dependency::Attribute(a) => DependencyIndexed::Attribute(AttributeDependencyIndexed {
                 name: self.add_string(&a.name),
@@ -1,8 +1,15 @@
 //! Utilities for working with vertex skinning.
-use glam::{Vec3, Vec4};
+use std::collections::BTreeSet;
+
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
 use log::error;
 use xc3_lib::{mxmd::RenderPassType, vertex::WeightLod};
 
+use crate::{
+    vertex::{AttributeData, ModelBuffers, VertexBuffer},
+    Skeleton,
+};
+
 #[cfg(feature = "arbitrary")]
 use crate::{arbitrary_vec3, arbitrary_vec4s};
 
@@ -54,6 +61,48 @@ pub enum BoneConstraintType {
     Distance,
 }
 
+impl Skinning {
+    /// Apply the bone constraints to the model space `transforms` for each bone in [bones](#structfield.bones).
+    ///
+    /// Bones are processed in order, so parent bones should appear before their children.
+    /// Bones without a constraint or with an invalid parent are unchanged.
+    ///
+    /// The supported constraint types are
+    /// [BoneConstraintType::FixedOffset] and [BoneConstraintType::Distance].
+    /// Fixed offset bones are placed at the fixed offset relative to the parent bone.
+    /// Distance bones are kept within the max distance of the fixed offset relative to the parent bone.
+    /// Constraints only affect the translation of each bone.
+    pub fn apply_constraints(&self, transforms: &mut [crate::Transform]) {
+        for (i, bone) in self.bones.iter().enumerate() {
+            let Some(constraint) = &bone.constraint else {
+                continue;
+            };
+            let Some(parent_index) = constraint.parent_index.filter(|p| *p != i) else {
+                continue;
+            };
+            let Some(parent) = transforms.get(parent_index).copied() else {
+                continue;
+            };
+            let Some(transform) = transforms.get_mut(i) else {
+                continue;
+            };
+
+            let target = parent.translation + parent.rotation * constraint.fixed_offset;
+
+            match constraint.constraint_type {
+                BoneConstraintType::FixedOffset => transform.translation = target,
+                BoneConstraintType::Distance => {
+                    let offset = transform.translation - target;
+                    if offset.length() > constraint.max_distance {
+                        transform.translation =
+                            target + offset.normalize_or_zero() * constraint.max_distance;
+                    }
+                }
+            }
+        }
+    }
+}
+
 // TODO: come up with a better name?
 /// See [Weights](xc3_lib::vertex::Weights).
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
@@ -486,12 +535,262 @@ pub(crate) fn create_skinning(skinning: &xc3_lib::mxmd::Skinning) -> Skinning {
     }
 }
 
+/// Transform the vertices for all meshes in `models` by the weighted `skinning_transforms`
+/// and remove all skin weights from the `buffers` used by `models`.
+pub(crate) fn bake_skinning(
+    models: &crate::Models,
+    buffers: &mut [ModelBuffers],
+    skeleton: &Skeleton,
+    skinning_transforms: &[Mat4],
+) {
+    // The weights may be defined with a different bone ordering.
+    let bone_names: Vec<_> = skeleton.bones.iter().map(|b| b.name.clone()).collect();
+
+    // Match the renderer by blending the inverse transpose for normals and tangents.
+    let normal_transforms: Vec<_> = skinning_transforms
+        .iter()
+        .map(|t| t.inverse().transpose())
+        .collect();
+
+    // Vertex buffers can be shared between meshes, so only transform each buffer once.
+    let mut baked_buffers = BTreeSet::new();
+    for model in &models.models {
+        let Some(buffers) = buffers.get_mut(model.model_buffers_index) else {
+            continue;
+        };
+        let Some(weights) = &buffers.weights else {
+            continue;
+        };
+
+        for mesh in &model.meshes {
+            if !baked_buffers.insert((model.model_buffers_index, mesh.vertex_buffer_index)) {
+                continue;
+            }
+
+            let Some(skin_weights) = weights.weight_buffer(mesh.flags2.into()) else {
+                continue;
+            };
+            let skin_weights = skin_weights.reindex_bones(bone_names.clone());
+
+            let pass_type = models
+                .materials
+                .get(mesh.material_index)
+                .map(|m| m.pass_type)
+                .unwrap_or(RenderPassType::Unk0);
+            let start_index = weights.weight_groups.weights_start_index(
+                mesh.flags2.into(),
+                mesh.lod_item_index,
+                pass_type,
+            );
+
+            if let Some(buffer) = buffers.vertex_buffers.get_mut(mesh.vertex_buffer_index) {
+                let transforms =
+                    vertex_transforms(buffer, &skin_weights, start_index, skinning_transforms);
+                let vertex_normal_transforms =
+                    vertex_transforms(buffer, &skin_weights, start_index, &normal_transforms);
+                transform_vertex_buffer(buffer, &transforms, &vertex_normal_transforms);
+            }
+        }
+    }
+
+    let buffers_indices: BTreeSet<_> = models
+        .models
+        .iter()
+        .map(|m| m.model_buffers_index)
+        .collect();
+    for buffers in buffers
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(i, b)| buffers_indices.contains(&i).then_some(b))
+    {
+        buffers.weights = None;
+        for buffer in &mut buffers.vertex_buffers {
+            buffer.attributes.retain(|a| {
+                !matches!(
+                    a,
+                    AttributeData::WeightIndex(_)
+                        | AttributeData::WeightIndex2(_)
+                        | AttributeData::SkinWeights(_)
+                        | AttributeData::SkinWeights2(_)
+                        | AttributeData::BoneIndices(_)
+                        | AttributeData::BoneIndices2(_)
+                )
+            });
+        }
+    }
+}
+
+fn vertex_transforms(
+    buffer: &VertexBuffer,
+    skin_weights: &SkinWeights,
+    start_index: usize,
+    skinning_transforms: &[Mat4],
+) -> Vec<Mat4> {
+    let weight_indices = buffer.attributes.iter().find_map(|a| match a {
+        AttributeData::WeightIndex(indices) => Some(indices),
+        AttributeData::WeightIndex2(indices) => Some(indices),
+        _ => None,
+    });
+
+    match weight_indices {
+        Some(weight_indices) => weight_indices
+            .iter()
+            .map(|i| {
+                let index = i[0] as usize + start_index;
+                match (
+                    skin_weights.bone_indices.get(index),
+                    skin_weights.weights.get(index),
+                ) {
+                    (Some(bone_indices), Some(weights)) => {
+                        blend_transforms(bone_indices, *weights, skinning_transforms)
+                    }
+                    _ => Mat4::IDENTITY,
+                }
+            })
+            .collect(),
+        None => vec![Mat4::IDENTITY; buffer.vertex_count()],
+    }
+}
+
+fn blend_transforms(bone_indices: &[u8; 4], weights: Vec4, skinning_transforms: &[Mat4]) -> Mat4 {
+    let mut transform = Mat4::ZERO;
+    let mut total_weight = 0.0;
+    for (bone_index, weight) in bone_indices.iter().zip(weights.to_array()) {
+        if let Some(bone_transform) = skinning_transforms.get(*bone_index as usize) {
+            transform += *bone_transform * weight;
+            total_weight += weight;
+        }
+    }
+
+    // Unweighted vertices should not move.
+    if total_weight > 0.0 {
+        transform
+    } else {
+        Mat4::IDENTITY
+    }
+}
+
+fn transform_vertex_buffer(
+    buffer: &mut VertexBuffer,
+    vertex_transforms: &[Mat4],
+    normal_transforms: &[Mat4],
+) {
+    for attribute in buffer
+        .attributes
+        .iter_mut()
+        .chain(buffer.morph_blend_target.iter_mut())
+    {
+        match attribute {
+            AttributeData::Position(values) => {
+                for (v, transform) in values.iter_mut().zip(vertex_transforms) {
+                    *v = transform.transform_point3(*v);
+                }
+            }
+            AttributeData::Normal(values)
+            | AttributeData::Normal2(values)
+            | AttributeData::Tangent(values) => {
+                for (v, transform) in values.iter_mut().zip(normal_transforms) {
+                    *v = transform_direction(*transform, *v, true);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Morph targets store differences from the base target.
+    for target in &mut buffer.morph_targets {
+        for (i, vertex_index) in target.vertex_indices.iter().enumerate() {
+            if let Some(transform) = vertex_transforms.get(*vertex_index as usize) {
+                if let Some(v) = target.position_deltas.get_mut(i) {
+                    *v = transform.transform_vector3(*v);
+                }
+            }
+            if let Some(transform) = normal_transforms.get(*vertex_index as usize) {
+                if let Some(v) = target.normals.get_mut(i) {
+                    *v = transform_direction(*transform, *v, false);
+                }
+                if let Some(v) = target.tangents.get_mut(i) {
+                    *v = transform_direction(*transform, *v, false);
+                }
+            }
+        }
+    }
+}
+
+fn transform_direction(transform: Mat4, v: Vec4, normalize: bool) -> Vec4 {
+    // Preserve the sign in the W component used for tangents.
+    let xyz = transform.transform_vector3(v.xyz());
+    let xyz = if normalize {
+        xyz.normalize_or_zero()
+    } else {
+        xyz
+    };
+    xyz.extend(v.w)
+}
+
 // TODO: Test using a different bone name list.
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use glam::vec4;
+    use glam::{vec3, vec4, Quat};
+
+    use crate::{
+        animation::{Animation, BlendMode, PlayMode, SpaceMode},
+        Mesh, Model, Models, Transform,
+    };
+
+    fn constraint_bone(constraint_type: BoneConstraintType, max_distance: f32) -> Bone {
+        Bone {
+            name: "b".to_string(),
+            bounds: None,
+            constraint: Some(BoneConstraint {
+                fixed_offset: vec3(0.0, 1.0, 0.0),
+                max_distance,
+                constraint_type,
+                parent_index: Some(0),
+            }),
+            no_camera_overlap: false,
+        }
+    }
+
+    #[test]
+    fn apply_constraints_fixed_offset_distance() {
+        let skinning = Skinning {
+            bones: vec![
+                Bone {
+                    name: "a".to_string(),
+                    bounds: None,
+                    constraint: None,
+                    no_camera_overlap: false,
+                },
+                constraint_bone(BoneConstraintType::FixedOffset, 0.0),
+                constraint_bone(BoneConstraintType::Distance, 0.5),
+                constraint_bone(BoneConstraintType::Distance, 2.0),
+            ],
+        };
+
+        let parent = Transform {
+            translation: vec3(1.0, 2.0, 3.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::ONE,
+        };
+        let bone = Transform {
+            translation: vec3(0.0, 4.0, 3.0),
+            ..Transform::IDENTITY
+        };
+        let mut transforms = vec![parent, bone, bone, bone];
+        skinning.apply_constraints(&mut transforms);
+
+        assert_eq!(parent, transforms[0]);
+        assert!(transforms[1]
+            .translation
+            .abs_diff_eq(vec3(0.0, 2.0, 3.0), 0.0001));
+        assert!(transforms[2]
+            .translation
+            .abs_diff_eq(vec3(0.0, 2.5, 3.0), 0.0001));
+        assert_eq!(bone, transforms[3]);
+    }
 
     #[test]
     fn bone_indices_weights_no_influences() {
@@ -808,4 +1107,246 @@ mod tests {
             skin_weights
         );
     }
+
+    fn skinned_model() -> (Models, ModelBuffers, Skeleton) {
+        let models = Models {
+            models: vec![Model {
+                meshes: vec![Mesh {
+                    flags1: 0,
+                    flags2: 0u32.try_into().unwrap(),
+                    vertex_buffer_index: 0,
+                    index_buffer_index: 0,
+                    index_buffer_index2: 0,
+                    material_index: 0,
+                    ext_mesh_index: None,
+                    lod_item_index: None,
+                    base_mesh_index: None,
+                }],
+                instances: vec![Mat4::IDENTITY],
+                model_buffers_index: 0,
+                max_xyz: Vec3::ZERO,
+                min_xyz: Vec3::ZERO,
+                bounding_radius: 0.0,
+            }],
+            materials: Vec::new(),
+            samplers: Vec::new(),
+            skinning: Some(Skinning { bones: Vec::new() }),
+            lod_data: None,
+            morph_controller_names: Vec::new(),
+            animation_morph_names: Vec::new(),
+            max_xyz: Vec3::ZERO,
+            min_xyz: Vec3::ZERO,
+        };
+
+        let buffers = ModelBuffers {
+            vertex_buffers: vec![VertexBuffer {
+                attributes: vec![
+                    AttributeData::Position(vec![vec3(1.0, 0.0, 0.0), vec3(0.0, 2.0, 0.0)]),
+                    AttributeData::Normal(vec![vec4(0.0, 1.0, 0.0, 0.0), vec4(1.0, 0.0, 0.0, 0.0)]),
+                    AttributeData::WeightIndex(vec![[1, 0], [0, 0]]),
+                ],
+                morph_blend_target: Vec::new(),
+                morph_targets: Vec::new(),
+                outline_buffer_index: None,
+            }],
+            outline_buffers: Vec::new(),
+            index_buffers: Vec::new(),
+            unk_buffers: Vec::new(),
+            unk_data: None,
+            weights: Some(Weights {
+                weight_buffers: vec![SkinWeights {
+                    bone_indices: vec![[0, 1, 0, 0], [1, 0, 0, 0]],
+                    weights: vec![vec4(0.5, 0.5, 0.0, 0.0), vec4(1.0, 0.0, 0.0, 0.0)],
+                    bone_names: vec!["b".to_string(), "a".to_string()],
+                }],
+                weight_groups: WeightGroups::Groups {
+                    weight_groups: Vec::new(),
+                    weight_lods: Vec::new(),
+                },
+            }),
+        };
+
+        let skeleton = Skeleton {
+            bones: vec![
+                crate::Bone {
+                    name: "a".to_string(),
+                    transform: Transform {
+                        translation: vec3(1.0, 2.0, 3.0),
+                        rotation: Quat::from_rotation_z(0.5),
+                        scale: Vec3::ONE,
+                    },
+                    parent_index: None,
+                },
+                crate::Bone {
+                    name: "b".to_string(),
+                    transform: Transform {
+                        translation: vec3(0.0, 1.0, 0.0),
+                        rotation: Quat::IDENTITY,
+                        scale: Vec3::splat(2.0),
+                    },
+                    parent_index: Some(0),
+                },
+            ],
+        };
+
+        (models, buffers, skeleton)
+    }
+
+    fn positions(buffers: &ModelBuffers) -> &[Vec3] {
+        buffers.vertex_buffers[0]
+            .attributes
+            .iter()
+            .find_map(|a| match a {
+                AttributeData::Position(values) => Some(values.as_slice()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn bake_rest_pose_matches_skinned_rest_pose() {
+        let (mut models, mut buffers, skeleton) = skinned_model();
+
+        // An animation without tracks uses the rest pose.
+        let animation = Animation {
+            name: String::new(),
+            space_mode: SpaceMode::Local,
+            play_mode: PlayMode::Single,
+            blend_mode: BlendMode::Blend,
+            frames_per_second: 30.0,
+            frame_count: 1,
+            tracks: Vec::new(),
+            morph_tracks: std::collections::BTreeMap::new(),
+            root_translation: None,
+        };
+        let skinning_transforms = animation.skinning_transforms(&skeleton, 0.0);
+
+        let skin_weights = buffers.weights.as_ref().unwrap().weight_buffers[0]
+            .reindex_bones(vec!["a".to_string(), "b".to_string()]);
+        let skinned: Vec<_> = vertex_transforms(
+            &buffers.vertex_buffers[0],
+            &skin_weights,
+            0,
+            &skinning_transforms,
+        )
+        .iter()
+        .zip(positions(&buffers))
+        .map(|(t, p)| t.transform_point3(*p))
+        .collect();
+
+        models.bake_rest_pose(std::slice::from_mut(&mut buffers), &skeleton);
+
+        for (expected, actual) in skinned.iter().zip(positions(&buffers)) {
+            assert!(
+                expected.abs_diff_eq(*actual, 0.0001),
+                "{expected} != {actual}"
+            );
+        }
+        assert!(models.skinning.is_none());
+        assert!(buffers.weights.is_none());
+        assert!(!buffers.vertex_buffers[0]
+            .attributes
+            .iter()
+            .any(|a| matches!(a, AttributeData::WeightIndex(_))));
+    }
+
+    #[test]
+    fn bake_pose_translation() {
+        let (mut models, mut buffers, skeleton) = skinned_model();
+
+        // Bone "a" is index 0 in the skeleton and index 1 in the weights.
+        let skinning_transforms = [
+            Mat4::from_translation(vec3(2.0, 0.0, 0.0)),
+            Mat4::from_translation(vec3(0.0, 0.0, 4.0)),
+        ];
+        models.bake_pose(
+            std::slice::from_mut(&mut buffers),
+            &skeleton,
+            &skinning_transforms,
+        );
+
+        assert_eq!(
+            &[vec3(3.0, 0.0, 0.0), vec3(1.0, 2.0, 2.0)],
+            positions(&buffers)
+        );
+        assert_eq!(
+            &AttributeData::Normal(vec![vec4(0.0, 1.0, 0.0, 0.0), vec4(1.0, 0.0, 0.0, 0.0)]),
+            &buffers.vertex_buffers[0].attributes[1]
+        );
+    }
+
+    #[test]
+    fn bake_pose_non_identity_bind_pose() {
+        let (mut models, mut buffers, skeleton) = skinned_model();
+
+        // Scale each bone non uniformly in its local space from the rotated bind pose.
+        let bind_transforms = skeleton.model_space_transforms();
+        let skinning_transforms: Vec<_> = bind_transforms
+            .iter()
+            .map(|t| {
+                let bind = t.to_matrix();
+                bind * Mat4::from_scale(vec3(3.0, 1.0, 1.0)) * bind.inverse()
+            })
+            .collect();
+
+        models.bake_pose(
+            std::slice::from_mut(&mut buffers),
+            &skeleton,
+            &skinning_transforms,
+        );
+
+        // The second vertex is fully weighted to bone "a" at skeleton index 0.
+        let transform = skinning_transforms[0];
+        let expected_position = transform.transform_point3(vec3(0.0, 2.0, 0.0));
+        assert!(positions(&buffers)[1].abs_diff_eq(expected_position, 0.0001));
+
+        // Normals stay perpendicular to the surface with non uniform scale.
+        let expected_normal = transform
+            .inverse()
+            .transpose()
+            .transform_vector3(vec3(1.0, 0.0, 0.0))
+            .normalize();
+        let AttributeData::Normal(normals) = &buffers.vertex_buffers[0].attributes[1] else {
+            panic!("expected normals")
+        };
+        assert!(normals[1].xyz().abs_diff_eq(expected_normal, 0.0001));
+        assert!(!normals[1].xyz().abs_diff_eq(
+            transform.transform_vector3(vec3(1.0, 0.0, 0.0)).normalize(),
+            0.01
+        ));
+    }
+
+    #[test]
+    fn bake_pose_model_buffers_index() {
+        let (mut models, buffers, skeleton) = skinned_model();
+
+        // The second model uses different buffers with the same weights.
+        let mut model = models.models[0].clone();
+        model.model_buffers_index = 1;
+        models.models.push(model);
+
+        let mut second_buffers = buffers.clone();
+        if let AttributeData::Position(positions) =
+            &mut second_buffers.vertex_buffers[0].attributes[0]
+        {
+            positions[0] = vec3(5.0, 0.0, 0.0);
+        }
+        let mut buffers = vec![buffers, second_buffers];
+
+        let skinning_transforms = [
+            Mat4::from_translation(vec3(2.0, 0.0, 0.0)),
+            Mat4::from_translation(vec3(0.0, 0.0, 4.0)),
+        ];
+        models.bake_pose(&mut buffers, &skeleton, &skinning_transforms);
+
+        assert_eq!(
+            &[vec3(3.0, 0.0, 0.0), vec3(1.0, 2.0, 2.0)],
+            positions(&buffers[0])
+        );
+        assert_eq!(
+            &[vec3(7.0, 0.0, 0.0), vec3(1.0, 2.0, 2.0)],
+            positions(&buffers[1])
+        );
+        assert!(buffers.iter().all(|b| b.weights.is_none()));
+    }
 }
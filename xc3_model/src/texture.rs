@@ -2,6 +2,7 @@ use image_dds::{ddsfile::Dds, error::CreateImageError, CreateDdsError, Surface};
 use log::error;
 use thiserror::Error;
 use xc3_lib::{
+    dds::DdsExt,
     mibl::{CreateMiblError, Mibl},
     msrd::streaming::ExtractedTexture,
     mtxt::Mtxt,
@@ -11,7 +12,7 @@ use xc3_lib::{
 pub use xc3_lib::mibl::{ImageFormat, ViewDimension};
 pub use xc3_lib::mxmd::TextureUsage;
 
-use crate::get_bytes;
+use crate::{get_bytes, LoadModelError};
 
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 #[derive(Debug)]
@@ -81,15 +82,16 @@ impl ImageTexture {
         name: Option<String>,
         usage: Option<TextureUsage>,
     ) -> Result<Self, CreateImageTextureError> {
+        let (width, height, depth) = mibl.dimensions();
         Ok(Self {
             name,
             usage,
-            width: mibl.footer.width,
-            height: mibl.footer.height,
-            depth: mibl.footer.depth,
-            view_dimension: mibl.footer.view_dimension,
-            image_format: mibl.footer.image_format,
-            mipmap_count: mibl.footer.mipmap_count,
+            width,
+            height,
+            depth,
+            view_dimension: mibl_view_dimension(&mibl.footer),
+            image_format: mibl.format(),
+            mipmap_count: mibl.mip_count(),
             image_data: mibl.deswizzled_image_data()?,
         })
     }
@@ -123,6 +125,24 @@ impl ImageTexture {
         Self::from_mibl(&mibl, Some(texture.name.clone()), Some(texture.usage)).map_err(Into::into)
     }
 
+    /// The [name](Self::name) with characters that are not valid in file names on
+    /// common operating systems like `:` or `*` replaced with `_`.
+    pub fn safe_name(&self) -> Option<String> {
+        self.name.as_ref().map(|name| {
+            name.chars()
+                .map(|c| {
+                    if c.is_control()
+                        || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+                    {
+                        '_'
+                    } else {
+                        c
+                    }
+                })
+                .collect()
+        })
+    }
+
     /// Decode the first mip level for all depth slices and array layers to RGBA8.
     pub fn to_image(&self) -> Result<image_dds::image::RgbaImage, CreateImageError> {
         // Only decode the mip we actually use to improve performance.
@@ -156,7 +176,7 @@ impl ImageTexture {
 
     // TODO: use a dedicated error type
     pub fn to_dds(&self) -> Result<Dds, CreateDdsError> {
-        self.to_surface().to_dds()
+        xc3_lib::dds::dds_from_surface(&self.to_surface())
     }
 
     pub fn from_surface<T: AsRef<[u8]>>(
@@ -207,6 +227,14 @@ impl ImageTexture {
 }
 
 // TODO: Should the publicly exposed image format type just use image_dds?
+// Some volume textures like toon gradients may not set the dimension in the footer.
+fn mibl_view_dimension(footer: &xc3_lib::mibl::MiblFooter) -> ViewDimension {
+    match footer.view_dimension {
+        ViewDimension::D2 if footer.depth > 1 => ViewDimension::D3,
+        view_dimension => view_dimension,
+    }
+}
+
 fn mtxt_image_format(image_format: xc3_lib::mtxt::SurfaceFormat) -> ImageFormat {
     match image_format {
         xc3_lib::mtxt::SurfaceFormat::R8G8B8A8Unorm => ImageFormat::R8G8B8A8Unorm,
@@ -267,6 +295,44 @@ pub fn load_textures(
     }
 }
 
+/// Extract the textures embedded in a `.wimdo` or `.pcmdo` file.
+///
+/// Desktop PC models store packed textures as DDS rather than [Mibl].
+pub(crate) fn packed_textures(
+    textures: &[PackedTexture],
+    is_pc: bool,
+) -> Result<ExtractedTextures, LoadModelError> {
+    if is_pc {
+        textures
+            .iter()
+            .map(|t| {
+                Ok(ExtractedTexture {
+                    name: t.name.clone(),
+                    usage: t.usage,
+                    low: <Dds as DdsExt>::from_bytes(&t.mibl_data)
+                        .map_err(|e| LoadModelError::PcmdoPackedTexture { source: e })?,
+                    high: None,
+                })
+            })
+            .collect::<Result<Vec<_>, LoadModelError>>()
+            .map(ExtractedTextures::Pc)
+    } else {
+        textures
+            .iter()
+            .map(|t| {
+                Ok(ExtractedTexture {
+                    name: t.name.clone(),
+                    usage: t.usage,
+                    low: Mibl::from_bytes(&t.mibl_data)
+                        .map_err(|e| LoadModelError::WimdoPackedTexture { source: e })?,
+                    high: None,
+                })
+            })
+            .collect::<Result<Vec<_>, LoadModelError>>()
+            .map(ExtractedTextures::Switch)
+    }
+}
+
 pub fn load_textures_legacy(
     mxmd: &MxmdLegacy,
     casmt: Option<Vec<u8>>,
@@ -324,3 +390,129 @@ fn arbitrary_dds_textures(
     // TODO: Generate random DDS files?
     Ok(Vec::new())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba8_mibl(depth: u32, layers: u32) -> Mibl {
+        let size = 4 * 4 * 4 * depth * layers;
+        Mibl::from_surface(Surface {
+            width: 4,
+            height: 4,
+            depth,
+            layers,
+            mipmaps: 1,
+            image_format: image_dds::ImageFormat::Rgba8Unorm,
+            data: (0..size).map(|i| i as u8).collect::<Vec<_>>(),
+        })
+        .unwrap()
+    }
+
+    fn packed_texture(data: Vec<u8>) -> PackedTexture {
+        PackedTexture {
+            usage: TextureUsage::Col,
+            mibl_data: data,
+            name: "texture".to_string(),
+        }
+    }
+
+    #[test]
+    fn packed_textures_switch() {
+        let mibl = rgba8_mibl(1, 1);
+        let mut writer = std::io::Cursor::new(Vec::new());
+        mibl.write(&mut writer).unwrap();
+
+        let textures = packed_textures(&[packed_texture(writer.into_inner())], false).unwrap();
+        let images = load_textures(&textures).unwrap();
+        assert!(matches!(textures, ExtractedTextures::Switch(_)));
+        assert_eq!(1, images.len());
+        assert_eq!(Some("texture".to_string()), images[0].name);
+        assert_eq!(mibl.to_surface().unwrap().as_ref(), images[0].to_surface());
+    }
+
+    #[test]
+    fn packed_textures_pc() {
+        let surface = rgba8_mibl(1, 1).to_surface().unwrap();
+        let dds = surface.to_dds().unwrap();
+        let mut data = Vec::new();
+        dds.write(&mut data).unwrap();
+
+        let textures = packed_textures(&[packed_texture(data)], true).unwrap();
+        let images = load_textures(&textures).unwrap();
+        assert!(matches!(textures, ExtractedTextures::Pc(_)));
+        assert_eq!(1, images.len());
+        assert_eq!(Some(TextureUsage::Col), images[0].usage);
+        assert_eq!(surface.as_ref(), images[0].to_surface());
+    }
+
+    #[test]
+    fn packed_textures_pc_invalid() {
+        let result = packed_textures(&[packed_texture(vec![0u8; 16])], true);
+        assert!(matches!(
+            result,
+            Err(LoadModelError::PcmdoPackedTexture { .. })
+        ));
+    }
+
+    #[test]
+    fn from_mibl_cube() {
+        let mibl = rgba8_mibl(1, 6);
+        let image = ImageTexture::from_mibl(&mibl, None, None).unwrap();
+        assert_eq!(ViewDimension::Cube, image.view_dimension);
+        assert_eq!(6, image.layers());
+        assert_eq!(mibl.to_surface().unwrap().as_ref(), image.to_surface());
+    }
+
+    #[test]
+    fn from_mibl_3d() {
+        let mibl = rgba8_mibl(4, 1);
+        let image = ImageTexture::from_mibl(&mibl, None, None).unwrap();
+        assert_eq!(ViewDimension::D3, image.view_dimension);
+        assert_eq!(1, image.layers());
+        assert_eq!(mibl.to_surface().unwrap().as_ref(), image.to_surface());
+    }
+
+    #[test]
+    fn from_mibl_3d_footer_d2() {
+        let mut mibl = rgba8_mibl(4, 1);
+        mibl.footer.view_dimension = ViewDimension::D2;
+        let image = ImageTexture::from_mibl(&mibl, None, None).unwrap();
+        assert_eq!(ViewDimension::D3, image.view_dimension);
+        assert_eq!(4, image.depth);
+    }
+
+    #[test]
+    fn mibl_view_dimension_footer() {
+        let footer = |view_dimension, depth| xc3_lib::mibl::MiblFooter {
+            view_dimension,
+            depth,
+            ..rgba8_mibl(1, 1).footer
+        };
+        assert_eq!(
+            ViewDimension::D2,
+            mibl_view_dimension(&footer(ViewDimension::D2, 1))
+        );
+        assert_eq!(
+            ViewDimension::D3,
+            mibl_view_dimension(&footer(ViewDimension::D2, 4))
+        );
+        assert_eq!(
+            ViewDimension::D3,
+            mibl_view_dimension(&footer(ViewDimension::D3, 4))
+        );
+        assert_eq!(
+            ViewDimension::Cube,
+            mibl_view_dimension(&footer(ViewDimension::Cube, 1))
+        );
+    }
+
+    #[test]
+    fn safe_name() {
+        let mut image = ImageTexture::from_mibl(&rgba8_mibl(1, 1), None, None).unwrap();
+        assert_eq!(None, image.safe_name());
+
+        image.name = Some("tex/a:b*c?.witex".to_string());
+        assert_eq!(Some("tex_a_b_c_.witex".to_string()), image.safe_name());
+    }
+}
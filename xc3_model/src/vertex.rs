@@ -9,7 +9,8 @@
 //! This makes rendering each vertex cache friendly.
 //! A collection of [AttributeData] can always be packed into an interleaved form for rendering.
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
     io::{Cursor, Seek, SeekFrom, Write},
 };
 
@@ -124,6 +125,15 @@ impl VertexBuffer {
         // TODO: Check all attributes for consistency?
         self.attributes.first().map(|a| a.len()).unwrap_or_default()
     }
+
+    /// The target in [morph_targets](#structfield.morph_targets) for `morph_controller_index`
+    /// or [None] if this buffer is not affected by the controller.
+    /// See [Models::morph_target_index](crate::Models::morph_target_index).
+    pub fn morph_target(&self, morph_controller_index: usize) -> Option<&MorphTarget> {
+        self.morph_targets
+            .iter()
+            .find(|t| t.morph_controller_index == morph_controller_index)
+    }
 }
 
 // TODO: Add an option to convert a collection of these to the vertex above?
@@ -1097,6 +1107,72 @@ impl ModelBuffers {
         })
     }
 
+    /// Write the positions, normals, and first texture coordinates for the meshes in `model`
+    /// to Wavefront OBJ text for debugging.
+    ///
+    /// Each mesh is written as a separate object. Vertex buffers shared by multiple meshes are only written once.
+    /// Skinning, morph targets, and instance transforms are not applied.
+    pub fn to_obj(&self, model: &crate::Model) -> String {
+        let mut obj = String::new();
+
+        // OBJ indices start from 1 and are shared between all objects.
+        // Buffers may not have all attributes, so track the offsets separately.
+        let mut vertex_offsets = BTreeMap::new();
+        let mut position_count = 0;
+        let mut uv_count = 0;
+        let mut normal_count = 0;
+
+        for (i, mesh) in model.meshes.iter().enumerate() {
+            let (Some(vertex_buffer), Some(index_buffer)) = (
+                self.vertex_buffers.get(mesh.vertex_buffer_index),
+                self.index_buffers.get(mesh.index_buffer_index),
+            ) else {
+                continue;
+            };
+
+            let (v_offset, vt_offset, vn_offset) = *vertex_offsets
+                .entry(mesh.vertex_buffer_index)
+                .or_insert_with(|| {
+                    let (count, has_uvs, has_normals) = write_obj_vertices(&mut obj, vertex_buffer);
+                    let offsets = (
+                        position_count,
+                        has_uvs.then_some(uv_count),
+                        has_normals.then_some(normal_count),
+                    );
+                    position_count += count;
+                    if has_uvs {
+                        uv_count += count;
+                    }
+                    if has_normals {
+                        normal_count += count;
+                    }
+                    offsets
+                });
+
+            writeln!(&mut obj, "o mesh{i}").unwrap();
+
+            // TODO: Handle other primitive types?
+            for face in index_buffer.indices.chunks_exact(3) {
+                write!(&mut obj, "f").unwrap();
+                for index in face {
+                    let index = *index as usize + 1;
+                    let v = v_offset + index;
+                    match (vt_offset, vn_offset) {
+                        (Some(vt), Some(vn)) => {
+                            write!(&mut obj, " {v}/{}/{}", vt + index, vn + index).unwrap()
+                        }
+                        (Some(vt), None) => write!(&mut obj, " {v}/{}", vt + index).unwrap(),
+                        (None, Some(vn)) => write!(&mut obj, " {v}//{}", vn + index).unwrap(),
+                        (None, None) => write!(&mut obj, " {v}").unwrap(),
+                    }
+                }
+                writeln!(&mut obj).unwrap();
+            }
+        }
+
+        obj
+    }
+
     fn write_morph_targets(
         &self,
         writer: &mut Cursor<Vec<u8>>,
@@ -1694,6 +1770,46 @@ fn write_unorm8x4_signed<W: Write + Seek>(
         .write_options(writer, endian, ())
 }
 
+// Returns the vertex count and whether texture coordinates and normals were written.
+fn write_obj_vertices(obj: &mut String, buffer: &VertexBuffer) -> (usize, bool, bool) {
+    let mut positions = None;
+    let mut normals = None;
+    let mut uvs = None;
+    for attribute in &buffer.attributes {
+        match attribute {
+            AttributeData::Position(values) => positions = Some(values),
+            AttributeData::Normal(values) => normals = Some(values),
+            AttributeData::TexCoord0(values) => uvs = Some(values),
+            _ => (),
+        }
+    }
+
+    let Some(positions) = positions else {
+        return (0, false, false);
+    };
+
+    // Only write attributes with a value for each vertex.
+    let normals = normals.filter(|n| n.len() == positions.len());
+    let uvs = uvs.filter(|u| u.len() == positions.len());
+
+    for p in positions {
+        writeln!(obj, "v {} {} {}", p.x, p.y, p.z).unwrap();
+    }
+    if let Some(uvs) = uvs {
+        // OBJ texture coordinates start from the bottom left.
+        for uv in uvs {
+            writeln!(obj, "vt {} {}", uv.x, 1.0 - uv.y).unwrap();
+        }
+    }
+    if let Some(normals) = normals {
+        for n in normals {
+            writeln!(obj, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+        }
+    }
+
+    (positions.len(), uvs.is_some(), normals.is_some())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1704,6 +1820,25 @@ mod tests {
     use hexlit::hex;
     use xc3_lib::vertex::VertexAttribute;
 
+    #[test]
+    fn vertex_buffer_morph_target() {
+        let target = |morph_controller_index| MorphTarget {
+            morph_controller_index,
+            position_deltas: Vec::new(),
+            normals: Vec::new(),
+            tangents: Vec::new(),
+            vertex_indices: Vec::new(),
+        };
+        let buffer = VertexBuffer {
+            attributes: Vec::new(),
+            morph_blend_target: Vec::new(),
+            morph_targets: vec![target(3), target(1)],
+            outline_buffer_index: None,
+        };
+        assert_eq!(Some(&target(1)), buffer.morph_target(1));
+        assert_eq!(None, buffer.morph_target(0));
+    }
+
     #[test]
     fn vertex_buffer_indices() {
         // xeno3/chr/ch/ch01012013.wismt, index buffer 0
@@ -2672,4 +2807,106 @@ mod tests {
         assert_eq!(new_descriptor, descriptor);
         assert_hex_eq!(data, writer.into_inner());
     }
+
+    fn obj_model(mesh_count: usize) -> crate::Model {
+        crate::Model {
+            meshes: (0..mesh_count)
+                .map(|i| crate::Mesh {
+                    flags1: 0,
+                    flags2: 0u32.try_into().unwrap(),
+                    vertex_buffer_index: 0,
+                    index_buffer_index: i,
+                    index_buffer_index2: 0,
+                    material_index: 0,
+                    ext_mesh_index: None,
+                    lod_item_index: None,
+                    base_mesh_index: None,
+                })
+                .collect(),
+            instances: vec![glam::Mat4::IDENTITY],
+            model_buffers_index: 0,
+            max_xyz: Vec3::ZERO,
+            min_xyz: Vec3::ZERO,
+            bounding_radius: 0.0,
+        }
+    }
+
+    fn obj_buffers(attributes: Vec<AttributeData>, index_buffer_count: usize) -> ModelBuffers {
+        ModelBuffers {
+            vertex_buffers: vec![VertexBuffer {
+                attributes,
+                morph_blend_target: Vec::new(),
+                morph_targets: Vec::new(),
+                outline_buffer_index: None,
+            }],
+            outline_buffers: Vec::new(),
+            index_buffers: (0..index_buffer_count)
+                .map(|i| IndexBuffer {
+                    indices: if i == 0 { vec![0, 1, 2] } else { vec![2, 1, 0] },
+                    primitive_type: PrimitiveType::TriangleList,
+                })
+                .collect(),
+            unk_buffers: Vec::new(),
+            unk_data: None,
+            weights: None,
+        }
+    }
+
+    #[test]
+    fn to_obj_positions_normals_uvs() {
+        let buffers = obj_buffers(
+            vec![
+                AttributeData::Position(vec![
+                    vec3(0.0, 0.0, 0.0),
+                    vec3(1.0, 0.0, 0.0),
+                    vec3(0.0, 1.0, 0.0),
+                ]),
+                AttributeData::Normal(vec![vec4(0.0, 0.0, 1.0, 0.0); 3]),
+                AttributeData::TexCoord0(vec![vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 0.25)]),
+            ],
+            1,
+        );
+
+        assert_eq!(
+            concat!(
+                "v 0 0 0\n",
+                "v 1 0 0\n",
+                "v 0 1 0\n",
+                "vt 0 1\n",
+                "vt 1 1\n",
+                "vt 0 0.75\n",
+                "vn 0 0 1\n",
+                "vn 0 0 1\n",
+                "vn 0 0 1\n",
+                "o mesh0\n",
+                "f 1/1/1 2/2/2 3/3/3\n",
+            ),
+            buffers.to_obj(&obj_model(1))
+        );
+    }
+
+    #[test]
+    fn to_obj_shared_vertex_buffer_positions() {
+        let buffers = obj_buffers(
+            vec![AttributeData::Position(vec![
+                vec3(0.0, 0.0, 0.0),
+                vec3(1.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 0.0),
+            ])],
+            2,
+        );
+
+        assert_eq!(
+            concat!(
+                "v 0 0 0\n",
+                "v 1 0 0\n",
+                "v 0 1 0\n",
+                "o mesh0\n",
+                "f 1 2 3\n",
+                "o mesh1\n",
+                "f 3 2 1\n",
+            ),
+            buffers.to_obj(&obj_model(2))
+        );
+    }
 }
@@ -8,7 +8,8 @@ use crate::{
 use indoc::indoc;
 use std::sync::LazyLock;
 use xc3_model::shader_database::{
-    AttributeDependency, BufferDependency, Dependency, TexCoord, TexCoordParams, TextureDependency,
+    AttributeDependency, BufferDependency, Dependency, TexCoord, TexCoordParams, TextureArrayLayer,
+    TextureDependency,
 };
 
 pub fn input_dependencies(
@@ -114,7 +115,11 @@ fn texture_dependencies(
         .collect()
 }
 
-fn texture_dependency(e: &Expr, graph: &Graph, attributes: &Attributes) -> Option<Dependency> {
+pub(crate) fn texture_dependency(
+    e: &Expr,
+    graph: &Graph,
+    attributes: &Attributes,
+) -> Option<Dependency> {
     if let Expr::Func {
         name,
         args,
@@ -123,12 +128,14 @@ fn texture_dependency(e: &Expr, graph: &Graph, attributes: &Attributes) -> Optio
     {
         if name.starts_with("texture") {
             if let Some(Expr::Global { name, .. }) = args.first() {
-                let texcoords = texcoord_args(args, graph, attributes);
+                let (coords, array_layer) = texture_array_layer(name, args, graph, attributes);
+                let texcoords = texcoord_args(coords, graph, attributes);
 
                 Some(Dependency::Texture(TextureDependency {
                     name: name.into(),
                     channel: *channel,
                     texcoords,
+                    array_layer,
                 }))
             } else {
                 None
@@ -141,11 +148,47 @@ fn texture_dependency(e: &Expr, graph: &Graph, attributes: &Attributes) -> Optio
     }
 }
 
+fn texture_array_layer<'a>(
+    texture: &str,
+    args: &'a [Expr],
+    graph: &Graph,
+    attributes: &Attributes,
+) -> (&'a [Expr], Option<TextureArrayLayer>) {
+    // The first arg is always the texture name.
+    // Array textures use the last component of texture(s0, vec3(u, v, layer)).
+    // Samplers like sampler3D also use vec3, so check the declared sampler type.
+    if let Some(Expr::Func { name, args, .. }) = args.get(1) {
+        if name == "vec3" && args.len() == 3 && attributes.array_samplers.contains(texture) {
+            if let Some(layer) = array_layer(&args[2], graph) {
+                return (&args[..2], Some(layer));
+            }
+        }
+    }
+    (args.get(1..).unwrap_or_default(), None)
+}
+
+fn array_layer(e: &Expr, graph: &Graph) -> Option<TextureArrayLayer> {
+    // The layer is often converted with float(int(x)) or similar.
+    let mut e = assign_x_recursive(&graph.nodes, e);
+    while let Expr::Func { name, args, .. } = e {
+        if (name == "float" || name == "int") && args.len() == 1 {
+            e = assign_x_recursive(&graph.nodes, &args[0]);
+        } else {
+            break;
+        }
+    }
+
+    match e {
+        Expr::Float(f) => Some(TextureArrayLayer::Constant((*f).into())),
+        Expr::Int(i) => Some(TextureArrayLayer::Constant((*i as f32).into())),
+        Expr::Uint(u) => Some(TextureArrayLayer::Constant((*u as f32).into())),
+        _ => buffer_dependency(e).map(TextureArrayLayer::Buffer),
+    }
+}
+
 fn texcoord_args(args: &[Expr], graph: &Graph, attributes: &Attributes) -> Vec<TexCoord> {
     // Search recursively to find texcoord variables.
-    // The first arg is always the texture name.
     args.iter()
-        .skip(1)
         .flat_map(|a| a.exprs_recursive())
         .filter_map(|e| {
             // Detect common cases for transforming UV coordinates.
@@ -447,7 +490,142 @@ mod tests {
                         channel: Some('w'),
                         params: None
                     }
-                ]
+                ],
+                array_layer: None,
+            })],
+            input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
+        );
+    }
+
+    #[test]
+    fn input_dependencies_array_layer_constant() {
+        let glsl = indoc! {"
+            layout(location = 0) in vec4 in_attr0;
+            layout(binding = 0) uniform sampler2DArray texture1;
+
+            void main() 
+            {
+                float x = in_attr0.x;
+                float y = in_attr0.y;
+                float layer = float(2);
+                float a = texture(texture1, vec3(x, y, layer)).z;
+            }
+        "};
+
+        let tu = TranslationUnit::parse(glsl).unwrap();
+        let graph = Graph::from_glsl(&tu);
+        let attributes = find_attribute_locations(&tu);
+        let assignments = graph.assignments_recursive("a", None, None);
+        let dependent_lines = graph.dependencies_recursive("a", None, None);
+
+        assert_eq!(
+            vec![Dependency::Texture(TextureDependency {
+                name: "texture1".into(),
+                channel: Some('z'),
+                texcoords: vec![
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('x'),
+                        params: None
+                    },
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('y'),
+                        params: None
+                    }
+                ],
+                array_layer: Some(TextureArrayLayer::Constant(2.0.into())),
+            })],
+            input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
+        );
+    }
+
+    #[test]
+    fn input_dependencies_array_layer_parameter() {
+        let glsl = indoc! {"
+            layout(location = 0) in vec4 in_attr0;
+            layout(binding = 0) uniform sampler2DArray texture1;
+
+            void main() 
+            {
+                float x = in_attr0.x;
+                float y = in_attr0.y;
+                float layer = U_Mate.gWrkFl4[1].z;
+                float a = texture(texture1, vec3(x, y, layer)).x;
+            }
+        "};
+
+        let tu = TranslationUnit::parse(glsl).unwrap();
+        let graph = Graph::from_glsl(&tu);
+        let attributes = find_attribute_locations(&tu);
+        let assignments = graph.assignments_recursive("a", None, None);
+        let dependent_lines = graph.dependencies_recursive("a", None, None);
+
+        assert_eq!(
+            vec![Dependency::Texture(TextureDependency {
+                name: "texture1".into(),
+                channel: Some('x'),
+                texcoords: vec![
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('x'),
+                        params: None
+                    },
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('y'),
+                        params: None
+                    }
+                ],
+                array_layer: Some(TextureArrayLayer::Buffer(BufferDependency {
+                    name: "U_Mate".into(),
+                    field: "gWrkFl4".into(),
+                    index: Some(1),
+                    channel: Some('z')
+                })),
+            })],
+            input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
+        );
+    }
+
+    #[test]
+    fn input_dependencies_3d_texture() {
+        let glsl = indoc! {"
+            layout(location = 0) in vec4 in_attr0;
+            layout(binding = 0) uniform sampler3D texture1;
+
+            void main() 
+            {
+                float x = in_attr0.x;
+                float y = in_attr0.y;
+                float z = float(2);
+                float a = texture(texture1, vec3(x, y, z)).x;
+            }
+        "};
+
+        let tu = TranslationUnit::parse(glsl).unwrap();
+        let graph = Graph::from_glsl(&tu);
+        let attributes = find_attribute_locations(&tu);
+        let assignments = graph.assignments_recursive("a", None, None);
+        let dependent_lines = graph.dependencies_recursive("a", None, None);
+
+        assert_eq!(
+            vec![Dependency::Texture(TextureDependency {
+                name: "texture1".into(),
+                channel: Some('x'),
+                texcoords: vec![
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('x'),
+                        params: None
+                    },
+                    TexCoord {
+                        name: "in_attr0".into(),
+                        channel: Some('y'),
+                        params: None
+                    }
+                ],
+                array_layer: None,
             })],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
         );
@@ -548,7 +726,8 @@ mod tests {
                             }
                         ]))
                     }
-                ]
+                ],
+                array_layer: None,
             })],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
         );
@@ -602,7 +781,8 @@ mod tests {
                             channel: Some('w')
                         }))
                     }
-                ]
+                ],
+                array_layer: None,
             })],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
         );
@@ -629,7 +809,8 @@ mod tests {
             vec![Dependency::Texture(TextureDependency {
                 name: "texture1".into(),
                 channel: Some('z'),
-                texcoords: Vec::new()
+                texcoords: Vec::new(),
+                array_layer: None,
             })],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
         );
@@ -656,12 +837,14 @@ mod tests {
                 Dependency::Texture(TextureDependency {
                     name: "texture1".into(),
                     channel: Some('z'),
-                    texcoords: Vec::new()
+                    texcoords: Vec::new(),
+                    array_layer: None,
                 }),
                 Dependency::Texture(TextureDependency {
                     name: "texture1".into(),
                     channel: Some('w'),
-                    texcoords: Vec::new()
+                    texcoords: Vec::new(),
+                    array_layer: None,
                 })
             ],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
@@ -691,7 +874,8 @@ mod tests {
             vec![Dependency::Texture(TextureDependency {
                 name: "texture1".into(),
                 channel: Some('x'),
-                texcoords: Vec::new()
+                texcoords: Vec::new(),
+                array_layer: None,
             })],
             input_dependencies(
                 &graph,
@@ -790,7 +974,8 @@ mod tests {
             vec![Dependency::Texture(TextureDependency {
                 name: "tex".into(),
                 channel: Some('x'),
-                texcoords: Vec::new()
+                texcoords: Vec::new(),
+                array_layer: None,
             })],
             input_dependencies(&graph, &attributes, &assignments, &dependent_lines)
         );
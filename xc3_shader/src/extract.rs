@@ -15,7 +15,24 @@ use xc3_lib::{
     spch::{Nvsd, ShaderBinary, Spch},
 };
 
+/// Extract and decompile shaders using Ryujinx.ShaderTools into a folder for each .wimdo, .wismhd, or .wishp file.
+/// See [extract_shader_binaries_only] for saving the binaries without decompiling.
 pub fn extract_and_decompile_shaders(input: &str, output: &str, shader_tools: Option<&str>) {
+    extract_shaders_from_files(input, output, shader_tools, false);
+}
+
+/// Extract the vertex and fragment shader binaries and metadata into a folder for each .wimdo, .wismhd, or .wishp file
+/// without decompiling.
+pub fn extract_shader_binaries_only(input: &str, output: &str) {
+    extract_shaders_from_files(input, output, None, true);
+}
+
+fn extract_shaders_from_files(
+    input: &str,
+    output: &str,
+    shader_tools: Option<&str>,
+    save_binaries: bool,
+) {
     globwalk::GlobWalkerBuilder::from_patterns(input, &["*.wimdo"])
         .build()
         .unwrap()
@@ -33,7 +50,7 @@ pub fn extract_and_decompile_shaders(input: &str, output: &str, shader_tools: Op
             match Mxmd::from_file(path) {
                 Ok(mxmd) => {
                     if let Some(spch) = mxmd.spch {
-                        extract_shaders(&spch, &output_folder, shader_tools, false);
+                        extract_shaders(&spch, &output_folder, shader_tools, save_binaries);
                     }
                 }
                 Err(e) => println!("Error reading {path:?}: {e}"),
@@ -42,7 +59,7 @@ pub fn extract_and_decompile_shaders(input: &str, output: &str, shader_tools: Op
             match Msrd::from_file(path.with_extension("wismt")) {
                 Ok(msrd) => {
                     let (_, spch, _) = msrd.extract_files(None).unwrap();
-                    extract_shaders(&spch, &output_folder, shader_tools, false);
+                    extract_shaders(&spch, &output_folder, shader_tools, save_binaries);
                 }
                 Err(e) => println!("Error reading {path:?}: {e}"),
             }
@@ -60,7 +77,13 @@ pub fn extract_and_decompile_shaders(input: &str, output: &str, shader_tools: Op
                     std::fs::create_dir_all(&output_folder).unwrap();
                     println!("{output_folder:?}");
 
-                    extract_and_decompile_msmd_shaders(path, msmd, output_folder, shader_tools);
+                    extract_and_decompile_msmd_shaders(
+                        path,
+                        msmd,
+                        output_folder,
+                        shader_tools,
+                        save_binaries,
+                    );
                 }
                 Err(e) => println!("Error reading {path:?}: {e}"),
             }
@@ -78,7 +101,7 @@ pub fn extract_and_decompile_shaders(input: &str, output: &str, shader_tools: Op
                     std::fs::create_dir_all(&output_folder).unwrap();
                     println!("{output_folder:?}");
 
-                    extract_shaders(&spch, &output_folder, shader_tools, false);
+                    extract_shaders(&spch, &output_folder, shader_tools, save_binaries);
                 }
                 Err(e) => println!("Error reading {path:?}: {e}"),
             }
@@ -90,6 +113,7 @@ fn extract_and_decompile_msmd_shaders(
     msmd: Msmd,
     output_folder: std::path::PathBuf,
     shader_tools: Option<&str>,
+    save_binaries: bool,
 ) {
     let mut wismda = BufReader::new(std::fs::File::open(path.with_extension("wismda")).unwrap());
     let compressed = msmd.wismda_info.compressed_length != msmd.wismda_info.decompressed_length;
@@ -100,7 +124,7 @@ fn extract_and_decompile_msmd_shaders(
         let model_folder = output_folder.join("map").join(i.to_string());
         std::fs::create_dir_all(&model_folder).unwrap();
 
-        extract_shaders(&data.spch, &model_folder, shader_tools, false);
+        extract_shaders(&data.spch, &model_folder, shader_tools, save_binaries);
     }
 
     for (i, model) in msmd.prop_models.iter().enumerate() {
@@ -109,7 +133,7 @@ fn extract_and_decompile_msmd_shaders(
         let model_folder = output_folder.join("prop").join(i.to_string());
         std::fs::create_dir_all(&model_folder).unwrap();
 
-        extract_shaders(&data.spch, &model_folder, shader_tools, false);
+        extract_shaders(&data.spch, &model_folder, shader_tools, save_binaries);
     }
 
     for (i, model) in msmd.env_models.iter().enumerate() {
@@ -118,7 +142,7 @@ fn extract_and_decompile_msmd_shaders(
         let model_folder = output_folder.join("env").join(i.to_string());
         std::fs::create_dir_all(&model_folder).unwrap();
 
-        extract_shaders(&data.spch, &model_folder, shader_tools, false);
+        extract_shaders(&data.spch, &model_folder, shader_tools, save_binaries);
     }
 
     // TODO: Foliage shaders?
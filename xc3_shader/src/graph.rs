@@ -1,5 +1,11 @@
 use std::collections::{BTreeMap, BTreeSet};
 
+#[cfg(feature = "xc3")]
+use xc3_model::shader_database::{Dependency, TextureDependency};
+
+#[cfg(feature = "xc3")]
+use crate::{dependencies::texture_dependency, shader_database::Attributes};
+
 pub mod glsl;
 #[cfg(feature = "xc3")]
 pub mod latte;
@@ -137,6 +143,32 @@ impl Graph {
             .collect()
     }
 
+    /// Return the unique textures sampled by any dependent node for `variable` and `channel`
+    /// in the order they are assigned.
+    ///
+    /// The `attributes` are used to find the input attributes for the texture coordinates.
+    /// This only includes the texture samples and not other inputs like parameters or attributes.
+    #[cfg(feature = "xc3")]
+    pub fn output_textures(
+        &self,
+        variable: &str,
+        channel: Option<char>,
+        attributes: &Attributes,
+    ) -> Vec<TextureDependency> {
+        let mut textures = Vec::new();
+        for i in self.dependencies_recursive(variable, channel, None) {
+            for e in self.nodes[i].input.exprs_recursive() {
+                if let Some(Dependency::Texture(texture)) = texture_dependency(e, self, attributes)
+                {
+                    if !textures.contains(&texture) {
+                        textures.push(texture);
+                    }
+                }
+            }
+        }
+        textures
+    }
+
     fn add_dependencies(&self, node_index: usize, dependent_lines: &mut BTreeSet<usize>) {
         if let Some(n) = self.nodes.get(node_index) {
             // Avoid processing the subtree rooted at a line more than once.
@@ -228,6 +260,21 @@ impl Graph {
         output
     }
 
+    /// Return the fully inlined expression for the last assignment to `variable` and `channel`.
+    ///
+    /// This contains the same nodes as [Self::dependencies_recursive]
+    /// but replaces each [Expr::Node] with the assigned value.
+    /// Returns `None` if `variable` and `channel` are never assigned.
+    pub fn dependency_tree(&self, variable: &str, channel: Option<char>) -> Option<Expr> {
+        let node = self
+            .nodes
+            .iter()
+            .rfind(|n| n.output.name == variable && n.output.channel == channel)?;
+
+        let mut simplified = BTreeMap::new();
+        Some(simplify(&node.input, &self.nodes, &mut simplified))
+    }
+
     /// Simplify the `node` using variable substitution to eliminate assignments
     /// and other algebraic identities.
     pub fn simplify(&self, node: &Node) -> Self {
@@ -240,6 +287,99 @@ impl Graph {
 
         Self { nodes }
     }
+
+    /// Fold arithmetic on float literals and remove identities like `x * 1.0`,
+    /// `x + 0.0`, or `0.0 - (0.0 - x)` for all nodes.
+    ///
+    /// References to nodes assigned a float literal are replaced with the literal.
+    /// The number and order of nodes is unchanged.
+    pub fn constant_fold(&mut self) {
+        // Nodes only reference previous nodes, so a single pass is sufficient.
+        let mut constants = Vec::with_capacity(self.nodes.len());
+        for node in &mut self.nodes {
+            node.input = constant_fold(&node.input, &constants);
+            constants.push(match node.input {
+                Expr::Float(f) => Some(f),
+                _ => None,
+            });
+        }
+    }
+}
+
+fn constant_fold(input: &Expr, constants: &[Option<f32>]) -> Expr {
+    match input {
+        Expr::Node { node_index, .. } => match constants.get(*node_index) {
+            Some(Some(f)) => Expr::Float(*f),
+            _ => input.clone(),
+        },
+        Expr::Parameter {
+            name,
+            field,
+            index,
+            channel,
+        } => Expr::Parameter {
+            name: name.clone(),
+            field: field.clone(),
+            index: index
+                .as_ref()
+                .map(|i| Box::new(constant_fold(i, constants))),
+            channel: *channel,
+        },
+        Expr::Unary(op, a) => {
+            let a = constant_fold(a, constants);
+            match (op, a) {
+                (UnaryOp::Negate, Expr::Float(f)) => Expr::Float(-f),
+                // -(-x) == x
+                (UnaryOp::Negate, Expr::Unary(UnaryOp::Negate, x)) => *x,
+                (op, a) => Expr::Unary(*op, Box::new(a)),
+            }
+        }
+        Expr::Binary(op, a, b) => {
+            let a = constant_fold(a, constants);
+            let b = constant_fold(b, constants);
+            fold_binary(*op, a, b)
+        }
+        Expr::Ternary(a, b, c) => Expr::Ternary(
+            Box::new(constant_fold(a, constants)),
+            Box::new(constant_fold(b, constants)),
+            Box::new(constant_fold(c, constants)),
+        ),
+        Expr::Func {
+            name,
+            args,
+            channel,
+        } => Expr::Func {
+            name: name.clone(),
+            args: args.iter().map(|a| constant_fold(a, constants)).collect(),
+            channel: *channel,
+        },
+        i => i.clone(),
+    }
+}
+
+fn fold_binary(op: BinaryOp, a: Expr, b: Expr) -> Expr {
+    match (op, a, b) {
+        (BinaryOp::Add, Expr::Float(a), Expr::Float(b)) => Expr::Float(a + b),
+        (BinaryOp::Sub, Expr::Float(a), Expr::Float(b)) => Expr::Float(a - b),
+        (BinaryOp::Mul, Expr::Float(a), Expr::Float(b)) => Expr::Float(a * b),
+        // Avoid introducing infinity or NaN.
+        (BinaryOp::Div, Expr::Float(a), Expr::Float(b)) if b != 0.0 => Expr::Float(a / b),
+        // x + 0.0 == 0.0 + x == x
+        (BinaryOp::Add, x, Expr::Float(0.0)) | (BinaryOp::Add, Expr::Float(0.0), x) => x,
+        // x - 0.0 == x
+        (BinaryOp::Sub, x, Expr::Float(0.0)) => x,
+        // x * 1.0 == 1.0 * x == x
+        (BinaryOp::Mul, x, Expr::Float(1.0)) | (BinaryOp::Mul, Expr::Float(1.0), x) => x,
+        // x / 1.0 == x
+        (BinaryOp::Div, x, Expr::Float(1.0)) => x,
+        // 0.0 - (0.0 - x) == x
+        (BinaryOp::Sub, Expr::Float(0.0), Expr::Binary(BinaryOp::Sub, a, x))
+            if *a == Expr::Float(0.0) =>
+        {
+            *x
+        }
+        (op, a, b) => Expr::Binary(op, Box::new(a), Box::new(b)),
+    }
 }
 
 fn simplify(input: &Expr, nodes: &[Node], simplified: &mut BTreeMap<usize, Expr>) -> Expr {
@@ -334,6 +474,56 @@ mod tests {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
 
+    #[cfg(feature = "xc3")]
+    use glsl_lang::{ast::TranslationUnit, parse::DefaultParse};
+    #[cfg(feature = "xc3")]
+    use xc3_model::shader_database::TexCoord;
+
+    #[cfg(feature = "xc3")]
+    #[test]
+    fn output_textures_multiple_samples() {
+        let glsl = indoc! {"
+            layout(location = 0) in vec4 in_attr0;
+
+            void main() 
+            {
+                float x = in_attr0.x;
+                float y = in_attr0.y;
+                float a = texture(texture1, vec2(x, y)).x;
+                float b = texture(texture2, vec2(x, y)).y * texture(texture1, vec2(x, y)).x;
+                float c = a + b;
+                float d = texture(texture3, vec2(x, y)).z;
+            }
+        "};
+
+        let tu = TranslationUnit::parse(glsl).unwrap();
+        let graph = Graph::from_glsl(&tu);
+        let attributes = crate::shader_database::find_attribute_locations(&tu);
+
+        let texture = |name: &str, channel| TextureDependency {
+            name: name.into(),
+            channel: Some(channel),
+            texcoords: vec![
+                TexCoord {
+                    name: "in_attr0".into(),
+                    channel: Some('x'),
+                    params: None,
+                },
+                TexCoord {
+                    name: "in_attr0".into(),
+                    channel: Some('y'),
+                    params: None,
+                },
+            ],
+            array_layer: None,
+        };
+        assert_eq!(
+            vec![texture("texture1", 'x'), texture("texture2", 'y')],
+            graph.output_textures("c", None, &attributes)
+        );
+        assert!(graph.output_textures("x", None, &attributes).is_empty());
+    }
+
     #[test]
     fn simplify_statements() {
         let glsl = indoc! {"
@@ -358,4 +548,109 @@ mod tests {
             graph.simplify(graph.nodes.last().unwrap()).to_glsl()
         );
     }
+
+    #[test]
+    fn constant_fold_literals() {
+        let glsl = indoc! {"
+            void main() {
+                a = 2.0;
+                b = a * 3.0 + 1.0;
+                c = 1.0 / 4.0;
+                d = -c;
+                e = 1.0 / 0.0;
+            }
+        "};
+        let mut graph = Graph::parse_glsl(glsl).unwrap();
+        graph.constant_fold();
+
+        let expected = indoc! {"
+            a = 2.0;
+            b = 7.0;
+            c = 0.25;
+            d = -0.25;
+            e = 1.0 / 0.0;
+        "};
+        assert_eq!(expected, graph.to_glsl());
+    }
+
+    #[test]
+    fn constant_fold_identities() {
+        let glsl = indoc! {"
+            void main() {
+                a = x * 1.0;
+                b = 1.0 * x;
+                c = x + 0.0;
+                d = 0.0 + x;
+                e = x - 0.0;
+                f = x / 1.0;
+                g = 0.0 - (0.0 - x);
+                h = -(-x);
+                i = 0.0 - x;
+            }
+        "};
+        let mut graph = Graph::parse_glsl(glsl).unwrap();
+        graph.constant_fold();
+
+        let expected = indoc! {"
+            a = x;
+            b = x;
+            c = x;
+            d = x;
+            e = x;
+            f = x;
+            g = x;
+            h = x;
+            i = 0.0 - x;
+        "};
+        assert_eq!(expected, graph.to_glsl());
+    }
+
+    #[test]
+    fn constant_fold_nested_identities() {
+        let glsl = indoc! {"
+            void main() {
+                a = 0.0;
+                b = sqrt(x * (a + 1.0)) + a;
+            }
+        "};
+        let mut graph = Graph::parse_glsl(glsl).unwrap();
+        graph.constant_fold();
+
+        let expected = indoc! {"
+            a = 0.0;
+            b = sqrt(x);
+        "};
+        assert_eq!(expected, graph.to_glsl());
+    }
+
+    #[test]
+    fn dependency_tree_output() {
+        let glsl = indoc! {"
+            void main() {
+                a = in_attr0.x;
+                b = a * 2.0;
+                out_attr0.x = 1.0;
+                out_attr0.x = b + a;
+            }
+        "};
+        let graph = Graph::parse_glsl(glsl).unwrap();
+
+        let attr = Expr::Global {
+            name: "in_attr0".into(),
+            channel: Some('x'),
+        };
+        assert_eq!(
+            Some(Expr::Binary(
+                BinaryOp::Add,
+                Box::new(Expr::Binary(
+                    BinaryOp::Mul,
+                    Box::new(attr.clone()),
+                    Box::new(Expr::Float(2.0))
+                )),
+                Box::new(attr)
+            )),
+            graph.dependency_tree("out_attr0", Some('x'))
+        );
+        assert_eq!(None, graph.dependency_tree("out_attr0", Some('y')));
+    }
 }
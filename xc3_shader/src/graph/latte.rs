@@ -1,3 +1,4 @@
+use log::warn;
 use pest::{iterators::Pair, Parser};
 use pest_derive::Parser;
 
@@ -7,20 +8,110 @@ use super::*;
 struct Nodes {
     nodes: Vec<Node>,
     node_index_alu_unit_inst_count: Vec<(usize, Option<char>, usize)>,
+    /// The condition for pixels currently executing or `None` if all pixels are active.
+    active_condition: Option<Expr>,
+    stack: Vec<StackEntry>,
+}
+
+/// The execution state saved by a push for branching instructions.
+struct StackEntry {
+    active_condition: Option<Expr>,
+    /// The condition from the most recent predicate update for handling else branches.
+    condition: Option<Expr>,
 }
 
 impl Nodes {
-    fn add_node(&mut self, node: Node, alu_unit: Option<char>, inst_count: usize) -> usize {
+    fn add_node(&mut self, mut node: Node, alu_unit: Option<char>, inst_count: usize) -> usize {
+        // Inactive pixels preserve the previous register value.
+        if let Some(condition) = &self.active_condition {
+            if node.output.name.starts_with('R') {
+                let previous = previous_assignment(&node.output.name, node.output.channel, self);
+                node.input = Expr::Ternary(
+                    Box::new(condition.clone()),
+                    Box::new(node.input),
+                    Box::new(previous),
+                );
+            }
+        }
+
         let index = self.nodes.len();
         self.nodes.push(node);
         self.node_index_alu_unit_inst_count
             .push((index, alu_unit, inst_count));
         index
     }
+
+    fn push(&mut self) {
+        self.stack.push(StackEntry {
+            active_condition: self.active_condition.clone(),
+            condition: None,
+        });
+    }
+
+    fn pop(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(entry) = self.stack.pop() {
+                self.active_condition = entry.active_condition;
+            }
+        }
+    }
+
+    fn update_predicate(&mut self, condition: Expr) {
+        let parent = match self.stack.last_mut() {
+            Some(entry) => {
+                entry.condition = Some(condition.clone());
+                entry.active_condition.clone()
+            }
+            None => self.active_condition.clone(),
+        };
+        self.active_condition = Some(and_condition(parent, condition));
+    }
+
+    fn else_branch(&mut self) {
+        // Pixels that failed the condition for the current stack level become active.
+        if let Some(entry) = self.stack.last() {
+            if let Some(condition) = &entry.condition {
+                self.active_condition = Some(and_condition(
+                    entry.active_condition.clone(),
+                    invert_condition(condition.clone()),
+                ));
+            }
+        }
+    }
+}
+
+fn and_condition(parent: Option<Expr>, condition: Expr) -> Expr {
+    match parent {
+        Some(parent) => Expr::Binary(BinaryOp::And, Box::new(parent), Box::new(condition)),
+        None => condition,
+    }
+}
+
+fn invert_condition(condition: Expr) -> Expr {
+    // Invert the comparison to avoid needing parentheses in the GLSL output.
+    match condition {
+        Expr::Binary(op, a, b) => {
+            let op = match op {
+                BinaryOp::Equal => BinaryOp::NotEqual,
+                BinaryOp::NotEqual => BinaryOp::Equal,
+                BinaryOp::Greater => BinaryOp::LessEqual,
+                BinaryOp::GreaterEqual => BinaryOp::Less,
+                BinaryOp::Less => BinaryOp::GreaterEqual,
+                BinaryOp::LessEqual => BinaryOp::Greater,
+                op => return Expr::Unary(UnaryOp::Not, Box::new(Expr::Binary(op, a, b))),
+            };
+            Expr::Binary(op, a, b)
+        }
+        e => Expr::Unary(UnaryOp::Not, Box::new(e)),
+    }
 }
 
 // TODO: The first registers are always input attributes?
 impl Graph {
+    /// Convert Latte assembly text into a graph representation.
+    ///
+    /// Branches use predicated assignments like `R1.x = condition ? value : R1.x;`.
+    /// Loop bodies are only included once since the iteration count is not known.
     pub fn from_latte_asm(asm: &str) -> Self {
         // TODO: The FETCH instruction isn't part of the official grammar?
         let asm = asm
@@ -43,12 +134,7 @@ impl Graph {
             if pair.as_rule() == Rule::instruction {
                 let inst = pair.into_inner().next().unwrap();
                 match inst.as_rule() {
-                    Rule::cf_inst => {
-                        let mut inner = inst.into_inner();
-                        let _inst_count: usize = inner.next().unwrap().as_str().parse().unwrap();
-                        let _op_code = inner.next().unwrap().as_str();
-                        for _property in inner {}
-                    }
+                    Rule::cf_inst => add_cf_inst(inst, &mut nodes),
                     Rule::cf_exp_inst => add_exp_inst(inst, &mut nodes),
                     Rule::tex_clause => add_tex_clause(inst, &mut nodes),
                     Rule::alu_clause => add_alu_clause(inst, &mut nodes),
@@ -61,6 +147,35 @@ impl Graph {
     }
 }
 
+fn add_cf_inst(inst: Pair<Rule>, nodes: &mut Nodes) {
+    let mut inner = inst.into_inner();
+    let _inst_count: usize = inner.next().unwrap().as_str().parse().unwrap();
+    let op_code = inner.next().unwrap().as_str();
+
+    let mut pop_count = 0;
+    for property in inner {
+        for inner in property.into_inner() {
+            if inner.as_rule() == Rule::pop_cnt {
+                pop_count = inner.into_inner().next().unwrap().as_str().parse().unwrap();
+            }
+        }
+    }
+
+    // Jumps skip code with no active pixels and don't affect the results.
+    // The pop count for jumps only applies if the jump is taken.
+    match op_code {
+        "PUSH" => nodes.push(),
+        "PUSH_ELSE" => {
+            nodes.push();
+            nodes.else_branch();
+        }
+        "ELSE" => nodes.else_branch(),
+        "POP" => nodes.pop(pop_count),
+        op if op.starts_with("UNKNOWN_") => warn!("Skipping unknown instruction {op}"),
+        _ => (),
+    }
+}
+
 fn add_exp_inst(inst: Pair<Rule>, nodes: &mut Nodes) {
     let mut inner = inst.into_inner();
     let inst_count: usize = inner.next().unwrap().as_str().parse().unwrap();
@@ -120,6 +235,7 @@ struct AluScalar {
     output_modifier: Option<String>,
     output: Output,
     sources: Vec<Expr>,
+    update_exec_mask: bool,
 }
 
 impl AluScalar {
@@ -140,16 +256,26 @@ impl AluScalar {
 
         let output = alu_dst_output(inner.next().unwrap(), inst_count, alu_unit);
         let sources = inner
+            .by_ref()
             .take(source_count)
             .map(|p| alu_src_expr(p, nodes))
             .collect();
 
+        let update_exec_mask = inner
+            .next()
+            .map(|p| {
+                p.into_inner()
+                    .any(|p| p.as_rule() == Rule::update_exec_mask)
+            })
+            .unwrap_or_default();
+
         Self {
             alu_unit,
             op_code,
             output_modifier,
             output,
             sources,
+            update_exec_mask,
         }
     }
 }
@@ -157,8 +283,13 @@ impl AluScalar {
 fn add_alu_clause(inst: Pair<Rule>, nodes: &mut Nodes) {
     let mut inner = inst.into_inner();
     let _inst_count: usize = inner.next().unwrap().as_str().parse().unwrap();
-    let _inst_type = inner.next().unwrap().as_str();
+    let inst_type = inner.next().unwrap().as_str();
     let _properties = inner.next().unwrap().as_str();
+
+    if inst_type == "ALU_PUSH_BEFORE" {
+        nodes.push();
+    }
+
     for group in inner {
         let mut inner = group.into_inner();
         let inst_count: usize = inner.next().unwrap().as_str().trim().parse().unwrap();
@@ -175,6 +306,12 @@ fn add_alu_clause(inst: Pair<Rule>, nodes: &mut Nodes) {
 
         let dot_node_index = dot_product_node_index(&scalars, inst_count, nodes);
 
+        // Predicate updates affect the execution of later instruction groups.
+        let predicate = scalars
+            .iter()
+            .filter(|s| s.update_exec_mask)
+            .find_map(|s| predicate_condition(&s.op_code, &s.sources));
+
         for scalar in scalars {
             if scalar.op_code.starts_with("DOT4") {
                 // Dot products write the result to all vector components.
@@ -192,9 +329,42 @@ fn add_alu_clause(inst: Pair<Rule>, nodes: &mut Nodes) {
                 add_scalar(scalar, nodes, inst_count);
             }
         }
+
+        if let Some(condition) = predicate {
+            nodes.update_predicate(condition);
+        }
+    }
+
+    match inst_type {
+        "ALU_POP_AFTER" => nodes.pop(1),
+        "ALU_POP2_AFTER" => nodes.pop(2),
+        "ALU_ELSE_AFTER" => nodes.else_branch(),
+        _ => (),
     }
 }
 
+fn predicate_condition(op_code: &str, sources: &[Expr]) -> Option<Expr> {
+    op_code
+        .strip_prefix("PRED_")
+        .and_then(|op_code| comparison(op_code, sources))
+}
+
+fn comparison(op_code: &str, sources: &[Expr]) -> Option<Expr> {
+    // The condition is the same for float, int, and push variants.
+    let op = match op_code.split('_').next()? {
+        "SETE" => BinaryOp::Equal,
+        "SETGT" => BinaryOp::Greater,
+        "SETGE" => BinaryOp::GreaterEqual,
+        "SETNE" => BinaryOp::NotEqual,
+        _ => return None,
+    };
+    Some(Expr::Binary(
+        op,
+        Box::new(sources.first()?.clone()),
+        Box::new(sources.get(1)?.clone()),
+    ))
+}
+
 fn dot_product_node_index(
     scalars: &[AluScalar],
     inst_count: usize,
@@ -292,6 +462,19 @@ fn add_scalar(scalar: AluScalar, nodes: &mut Nodes, inst_count: usize) {
             };
             nodes.add_node(node, Some(scalar.alu_unit), inst_count)
         }
+        "SETE" | "SETGT" | "SETGE" | "SETNE" | "PRED_SETE" | "PRED_SETGT" | "PRED_SETGE"
+        | "PRED_SETNE" => {
+            let op_code = scalar.op_code.trim_start_matches("PRED_");
+            let node = Node {
+                output,
+                input: Expr::Ternary(
+                    Box::new(comparison(op_code, &scalar.sources).unwrap()),
+                    Box::new(Expr::Float(1.0)),
+                    Box::new(Expr::Float(0.0)),
+                ),
+            };
+            nodes.add_node(node, Some(scalar.alu_unit), inst_count)
+        }
         "DOT4" | "DOT4_IEEE" => {
             // Handled in a previous check.
             unreachable!()
@@ -327,7 +510,31 @@ fn add_scalar(scalar: AluScalar, nodes: &mut Nodes, inst_count: usize) {
             };
             nodes.add_node(node, Some(scalar.alu_unit), inst_count)
         }
+        "CNDE" | "CNDGT" | "CNDGE" => {
+            let op = match scalar.op_code.as_str() {
+                "CNDE" => BinaryOp::Equal,
+                "CNDGT" => BinaryOp::Greater,
+                _ => BinaryOp::GreaterEqual,
+            };
+            let node = Node {
+                output,
+                input: Expr::Ternary(
+                    Box::new(Expr::Binary(
+                        op,
+                        Box::new(scalar.sources[0].clone()),
+                        Box::new(Expr::Float(0.0)),
+                    )),
+                    Box::new(scalar.sources[1].clone()),
+                    Box::new(scalar.sources[2].clone()),
+                ),
+            };
+            nodes.add_node(node, Some(scalar.alu_unit), inst_count)
+        }
         "NOP" => 0,
+        // Keep unknown instructions in the output instead of silently skipping them.
+        op if op.starts_with("UNKNOWN_") => {
+            add_func(op, scalar.sources.len(), &scalar, output, inst_count, nodes)
+        }
         // TODO: Handle additional opcodes?
         _ => 0,
     };
@@ -479,7 +686,11 @@ fn alu_src_expr(source: Pair<Rule>, nodes: &Nodes) -> Expr {
             let a = inner.next().unwrap();
             let b = inner.next();
             let value = match (a.as_rule(), b.as_ref().map(|b| b.as_rule())) {
-                (Rule::hex_number, None) => a.as_str().parse().unwrap(),
+                (Rule::hex_number, None) => {
+                    // Values like NaN or infinity are only written as the raw bits.
+                    let bits = a.as_str().trim_start_matches("0x");
+                    f32::from_bits(u32::from_str_radix(bits, 16).unwrap())
+                }
                 (Rule::float, None) => a.as_str().trim_end_matches('f').parse().unwrap(),
                 (Rule::hex_number, Some(Rule::float)) => {
                     // Extract the non hex portion from a float literal.
@@ -756,6 +967,7 @@ struct LatteParser;
 mod tests {
     use super::*;
 
+    use indoc::indoc;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -779,4 +991,137 @@ mod tests {
         let graph = Graph::from_latte_asm(asm);
         assert_eq!(expected, graph.to_glsl());
     }
+
+    #[test]
+    fn graph_from_asm_branch() {
+        // if (R0.x > 0.5) { R1.x = 1.0 } else { R1.x = KC0[0].x }
+        let asm = indoc! {"
+            00 ALU_PUSH_BEFORE: ADDR(32) CNT(2) KCACHE0(CB1:0-15)
+                  0   x: MOV             R1.x, 0.0f
+                      y: PRED_SETGT      ____, R0.x, 0.5f UPDATE_EXEC_MASK UPDATE_PRED
+            01 JUMP POP_CNT(1) ADDR(4)
+            02 ALU: ADDR(34) CNT(1)
+                  1   x: MOV             R1.x, 1.0f
+            03 ELSE POP_CNT(1) ADDR(5)
+            04 ALU_POP_AFTER: ADDR(35) CNT(1) KCACHE0(CB1:0-15)
+                  2   x: MOV             R1.x, KC0[0].x
+            05 EXP_DONE: PIX0, R1.xyzw
+            END_OF_PROGRAM
+        "};
+
+        let expected = indoc! {"
+            R1.x = 0.0;
+            PV0.y = R0.x > 0.5 ? 1.0 : 0.0;
+            R1.x = R0.x > 0.5 ? 1.0 : R1.x;
+            R1.x = R0.x <= 0.5 ? KC0[0].x : R1.x;
+            PIX0.x = R1.x;
+            PIX0.y = R1.y;
+            PIX0.z = R1.z;
+            PIX0.w = R1.w;
+        "};
+
+        let graph = Graph::from_latte_asm(asm);
+        let glsl = graph.to_glsl();
+        assert_eq!(expected, glsl);
+
+        // Check that the conditions are preserved when parsing the GLSL output.
+        let glsl_graph = Graph::parse_glsl(&format!("void main() {{ {glsl} }}")).unwrap();
+        assert_eq!(glsl, glsl_graph.to_glsl());
+    }
+
+    #[test]
+    fn graph_from_disassembled_binary() {
+        let words = [
+            // 00 TEX: ADDR(9) CNT(1)
+            9,
+            (1 << 23) | (1 << 31),
+            // 01 VTX: ADDR(11) CNT(1)
+            11,
+            (2 << 23) | (1 << 31),
+            // 02 UNKNOWN_CF_25
+            0,
+            (25 << 23) | (1 << 31),
+            // 03 ALU: ADDR(5) CNT(4)
+            5,
+            (3 << 18) | (8 << 26) | (1 << 31),
+            // 04 EXP_DONE: PIX0, R1.xyzw
+            1 << 15,
+            (1 << 3) | (2 << 6) | (3 << 9) | (1 << 21) | (40 << 23) | (1 << 31),
+            // MUL R1.x, R2.x, NaN
+            2 | (253 << 13),
+            (1 << 4) | (0x01 << 7) | (1 << 21),
+            // UNKNOWN_OP2_7 R1.y, R0.x, R0.x
+            1 << 31,
+            (1 << 4) | (0x07 << 7) | (1 << 21) | (1 << 29),
+            0x7FC00000,
+            0,
+            // MOV R1.z, UNKNOWN_SRC_200
+            200 | (1 << 31),
+            (1 << 4) | (0x19 << 7) | (1 << 21) | (2 << 29),
+            // SAMPLE R2.xyzw, R0.xy00, t0, s0
+            16,
+            2 | (1 << 12) | (2 << 15) | (3 << 18),
+            (1 << 23) | (4 << 26) | (4 << 29),
+            0,
+            // VTX_FETCH R3.xyzw, R0.x, b0
+            0,
+            3 | (1 << 12) | (2 << 15) | (3 << 18),
+            0,
+            0,
+        ];
+        let binary: Vec<u8> = words.iter().flat_map(|w: &u32| w.to_le_bytes()).collect();
+        let asm = xc3_lib::mths::latte::disassemble(&binary);
+
+        // Check every line including fetches that are skipped when creating the graph.
+        LatteParser::parse(Rule::program, &asm).unwrap();
+
+        let expected = indoc! {"
+            R2.x = texture(t0, vec2(R0.x, R0.y)).x;
+            R2.y = texture(t0, vec2(R0.x, R0.y)).y;
+            R2.z = texture(t0, vec2(R0.x, R0.y)).z;
+            R2.w = texture(t0, vec2(R0.x, R0.y)).w;
+            R1.x = R2.x * NaN;
+            R1.y = UNKNOWN_OP2_7(R0.x, R0.x);
+            R1.z = UNKNOWN_SRC_200;
+            PIX0.x = R1.x;
+            PIX0.y = R1.y;
+            PIX0.z = R1.z;
+            PIX0.w = R1.w;
+        "};
+
+        let graph = Graph::from_latte_asm(&asm);
+        assert_eq!(expected, graph.to_glsl());
+    }
+
+    #[test]
+    fn graph_from_asm_nested_branch() {
+        let asm = indoc! {"
+            00 ALU_PUSH_BEFORE: ADDR(32) CNT(1)
+                  0   x: PRED_SETGT      ____, R0.x, 0.0f UPDATE_EXEC_MASK UPDATE_PRED
+            01 JUMP POP_CNT(1) ADDR(5)
+            02 ALU_PUSH_BEFORE: ADDR(33) CNT(1)
+                  1   y: PRED_SETE       ____, R0.y, 1.0f UPDATE_EXEC_MASK UPDATE_PRED
+            03 JUMP POP_CNT(2) ADDR(5)
+            04 ALU_POP2_AFTER: ADDR(34) CNT(1)
+                  2   x: MOV             R1.x, R0.z
+            05 ALU: ADDR(35) CNT(1)
+                  3   x: CNDGE           R1.x, R0.w, R1.x, 0.0f
+            06 EXP_DONE: PIX0, R1.xyzw
+            END_OF_PROGRAM
+        "};
+
+        let expected = indoc! {"
+            PV0.x = R0.x > 0.0 ? 1.0 : 0.0;
+            PV1.y = R0.y == 1.0 ? 1.0 : 0.0;
+            R1.x = R0.x > 0.0 && R0.y == 1.0 ? R0.z : R1.x;
+            R1.x = R0.w >= 0.0 ? R1.x : 0.0;
+            PIX0.x = R1.x;
+            PIX0.y = R1.y;
+            PIX0.z = R1.z;
+            PIX0.w = R1.w;
+        "};
+
+        let graph = Graph::from_latte_asm(asm);
+        assert_eq!(expected, graph.to_glsl());
+    }
 }
@@ -220,6 +220,58 @@ pub fn mix_a_b_ratio<'a>(
     Some((a, b, ratio))
 }
 
+static SMOOTHSTEP_EDGE0_EDGE1_X: LazyLock<Graph> = LazyLock::new(|| {
+    let query = indoc! {"
+        void main() {
+            neg_edge0 = 0.0 - edge0;
+            x_minus_edge0 = x + neg_edge0;
+            edge1_minus_edge0 = edge1 + neg_edge0;
+            inv_edge1_minus_edge0 = 1.0 / edge1_minus_edge0;
+            t = x_minus_edge0 * inv_edge1_minus_edge0;
+            t = clamp(t, 0.0, 1.0);
+            three_minus_two_t = fma(t, -2.0, 3.0);
+            t_t = t * t;
+            result = t_t * three_minus_two_t;
+        }
+    "};
+    Graph::parse_glsl(query).unwrap()
+});
+
+static SMOOTHSTEP_X: LazyLock<Graph> = LazyLock::new(|| {
+    let query = indoc! {"
+        void main() {
+            t = clamp(x, 0.0, 1.0);
+            three_minus_two_t = fma(t, -2.0, 3.0);
+            t_t = t * t;
+            result = t_t * three_minus_two_t;
+        }
+    "};
+    Graph::parse_glsl(query).unwrap()
+});
+
+static ZERO: Expr = Expr::Float(0.0);
+static ONE: Expr = Expr::Float(1.0);
+
+/// Detect the polynomial `t * t * (3.0 - 2.0 * t)` used for `smoothstep(edge0, edge1, x)`.
+///
+/// Shaders that clamp the input directly without remapping
+/// are treated as `smoothstep(0.0, 1.0, x)`.
+pub fn smoothstep_edge0_edge1_x<'a>(
+    nodes: &'a [Node],
+    expr: &'a Expr,
+) -> Option<(&'a Expr, &'a Expr, &'a Expr)> {
+    if let Some(result) = query_nodes(expr, nodes, &SMOOTHSTEP_EDGE0_EDGE1_X.nodes) {
+        let edge0 = result.get("edge0")?;
+        let edge1 = result.get("edge1")?;
+        let x = result.get("x")?;
+        Some((edge0, edge1, x))
+    } else {
+        let result = query_nodes(expr, nodes, &SMOOTHSTEP_X.nodes)?;
+        let x = result.get("x")?;
+        Some((&ZERO, &ONE, x))
+    }
+}
+
 pub fn node_expr<'a>(nodes: &'a [Node], e: &Expr) -> Option<&'a Expr> {
     if let Expr::Node { node_index, .. } = e {
         nodes.get(*node_index).map(|n| &n.input)
@@ -416,4 +468,93 @@ mod tests {
         )
         .is_some());
     }
+
+    #[test]
+    fn query_smoothstep_edge0_edge1_x() {
+        let graph = Graph::parse_glsl(indoc! {"
+            void main() {
+                temp_0 = 0.0 - U_Mate.gWrkFl4[0].x;
+                temp_1 = in_attr0.x + temp_0;
+                temp_2 = U_Mate.gWrkFl4[0].y + temp_0;
+                temp_3 = 1.0 / temp_2;
+                temp_4 = temp_1 * temp_3;
+                temp_5 = clamp(temp_4, 0.0, 1.0);
+                temp_6 = fma(temp_5, -2.0, 3.0);
+                temp_7 = temp_5 * temp_5;
+                temp_8 = temp_7 * temp_6;
+            }
+        "})
+        .unwrap();
+
+        let (edge0, edge1, x) =
+            smoothstep_edge0_edge1_x(&graph.nodes, &graph.nodes.last().unwrap().input).unwrap();
+        assert_eq!(
+            &Expr::Parameter {
+                name: "U_Mate".into(),
+                field: Some("gWrkFl4".into()),
+                index: Some(Box::new(Expr::Int(0))),
+                channel: Some('x')
+            },
+            edge0
+        );
+        assert_eq!(
+            &Expr::Parameter {
+                name: "U_Mate".into(),
+                field: Some("gWrkFl4".into()),
+                index: Some(Box::new(Expr::Int(0))),
+                channel: Some('y')
+            },
+            edge1
+        );
+        assert_eq!(
+            &Expr::Global {
+                name: "in_attr0".into(),
+                channel: Some('x')
+            },
+            x
+        );
+    }
+
+    #[test]
+    fn query_smoothstep_clamped_x() {
+        let graph = Graph::parse_glsl(indoc! {"
+            void main() {
+                temp_0 = clamp(in_attr0.x, 0.0, 1.0);
+                temp_1 = fma(temp_0, -2.0, 3.0);
+                temp_2 = temp_0 * temp_0;
+                temp_3 = temp_2 * temp_1;
+            }
+        "})
+        .unwrap();
+
+        let (edge0, edge1, x) =
+            smoothstep_edge0_edge1_x(&graph.nodes, &graph.nodes.last().unwrap().input).unwrap();
+        assert_eq!(&Expr::Float(0.0), edge0);
+        assert_eq!(&Expr::Float(1.0), edge1);
+        assert_eq!(
+            &Expr::Global {
+                name: "in_attr0".into(),
+                channel: Some('x')
+            },
+            x
+        );
+    }
+
+    #[test]
+    fn query_smoothstep_invalid() {
+        let graph = Graph::parse_glsl(indoc! {"
+            void main() {
+                temp_0 = clamp(in_attr0.x, 0.0, 1.0);
+                temp_1 = fma(temp_0, -2.0, 2.0);
+                temp_2 = temp_0 * temp_0;
+                temp_3 = temp_2 * temp_1;
+            }
+        "})
+        .unwrap();
+
+        assert_eq!(
+            None,
+            smoothstep_edge0_edge1_x(&graph.nodes, &graph.nodes.last().unwrap().input)
+        );
+    }
 }
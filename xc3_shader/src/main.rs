@@ -1,11 +1,18 @@
+use std::path::Path;
+
 use clap::{Parser, Subcommand};
+use glsl_lang::{ast::TranslationUnit, parse::DefaultParse};
 
 use xc3_model::shader_database::ShaderDatabase;
 use xc3_shader::dependencies::latte_dependencies;
-use xc3_shader::extract::{extract_and_decompile_shaders, extract_and_disassemble_shaders};
-use xc3_shader::shader_database::{create_shader_database, create_shader_database_legacy};
+use xc3_shader::extract::{
+    extract_and_decompile_shaders, extract_and_disassemble_shaders, extract_shader_binaries_only,
+};
+use xc3_shader::shader_database::{
+    create_shader_database, create_shader_database_legacy, shader_from_glsl,
+};
 
-use xc3_shader::graph::glsl::glsl_dependencies;
+use xc3_shader::graph::glsl::{glsl_dependencies, shader_source_no_extensions};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -27,6 +34,14 @@ enum Commands {
         /// The path to the Ryujinx.ShaderTools executable
         shader_tools: Option<String>,
     },
+    /// Extract shader binaries without decompiling into a folder for each .wimdo or .wismhd file.
+    /// Metadata for each program will also be saved in the output folder.
+    ExtractShaderBinaries {
+        /// The root folder for Xenoblade 1 DE, Xenoblade 2, or Xenoblade 3.
+        input_folder: String,
+        /// The output folder for the shader binaries.
+        output_folder: String,
+    },
     /// Extract and disassemble shaders into a folder for each .camdo file.
     DisassembleLegacyShaders {
         /// The root folder for Xenoblade X.
@@ -57,6 +72,14 @@ enum Commands {
         /// The input database files.
         input_files: Vec<String>,
     },
+    /// Export the dependencies for each output of all programs in a database as CSV.
+    /// Each row contains the program hash, output, channel, and dependency.
+    DatabaseCsv {
+        /// The input database file.
+        input_file: String,
+        /// The output CSV file.
+        output_file: String,
+    },
     /// Find all lines of GLSL code influencing the final assignment of a variable.
     GlslDependencies {
         /// The input GLSL file.
@@ -66,6 +89,14 @@ enum Commands {
         /// The name of the variable to analyze.
         var: String,
     },
+    /// Find the database dependencies for each output of a single GLSL program.
+    /// The vertex shader with the same name and ".vert" extension is used if present.
+    GlslOutputDependencies {
+        /// The input fragment GLSL file.
+        input: String,
+        /// The output text file or JSON file if the extension is ".json".
+        output: String,
+    },
     /// Find all lines of GLSL code influencing the final assignment of a variable.
     LatteDependencies {
         /// The input Latte ASM file.
@@ -93,6 +124,10 @@ fn main() {
             output_folder,
             shader_tools,
         } => extract_and_decompile_shaders(&input_folder, &output_folder, shader_tools.as_deref()),
+        Commands::ExtractShaderBinaries {
+            input_folder,
+            output_folder,
+        } => extract_shader_binaries_only(&input_folder, &output_folder),
         Commands::DisassembleLegacyShaders {
             input_folder,
             output_folder,
@@ -112,12 +147,40 @@ fn main() {
             let database = create_shader_database_legacy(&input_folder);
             database.save(output_file).unwrap();
         }
+        Commands::DatabaseCsv {
+            input_file,
+            output_file,
+        } => {
+            let database = ShaderDatabase::from_file(input_file).unwrap();
+            let mut writer = std::io::BufWriter::new(std::fs::File::create(output_file).unwrap());
+            database.write_output_dependencies_csv(&mut writer).unwrap();
+        }
         Commands::GlslDependencies { input, output, var } => {
             let source = std::fs::read_to_string(input).unwrap();
             let (var, channels) = var.split_once('.').unwrap_or((&var, ""));
             let source_out = glsl_dependencies(&source, var, channels.chars().next());
             std::fs::write(output, source_out).unwrap();
         }
+        Commands::GlslOutputDependencies { input, output } => {
+            let input = Path::new(&input);
+            let vertex_source = std::fs::read_to_string(input.with_extension("vert")).ok();
+            let vertex = vertex_source
+                .map(|s| TranslationUnit::parse(shader_source_no_extensions(&s)).unwrap());
+
+            let frag_source = std::fs::read_to_string(input).unwrap();
+            let fragment =
+                TranslationUnit::parse(shader_source_no_extensions(&frag_source)).unwrap();
+
+            let program = shader_from_glsl(vertex.as_ref(), &fragment);
+
+            let output = Path::new(&output);
+            if output.extension().and_then(|e| e.to_str()) == Some("json") {
+                let json = serde_json::to_string_pretty(&program).unwrap();
+                std::fs::write(output, json).unwrap();
+            } else {
+                std::fs::write(output, format!("{program:#?}")).unwrap();
+            }
+        }
         Commands::LatteDependencies { input, output, var } => {
             let source = std::fs::read_to_string(input).unwrap();
             let (var, channels) = var.split_once('.').unwrap_or((&var, ""));
@@ -128,11 +191,21 @@ fn main() {
             input_files,
             output_file,
         } => {
-            if let Some(merged) = input_files
-                .iter()
-                .map(|path| ShaderDatabase::from_file(path).unwrap())
-                .reduce(|a, b| ShaderDatabase::merge(&a, &b))
-            {
+            let mut merged: Option<ShaderDatabase> = None;
+            for path in &input_files {
+                let database = ShaderDatabase::from_file(path).unwrap();
+                merged = Some(match merged {
+                    Some(merged) => {
+                        let (merged, conflicts) = merged.merge_checked(&database);
+                        for hash in conflicts {
+                            println!("Replacing program {hash:?} with data from {path:?}");
+                        }
+                        merged
+                    }
+                    None => database,
+                });
+            }
+            if let Some(merged) = merged {
                 merged.save(output_file).unwrap();
             }
         }
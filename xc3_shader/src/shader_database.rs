@@ -1,10 +1,14 @@
-use std::{collections::BTreeMap, path::Path, sync::LazyLock};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::LazyLock,
+};
 
 use bimap::BiBTreeMap;
 use glsl_lang::{
     ast::{
         ExprData, LayoutQualifierSpecData, SingleDeclaration, StorageQualifierData,
-        TranslationUnit, TypeQualifierSpecData,
+        TranslationUnit, TypeQualifierSpecData, TypeSpecifierNonArrayData,
     },
     parse::DefaultParse,
     visitor::{Host, Visit, Visitor},
@@ -31,15 +35,29 @@ use crate::{
         glsl::shader_source_no_extensions,
         query::{
             assign_x, assign_x_recursive, dot3_a_b, fma_a_b_c, fma_half_half, mix_a_b_ratio,
-            node_expr, normalize, query_nodes,
+            node_expr, normalize, query_nodes, smoothstep_edge0_edge1_x,
         },
         Expr, Graph, Node,
     },
 };
 
-fn shader_from_glsl(vertex: Option<&TranslationUnit>, fragment: &TranslationUnit) -> ShaderProgram {
+/// Find the output dependencies for the `fragment` shader and optional `vertex` shader.
+///
+/// If `vertex` is `None`, known vertex attribute names like `vColor` or `vNormal`
+/// used in the fragment shader are treated as input attributes.
+/// The results are less precise without the vertex shader
+/// since texture coordinate transforms and attribute names for fragment inputs like `in_attr0`
+/// can only be determined from the vertex shader.
+pub fn shader_from_glsl(
+    vertex: Option<&TranslationUnit>,
+    fragment: &TranslationUnit,
+) -> ShaderProgram {
     let frag = &Graph::from_glsl(fragment);
-    let frag_attributes = &find_attribute_locations(fragment);
+    let mut frag_attributes = find_attribute_locations(fragment);
+    if vertex.is_none() {
+        add_known_attributes(frag, &mut frag_attributes);
+    }
+    let frag_attributes = &frag_attributes;
 
     let vertex = vertex.map(|v| (Graph::from_glsl(v), find_attribute_locations(v)));
     let (vert, vert_attributes) = vertex.unzip();
@@ -52,6 +70,10 @@ fn shader_from_glsl(vertex: Option<&TranslationUnit>, fragment: &TranslationUnit
     let mut output_dependencies = IndexMap::new();
     for i in 0..=5 {
         for c in "xyzw".chars() {
+            if is_velocity_or_depth_output(i, c) {
+                continue;
+            }
+
             let name = format!("out_attr{i}");
             let assignments = frag.assignments_recursive(&name, Some(c), None);
             let dependent_lines = frag.dependencies_recursive(&name, Some(c), None);
@@ -134,6 +156,43 @@ fn shader_from_glsl(vertex: Option<&TranslationUnit>, fragment: &TranslationUnit
     }
 }
 
+/// Returns `true` if the G-Buffer output channel only stores velocity or depth
+/// for Xenoblade 1 DE, Xenoblade 2, and Xenoblade 3.
+///
+/// These channels are calculated from the vertex positions and don't depend on the material.
+/// The remaining channels `o3.z` and `o4.w` can still contain material values.
+/// Xenoblade X uses a different output layout and should not skip any outputs.
+fn is_velocity_or_depth_output(output_index: usize, channel: char) -> bool {
+    match output_index {
+        3 => "xyw".contains(channel),
+        4 => "xyz".contains(channel),
+        _ => false,
+    }
+}
+
+// Vertex input attribute names that may be used directly in fragment only shaders.
+const KNOWN_ATTRIBUTE_NAMES: [&str; 14] = [
+    "vPos", "vNormal", "vTan", "vBitan", "vColor", "vTex0", "vTex1", "vTex2", "vTex3", "vTex4",
+    "vTex5", "vTex6", "vTex7", "vTex8",
+];
+
+fn add_known_attributes(frag: &Graph, attributes: &mut Attributes) {
+    for node in &frag.nodes {
+        for e in node.input.exprs_recursive() {
+            if let Expr::Global { name, .. } = e {
+                if let Some(i) = KNOWN_ATTRIBUTE_NAMES.iter().position(|n| n == name) {
+                    // Use negative locations to avoid conflicts with declared attributes.
+                    if !attributes.input_locations.contains_left(name) {
+                        attributes
+                            .input_locations
+                            .insert(name.clone(), -(i as i32) - 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
 static OUTLINE_WIDTH_PARAMETER: LazyLock<Graph> = LazyLock::new(|| {
     let query = indoc! {"
         void main() {
@@ -595,6 +654,11 @@ fn ratio_dependency(
         is_fresnel = true;
     }
 
+    // Ratios remapped with smoothstep still depend on the input value.
+    if let Some((_edge0, _edge1, x)) = smoothstep_edge0_edge1_x(nodes, ratio) {
+        ratio = assign_x_recursive(nodes, x);
+    }
+
     (is_fresnel, dependency_cached_texture(ratio, dependencies))
 }
 
@@ -1007,11 +1071,17 @@ struct AttributeVisitor {
 pub struct Attributes {
     pub input_locations: BiBTreeMap<String, i32>,
     pub output_locations: BiBTreeMap<String, i32>,
+    /// The names of samplers declared with an array type like `sampler2DArray`.
+    pub array_samplers: BTreeSet<String>,
 }
 
 impl Visitor for AttributeVisitor {
     fn visit_single_declaration(&mut self, declaration: &SingleDeclaration) -> Visit {
         if let Some(name) = &declaration.name {
+            if is_array_sampler(&declaration.ty.content.ty.content.ty.content) {
+                self.attributes.array_samplers.insert(name.0.to_string());
+            }
+
             if let Some(qualifier) = &declaration.ty.content.qualifier {
                 let mut is_input = None;
                 let mut location = None;
@@ -1063,6 +1133,24 @@ impl Visitor for AttributeVisitor {
     }
 }
 
+fn is_array_sampler(ty: &TypeSpecifierNonArrayData) -> bool {
+    matches!(
+        ty,
+        TypeSpecifierNonArrayData::Sampler1DArray
+            | TypeSpecifierNonArrayData::Sampler2DArray
+            | TypeSpecifierNonArrayData::SamplerCubeArray
+            | TypeSpecifierNonArrayData::Sampler1DArrayShadow
+            | TypeSpecifierNonArrayData::Sampler2DArrayShadow
+            | TypeSpecifierNonArrayData::SamplerCubeArrayShadow
+            | TypeSpecifierNonArrayData::ISampler1DArray
+            | TypeSpecifierNonArrayData::ISampler2DArray
+            | TypeSpecifierNonArrayData::ISamplerCubeArray
+            | TypeSpecifierNonArrayData::USampler1DArray
+            | TypeSpecifierNonArrayData::USampler2DArray
+            | TypeSpecifierNonArrayData::USamplerCubeArray
+    )
+}
+
 pub fn find_attribute_locations(translation_unit: &TranslationUnit) -> Attributes {
     let mut visitor = AttributeVisitor::default();
     translation_unit.visit(&mut visitor);
@@ -1112,6 +1200,7 @@ mod tests {
                 ]
                 .into_iter()
                 .collect(),
+                array_samplers: BTreeSet::new(),
             },
             find_attribute_locations(&tu)
         );
@@ -1146,7 +1235,8 @@ mod tests {
                             channel: Some('y'),
                             params: None,
                         },
-                    ]
+                    ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -1210,6 +1300,7 @@ mod tests {
                             }))
                         },
                     ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -1242,6 +1333,7 @@ mod tests {
                             }))
                         },
                     ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -1274,6 +1366,7 @@ mod tests {
                             }))
                         },
                     ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -1313,7 +1406,8 @@ mod tests {
                             channel: Some('y'),
                             params: None,
                         },
-                    ]
+                    ],
+                    array_layer: None,
                 })],
                 layers: vec![
                     TextureLayer {
@@ -1332,6 +1426,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         }),
                         ratio: None,
                         blend_mode: LayerBlendMode::Mix,
@@ -1380,7 +1475,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1401,7 +1497,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1427,7 +1524,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1448,7 +1546,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1474,7 +1573,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1495,7 +1595,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1521,7 +1622,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Add,
@@ -1542,7 +1644,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Buffer(BufferDependency {
                         name: "U_Mate".into(),
@@ -1596,7 +1699,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1633,7 +1737,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1672,6 +1777,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "gTResidentTex09".into(),
@@ -1688,6 +1794,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s2".into(),
@@ -1704,6 +1811,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s2".into(),
@@ -1720,6 +1828,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s3".into(),
@@ -1736,6 +1845,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s3".into(),
@@ -1752,6 +1862,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                 ],
                 layers: vec![
@@ -1770,7 +1881,8 @@ mod tests {
                                     channel: Some('y'),
                                     params: None
                                 }
-                            ]
+                            ],
+                            array_layer: None,
                         }),
                         ratio: None,
                         blend_mode: LayerBlendMode::Add,
@@ -1791,7 +1903,8 @@ mod tests {
                                     channel: Some('w'),
                                     params: None
                                 }
-                            ]
+                            ],
+                            array_layer: None,
                         }),
                         ratio: Some(Dependency::Buffer(BufferDependency {
                             name: "U_Mate".into(),
@@ -1817,7 +1930,8 @@ mod tests {
                                     channel: Some('y'),
                                     params: None
                                 }
-                            ]
+                            ],
+                            array_layer: None,
                         }),
                         ratio: Some(Dependency::Buffer(BufferDependency {
                             name: "U_Mate".into(),
@@ -1859,7 +1973,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1896,7 +2011,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -1935,6 +2051,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s2".into(),
@@ -1951,6 +2068,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                 ],
                 layers: Vec::new()
@@ -1997,6 +2115,7 @@ mod tests {
                                         params: None,
                                     },
                                 ],
+                                array_layer: None,
                             }),
                             ratio: BufferDependency {
                                 name: "U_Mate".into(),
@@ -2031,6 +2150,7 @@ mod tests {
                                         params: None,
                                     },
                                 ],
+                                array_layer: None,
                             }),
                             ratio: BufferDependency {
                                 name: "U_Mate".into(),
@@ -2041,6 +2161,7 @@ mod tests {
                         }),
                     },
                 ],
+                array_layer: None,
             }),
             shader.output_dependencies[&SmolStr::from("o0.x")].dependencies[0]
         );
@@ -2074,6 +2195,7 @@ mod tests {
                             params: None,
                         },
                     ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -2096,7 +2218,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Add,
@@ -2117,7 +2240,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s1".into(),
@@ -2134,6 +2258,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Mix,
                     is_fresnel: false
@@ -2158,7 +2283,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Add,
@@ -2179,7 +2305,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s1".into(),
@@ -2196,6 +2323,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Mix,
                     is_fresnel: false
@@ -2230,7 +2358,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2251,7 +2380,8 @@ mod tests {
                                 channel: Some('x'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s1".into(),
@@ -2268,6 +2398,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Add,
                     is_fresnel: false
@@ -2287,7 +2418,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2314,6 +2446,7 @@ mod tests {
                             params: None,
                         },
                     ],
+                    array_layer: None,
                 })],
                 layers: Vec::new()
             },
@@ -2336,7 +2469,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Add,
@@ -2357,7 +2491,8 @@ mod tests {
                                 channel: Some('w'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Buffer(BufferDependency {
                         name: "U_Mate".into(),
@@ -2399,6 +2534,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s2".into(),
@@ -2415,6 +2551,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s3".into(),
@@ -2431,6 +2568,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s3".into(),
@@ -2447,6 +2585,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s4".into(),
@@ -2463,6 +2602,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s5".into(),
@@ -2479,6 +2619,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s5".into(),
@@ -2495,6 +2636,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     Dependency::Texture(TextureDependency {
                         name: "s6".into(),
@@ -2511,6 +2653,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                 ],
                 layers: vec![
@@ -2530,6 +2673,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         }),
                         ratio: None,
                         blend_mode: LayerBlendMode::Add,
@@ -2551,6 +2695,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         }),
                         ratio: Some(Dependency::Texture(TextureDependency {
                             name: "s4".into(),
@@ -2567,6 +2712,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         })),
                         blend_mode: LayerBlendMode::AddNormal,
                         is_fresnel: false,
@@ -2587,6 +2733,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         }),
                         ratio: Some(Dependency::Texture(TextureDependency {
                             name: "s6".into(),
@@ -2603,6 +2750,7 @@ mod tests {
                                     params: None,
                                 },
                             ],
+                            array_layer: None,
                         })),
                         blend_mode: LayerBlendMode::AddNormal,
                         is_fresnel: false,
@@ -2638,7 +2786,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2659,7 +2808,8 @@ mod tests {
                                 channel: Some('y'),
                                 params: None
                             }
-                        ]
+                        ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2681,6 +2831,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s4".into(),
@@ -2697,6 +2848,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Mix,
                     is_fresnel: false,
@@ -2748,6 +2900,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2769,6 +2922,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s5".into(),
@@ -2785,6 +2939,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Overlay,
                     is_fresnel: false,
@@ -2820,6 +2975,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -2901,6 +3057,7 @@ mod tests {
                                             params: None,
                                         },
                                     ],
+                                    array_layer: None,
                                 }),
                                 mask_b: Dependency::Buffer(BufferDependency {
                                     name: "U_Mate".into(),
@@ -2935,6 +3092,7 @@ mod tests {
                                             params: None,
                                         },
                                     ],
+                                    array_layer: None,
                                 }),
                                 mask_b: Dependency::Buffer(BufferDependency {
                                     name: "U_Mate".into(),
@@ -2951,6 +3109,7 @@ mod tests {
                             }),
                         },
                     ],
+                    array_layer: None,
                 }),
                 Dependency::Texture(TextureDependency {
                     name: "s1".into(),
@@ -2967,6 +3126,7 @@ mod tests {
                             params: None,
                         },
                     ],
+                    array_layer: None,
                 }),
             ],
             shader.output_dependencies[&SmolStr::from("o0.x")].dependencies
@@ -2994,6 +3154,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn shader_from_fragment_known_attributes() {
+        let glsl = indoc! {"
+            layout(location = 0) out vec4 out_attr0;
+
+            void main() {
+                out_attr0.x = vColor.x;
+                out_attr0.y = in_attr0.y;
+            }
+        "};
+
+        let fragment = TranslationUnit::parse(glsl).unwrap();
+        let shader = shader_from_glsl(None, &fragment);
+        assert_eq!(
+            ShaderProgram {
+                output_dependencies: [(
+                    "o0.x".into(),
+                    OutputDependencies {
+                        dependencies: vec![Dependency::Attribute(AttributeDependency {
+                            name: "vColor".into(),
+                            channel: Some('x'),
+                        })],
+                        layers: Vec::new()
+                    },
+                )]
+                .into(),
+                outline_width: None
+            },
+            shader
+        );
+    }
+
     #[test]
     fn shader_from_fragment_panacea_body() {
         // xeno3/chr/ch/ch44000210, "ch45133501_body", shd0029.frag
@@ -3020,6 +3212,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -3052,6 +3245,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: Some(Dependency::Texture(TextureDependency {
                         name: "s1".into(),
@@ -3068,6 +3262,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     })),
                     blend_mode: LayerBlendMode::Add,
                     is_fresnel: false,
@@ -3088,6 +3283,7 @@ mod tests {
                                 params: None,
                             },
                         ],
+                        array_layer: None,
                     }),
                     ratio: None,
                     blend_mode: LayerBlendMode::Mix,
@@ -3098,6 +3294,53 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ratio_dependency_smoothstep() {
+        let glsl = indoc! {"
+            void main() {
+                x = U_Mate.gWrkFl4[1].y;
+                t = clamp(x, 0.0, 1.0);
+                three_minus_two_t = fma(t, -2.0, 3.0);
+                t_t = t * t;
+                result = t_t * three_minus_two_t;
+            }
+        "};
+        let graph = Graph::parse_glsl(glsl).unwrap();
+        let ratio = &graph.nodes.last().unwrap().input;
+
+        assert_eq!(
+            (
+                false,
+                Some(Dependency::Buffer(BufferDependency {
+                    name: "U_Mate".into(),
+                    field: "gWrkFl4".into(),
+                    index: Some(1),
+                    channel: Some('y'),
+                }))
+            ),
+            ratio_dependency(ratio, &graph.nodes, &[])
+        );
+    }
+
+    #[test]
+    fn shader_from_fragment_skip_velocity_depth() {
+        // xeno3/chr/ch/ch01011013, "body_outline", shd0000.frag
+        let glsl = include_str!("data/xc3/ch01011013.0.frag");
+
+        let fragment = TranslationUnit::parse(glsl).unwrap();
+        let shader = shader_from_glsl(None, &fragment);
+        assert!(["o3.x", "o3.y", "o3.w", "o4.x", "o4.y", "o4.z"]
+            .iter()
+            .all(|o| !shader.output_dependencies.contains_key(*o)));
+        assert_eq!(
+            Some(&OutputDependencies {
+                dependencies: vec![Dependency::Constant(0.5.into())],
+                layers: Vec::new()
+            }),
+            shader.output_dependencies.get("o4.w")
+        );
+    }
+
     #[test]
     fn shader_from_latte_asm_pc221115_frag_0() {
         // Elma's legs (visible on title screen).
@@ -3183,21 +3426,25 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "gIBL".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3211,21 +3458,25 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "gIBL".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3239,21 +3490,25 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "gIBL".into(),
                                     channel: Some('z'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3267,16 +3522,19 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "gIBL".into(),
                                     channel: Some('w'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3290,26 +3548,31 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s0".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "texRef".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3323,26 +3586,31 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s0".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "texRef".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3356,26 +3624,31 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s1".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s0".into(),
                                     channel: Some('z'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "texRef".into(),
                                     channel: Some('z'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3396,11 +3669,13 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3414,11 +3689,13 @@ mod tests {
                                     name: "s2".into(),
                                     channel: Some('x'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                                 Dependency::Texture(TextureDependency {
                                     name: "s2".into(),
                                     channel: Some('y'),
                                     texcoords: Vec::new(),
+                                    array_layer: None,
                                 }),
                             ],
                             layers: Vec::new()
@@ -3431,6 +3708,7 @@ mod tests {
                                 name: "s3".into(),
                                 channel: Some('x'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             })],
                             layers: Vec::new()
                         },
@@ -3442,6 +3720,7 @@ mod tests {
                                 name: "s3".into(),
                                 channel: Some('y'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             })],
                             layers: Vec::new()
                         },
@@ -3453,6 +3732,7 @@ mod tests {
                                 name: "s3".into(),
                                 channel: Some('z'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             })],
                             layers: Vec::new()
                         },
@@ -3476,6 +3756,7 @@ mod tests {
                                 name: "s1".into(),
                                 channel: Some('z'),
                                 texcoords: Vec::new(),
+                                array_layer: None,
                             })],
                             layers: Vec::new()
                         },
@@ -11,7 +11,7 @@ use rayon::prelude::*;
 use xc3_lib::{
     apmd::Apmd,
     bc::Bc,
-    beb::{Beb, BebData},
+    beb::{Beb, BebEntryType},
     beh::Beh,
     bmn::Bmn,
     dhal::Dhal,
@@ -25,13 +25,14 @@ use xc3_lib::{
     last::Last,
     ltpc::Ltpc,
     mibl::Mibl,
-    msmd::Msmd,
+    msmd::{Msmd, MsmdFile},
     msrd::{streaming::chr_tex_nx_folder, Msrd},
     mths::Mths,
     mtxt::Mtxt,
-    mxmd::{legacy::MxmdLegacy, Mxmd},
+    mxmd::{legacy::MxmdLegacy, Mxmd, MxmdVersion},
     sar1::{ChCl, Csvb, Sar1},
     spch::Spch,
+    wipac::Wipac,
     xbc1::{MaybeXbc1, Xbc1},
 };
 use xc3_model::{load_skel, monolib::ShaderTextures, ModelRoot};
@@ -129,6 +130,10 @@ struct Cli {
     #[arg(long)]
     mths: bool,
 
+    /// Process ARC effect files from .wipac
+    #[arg(long)]
+    wipac: bool,
+
     /// Process all file types except gltf and wimdo-model.
     #[arg(long)]
     all: bool,
@@ -260,6 +265,11 @@ fn main() {
         check_all(root, &["*.wiefb"], check_efb0, Endian::Little, cli.rw);
     }
 
+    if cli.wipac || cli.all {
+        println!("Checking Wipac files ...");
+        check_all(root, &["*.wipac"], check_wipac, Endian::Little, cli.rw);
+    }
+
     if cli.fnt || cli.all {
         println!("Checking fnt files ...");
         check_all(root, &["*.fnt"], check_fnt, Endian::Big, cli.rw);
@@ -418,7 +428,27 @@ fn check_sar1_data(
 }
 
 fn check_msrd(msrd: Msrd, path: &Path, original_bytes: &[u8], check_read_write: bool) {
-    // TODO: check stream flags?
+    if let Some(info) = msrd.streaming.stream_info() {
+        let has_textures = info
+            .entries
+            .iter()
+            .any(|e| e.entry_type == xc3_lib::msrd::EntryType::Texture);
+        if info.flags.has_textures() != has_textures {
+            println!(
+                "Unexpected has_textures flag {} for {path:?}",
+                info.flags.has_textures()
+            );
+        }
+
+        let has_chr_textures = info.chr_texture_count > 0;
+        if info.flags.has_chr_textures() != has_chr_textures {
+            println!(
+                "Unexpected has_chr_textures flag {} for {path:?}",
+                info.flags.has_chr_textures()
+            );
+        }
+    }
+
     let chr_tex_nx = chr_tex_nx_folder(path);
     let (vertex, spch, textures) = msrd.extract_files(chr_tex_nx.as_deref()).unwrap();
 
@@ -471,12 +501,11 @@ fn check_vertex_data(
 
 fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write: bool) {
     // Parse all the data from the .wismda
-    let mut reader = BufReader::new(std::fs::File::open(path.with_extension("wismda")).unwrap());
-
-    let compressed = msmd.wismda_info.compressed_length != msmd.wismda_info.decompressed_length;
+    let reader = BufReader::new(std::fs::File::open(path.with_extension("wismda")).unwrap());
+    let mut msmd = MsmdFile::new(msmd, reader);
 
-    for (i, model) in msmd.map_models.iter().enumerate() {
-        match model.entry.extract(&mut reader, compressed) {
+    for (i, model) in msmd.map_models().enumerate() {
+        match model {
             Ok(model) => {
                 check_spch(model.spch, path, &[], false);
             }
@@ -484,8 +513,8 @@ fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write:
         }
     }
 
-    for (i, model) in msmd.prop_models.iter().enumerate() {
-        match model.entry.extract(&mut reader, compressed) {
+    for (i, model) in msmd.prop_models().enumerate() {
+        match model {
             Ok(model) => {
                 check_spch(model.spch, path, &[], false);
             }
@@ -493,8 +522,8 @@ fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write:
         }
     }
 
-    for (i, model) in msmd.env_models.iter().enumerate() {
-        match model.entry.extract(&mut reader, compressed) {
+    for (i, model) in msmd.env_models().enumerate() {
+        match model {
             Ok(model) => {
                 for texture in model.textures.textures {
                     let mibl = Mibl::from_bytes(&texture.mibl_data).unwrap();
@@ -505,18 +534,18 @@ fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write:
         }
     }
 
-    for (i, entry) in msmd.prop_vertex_data.iter().enumerate() {
-        match entry.extract(&mut reader, compressed) {
+    for (i, entry) in msmd.msmd.prop_vertex_data.clone().iter().enumerate() {
+        match msmd.extract(entry) {
             Ok(vertex_data) => {
-                let original_bytes = entry.decompress(&mut reader, compressed).unwrap();
+                let original_bytes = msmd.decompress(entry).unwrap();
                 check_vertex_data(vertex_data, path, &original_bytes, check_read_write);
             }
             Err(e) => println!("Error extracting prop VertexData {i} in {path:?}: {e}"),
         }
     }
 
-    for (i, model) in msmd.foliage_models.iter().enumerate() {
-        match model.entry.extract(&mut reader, compressed) {
+    for (i, model) in msmd.foliage_models().enumerate() {
+        match model {
             Ok(model) => {
                 check_vertex_data(model.vertex_data, path, &[], false);
                 for texture in model.textures.textures {
@@ -528,19 +557,18 @@ fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write:
         }
     }
 
-    for entry in msmd.prop_positions {
-        entry.extract(&mut reader, compressed).unwrap();
+    for entry in msmd.prop_positions() {
+        entry.unwrap();
     }
 
-    for entry in msmd.low_textures {
-        let entry = entry.extract(&mut reader, compressed).unwrap();
-        for texture in entry.textures {
+    for entry in msmd.low_textures() {
+        for texture in entry.unwrap().textures {
             Mibl::from_bytes(&texture.mibl_data).unwrap();
         }
     }
 
-    for (i, model) in msmd.low_models.iter().enumerate() {
-        match model.entry.extract(&mut reader, compressed) {
+    for (i, model) in msmd.low_models().enumerate() {
+        match model {
             Ok(model) => {
                 check_vertex_data(model.vertex_data, path, &[], false);
                 check_spch(model.spch, path, &[], false);
@@ -549,14 +577,14 @@ fn check_msmd(msmd: Msmd, path: &Path, _original_bytes: &[u8], check_read_write:
         }
     }
 
-    for entry in msmd.unk_foliage_data {
-        entry.extract(&mut reader, compressed).unwrap();
+    for entry in msmd.unk_foliage_data() {
+        entry.unwrap();
     }
 
-    for (i, entry) in msmd.map_vertex_data.iter().enumerate() {
-        match entry.extract(&mut reader, compressed) {
+    for (i, entry) in msmd.msmd.map_vertex_data.clone().iter().enumerate() {
+        match msmd.extract(entry) {
             Ok(vertex_data) => {
-                let original_bytes = entry.decompress(&mut reader, compressed).unwrap();
+                let original_bytes = msmd.decompress(entry).unwrap();
                 check_vertex_data(vertex_data, path, &original_bytes, check_read_write);
             }
             Err(e) => println!("Error extracting map VertexData {i} in {path:?}: {e}"),
@@ -573,6 +601,25 @@ fn check_mibl(mibl: Mibl, path: &Path, original_bytes: &[u8], check_read_write:
         println!("Mibl/DDS conversion not 1:1 for {path:?}");
     }
 
+    // Surface conversions should preserve the image format like R8 or BC4 for masks.
+    let new_mibl = Mibl::from_surface(mibl.to_surface().unwrap()).unwrap();
+    if mibl != new_mibl {
+        println!("Mibl/Surface conversion not 1:1 for {path:?}");
+    }
+
+    // Removing mipmaps should preserve the base mip level.
+    let base_mip = mibl.without_mipmaps().unwrap().to_surface().unwrap();
+    let surface = mibl.to_surface().unwrap();
+    if base_mip.get(0, 0, 0) != surface.get(0, 0, 0) {
+        println!("Mibl base mip not preserved without mipmaps for {path:?}");
+    }
+
+    // Cube maps like k_metal_env.witex and volume textures should keep all layers and depth slices.
+    let image = xc3_model::ImageTexture::from_mibl(&mibl, None, None).unwrap();
+    if image.to_surface() != surface.as_ref() {
+        println!("Mibl/ImageTexture conversion not 1:1 for {path:?}");
+    }
+
     if check_read_write && !write_le_bytes_equals(&mibl, original_bytes) {
         println!("Mibl read/write not 1:1 for {path:?}");
     }
@@ -657,6 +704,15 @@ enum Wimdo {
 }
 
 fn check_wimdo(data: Wimdo, path: &Path, original_bytes: &[u8], check_read_write: bool) {
+    match Mxmd::peek_version(&mut Cursor::new(original_bytes)) {
+        Ok(info) => {
+            if info.is_apmd != matches!(data, Wimdo::Apmd(_)) {
+                println!("Inconsistent Mxmd::peek_version Apmd for {path:?}");
+            }
+        }
+        Err(e) => println!("Error peeking Mxmd version for {path:?}: {e}"),
+    }
+
     match data {
         Wimdo::Mxmd(mxmd) => {
             check_mxmd(*mxmd, path, original_bytes, check_read_write);
@@ -687,6 +743,16 @@ fn check_wimdo(data: Wimdo, path: &Path, original_bytes: &[u8], check_read_write
 }
 
 fn check_mxmd(mxmd: Mxmd, path: &Path, original_bytes: &[u8], check_read_write: bool) {
+    let version = Mxmd::peek_version(&mut Cursor::new(original_bytes)).map(|i| i.version);
+    let expected = match mxmd.version {
+        10111 => Some(MxmdVersion::V111),
+        10112 => Some(MxmdVersion::V112),
+        _ => None,
+    };
+    if version.ok() != expected {
+        println!("Inconsistent Mxmd::peek_version version for {path:?}");
+    }
+
     if !is_valid_models_flags(&mxmd) {
         println!("Inconsistent ModelsFlags for {path:?}");
     }
@@ -742,6 +808,13 @@ fn check_spch(spch: Spch, path: &Path, original_bytes: &[u8], check_read_write:
 }
 
 fn check_ltpc(ltpc: Ltpc, path: &Path, original_bytes: &[u8], check_read_write: bool) {
+    for (i, texture) in ltpc.textures.iter().enumerate() {
+        match texture.to_mibl() {
+            Ok(mibl) => check_mibl(mibl, path, &texture.mibl_data, check_read_write),
+            Err(e) => println!("Error reading Mibl {i} for {path:?}: {e}"),
+        }
+    }
+
     if check_read_write && !write_le_bytes_equals(&ltpc, original_bytes) {
         println!("Ltpc read/write not 1:1 for {path:?}");
     }
@@ -847,29 +920,21 @@ fn check_beb(beb: Beb, path: &Path, original_bytes: &[u8], check_read_write: boo
         println!("Beb read/write not 1:1 for {path:?}");
     }
 
-    for (i, offset) in beb.xbc1_offsets.iter().enumerate() {
-        match offset.value.decompress() {
-            Ok(bytes) => match BebData::read_le(&mut Cursor::new(&bytes)) {
-                Ok(data) => {
-                    for (offset, size) in data.offsets.iter().zip(&data.lengths) {
-                        // Skip the 4 floats at the start of each entry.
-                        let start = *offset as usize + 16;
-                        let entry_bytes = &bytes[start..start + *size as usize];
-                        // TODO: detect item type?
-                        if entry_bytes.get(..4) == Some(b"BC\x00\x00") {
-                            match Bc::from_bytes(entry_bytes) {
-                                Ok(bc) => check_bc(bc, path, entry_bytes, check_read_write),
-                                Err(e) => {
-                                    println!("Error reading BC in archive {i} in {path:?}: {e}")
-                                }
-                            }
-                        }
+    match beb.extract_entries() {
+        Ok(entries) => {
+            for entry in entries {
+                if entry.entry_type == BebEntryType::Bc {
+                    match Bc::from_bytes(&entry.data) {
+                        Ok(bc) => check_bc(bc, path, &entry.data, check_read_write),
+                        Err(e) => println!(
+                            "Error reading BC in archive {} in {path:?}: {e}",
+                            entry.archive_index
+                        ),
                     }
                 }
-                Err(e) => println!("Error reading data in archive {i} in {path:?}: {e}"),
-            },
-            Err(e) => println!("Error decompressing archive {i} in {path:?}: {e}"),
+            }
         }
+        Err(e) => println!("Error extracting entries in {path:?}: {e}"),
     }
 }
 
@@ -885,6 +950,18 @@ fn check_efb0(efb0: Efb0, path: &Path, original_bytes: &[u8], check_read_write:
     }
 }
 
+fn check_wipac(wipac: Wipac, path: &Path, original_bytes: &[u8], check_read_write: bool) {
+    for (name, cmp) in wipac.entries() {
+        if let Err(e) = cmp.decompress() {
+            println!("Error decompressing {name:?} for {path:?}: {e}");
+        }
+    }
+
+    if check_read_write && !write_le_bytes_equals(&wipac, original_bytes) {
+        println!("Wipac read/write not 1:1 for {path:?}");
+    }
+}
+
 fn check_idcm(idcm: Idcm, path: &Path, original_bytes: &[u8], check_read_write: bool) {
     if check_read_write && !write_le_bytes_equals(&idcm, original_bytes) {
         println!("Idcm read/write not 1:1 for {path:?}");
@@ -897,6 +974,29 @@ fn check_mxmd_legacy(
     _original_bytes: &[u8],
     check_read_write: bool,
 ) {
+    if check_read_write {
+        // TODO: Check that read/write is 1:1 once the data layout is known.
+        // Check that writing is stable since buffers may be placed differently than in game.
+        let mut writer = Cursor::new(Vec::new());
+        match mxmd.write(&mut writer) {
+            Ok(()) => {
+                let bytes = writer.into_inner();
+                match MxmdLegacy::from_bytes(&bytes) {
+                    Ok(new_mxmd) => {
+                        if new_mxmd != mxmd {
+                            println!("MxmdLegacy read/write not equivalent for {path:?}");
+                        }
+                        if !write_be_bytes_equals(&new_mxmd, &bytes) {
+                            println!("MxmdLegacy write/read/write not 1:1 for {path:?}");
+                        }
+                    }
+                    Err(e) => println!("Error reading written MxmdLegacy for {path:?}: {e}"),
+                }
+            }
+            Err(e) => println!("Error writing MxmdLegacy for {path:?}: {e}"),
+        }
+    }
+
     if let Some(textures) = mxmd.packed_textures {
         for texture in textures.textures {
             match Mtxt::from_bytes(&texture.mtxt_data) {
@@ -915,7 +1015,6 @@ fn check_mxmd_legacy(
         }
     }
 
-    // TODO: check read/write for camdo?
     // TODO: Also test loading casmt data?
 
     for buffer in mxmd.vertex.vertex_buffers {
@@ -934,6 +1033,18 @@ fn check_mths(mths: Mths, path: &Path, _original_bytes: &[u8], _check_read_write
     if let Err(e) = mths.fragment_shader() {
         println!("Error reading fragment shader in {path:?}: {e}")
     }
+
+    // Check that every instruction and source in real shaders has a known encoding.
+    match mths.disassemble() {
+        Ok((vertex, fragment)) => {
+            for (name, text) in [("vertex", vertex), ("fragment", fragment)] {
+                for line in text.lines().filter(|l| l.contains("UNKNOWN_")) {
+                    println!("Unknown {name} instruction in {path:?}: {}", line.trim());
+                }
+            }
+        }
+        Err(e) => println!("Error disassembling shaders in {path:?}: {e}"),
+    }
 }
 
 fn check_mtxt(mtxt: Mtxt, path: &Path, original_bytes: &[u8], check_read_write: bool) {
@@ -1062,6 +1173,7 @@ fn check_all_gltf<P: AsRef<Path>>(root: P) {
                         &[],
                         &shader_textures,
                         false,
+                        false,
                     ) {
                         println!("Error converting {path:?}: {e}");
                     }
@@ -1084,6 +1196,7 @@ fn check_all_gltf<P: AsRef<Path>>(root: P) {
                         &[],
                         &shader_textures,
                         true,
+                        false,
                     ) {
                         println!("Error converting {path:?}: {e}");
                     }
@@ -1139,6 +1252,10 @@ fn check_all_wimdo_model<P: AsRef<Path>>(root: P, check_read_write: bool) {
 
                     match ModelRoot::from_mxmd_model(&mxmd, skel, &streaming_data, None) {
                         Ok(root) => {
+                            if let Err(e) = root.validate() {
+                                println!("Invalid model for {path:?}: {e}");
+                            }
+
                             if check_read_write {
                                 check_model(root, &mxmd, &streaming_data.vertex, path);
                             }
@@ -1241,9 +1358,9 @@ where
     T: Xc3Write + 'static,
     for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
 {
-    let mut writer = Cursor::new(Vec::new());
-    xc3_write::write_full(value, &mut writer, 0, &mut 0, xc3_write::Endian::Little, ()).unwrap();
-    writer.into_inner() == original_bytes
+    let result = xc3_lib::testing::write_eq(value, original_bytes, binrw::Endian::Little);
+    print_roundtrip_result(&result);
+    result.is_equal()
 }
 
 fn write_be_bytes_equals<T>(value: &T, original_bytes: &[u8]) -> bool
@@ -1251,7 +1368,16 @@ where
     T: Xc3Write + 'static,
     for<'a> T::Offsets<'a>: Xc3WriteOffsets<Args = ()>,
 {
-    let mut writer = Cursor::new(Vec::new());
-    xc3_write::write_full(value, &mut writer, 0, &mut 0, xc3_write::Endian::Big, ()).unwrap();
-    writer.into_inner() == original_bytes
+    let result = xc3_lib::testing::write_eq(value, original_bytes, binrw::Endian::Big);
+    print_roundtrip_result(&result);
+    result.is_equal()
+}
+
+fn print_roundtrip_result(result: &xc3_lib::testing::RoundtripResult) {
+    match result {
+        xc3_lib::testing::RoundtripResult::Equal => (),
+        xc3_lib::testing::RoundtripResult::Mismatch(mismatch) => println!("{mismatch}"),
+        xc3_lib::testing::RoundtripResult::ReadError(e) => println!("Error reading: {e}"),
+        xc3_lib::testing::RoundtripResult::WriteError(e) => println!("Error writing: {e}"),
+    }
 }
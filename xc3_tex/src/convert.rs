@@ -1,4 +1,7 @@
-use std::{io::Cursor, path::Path};
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{anyhow, Context};
 use binrw::BinRead;
@@ -10,13 +13,15 @@ use image_dds::{
 use rayon::prelude::*;
 use xc3_lib::{
     bmn::Bmn,
-    dds::DdsExt,
+    dds::{dds_from_surface, DdsExt},
     dhal::Dhal,
     fnt::Fnt,
     laft::Laft,
     lagp::Lagp,
     laps::Laps,
+    ltpc::Ltpc,
     mibl::Mibl,
+    msmd::{Msmd, MsmdFile},
     msrd::{
         streaming::{chr_tex_nx_folder, ExtractedTexture},
         Msrd,
@@ -38,6 +43,8 @@ pub enum File {
     Wimdo(Box<Mxmd>),
     Camdo(Box<MxmdLegacy>),
     Bmn(Bmn),
+    Wiltp(Ltpc),
+    Wismhd(Box<Msmd>),
     Wifnt(MaybeXbc1<Laft>),
     XcxFnt(Fnt),
 }
@@ -79,9 +86,8 @@ impl File {
                 // Handle changes in image format while preserving layers and mipmaps.
                 // TODO: dds doesn't implement clone?
                 match format {
-                    Some(format) => Surface::from_dds(dds)?
-                        .decode_rgba8()?
-                        .encode(
+                    Some(format) => {
+                        dds_from_surface(&Surface::from_dds(dds)?.decode_rgba8()?.encode(
                             format,
                             quality.unwrap_or(Quality::Normal),
                             if mipmaps {
@@ -89,44 +95,13 @@ impl File {
                             } else {
                                 Mipmaps::Disabled
                             },
-                        )?
-                        .to_dds()
-                        .with_context(|| "failed to convert surface to DDS"),
+                        )?)
+                        .with_context(|| "failed to convert surface to DDS")
+                    }
                     None => Ok(clone_dds(dds)),
                 }
             }
-            File::Image(image) => {
-                let format =
-                    format.ok_or(anyhow::anyhow!("missing required image output format"))?;
-                let quality = quality.unwrap_or(Quality::Normal);
-                let mipmaps = if mipmaps {
-                    Mipmaps::GeneratedAutomatic
-                } else {
-                    Mipmaps::Disabled
-                };
-
-                if cube {
-                    // Assume a square image.
-                    image_dds::SurfaceRgba8::from_image_layers(
-                        image,
-                        image.height() / image.width(),
-                    )
-                    .encode(format, quality, mipmaps)
-                    .with_context(|| "failed to encode image to DDS")?
-                    .to_dds()
-                    .with_context(|| "failed to create DDS")
-                } else if depth {
-                    // Assume a square image.
-                    image_dds::SurfaceRgba8::from_image_depth(image, image.height() / image.width())
-                        .encode(format, quality, mipmaps)
-                        .with_context(|| "failed to encode image to DDS")?
-                        .to_dds()
-                        .with_context(|| "failed to create DDS")
-                } else {
-                    image_dds::dds_from_image(image, format, quality, mipmaps)
-                        .with_context(|| "failed to encode image to DDS")
-                }
-            }
+            File::Image(image) => image_to_dds(image, format, quality, mipmaps, cube, depth),
             File::Wilay(_) => Err(anyhow::anyhow!(
                 "wilay textures must be saved to an output folder instead of a single image"
             )),
@@ -139,6 +114,12 @@ impl File {
             File::Bmn(_) => Err(anyhow::anyhow!(
                 "bmn textures must be saved to an output folder instead of a single image"
             )),
+            File::Wiltp(_) => Err(anyhow::anyhow!(
+                "wiltp textures must be saved to an output folder instead of a single image"
+            )),
+            File::Wismhd(_) => Err(anyhow::anyhow!(
+                "wismhd textures must be saved to an output folder instead of a single image"
+            )),
         }
     }
 
@@ -147,6 +128,8 @@ impl File {
         format: Option<ImageFormat>,
         quality: Option<Quality>,
         mipmaps: bool,
+        cube: bool,
+        depth: bool,
     ) -> anyhow::Result<Mibl> {
         // TODO: decode and encode again if needed.
         match self {
@@ -158,18 +141,7 @@ impl File {
                 .with_context(|| "failed to convert Fnt to Mibl"),
             File::Dds(dds) => Mibl::from_dds(dds).with_context(|| "failed to create Mibl from DDS"),
             File::Image(image) => {
-                let dds = image_dds::dds_from_image(
-                    image,
-                    format.ok_or(anyhow::anyhow!("missing required image output format"))?,
-                    quality.unwrap_or(Quality::Normal),
-                    if mipmaps {
-                        Mipmaps::GeneratedAutomatic
-                    } else {
-                        Mipmaps::Disabled
-                    },
-                )
-                .with_context(|| "failed to create encode image to DDS")?;
-
+                let dds = image_to_dds(image, format, quality, mipmaps, cube, depth)?;
                 Mibl::from_dds(&dds)
                     .with_context(|| "failed to create Mibl from image encoded to DDS")
             }
@@ -185,6 +157,12 @@ impl File {
             File::Bmn(_) => Err(anyhow::anyhow!(
                 "bmn textures must be saved to an output folder instead of a single image"
             )),
+            File::Wiltp(_) => Err(anyhow::anyhow!(
+                "wiltp textures must be saved to an output folder instead of a single image"
+            )),
+            File::Wismhd(_) => Err(anyhow::anyhow!(
+                "wismhd textures must be saved to an output folder instead of a single image"
+            )),
         }
     }
 
@@ -214,17 +192,59 @@ impl File {
             File::Bmn(_) => Err(anyhow::anyhow!(
                 "bmn textures must be saved to an output folder instead of a single image"
             )),
+            File::Wiltp(_) => Err(anyhow::anyhow!(
+                "wiltp textures must be saved to an output folder instead of a single image"
+            )),
+            File::Wismhd(_) => Err(anyhow::anyhow!(
+                "wismhd textures must be saved to an output folder instead of a single image"
+            )),
         }
     }
 }
 
+fn image_to_dds(
+    image: &RgbaImage,
+    format: Option<ImageFormat>,
+    quality: Option<Quality>,
+    mipmaps: bool,
+    cube: bool,
+    depth: bool,
+) -> anyhow::Result<Dds> {
+    let format = format.ok_or(anyhow::anyhow!("missing required image output format"))?;
+    let quality = quality.unwrap_or(Quality::Normal);
+    let mipmaps = if mipmaps {
+        Mipmaps::GeneratedAutomatic
+    } else {
+        Mipmaps::Disabled
+    };
+
+    if cube {
+        // Assume a square image.
+        let surface =
+            image_dds::SurfaceRgba8::from_image_layers(image, image.height() / image.width())
+                .encode(format, quality, mipmaps)
+                .with_context(|| "failed to encode image to DDS")?;
+        dds_from_surface(&surface).with_context(|| "failed to create DDS")
+    } else if depth {
+        // Assume a square image.
+        let surface =
+            image_dds::SurfaceRgba8::from_image_depth(image, image.height() / image.width())
+                .encode(format, quality, mipmaps)
+                .with_context(|| "failed to encode image to DDS")?;
+        dds_from_surface(&surface).with_context(|| "failed to create DDS")
+    } else {
+        image_dds::dds_from_image(image, format, quality, mipmaps)
+            .with_context(|| "failed to encode image to DDS")
+    }
+}
+
 pub fn update_wilay_from_folder(
     input: &str,
     input_folder: &str,
     output: &str,
 ) -> anyhow::Result<usize> {
     // Replace existing images in a .wilay file.
-    // LAPS files have no images to replace.
+    // LAPS files have no images to replace but should still be saved to the output.
     // TODO: Error if indices are out of range?
     let mut wilay = MaybeXbc1::<Wilay>::from_file(input)?;
     let mut count = 0;
@@ -238,7 +258,7 @@ pub fn update_wilay_from_folder(
                 replace_lagp_textures(lagp, &mut count, input, input_folder)?;
                 lagp.save(output)?;
             }
-            Wilay::Laps(_) => (),
+            Wilay::Laps(laps) => laps.save(output)?,
         },
         MaybeXbc1::Xbc1(xbc1) => {
             let mut wilay: Wilay = xbc1.extract()?;
@@ -253,7 +273,10 @@ pub fn update_wilay_from_folder(
                     let xbc1 = Xbc1::new(xbc1.name.clone(), lagp, CompressionType::Zlib)?;
                     xbc1.save(output)?;
                 }
-                Wilay::Laps(_) => (),
+                Wilay::Laps(laps) => {
+                    let xbc1 = Xbc1::new(xbc1.name.clone(), laps, CompressionType::Zlib)?;
+                    xbc1.save(output)?;
+                }
             }
         }
     }
@@ -337,56 +360,127 @@ fn replace_wilay_jpeg(
     Ok(count)
 }
 
+/// A replacement image in the folder for [update_wimdo_from_folder].
+pub struct WimdoFolderImage {
+    pub path: PathBuf,
+    /// The texture index from the file name or [None] if the file is ignored.
+    pub index: Option<usize>,
+    /// The error converting the image or [None] if the image can be used.
+    pub error: Option<anyhow::Error>,
+}
+
+impl WimdoFolderImage {
+    /// Returns `true` if this image will replace the texture at [index](#structfield.index).
+    pub fn is_used(&self) -> bool {
+        self.index.is_some() && self.error.is_none()
+    }
+}
+
+/// The results of validating the images for [update_wimdo_from_folder] with [check_wimdo_folder].
+pub struct WimdoFolderSummary {
+    /// The number of textures in the original model.
+    pub original_texture_count: usize,
+    /// All files in the folder sorted by path.
+    pub images: Vec<WimdoFolderImage>,
+    /// Problems with the images that would prevent creating the output.
+    pub errors: Vec<anyhow::Error>,
+    /// Differences from the original textures like a changed format or size.
+    /// These don't prevent creating the output but may not be intended.
+    pub warnings: Vec<anyhow::Error>,
+}
+
+/// Run the validation for [update_wimdo_from_folder] without writing any files.
+pub fn check_wimdo_folder(
+    input: &str,
+    input_folder: &str,
+    chr_tex_nx: Option<String>,
+) -> anyhow::Result<WimdoFolderSummary> {
+    let wimdo = read_wimdo(input, chr_tex_nx)?;
+
+    let images = read_folder_images(input, input_folder)?;
+    let errors = folder_image_errors(&images);
+    let warnings = original_texture_warnings(&images, &wimdo.textures);
+
+    Ok(WimdoFolderSummary {
+        original_texture_count: wimdo.textures.len(),
+        images: images.into_iter().map(|(image, _)| image).collect(),
+        errors,
+        warnings,
+    })
+}
+
+fn original_texture_warnings(
+    images: &[(WimdoFolderImage, Option<Mibl>)],
+    textures: &[ExtractedTexture<Mibl, xc3_lib::mxmd::TextureUsage>],
+) -> Vec<anyhow::Error> {
+    images
+        .iter()
+        .filter_map(|(image, mibl)| Some((&image.path, image.index?, mibl.as_ref()?)))
+        .filter_map(|(path, i, mibl)| {
+            let Some(texture) = textures.get(i) else {
+                return Some(anyhow!(
+                    "{path:?} adds texture {i} not in the original model"
+                ));
+            };
+
+            // The base mip level doubles the dimensions of the high texture.
+            let (original, scale) = match &texture.high {
+                Some(high) => (&high.mid, if high.base_mip.is_some() { 2 } else { 1 }),
+                None => (&texture.low, 1),
+            };
+            let size = (mibl.footer.width, mibl.footer.height);
+            let original_size = (
+                original.footer.width * scale,
+                original.footer.height * scale,
+            );
+
+            if mibl.footer.image_format != original.footer.image_format {
+                Some(anyhow!(
+                    "{path:?} has format {:?} but texture {i} has format {:?}",
+                    mibl.footer.image_format,
+                    original.footer.image_format
+                ))
+            } else if size != original_size {
+                Some(anyhow!(
+                    "{path:?} has size {}x{} but texture {i} has size {}x{}",
+                    size.0,
+                    size.1,
+                    original_size.0,
+                    original_size.1
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
 pub fn update_wimdo_from_folder(
     input: &str,
     input_folder: &str,
     output: &str,
     chr_tex_nx: Option<String>,
 ) -> anyhow::Result<usize> {
-    let input_path = Path::new(input);
     let output_path = Path::new(output);
 
-    // TODO: Error if indices are out of range?
-    // TODO: avoid duplicating logic with xc3_model?
-    let mut mxmd =
-        Mxmd::from_file(input).with_context(|| format!("{input:?} is not a valid wimdo file"))?;
-
-    let uses_chr = has_chr_textures(&mxmd);
-
-    let chr_tex_nx_input = chr_tex_nx_folder(input_path).or(chr_tex_nx.map(Into::into));
-    if uses_chr && chr_tex_nx_input.is_none() {
-        panic!(
-            "chr/tex/nx folder required by wimdo and wismt but cannot be inferred from input path"
-        );
-    }
-
-    // We need to repack the entire wismt even though we only modify textures.
-    let msrd = Msrd::from_file(input_path.with_extension("wismt"))?;
-    let (vertex, spch, mut textures) = msrd.extract_files(chr_tex_nx_input.as_deref())?;
+    let WimdoFiles {
+        mut mxmd,
+        vertex,
+        spch,
+        textures,
+        uses_chr,
+    } = read_wimdo(input, chr_tex_nx)?;
 
     // Replace all textures to support adding or deleting textures.
-    let mut mibls: Vec<_> = std::fs::read_dir(input_folder)?
-        .filter_map(|e| {
-            let path = e.unwrap().path();
-            image_index(&path, input).and_then(|i| {
-                let dds = Dds::from_file(path).ok()?;
-                let mibl = Mibl::from_dds(&dds).unwrap();
-                Some((i, mibl))
-            })
-        })
-        .collect();
-    mibls.sort_by_key(|(i, _)| *i);
-
-    // Check if all indices in 0..N are used.
-    for (i, (index, _)) in mibls.iter().enumerate() {
-        if i != *index {
-            return Err(anyhow!("Found image index {index} but expected {i}"));
-        }
+    let images = read_folder_images(input, input_folder)?;
+    if let Some(e) = folder_image_errors(&images).into_iter().next() {
+        return Err(e);
     }
 
     // TODO: Also extract the name.
-    textures = mibls
+    let textures: Vec<_> = images
         .into_iter()
+        .filter_map(|(image, mibl)| Some((image.index?, mibl?)))
         .map(|(i, mibl)| {
             ExtractedTexture::from_mibl(
                 &mibl,
@@ -409,6 +503,104 @@ pub fn update_wimdo_from_folder(
     Ok(textures.len())
 }
 
+struct WimdoFiles {
+    mxmd: Mxmd,
+    vertex: xc3_lib::vertex::VertexData,
+    spch: xc3_lib::spch::Spch,
+    textures: Vec<ExtractedTexture<Mibl, xc3_lib::mxmd::TextureUsage>>,
+    uses_chr: bool,
+}
+
+fn read_wimdo(input: &str, chr_tex_nx: Option<String>) -> anyhow::Result<WimdoFiles> {
+    let input_path = Path::new(input);
+
+    // TODO: Error if indices are out of range?
+    // TODO: avoid duplicating logic with xc3_model?
+    let mxmd =
+        Mxmd::from_file(input).with_context(|| format!("{input:?} is not a valid wimdo file"))?;
+
+    let uses_chr = has_chr_textures(&mxmd);
+
+    let chr_tex_nx_input = chr_tex_nx_folder(input_path).or(chr_tex_nx.map(Into::into));
+    if uses_chr && chr_tex_nx_input.is_none() {
+        panic!(
+            "chr/tex/nx folder required by wimdo and wismt but cannot be inferred from input path"
+        );
+    }
+
+    // We need to repack the entire wismt even though we only modify textures.
+    let msrd = Msrd::from_file(input_path.with_extension("wismt"))?;
+    let (vertex, spch, textures) = msrd.extract_files(chr_tex_nx_input.as_deref())?;
+
+    Ok(WimdoFiles {
+        mxmd,
+        vertex,
+        spch,
+        textures,
+        uses_chr,
+    })
+}
+
+fn read_folder_images(
+    input: &str,
+    input_folder: &str,
+) -> anyhow::Result<Vec<(WimdoFolderImage, Option<Mibl>)>> {
+    let mut paths = std::fs::read_dir(input_folder)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let is_dds = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.eq_ignore_ascii_case("dds"))
+                .unwrap_or_default();
+            let index = is_dds.then(|| image_index(&path, input)).flatten();
+
+            let (mibl, error) = match index {
+                Some(_) => match read_mibl(&path) {
+                    Ok(mibl) => (Some(mibl), None),
+                    Err(e) => (None, Some(e)),
+                },
+                None => (None, None),
+            };
+
+            (WimdoFolderImage { path, index, error }, mibl)
+        })
+        .collect())
+}
+
+fn read_mibl(path: &Path) -> anyhow::Result<Mibl> {
+    let dds = Dds::from_file(path).with_context(|| format!("{path:?} is not a valid DDS file"))?;
+    Mibl::from_dds(&dds).with_context(|| format!("failed to convert {path:?} to Mibl"))
+}
+
+fn folder_image_errors(images: &[(WimdoFolderImage, Option<Mibl>)]) -> Vec<anyhow::Error> {
+    let mut errors: Vec<_> = images
+        .iter()
+        .filter_map(|(image, _)| image.error.as_ref().map(|e| anyhow!("{e:#}")))
+        .collect();
+
+    // Check if all indices in 0..N are used.
+    let mut indices: Vec<_> = images
+        .iter()
+        .filter(|(image, _)| image.is_used())
+        .filter_map(|(image, _)| image.index)
+        .collect();
+    indices.sort();
+    for (i, index) in indices.iter().enumerate() {
+        if i != *index {
+            errors.push(anyhow!("Found image index {index} but expected {i}"));
+            break;
+        }
+    }
+
+    errors
+}
+
 fn has_chr_textures(mxmd: &Mxmd) -> bool {
     // Some Xenoblade 3 models still require empty chr/tex/nx data even if disabled by flags.
     // Check the offset instead of flags to be safe.
@@ -672,6 +864,89 @@ pub fn extract_bmn_images_to_folder(
     Ok(textures.len())
 }
 
+pub fn extract_wiltp_to_folder(
+    ltpc: Ltpc,
+    input: &Path,
+    output_folder: &Path,
+) -> anyhow::Result<usize> {
+    let textures = extract_wiltp_textures(ltpc)?;
+    let file_name = input.file_name().unwrap();
+    save_unnamed_dds(&textures, output_folder, file_name)?;
+    Ok(textures.len())
+}
+
+pub fn extract_wiltp_images_to_folder(
+    ltpc: Ltpc,
+    input: &Path,
+    output_folder: &Path,
+    ext: &str,
+) -> anyhow::Result<usize> {
+    let textures = extract_wiltp_textures(ltpc)?;
+    let file_name = input.file_name().unwrap();
+    save_unnamed_dds_images(&textures, output_folder, file_name, ext)?;
+    Ok(textures.len())
+}
+
+pub fn extract_wismhd_to_folder(
+    msmd: Msmd,
+    input: &Path,
+    output_folder: &Path,
+) -> anyhow::Result<usize> {
+    let textures = extract_wismhd_textures(msmd, input)?;
+    let file_name = input.file_name().unwrap();
+    save_unnamed_dds(&textures, output_folder, file_name)?;
+    Ok(textures.len())
+}
+
+pub fn extract_wismhd_images_to_folder(
+    msmd: Msmd,
+    input: &Path,
+    output_folder: &Path,
+    ext: &str,
+) -> anyhow::Result<usize> {
+    let textures = extract_wismhd_textures(msmd, input)?;
+    let file_name = input.file_name().unwrap();
+    save_unnamed_dds_images(&textures, output_folder, file_name, ext)?;
+    Ok(textures.len())
+}
+
+fn extract_wismhd_textures(msmd: Msmd, input: &Path) -> anyhow::Result<Vec<Dds>> {
+    let wismda_path = input.with_extension("wismda");
+    let wismda = std::fs::File::open(&wismda_path)
+        .with_context(|| format!("failed to open {wismda_path:?} required for .wismhd textures"))?;
+    let mut msmd = MsmdFile::new(msmd, std::io::BufReader::new(wismda));
+
+    // High resolution textures use a separate base mip level.
+    let mut result = Vec::new();
+    for texture in msmd.msmd.textures.clone() {
+        let mid = msmd.extract(&texture.mid)?;
+        let surface = if texture.base_mip.decompressed_size > 0 {
+            let base_mip_level = msmd.decompress(&texture.base_mip)?;
+            mid.to_surface_with_base_mip(&base_mip_level)?
+        } else {
+            mid.to_surface()?
+        };
+        result.push(surface.to_dds()?);
+    }
+
+    // Save low resolution textures after the high resolution textures.
+    let low_textures: Vec<_> = msmd.low_textures().collect::<Result<_, _>>()?;
+    for textures in low_textures {
+        for texture in textures.textures {
+            result.push(Mibl::from_bytes(&texture.mibl_data)?.to_dds()?);
+        }
+    }
+
+    Ok(result)
+}
+
+fn extract_wiltp_textures(ltpc: Ltpc) -> anyhow::Result<Vec<Dds>> {
+    ltpc.textures
+        .iter()
+        .map(|texture| texture.to_mibl()?.to_dds().map_err(Into::into))
+        .collect()
+}
+
 fn extract_bmn_textures(bmn: Bmn) -> anyhow::Result<Vec<Dds>> {
     let mut result = Vec::new();
     if let Some(unk16) = bmn.unk16 {
@@ -718,35 +993,77 @@ pub fn create_wismt_single_tex(mibl: &Mibl) -> anyhow::Result<Xbc1> {
     Xbc1::new("middle.witx".to_string(), mibl, CompressionType::Zlib).map_err(Into::into)
 }
 
+/// The results of converting files with [batch_convert_files].
+pub struct BatchConvertSummary {
+    /// The number of files converted successfully.
+    pub converted: usize,
+    /// The path and error for each file that failed to convert sorted by path.
+    pub failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Convert all files matching `pattern` in `input_folder` recursively.
+///
+/// Errors for individual files are collected in the summary unless `fail_fast` is `true`.
+/// In that case, the first error is returned and no further files are converted.
 pub fn batch_convert_files(
     input_folder: &str,
     pattern: &str,
     ext: Option<&str>,
-) -> anyhow::Result<usize> {
-    // TODO: properly count converted files.
+    fail_fast: bool,
+) -> anyhow::Result<BatchConvertSummary> {
     let ext = ext.unwrap_or("png");
-    Ok(
-        globwalk::GlobWalkerBuilder::from_patterns(input_folder, &[pattern])
-            .build()
-            .unwrap()
+    let walker = globwalk::GlobWalkerBuilder::from_patterns(input_folder, &[pattern]).build()?;
+
+    if fail_fast {
+        let converted = walker
             .par_bridge()
             .map(|entry| {
-                // TODO: Avoid unwrap?
-                let path = entry.as_ref().unwrap().path();
-                let file = load_input_file(path).unwrap();
-                match ext.to_lowercase().as_str() {
-                    "dds" => {
-                        extract_and_save_dds(path, file).unwrap();
-                    }
-                    _ => {
-                        extract_and_save_image(path, file, ext).unwrap();
-                    }
-                }
+                let path = entry?.into_path();
+                convert_file(&path, ext).with_context(|| format!("failed to convert {path:?}"))?;
+                anyhow::Ok(1)
+            })
+            .try_reduce(|| 0, |a, b| Ok(a + b))?;
 
-                1
+        Ok(BatchConvertSummary {
+            converted,
+            failed: Vec::new(),
+        })
+    } else {
+        let results: Vec<_> = walker
+            .par_bridge()
+            .map(|entry| match entry {
+                Ok(entry) => {
+                    let path = entry.into_path();
+                    let result = convert_file(&path, ext);
+                    (path, result)
+                }
+                Err(e) => {
+                    let path = e
+                        .path()
+                        .map(|p| p.to_owned())
+                        .unwrap_or_else(|| PathBuf::from(input_folder));
+                    (path, Err(e.into()))
+                }
             })
-            .sum(),
-    )
+            .collect();
+
+        let converted = results.iter().filter(|(_, r)| r.is_ok()).count();
+        let mut failed: Vec<_> = results
+            .into_iter()
+            .filter_map(|(path, result)| result.err().map(|e| (path, e)))
+            .collect();
+        failed.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(BatchConvertSummary { converted, failed })
+    }
+}
+
+fn convert_file(path: &Path, ext: &str) -> anyhow::Result<()> {
+    let file = load_input_file(path)?;
+    match ext.to_lowercase().as_str() {
+        "dds" => extract_and_save_dds(path, file),
+        _ => extract_and_save_image(path, file, ext),
+    }
 }
 
 fn extract_and_save_dds(path: &Path, file: File) -> anyhow::Result<()> {
@@ -768,6 +1085,12 @@ fn extract_and_save_dds(path: &Path, file: File) -> anyhow::Result<()> {
         File::Bmn(bmn) => {
             extract_bmn_to_folder(bmn, path, path.parent().unwrap())?;
         }
+        File::Wiltp(ltpc) => {
+            extract_wiltp_to_folder(ltpc, path, path.parent().unwrap())?;
+        }
+        File::Wismhd(msmd) => {
+            extract_wismhd_to_folder(*msmd, path, path.parent().unwrap())?;
+        }
         File::Wifnt(laft) => laft_mibl(&laft)?
             .to_dds()?
             .save(path.with_extension("dds"))?,
@@ -800,6 +1123,12 @@ fn extract_and_save_image(path: &Path, file: File, ext: &str) -> anyhow::Result<
         File::Bmn(bmn) => {
             extract_bmn_images_to_folder(bmn, path, path.parent().unwrap(), ext)?;
         }
+        File::Wiltp(ltpc) => {
+            extract_wiltp_images_to_folder(ltpc, path, path.parent().unwrap(), ext)?;
+        }
+        File::Wismhd(msmd) => {
+            extract_wismhd_images_to_folder(*msmd, path, path.parent().unwrap(), ext)?;
+        }
         File::Wifnt(laft) => {
             laft_mibl(&laft)?.save_image(path.with_extension(ext))?;
         }
@@ -890,6 +1219,18 @@ fn clone_dds(dds: &Dds) -> Dds {
     }
 }
 
+/// Calculate the blue channel for a normal map with only XY channels.
+/// The alpha channel is unchanged.
+pub fn reconstruct_normal_z(image: &mut RgbaImage) {
+    for pixel in image.pixels_mut() {
+        // Remap from unorm to snorm values.
+        let x = pixel[0] as f32 / 255.0 * 2.0 - 1.0;
+        let y = pixel[1] as f32 / 255.0 * 2.0 - 1.0;
+        let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+        pixel[2] = ((z * 0.5 + 0.5) * 255.0).round() as u8;
+    }
+}
+
 pub trait SaveImageExt {
     fn save_image<P: AsRef<Path>>(&self, path: P) -> anyhow::Result<()>;
 }
@@ -949,4 +1290,251 @@ mod tests {
             image_index(Path::new("a/b/file.0.dds"), "b/c/file2.wilay")
         );
     }
+
+    #[test]
+    fn laps_wilay_extract_update() {
+        // Use a unique folder to avoid conflicts with concurrent test runs.
+        let folder = std::env::temp_dir().join(format!(
+            "xc3_tex_laps_wilay_extract_update_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&folder).unwrap();
+
+        let laps = Laps {
+            version: 10001,
+            width: 1280,
+            height: 720,
+            unk1: 0,
+            unk2: Vec::new(),
+            unk3: Vec::new(),
+            unk5: 0,
+            unk: [0; 5],
+        };
+        let input = folder.join("laps.wilay");
+        let output = folder.join("laps_out.wilay");
+        laps.save(&input).unwrap();
+
+        let wilay = MaybeXbc1::<Wilay>::from_file(&input).unwrap();
+        assert!(matches!(wilay, MaybeXbc1::Uncompressed(Wilay::Laps(_))));
+        assert_eq!(0, extract_wilay_to_folder(wilay, &input, &folder).unwrap());
+
+        assert_eq!(
+            0,
+            update_wilay_from_folder(
+                input.to_str().unwrap(),
+                folder.to_str().unwrap(),
+                output.to_str().unwrap()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            std::fs::read(&input).unwrap(),
+            std::fs::read(&output).unwrap()
+        );
+
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+
+    fn view_dimension_mibl(view_dimension: xc3_lib::mibl::ViewDimension) -> Mibl {
+        // 4x4 with 3 mipmaps and 6 faces or 4x4x4 with 3 mipmaps using 4 bytes per pixel.
+        let (depth, layers, size) = match view_dimension {
+            xc3_lib::mibl::ViewDimension::Cube => (1, 6, 6 * (64 + 16 + 4)),
+            xc3_lib::mibl::ViewDimension::D3 => (4, 1, 4 * 64 + 2 * 16 + 4),
+            _ => unimplemented!(),
+        };
+        Mibl::from_surface(Surface {
+            width: 4,
+            height: 4,
+            depth,
+            layers,
+            mipmaps: 3,
+            image_format: ImageFormat::Rgba8Unorm,
+            data: (0..size).map(|i| i as u8).collect::<Vec<_>>(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn view_dimension_mibl_dds_round_trip() {
+        // Cube maps like monolib/shader/k_metal_env.witex
+        // and 3D textures created with --depth should be lossless.
+        for view_dimension in [
+            xc3_lib::mibl::ViewDimension::Cube,
+            xc3_lib::mibl::ViewDimension::D3,
+        ] {
+            let mibl = view_dimension_mibl(view_dimension);
+            assert_eq!(view_dimension, mibl.footer.view_dimension);
+
+            let dds = File::Mibl(mibl.clone())
+                .to_dds(None, None, true, false, false)
+                .unwrap();
+            assert_eq!(
+                view_dimension == xc3_lib::mibl::ViewDimension::D3,
+                dds.header.caps2.contains(image_dds::ddsfile::Caps2::VOLUME)
+            );
+            assert_eq!(1, dds.get_num_array_layers());
+
+            // Check the values written to the file.
+            let mut writer = std::io::Cursor::new(Vec::new());
+            dds.write(&mut writer).unwrap();
+            let dds = Dds::read(&mut std::io::Cursor::new(writer.into_inner())).unwrap();
+
+            let surface = Surface::from_dds(&dds).unwrap();
+            let original_surface = mibl.to_surface().unwrap();
+            assert_eq!(
+                (original_surface.depth, original_surface.layers),
+                (surface.depth, surface.layers)
+            );
+
+            let new_mibl = File::Dds(dds)
+                .to_mibl(None, None, true, false, false)
+                .unwrap();
+            assert_eq!(mibl, new_mibl);
+        }
+    }
+
+    #[test]
+    fn view_dimension_image_to_mibl() {
+        for (view_dimension, height, cube, depth, dimensions) in [
+            (
+                xc3_lib::mibl::ViewDimension::Cube,
+                24,
+                true,
+                false,
+                (4, 4, 1),
+            ),
+            (xc3_lib::mibl::ViewDimension::D3, 16, false, true, (4, 4, 4)),
+        ] {
+            let image = RgbaImage::new(4, height);
+            let mibl = File::Image(image)
+                .to_mibl(Some(ImageFormat::Rgba8Unorm), None, false, cube, depth)
+                .unwrap();
+            assert_eq!(view_dimension, mibl.footer.view_dimension);
+            assert_eq!(dimensions, mibl.dimensions());
+        }
+    }
+
+    #[test]
+    fn reconstruct_normal_z_pixels() {
+        let mut image = RgbaImage::from_raw(
+            3,
+            1,
+            vec![128, 128, 0, 255, 255, 128, 77, 128, 0, 0, 255, 0],
+        )
+        .unwrap();
+        reconstruct_normal_z(&mut image);
+        assert_eq!(
+            vec![128, 128, 255, 255, 255, 128, 128, 128, 0, 0, 128, 0],
+            image.into_raw()
+        );
+    }
+
+    fn folder_image(path: &str, index: Option<usize>, error: Option<&str>) -> WimdoFolderImage {
+        WimdoFolderImage {
+            path: path.into(),
+            index,
+            error: error.map(|e| anyhow!("{e}")),
+        }
+    }
+
+    #[test]
+    fn folder_image_errors_valid() {
+        let images = [
+            (folder_image("a.1.dds", Some(1), None), None),
+            (folder_image("a.0.dds", Some(0), None), None),
+            (folder_image("b.2.dds", None, None), None),
+        ];
+        assert!(folder_image_errors(&images).is_empty());
+    }
+
+    #[test]
+    fn folder_image_errors_invalid() {
+        let images = [
+            (folder_image("a.0.dds", Some(0), None), None),
+            (folder_image("a.1.dds", Some(1), Some("invalid")), None),
+            (folder_image("a.2.dds", Some(2), None), None),
+        ];
+        let errors: Vec<_> = folder_image_errors(&images)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "invalid".to_string(),
+                "Found image index 2 but expected 1".to_string()
+            ],
+            errors
+        );
+    }
+
+    fn footer_mibl(width: u32, height: u32, image_format: xc3_lib::mibl::ImageFormat) -> Mibl {
+        Mibl {
+            image_data: Vec::new(),
+            footer: xc3_lib::mibl::MiblFooter {
+                image_size: 0,
+                unk: 0x1000,
+                width,
+                height,
+                depth: 1,
+                view_dimension: xc3_lib::mibl::ViewDimension::D2,
+                image_format,
+                mipmap_count: 1,
+                version: 10001,
+            },
+        }
+    }
+
+    #[test]
+    fn original_texture_warnings_format_size() {
+        use xc3_lib::mibl::ImageFormat::{BC1Unorm, BC7Unorm};
+        use xc3_lib::msrd::streaming::HighTexture;
+
+        let texture = |high: Option<HighTexture<Mibl>>| ExtractedTexture {
+            name: String::new(),
+            usage: xc3_lib::mxmd::TextureUsage::Col,
+            low: footer_mibl(4, 4, BC7Unorm),
+            high,
+        };
+        let textures = [
+            texture(Some(HighTexture {
+                mid: footer_mibl(16, 8, BC7Unorm),
+                base_mip: Some(Vec::new()),
+            })),
+            texture(None),
+            texture(None),
+        ];
+
+        let images = [
+            (
+                folder_image("a.0.dds", Some(0), None),
+                Some(footer_mibl(32, 16, BC7Unorm)),
+            ),
+            (
+                folder_image("a.1.dds", Some(1), None),
+                Some(footer_mibl(4, 4, BC1Unorm)),
+            ),
+            (
+                folder_image("a.2.dds", Some(2), None),
+                Some(footer_mibl(8, 8, BC7Unorm)),
+            ),
+            (
+                folder_image("a.3.dds", Some(3), None),
+                Some(footer_mibl(4, 4, BC7Unorm)),
+            ),
+            (folder_image("b.4.dds", None, None), None),
+        ];
+
+        let warnings: Vec<_> = original_texture_warnings(&images, &textures)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "\"a.1.dds\" has format BC1Unorm but texture 1 has format BC7Unorm".to_string(),
+                "\"a.2.dds\" has size 8x8 but texture 2 has size 4x4".to_string(),
+                "\"a.3.dds\" adds texture 3 not in the original model".to_string(),
+            ],
+            warnings
+        );
+    }
 }
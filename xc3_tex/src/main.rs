@@ -6,9 +6,9 @@ use std::{
 use anyhow::Context;
 use clap::{builder::PossibleValuesParser, Parser, Subcommand};
 use convert::{
-    batch_convert_files, create_wismt_single_tex, extract_wilay_to_folder, extract_wimdo_to_folder,
-    read_wismt_single_tex, update_wifnt, update_wilay_from_folder, update_wimdo_from_folder, File,
-    SaveImageExt, Wilay,
+    batch_convert_files, check_wimdo_folder, create_wismt_single_tex, extract_wilay_to_folder,
+    extract_wimdo_to_folder, read_wismt_single_tex, reconstruct_normal_z, update_wifnt,
+    update_wilay_from_folder, update_wimdo_from_folder, File, SaveImageExt, Wilay,
 };
 use image_dds::{ddsfile::Dds, image, ImageFormat, Quality};
 use strum::IntoEnumIterator;
@@ -17,13 +17,18 @@ use xc3_lib::{
     dds::DdsExt,
     fnt::Fnt,
     laft::Laft,
+    ltpc::Ltpc,
     mibl::Mibl,
+    msmd::Msmd,
     mtxt::Mtxt,
     mxmd::{legacy::MxmdLegacy, Mxmd},
     xbc1::MaybeXbc1,
 };
 
-use crate::convert::{extract_bmn_to_folder, extract_camdo_to_folder};
+use crate::convert::{
+    extract_bmn_to_folder, extract_camdo_to_folder, extract_wiltp_to_folder,
+    extract_wismhd_to_folder,
+};
 
 /// Convert texture files for Xenoblade X, Xenoblade 1 DE, Xenoblade 2, and Xenoblade 3.
 #[derive(Parser)]
@@ -41,7 +46,7 @@ struct Cli {
 
 #[derive(Parser)]
 struct ConvertArgs {
-    /// The input dds, witex, witx, wimdo, wismt, camdo, catex, or calut file.
+    /// The input dds, witex, witx, wimdo, wismt, wismhd, camdo, catex, or calut file.
     /// Most uncompressed image formats like png, tiff, or jpeg are also supported.
     // TODO: how to make this required?
     input: String,
@@ -57,14 +62,18 @@ struct ConvertArgs {
     /// Don't include any mipmaps when saving as a file like dds or witex
     #[arg(long)]
     no_mipmaps: bool,
-    /// Convert a width x (height * 6) image into a square cube map.
-    /// DDS inputs should instead use the appropriate flags.
+    /// Convert a width x (height * 6) image into a square cube map. Does not apply to DDS.
+    /// DDS inputs and outputs use the cube map flags to convert cube maps without this option.
     #[arg(long)]
     cube: bool,
     /// Convert a width x (height * depth) image into a square 3D texture. Does not apply to DDS.
     /// DDS inputs should instead use the appropriate flags.
     #[arg(long)]
     depth: bool,
+    /// Reconstruct the blue channel of an XY normal map when saving as an image like png.
+    /// This calculates Z as `sqrt(1 - x^2 - y^2)` for a conventional tangent space normal map.
+    #[arg(long)]
+    reconstruct_normal_z: bool,
 }
 
 #[derive(Subcommand)]
@@ -92,6 +101,9 @@ enum Commands {
         /// Required for most Xenoblade 3 models if the folder
         /// cannot be inferred from the input path.
         chr_tex_nx: Option<String>,
+        /// Check the images and report which will be used without writing any files.
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Replace the Mibl in a .wifnt file.
     EditWifnt {
@@ -113,6 +125,9 @@ enum Commands {
         /// This also selects the file format used for saving.
         /// Defaults to "png" if not specified.
         ext: Option<String>,
+        /// Stop converting on the first error instead of skipping files that fail to convert.
+        #[arg(long)]
+        fail_fast: bool,
     },
 }
 
@@ -148,14 +163,45 @@ fn main() -> anyhow::Result<()> {
                 input_folder,
                 output,
                 chr_tex_nx,
+                dry_run,
             } => {
-                let count = update_wimdo_from_folder(
-                    &input,
-                    &input_folder,
-                    output.as_ref().unwrap_or(&input),
-                    chr_tex_nx,
-                )?;
-                println!("Converted {count} file(s) in {:?}", start.elapsed());
+                if dry_run {
+                    let summary = check_wimdo_folder(&input, &input_folder, chr_tex_nx)?;
+                    for image in &summary.images {
+                        match (image.index, image.is_used()) {
+                            (Some(i), true) => println!("{:?}: index {i} used", image.path),
+                            (Some(i), false) => println!("{:?}: index {i} not used", image.path),
+                            (None, _) => println!("{:?}: ignored", image.path),
+                        }
+                    }
+                    for w in &summary.warnings {
+                        println!("Warning: {w:#}");
+                    }
+                    for e in &summary.errors {
+                        println!("Error: {e:#}");
+                    }
+                    println!(
+                        "Checked {} image(s) for {} original texture(s) with {} error(s) in {:?}",
+                        summary.images.iter().filter(|i| i.is_used()).count(),
+                        summary.original_texture_count,
+                        summary.errors.len(),
+                        start.elapsed()
+                    );
+                    if !summary.errors.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "found {} error(s) in {input_folder:?}",
+                            summary.errors.len()
+                        ));
+                    }
+                } else {
+                    let count = update_wimdo_from_folder(
+                        &input,
+                        &input_folder,
+                        output.as_ref().unwrap_or(&input),
+                        chr_tex_nx,
+                    )?;
+                    println!("Converted {count} file(s) in {:?}", start.elapsed());
+                }
             }
             Commands::EditWifnt {
                 input,
@@ -169,9 +215,19 @@ fn main() -> anyhow::Result<()> {
                 input_folder,
                 pattern,
                 ext,
+                fail_fast,
             } => {
-                let count = batch_convert_files(&input_folder, &pattern, ext.as_deref())?;
-                println!("Converted {count} file(s) in {:?}", start.elapsed());
+                let summary =
+                    batch_convert_files(&input_folder, &pattern, ext.as_deref(), fail_fast)?;
+                for (path, e) in &summary.failed {
+                    println!("Error converting {path:?}: {e:#}");
+                }
+                println!(
+                    "Converted {} file(s) with {} failure(s) in {:?}",
+                    summary.converted,
+                    summary.failed.len(),
+                    start.elapsed()
+                );
             }
         }
     } else if let Some(args) = cli.args {
@@ -187,7 +243,9 @@ fn main() -> anyhow::Result<()> {
             .output
             .map(PathBuf::from)
             .unwrap_or_else(|| match input_file {
-                File::Wilay(_) | File::Wimdo(_) => input.parent().unwrap().to_owned(),
+                File::Wilay(_) | File::Wimdo(_) | File::Wiltp(_) | File::Wismhd(_) => {
+                    input.parent().unwrap().to_owned()
+                }
                 _ => input.with_extension("dds"),
             });
 
@@ -224,6 +282,22 @@ fn main() -> anyhow::Result<()> {
                 let count = extract_bmn_to_folder(bmn, &input, &output)?;
                 println!("Converted {count} file(s) in {:?}", start.elapsed());
             }
+            File::Wiltp(ltpc) => {
+                // wiltp contain multiple images that need to be saved.
+                std::fs::create_dir_all(&output)
+                    .with_context(|| format!("failed to create output directory {output:?}"))?;
+
+                let count = extract_wiltp_to_folder(ltpc, &input, &output)?;
+                println!("Converted {count} file(s) in {:?}", start.elapsed());
+            }
+            File::Wismhd(msmd) => {
+                // wismhd and wismda contain multiple images that need to be saved.
+                std::fs::create_dir_all(&output)
+                    .with_context(|| format!("failed to create output directory {output:?}"))?;
+
+                let count = extract_wismhd_to_folder(*msmd, &input, &output)?;
+                println!("Converted {count} file(s) in {:?}", start.elapsed());
+            }
             _ => {
                 if let Some(parent) = output.parent() {
                     std::fs::create_dir_all(parent)
@@ -246,20 +320,23 @@ fn main() -> anyhow::Result<()> {
                     }
                     "witex" | "witx" => {
                         input_file
-                            .to_mibl(format, quality, mipmaps)?
+                            .to_mibl(format, quality, mipmaps, cube, depth)?
                             .save(&output)?;
                     }
                     "wismt" => {
                         // TODO: Also create base level?
-                        let mibl = input_file.to_mibl(format, quality, mipmaps)?;
+                        let mibl = input_file.to_mibl(format, quality, mipmaps, cube, depth)?;
                         let xbc1 = create_wismt_single_tex(&mibl)?;
                         xbc1.save(&output)?;
                     }
                     // TODO: Resave xenoblade x textures?
                     _ => {
                         // Assume other formats are image formats for now.
-                        input_file
-                            .to_image()?
+                        let mut image = input_file.to_image()?;
+                        if args.reconstruct_normal_z {
+                            reconstruct_normal_z(&mut image);
+                        }
+                        image
                             .save_image(&output)
                             .with_context(|| format!("failed to save image to {output:?}"))?;
                     }
@@ -300,6 +377,20 @@ fn load_input_file(input: &Path) -> anyhow::Result<File> {
         "bmn" => Bmn::from_file(input)
             .with_context(|| format!("{input:?} is not a valid .bmn file"))
             .map(File::Bmn),
+        "wiltp" => Ltpc::from_file(input)
+            .with_context(|| format!("{input:?} is not a valid .wiltp file"))
+            .map(File::Wiltp),
+        "wismhd" => {
+            // Texture data is stored in the corresponding .wismda file.
+            let wismda = input.with_extension("wismda");
+            if !wismda.exists() {
+                anyhow::bail!("{wismda:?} is required to extract textures from {input:?}");
+            }
+            Msmd::from_file(input)
+                .with_context(|| format!("{input:?} is not a valid .wismhd file"))
+                .map(Box::new)
+                .map(File::Wismhd)
+        }
         "wifnt" => MaybeXbc1::<Laft>::from_file(input)
             .with_context(|| format!("{input:?} is not a valid .wifnt file"))
             .map(File::Wifnt),
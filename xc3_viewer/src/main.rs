@@ -38,6 +38,7 @@ struct State<'a> {
 
     renderer: Renderer,
     render_mode: RenderMode,
+    bone_index: i32,
 
     model_names: String,
     groups: Vec<ModelGroup>,
@@ -106,18 +107,14 @@ impl<'a> State<'a> {
         };
         surface.configure(&device, &config);
 
-        // TODO: Make the monolib/shader path optional?
         // Assume paths are somewhere in a full game dump.
-        let mut root_folder = Path::new(&cli.files[0]);
-        while let Some(parent) = root_folder.parent() {
-            if root_folder.join("monolib/shader").exists() {
-                break;
-            } else {
-                root_folder = parent;
-            }
-        }
-        let monolib_shader =
-            MonolibShaderTextures::from_file(&device, &queue, root_folder.join("monolib/shader"));
+        // Files outside a game dump can still render using placeholder textures.
+        let monolib_shader = Path::new(&cli.files[0])
+            .ancestors()
+            .map(|folder| folder.join("monolib/shader"))
+            .find(|path| path.exists())
+            .map(|path| MonolibShaderTextures::from_file(&device, &queue, path))
+            .unwrap_or_default();
         let mut renderer = Renderer::new(
             &device,
             &queue,
@@ -241,6 +238,7 @@ impl<'a> State<'a> {
             .join(" ");
 
         let animations = match &cli.anim {
+            Some(p) if Path::new(p).is_dir() => load_folder_animations(Path::new(p))?,
             Some(p) => load_animations(p)
                 .with_context(|| format!("{p:?} is not a valid animation file"))?,
             None => Vec::new(),
@@ -268,6 +266,7 @@ impl<'a> State<'a> {
             draw_bones: cli.bones,
             draw_bounds: cli.bounds,
             render_mode: RenderMode::Shaded,
+            bone_index: -1,
         })
     }
 
@@ -368,7 +367,44 @@ impl<'a> State<'a> {
                             "5" => self.update_debug_settings(RenderMode::GBuffer4, -1),
                             "6" => self.update_debug_settings(RenderMode::GBuffer5, -1),
                             "7" => self.update_debug_settings(RenderMode::GBuffer6, -1),
+                            "8" => {
+                                self.bone_index = -1;
+                                self.update_debug_settings(RenderMode::BoneWeights, -1);
+                            }
+                            // Cycle between the first and second texture coordinates.
+                            "9" if event.state == ElementState::Released => {
+                                let render_mode = if self.render_mode == RenderMode::TexCoord0 {
+                                    RenderMode::TexCoord1
+                                } else {
+                                    RenderMode::TexCoord0
+                                };
+                                self.update_debug_settings(render_mode, -1);
+                            }
+                            // Count overlapping fragments.
+                            "o" => self.update_debug_settings(RenderMode::Overdraw, -1),
+                            // Select the bone to display for bone weights.
+                            "[" | "]"
+                                if event.state == ElementState::Released
+                                    && self.render_mode == RenderMode::BoneWeights =>
+                            {
+                                if c.as_str() == "]" {
+                                    // Stop at the last bone for any of the skeletons.
+                                    let bone_count =
+                                        self.groups.iter().map(|g| g.bone_count()).max();
+                                    let max_index = bone_count.unwrap_or_default() as i32 - 1;
+                                    self.bone_index = (self.bone_index + 1).min(max_index);
+                                } else {
+                                    self.bone_index = (self.bone_index - 1).max(-1);
+                                }
+                                self.update_debug_settings(
+                                    RenderMode::BoneWeights,
+                                    self.bone_index,
+                                );
+                            }
                             // Debug selected color channel.
+                            // Bone weights use the channel for the bone index instead.
+                            "r" | "x" | "g" | "y" | "b" | "z" | "a" | "w"
+                                if self.render_mode == RenderMode::BoneWeights => {}
                             "r" | "x" => self.update_debug_settings(self.render_mode, 0),
                             "g" | "y" => self.update_debug_settings(self.render_mode, 1),
                             "b" | "z" => self.update_debug_settings(self.render_mode, 2),
@@ -461,13 +497,35 @@ impl<'a> State<'a> {
     }
 }
 
+fn load_folder_animations(folder: &Path) -> anyhow::Result<Vec<Animation>> {
+    let mut paths: Vec<_> = std::fs::read_dir(folder)
+        .with_context(|| format!("failed to read animation folder {folder:?}"))?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("mot"))
+        .collect();
+    // Sort by file name for consistent ordering when cycling animations.
+    paths.sort();
+
+    let mut animations = Vec::new();
+    for path in paths {
+        match load_animations(&path) {
+            Ok(anims) => animations.extend(anims),
+            Err(e) => error!("Error loading animations from {path:?}: {e}"),
+        }
+    }
+    info!("Loaded {} animations from {folder:?}", animations.len());
+    Ok(animations)
+}
+
 fn update_window_title(window: &Window, model_names: &str, anims: &[Animation], anim_index: usize) {
     if let Some(anim) = anims.get(anim_index) {
         window.set_title(&format!(
-            "{} - {} - {}",
+            "{} - {} - {} ({}/{})",
             concat!("xc3_wgpu ", env!("CARGO_PKG_VERSION")),
             model_names,
-            anim.name
+            anim.name,
+            anim_index + 1,
+            anims.len()
         ));
     } else {
         window.set_title(&format!(
@@ -478,32 +536,20 @@ fn update_window_title(window: &Window, model_names: &str, anims: &[Animation],
     }
 }
 
-// TODO: Move to xc3_wgpu?
 fn calculate_camera_data(
     size: winit::dpi::PhysicalSize<u32>,
     translation: glam::Vec3,
     rotation: glam::Vec3,
 ) -> CameraData {
-    let aspect = size.width as f32 / size.height as f32;
-
-    let view = glam::Mat4::from_translation(translation)
-        * glam::Mat4::from_rotation_x(rotation.x)
-        * glam::Mat4::from_rotation_y(rotation.y);
-
-    let projection = glam::Mat4::perspective_rh(FOV_Y, aspect, Z_NEAR, Z_FAR);
-
-    let view_projection = projection * view;
-
-    let position = view.inverse().col(3);
-
-    CameraData {
-        view,
-        projection,
-        view_projection,
-        position,
-        width: size.width,
-        height: size.height,
-    }
+    CameraData::perspective(
+        size.width,
+        size.height,
+        translation,
+        rotation,
+        FOV_Y,
+        Z_NEAR,
+        Z_FAR,
+    )
 }
 
 pub fn current_time_seconds(
@@ -533,7 +579,7 @@ struct Cli {
     /// The shader database generated by xc3_shader.
     #[arg(long)]
     database: Option<String>,
-    /// The .mot animation file.
+    /// The .mot animation file or a folder containing .mot animation files.
     #[arg(long)]
     anim: Option<String>,
     /// The BC entry index for the ANIM. Defaults to 0.
@@ -12,6 +12,8 @@
 //!
 //! Models and maps are all loaded from the same [xc3_model] types.
 //! The shader database is optional but will improve rendering accuracy.
+//! The [MonolibShaderTextures] can be empty using [MonolibShaderTextures::default]
+//! for rendering files without the game's `monolib/shader` folder.
 //!
 //! In each frame, render the [ModelGroup] using [Renderer::render_models].
 //!
@@ -57,6 +59,7 @@ pub use collision::{load_collisions, Collision};
 pub use model::{load_map, load_model, Mesh, Model, ModelBuffers, ModelGroup, Models};
 pub use monolib::MonolibShaderTextures;
 pub use renderer::{CameraData, RenderMode, Renderer};
+pub use sampler::sampler_descriptor;
 
 use encase::{internal::WriteInto, ShaderSize, ShaderType, StorageBuffer, UniformBuffer};
 use wgpu::util::DeviceExt;
@@ -67,6 +70,10 @@ const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 const GBUFFER_COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 const GBUFFER_NORMAL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgb10a2Unorm;
 const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+const PICK_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba32Uint;
+
+/// The format for the depth texture used with [Renderer::render_depth].
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
 
 /// The features required by [Renderer].
 pub const FEATURES: wgpu::Features =
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use glam::{uvec4, vec4, Mat4, UVec4, Vec3, Vec4};
 use log::{error, info};
@@ -10,7 +10,10 @@ use crate::{
     animation::animated_skinning_transforms,
     culling::is_within_frustum,
     material::{materials, Material},
-    pipeline::{ModelPipelineData, Output5Type, PipelineKey},
+    pipeline::{
+        model_depth_pipeline, model_overdraw_pipeline, model_pick_pipeline, DepthPipelineKey,
+        ModelPipelineData, Output5Type, PipelineKey,
+    },
     sampler::create_sampler,
     shader,
     texture::create_texture,
@@ -29,6 +32,9 @@ pub struct ModelGroup {
 
     // Cache pipelines by their creation parameters.
     pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    depth_pipelines: HashMap<DepthPipelineKey, wgpu::RenderPipeline>,
+    overdraw_pipelines: HashMap<DepthPipelineKey, wgpu::RenderPipeline>,
+    pick_pipelines: HashMap<DepthPipelineKey, wgpu::RenderPipeline>,
 }
 
 pub struct ModelBuffers {
@@ -68,6 +74,7 @@ impl Models {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         models: &xc3_model::Models,
+        models_index: usize,
         buffers: &[xc3_model::vertex::ModelBuffers],
         skeleton: Option<&xc3_model::Skeleton>,
         pipelines: &mut HashMap<PipelineKey, wgpu::RenderPipeline>,
@@ -115,10 +122,12 @@ impl Models {
         let models = models
             .models
             .iter()
-            .map(|model| {
+            .enumerate()
+            .map(|(model_index, model)| {
                 create_model(
                     device,
                     model,
+                    (models_index, model_index),
                     buffers,
                     &materials,
                     weights,
@@ -143,8 +152,13 @@ pub struct Model {
     pub meshes: Vec<Mesh>,
     model_buffers_index: usize,
     instances: Instances,
+    /// A bit for each mesh in [meshes](#structfield.meshes) that is set if the mesh is hidden.
+    hidden_meshes: Vec<u64>,
 }
 
+/// The `(models, model, mesh)` indices for a mesh in a [ModelGroup].
+pub(crate) type PickIndices = (usize, usize, usize);
+
 pub struct Mesh {
     vertex_buffer_index: usize,
     index_buffer_index: usize,
@@ -224,7 +238,67 @@ impl Bounds {
     }
 }
 
+impl Model {
+    /// Returns `true` if the mesh at `mesh_index` has not been hidden.
+    pub fn is_mesh_visible(&self, mesh_index: usize) -> bool {
+        self.hidden_meshes
+            .get(mesh_index / 64)
+            .map(|bits| bits & (1 << (mesh_index % 64)) == 0)
+            .unwrap_or(true)
+    }
+
+    fn set_mesh_visible(&mut self, mesh_index: usize, visible: bool) {
+        let word = mesh_index / 64;
+        let bit = 1 << (mesh_index % 64);
+        if visible {
+            if let Some(bits) = self.hidden_meshes.get_mut(word) {
+                *bits &= !bit;
+            }
+        } else {
+            if self.hidden_meshes.len() <= word {
+                self.hidden_meshes.resize(word + 1, 0);
+            }
+            self.hidden_meshes[word] |= bit;
+        }
+    }
+
+    fn visible_meshes(&self) -> impl Iterator<Item = &Mesh> {
+        self.meshes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.is_mesh_visible(*i))
+            .map(|(_, mesh)| mesh)
+    }
+}
+
 impl ModelGroup {
+    /// Show or hide the mesh at `meshes[mesh_index]` in `models[models_index].models[model_index]`.
+    /// Hidden meshes are skipped when rendering.
+    ///
+    /// Invalid indices will be ignored.
+    pub fn set_mesh_visible(
+        &mut self,
+        models_index: usize,
+        model_index: usize,
+        mesh_index: usize,
+        visible: bool,
+    ) {
+        if let Some(model) = self
+            .models
+            .get_mut(models_index)
+            .and_then(|m| m.models.get_mut(model_index))
+        {
+            if mesh_index < model.meshes.len() {
+                model.set_mesh_visible(mesh_index, visible);
+            }
+        }
+    }
+
+    /// The number of bones in the skeleton or `0` if the group has no skeleton.
+    pub fn bone_count(&self) -> usize {
+        self.bone_count
+    }
+
     /// Draw each mesh for each model.
     pub fn draw<'a>(
         &'a self,
@@ -246,7 +320,7 @@ impl ModelGroup {
         {
             // TODO: cull aabb with instance transforms.
             for model in models.models.iter() {
-                for mesh in &model.meshes {
+                for mesh in model.visible_meshes() {
                     let material = &models.materials[mesh.material_index];
 
                     // TODO: Is there a flag that controls this?
@@ -296,6 +370,125 @@ impl ModelGroup {
         }
     }
 
+    /// Draw the depth for each opaque mesh for each model.
+    pub fn draw_depth<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, camera: &CameraData) {
+        self.draw_opaque(render_pass, camera, &self.depth_pipelines);
+    }
+
+    /// Draw the [PickIndices] and depth for each opaque mesh for each model.
+    pub(crate) fn draw_pick<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: &CameraData,
+    ) {
+        self.draw_opaque(render_pass, camera, &self.pick_pipelines);
+    }
+
+    fn draw_opaque<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: &CameraData,
+        pipelines: &'a HashMap<DepthPipelineKey, wgpu::RenderPipeline>,
+    ) {
+        self.per_group.set(render_pass);
+
+        for models in self
+            .models
+            .iter()
+            .filter(|m| is_within_frustum(m.bounds.min_xyz, m.bounds.max_xyz, camera))
+        {
+            for model in &models.models {
+                for mesh in model.visible_meshes() {
+                    let material = &models.materials[mesh.material_index];
+
+                    // Only include meshes from the opaque passes that write depth.
+                    if material.pipeline_key.write_to_all_outputs()
+                        && material.pipeline_key.flags.depth_write_mode != 1
+                        && !material.pipeline_key.is_outline
+                        && !material.name.contains("_speff_")
+                        && mesh.should_render_lod(models)
+                        && matches!(
+                            mesh.flags2.render_pass(),
+                            MeshRenderPass::Unk0 | MeshRenderPass::Unk1 | MeshRenderPass::Unk4
+                        )
+                    {
+                        mesh.per_mesh.set(render_pass);
+
+                        let key = DepthPipelineKey::from(&material.pipeline_key);
+                        render_pass.set_pipeline(&pipelines[&key]);
+
+                        material.bind_group2.set(render_pass);
+
+                        let instance_count = material
+                            .fur_shell_instance_count
+                            .unwrap_or(model.instances.count);
+
+                        self.draw_mesh(
+                            model,
+                            mesh,
+                            render_pass,
+                            false,
+                            material.pipeline_key.is_instanced_static,
+                            instance_count,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw each mesh for each model with additive blending to count overlapping fragments.
+    pub fn draw_overdraw<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        camera: &CameraData,
+    ) {
+        self.per_group.set(render_pass);
+
+        for models in self
+            .models
+            .iter()
+            .filter(|m| is_within_frustum(m.bounds.min_xyz, m.bounds.max_xyz, camera))
+        {
+            for model in &models.models {
+                for mesh in model
+                    .meshes
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, m)| model.is_mesh_visible(i).then_some(m))
+                {
+                    let material = &models.materials[mesh.material_index];
+
+                    // Outlines use separate vertex shaders and buffers.
+                    if !material.pipeline_key.is_outline
+                        && !material.name.contains("_speff_")
+                        && mesh.should_render_lod(models)
+                    {
+                        mesh.per_mesh.set(render_pass);
+
+                        let key = DepthPipelineKey::from(&material.pipeline_key);
+                        render_pass.set_pipeline(&self.overdraw_pipelines[&key]);
+
+                        material.bind_group2.set(render_pass);
+
+                        let instance_count = material
+                            .fur_shell_instance_count
+                            .unwrap_or(model.instances.count);
+
+                        self.draw_mesh(
+                            model,
+                            mesh,
+                            render_pass,
+                            false,
+                            material.pipeline_key.is_instanced_static,
+                            instance_count,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     /// Draw the bounding box for each model and group of models.
     pub fn draw_bounds<'a>(
         &'a self,
@@ -448,6 +641,49 @@ impl ModelGroup {
             }
         }
     }
+
+    /// Set the weight for all morph targets using the controller with name `controller_name`.
+    ///
+    /// Returns `false` and does not update any weights if no morph targets use the controller.
+    pub fn set_morph_weight(
+        &self,
+        queue: &wgpu::Queue,
+        controller_name: &str,
+        weight: f32,
+    ) -> bool {
+        let Some(controller_index) = self.models[0]
+            .morph_controller_names
+            .iter()
+            .position(|n| n == controller_name)
+        else {
+            return false;
+        };
+
+        let mut updated = false;
+        for buffers in &self.buffers {
+            for buffer in &buffers.vertex_buffers {
+                if let Some(morph_buffers) = &buffer.morph_buffers {
+                    // Weights are stored for each morph target.
+                    for (i, index) in morph_buffers
+                        .morph_target_controller_indices
+                        .iter()
+                        .enumerate()
+                    {
+                        if *index == controller_index {
+                            let offset = (i * std::mem::size_of::<f32>()) as u64;
+                            queue.write_buffer(
+                                &morph_buffers.weights_buffer,
+                                offset,
+                                bytemuck::bytes_of(&weight),
+                            );
+                            updated = true;
+                        }
+                    }
+                }
+            }
+        }
+        updated
+    }
 }
 
 impl Mesh {
@@ -587,11 +823,13 @@ fn create_model_group(
     let models = group
         .models
         .iter()
-        .map(|models| {
+        .enumerate()
+        .map(|(models_index, models)| {
             Models::from_models(
                 device,
                 queue,
                 models,
+                models_index,
                 &group.buffers,
                 skeleton,
                 &mut pipelines,
@@ -603,6 +841,24 @@ fn create_model_group(
         })
         .collect();
 
+    // Depth pipelines only depend on a few material parameters.
+    let depth_keys = pipelines
+        .keys()
+        .map(DepthPipelineKey::from)
+        .collect::<HashSet<_>>();
+    let depth_pipelines = depth_keys
+        .iter()
+        .map(|key| (*key, model_depth_pipeline(device, pipeline_data, key)))
+        .collect();
+    let overdraw_pipelines = depth_keys
+        .iter()
+        .map(|key| (*key, model_overdraw_pipeline(device, pipeline_data, key)))
+        .collect();
+    let pick_pipelines = depth_keys
+        .iter()
+        .map(|key| (*key, model_pick_pipeline(device, pipeline_data, key)))
+        .collect();
+
     ModelGroup {
         models,
         buffers,
@@ -612,6 +868,9 @@ fn create_model_group(
         bone_animated_transforms,
         bone_count,
         pipelines,
+        depth_pipelines,
+        overdraw_pipelines,
+        pick_pipelines,
     }
 }
 
@@ -641,6 +900,7 @@ fn model_index_buffers(
 fn create_model(
     device: &wgpu::Device,
     model: &xc3_model::Model,
+    (models_index, model_index): (usize, usize),
     buffers: &[xc3_model::vertex::ModelBuffers],
     materials: &[Material],
     weights: Option<&xc3_model::skinning::Weights>,
@@ -651,7 +911,8 @@ fn create_model(
     let meshes = model
         .meshes
         .iter()
-        .map(|mesh| Mesh {
+        .enumerate()
+        .map(|(mesh_index, mesh)| Mesh {
             vertex_buffer_index: mesh.vertex_buffer_index,
             index_buffer_index: mesh.index_buffer_index,
             material_index: mesh.material_index,
@@ -660,6 +921,7 @@ fn create_model(
             per_mesh: per_mesh_bind_group(
                 device,
                 model_buffers,
+                (models_index, model_index, mesh_index),
                 mesh,
                 &materials[mesh.material_index],
                 weights,
@@ -682,6 +944,7 @@ fn create_model(
         meshes,
         model_buffers_index: model.model_buffers_index,
         instances,
+        hidden_meshes: Vec::new(),
     }
 }
 
@@ -1037,6 +1300,7 @@ fn per_group_bind_group(
 fn per_mesh_bind_group(
     device: &wgpu::Device,
     buffers: &xc3_model::vertex::ModelBuffers,
+    (models_index, model_index, mesh_index): PickIndices,
     mesh: &xc3_model::Mesh,
     material: &Material,
     weights: Option<&xc3_model::skinning::Weights>,
@@ -1059,6 +1323,12 @@ fn per_mesh_bind_group(
         "per mesh buffer",
         &crate::shader::model::PerMesh {
             weight_group_indices: uvec4(start as u32, 0, 0, 0),
+            pick_indices: uvec4(
+                models_index as u32,
+                model_index as u32,
+                mesh_index as u32,
+                0,
+            ),
         },
     );
 
@@ -3,11 +3,17 @@ use std::{collections::BTreeMap, path::Path};
 use crate::texture::create_texture;
 
 /// Texture resources from the game's `monolib/shader` folder.
+///
+/// Use [MonolibShaderTextures::default] to render without the `monolib/shader` folder.
+/// Missing textures use neutral placeholder textures instead.
+#[derive(Default)]
 pub struct MonolibShaderTextures {
     global_textures: BTreeMap<String, wgpu::Texture>,
 }
 
 impl MonolibShaderTextures {
+    /// Load the supported textures from the `monolib/shader` folder at `path`.
+    /// Files that are missing or fail to load will use placeholder textures.
     pub fn from_file<P: AsRef<Path>>(device: &wgpu::Device, queue: &wgpu::Queue, path: P) -> Self {
         let textures = xc3_model::monolib::ShaderTextures::from_folder(path.as_ref());
 
@@ -1,6 +1,8 @@
 use xc3_model::material::{BlendMode, ColorWriteMode, CullMode, RenderPassType, StateFlags};
 
-use crate::{DEPTH_STENCIL_FORMAT, GBUFFER_COLOR_FORMAT, GBUFFER_NORMAL_FORMAT};
+use crate::{
+    DEPTH_FORMAT, DEPTH_STENCIL_FORMAT, GBUFFER_COLOR_FORMAT, GBUFFER_NORMAL_FORMAT, PICK_FORMAT,
+};
 
 #[derive(Debug)]
 pub struct ModelPipelineData {
@@ -214,6 +216,152 @@ fn model_pipeline_inner<const M: usize, const N: usize>(
     })
 }
 
+/// The components of a depth only pipeline for use with pipeline caching.
+#[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
+pub struct DepthPipelineKey {
+    pub cull_mode: CullMode,
+    pub is_instanced_static: bool,
+}
+
+impl From<&PipelineKey> for DepthPipelineKey {
+    fn from(key: &PipelineKey) -> Self {
+        Self {
+            cull_mode: key.flags.cull_mode,
+            is_instanced_static: key.is_instanced_static,
+        }
+    }
+}
+
+/// Create a pipeline that only writes depth using the existing vertex shaders and alpha testing.
+pub fn model_depth_pipeline(
+    device: &wgpu::Device,
+    data: &ModelPipelineData,
+    key: &DepthPipelineKey,
+) -> wgpu::RenderPipeline {
+    // There are no color outputs, but alpha testing still affects depth.
+    let fragment = crate::shader::model::fs_depth_entry([]);
+    depth_key_pipeline(
+        device,
+        data,
+        key,
+        "Model Depth Pipeline",
+        fragment,
+        Some(depth_write_state()),
+    )
+}
+
+/// Create a pipeline that additively counts fragments using the existing vertex shaders.
+pub fn model_overdraw_pipeline(
+    device: &wgpu::Device,
+    data: &ModelPipelineData,
+    key: &DepthPipelineKey,
+) -> wgpu::RenderPipeline {
+    let fragment = crate::shader::model::fs_overdraw_entry([Some(wgpu::ColorTargetState {
+        format: GBUFFER_COLOR_FORMAT,
+        blend: Some(wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+        }),
+        write_mask: wgpu::ColorWrites::all(),
+    })]);
+
+    // Disable depth testing to count every fragment including hidden fragments.
+    depth_key_pipeline(device, data, key, "Model Overdraw Pipeline", fragment, None)
+}
+
+/// Create a pipeline that writes mesh indices and depth using the existing vertex shaders.
+pub fn model_pick_pipeline(
+    device: &wgpu::Device,
+    data: &ModelPipelineData,
+    key: &DepthPipelineKey,
+) -> wgpu::RenderPipeline {
+    // Integer formats don't support blending.
+    let fragment = crate::shader::model::fs_pick_entry([Some(PICK_FORMAT.into())]);
+    depth_key_pipeline(
+        device,
+        data,
+        key,
+        "Model Pick Pipeline",
+        fragment,
+        Some(depth_write_state()),
+    )
+}
+
+fn depth_write_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: true,
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
+}
+
+fn depth_key_pipeline<const N: usize>(
+    device: &wgpu::Device,
+    data: &ModelPipelineData,
+    key: &DepthPipelineKey,
+    label: &str,
+    fragment_entry: crate::shader::model::FragmentEntry<N>,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+) -> wgpu::RenderPipeline {
+    let primitive = wgpu::PrimitiveState {
+        topology: wgpu::PrimitiveTopology::TriangleList,
+        polygon_mode: wgpu::PolygonMode::Fill,
+        cull_mode: cull_mode(key.cull_mode),
+        ..Default::default()
+    };
+
+    if key.is_instanced_static {
+        let vertex = crate::shader::model::vs_main_instanced_static_entry(
+            wgpu::VertexStepMode::Vertex,
+            wgpu::VertexStepMode::Vertex,
+            wgpu::VertexStepMode::Instance,
+        );
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&data.layout),
+            vertex: crate::shader::model::vertex_state(&data.module, &vertex),
+            fragment: Some(crate::shader::model::fragment_state(
+                &data.module,
+                &fragment_entry,
+            )),
+            primitive,
+            depth_stencil,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    } else {
+        let vertex = crate::shader::model::vs_main_entry(
+            wgpu::VertexStepMode::Vertex,
+            wgpu::VertexStepMode::Vertex,
+        );
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(&data.layout),
+            vertex: crate::shader::model::vertex_state(&data.module, &vertex),
+            fragment: Some(crate::shader::model::fragment_state(
+                &data.module,
+                &fragment_entry,
+            )),
+            primitive,
+            depth_stencil,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+}
+
 fn stencil_state(mode: xc3_model::material::StencilMode) -> wgpu::StencilState {
     wgpu::StencilState {
         front: wgpu::StencilFaceState {
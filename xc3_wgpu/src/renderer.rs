@@ -1,11 +1,11 @@
-use glam::{vec2, vec4, Mat4, Vec2, Vec4};
+use glam::{uvec4, vec2, vec4, Mat4, UVec4, Vec2, Vec3, Vec4};
 use wgpu::util::DeviceExt;
 use xc3_model::MeshRenderPass;
 
 use crate::{
     model::ModelGroup, pipeline::Output5Type, skeleton::BoneRenderer, Collision, DeviceBufferExt,
     MonolibShaderTextures, QueueBufferExt, COLOR_FORMAT, GBUFFER_COLOR_FORMAT,
-    GBUFFER_NORMAL_FORMAT,
+    GBUFFER_NORMAL_FORMAT, PICK_FORMAT,
 };
 
 const DEPTH_STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
@@ -17,6 +17,8 @@ pub struct Renderer {
     camera: CameraData,
 
     model_bind_group0: crate::shader::model::bind_groups::BindGroup0,
+    model_debug_settings_buffer: wgpu::Buffer,
+    model_pick_settings_buffer: wgpu::Buffer,
 
     deferred_debug_pipeline: wgpu::RenderPipeline,
     deferred_bind_group0: crate::shader::deferred::bind_groups::BindGroup0,
@@ -26,6 +28,7 @@ pub struct Renderer {
     deferred_bind_group2: [crate::shader::deferred::bind_groups::BindGroup2; 6],
 
     render_mode: RenderMode,
+    clear_color: wgpu::Color,
 
     textures: Textures,
 
@@ -68,6 +71,16 @@ pub enum RenderMode {
     GBuffer5 = 6,
     /// Debug the sixth gbuffer texture "gtSpecularCol".
     GBuffer6 = 7,
+    /// Debug skin weights as the dominant bone for each vertex
+    /// or the weight of a selected bone.
+    BoneWeights = 8,
+    /// Debug the first texture coordinate attribute as red and green.
+    TexCoord0 = 9,
+    /// Debug the second texture coordinate attribute as red and green.
+    TexCoord1 = 10,
+    /// Debug the number of overlapping fragments for each pixel as a heatmap.
+    /// Depth testing is disabled to include fragments hidden by other meshes.
+    Overdraw = 11,
 }
 
 // Group resizable resources to avoid duplicating this logic.
@@ -136,6 +149,43 @@ pub struct CameraData {
     pub height: u32,
 }
 
+impl CameraData {
+    /// Calculate camera data for a perspective camera with a viewport of `width` by `height` pixels.
+    ///
+    /// The view applies the `rotation` in radians for the X and Y axes followed by the `translation`.
+    /// The vertical field of view `fov_y` is in radians.
+    pub fn perspective(
+        width: u32,
+        height: u32,
+        translation: Vec3,
+        rotation: Vec3,
+        fov_y: f32,
+        z_near: f32,
+        z_far: f32,
+    ) -> Self {
+        let aspect = width as f32 / height as f32;
+
+        let view = Mat4::from_translation(translation)
+            * Mat4::from_rotation_x(rotation.x)
+            * Mat4::from_rotation_y(rotation.y);
+
+        let projection = Mat4::perspective_rh(fov_y, aspect, z_near, z_far);
+
+        let view_projection = projection * view;
+
+        let position = view.inverse().col(3);
+
+        Self {
+            view,
+            projection,
+            view_projection,
+            position,
+            width,
+            height,
+        }
+    }
+}
+
 // Fragment outputs for all 3 games to use in the deferred pass.
 // Names adapted from output functions from pcsmt fragment GLSL shaders.
 // TODO: Are there ever more than 6 outputs?
@@ -178,14 +228,31 @@ impl Renderer {
             },
         );
 
+        let render_mode = RenderMode::Shaded;
+        let model_debug_settings_buffer = device.create_uniform_buffer(
+            "Model Debug Settings",
+            &crate::shader::model::DebugSettings {
+                render_mode: render_mode as u32,
+                bone_index: -1,
+            },
+        );
+
+        let model_pick_settings_buffer = device.create_uniform_buffer(
+            "Model Pick Settings",
+            &crate::shader::model::PickSettings {
+                group_index: UVec4::ZERO,
+            },
+        );
+
         let model_bind_group0 = crate::shader::model::bind_groups::BindGroup0::from_bindings(
             device,
             crate::shader::model::bind_groups::BindGroupLayout0 {
                 camera: camera_buffer.as_entire_buffer_binding(),
+                debug_settings: model_debug_settings_buffer.as_entire_buffer_binding(),
+                pick_settings: model_pick_settings_buffer.as_entire_buffer_binding(),
             },
         );
 
-        let render_mode = RenderMode::Shaded;
         let debug_settings_buffer = device.create_uniform_buffer(
             "Debug Settings",
             &crate::shader::deferred::DebugSettings {
@@ -302,6 +369,8 @@ impl Renderer {
             camera_buffer,
             camera,
             model_bind_group0,
+            model_debug_settings_buffer,
+            model_pick_settings_buffer,
             deferred_pipelines,
             deferred_debug_pipeline,
             deferred_bind_group0,
@@ -311,6 +380,7 @@ impl Renderer {
             unbranch_to_depth_pipeline,
             textures,
             render_mode,
+            clear_color: wgpu::Color::BLACK,
             snn_filter_pipeline,
             blit_pipeline,
             blit_hair_pipeline,
@@ -346,6 +416,10 @@ impl Renderer {
             self.deferred_pass(encoder);
             self.alpha3_pass(encoder, models, &self.textures.deferred_output);
             self.snn_filter_pass(encoder);
+        } else if self.render_mode == RenderMode::Overdraw {
+            // The earlier passes still write depth for drawing bounds and bones.
+            self.overdraw_pass(encoder, models);
+            self.deferred_debug_pass(encoder);
         } else {
             // Move forward passes earlier to show all meshes in debug modes.
             self.alpha3_pass(encoder, models, &self.textures.gbuffer.color);
@@ -361,6 +435,188 @@ impl Renderer {
         );
     }
 
+    /// Render the depth of opaque meshes in `models` to `depth_view` using the current camera.
+    ///
+    /// The `depth_view` should have the same dimensions as the renderer
+    /// and use the [DEPTH_FORMAT](crate::DEPTH_FORMAT) format.
+    /// Alpha tested fragments are discarded to match the depth of the regular render.
+    /// This can be used for depth prepasses or compositing.
+    pub fn render_depth(
+        &self,
+        depth_view: &wgpu::TextureView,
+        encoder: &mut wgpu::CommandEncoder,
+        models: &[ModelGroup],
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.model_bind_group0.set(&mut render_pass);
+
+        for model in models {
+            model.draw_depth(&mut render_pass, &self.camera);
+        }
+    }
+
+    /// Find the front-most opaque mesh in `models` at pixel (`x`, `y`) using the current camera.
+    ///
+    /// Returns the `(group, models, model, mesh)` indices or `None` if no mesh covers the pixel.
+    /// The last three indices can be passed to [ModelGroup::set_mesh_visible]
+    /// for the [ModelGroup] at `models[group]`.
+    /// This blocks until the result is read back from the GPU.
+    ///
+    /// Picking renders the indices for each mesh to a separate ID texture with depth testing.
+    /// Only meshes from the opaque passes that write depth are rendered,
+    /// so transparent, outline, and other meshes without depth writes can not be picked.
+    pub fn pick(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        models: &[ModelGroup],
+        x: u32,
+        y: u32,
+    ) -> Option<(usize, usize, usize, usize)> {
+        if x >= self.camera.width || y >= self.camera.height {
+            return None;
+        }
+
+        let depth_view = create_texture(
+            device,
+            self.camera.width,
+            self.camera.height,
+            "pick depth texture",
+            crate::DEPTH_FORMAT,
+        );
+
+        let size = wgpu::Extent3d {
+            width: self.camera.width,
+            height: self.camera.height,
+            depth_or_array_layers: 1,
+        };
+        let pick_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("pick texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: PICK_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let pick_view = pick_texture.create_view(&Default::default());
+
+        // The group index is only known when rendering, so submit each group separately.
+        // Buffer writes are applied before the commands from the next submit.
+        for (i, model) in models.iter().enumerate() {
+            queue.write_uniform_data(
+                &self.model_pick_settings_buffer,
+                &crate::shader::model::PickSettings {
+                    group_index: uvec4(i as u32, 0, 0, 0),
+                },
+            );
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Pick Encoder"),
+            });
+            {
+                // Keep the results from previous groups.
+                let (color_load, depth_load) = if i == 0 {
+                    (
+                        wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        wgpu::LoadOp::Clear(1.0),
+                    )
+                } else {
+                    (wgpu::LoadOp::Load, wgpu::LoadOp::Load)
+                };
+
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Pick Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &pick_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: color_load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: &depth_view,
+                        depth_ops: Some(wgpu::Operations {
+                            load: depth_load,
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+                render_pass.set_scissor_rect(x, y, 1, 1);
+
+                self.model_bind_group0.set(&mut render_pass);
+                model.draw_pick(&mut render_pass, &self.camera);
+            }
+            queue.submit([encoder.finish()]);
+        }
+
+        // Copies require rows to be aligned even for a single pixel.
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pick output buffer"),
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Pick Copy Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                aspect: wgpu::TextureAspect::All,
+                texture: &pick_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &output_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit([encoder.finish()]);
+
+        let buffer_slice = output_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            tx.send(result).unwrap();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+
+        let pixel: [u32; 4] = bytemuck::pod_read_unaligned(&buffer_slice.get_mapped_range()[..16]);
+        output_buffer.unmap();
+
+        pick_indices(pixel)
+    }
+
     pub fn update_camera(&mut self, queue: &wgpu::Queue, camera_data: &CameraData) {
         queue.write_uniform_data(
             &self.camera_buffer,
@@ -375,11 +631,23 @@ impl Renderer {
         self.camera = *camera_data;
     }
 
+    /// Set the background color for pixels not covered by any models.
+    ///
+    /// This defaults to [wgpu::Color::BLACK].
+    /// Use [wgpu::Color::TRANSPARENT] for images with a transparent background.
+    pub fn set_clear_color(&mut self, color: wgpu::Color) {
+        self.clear_color = color;
+    }
+
     pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
         // Update each resource that depends on window size.
         self.textures = Textures::new(device, width, height);
     }
 
+    /// Set the debug `render_mode` and the `channel` to display or -1 for all channels.
+    ///
+    /// For [RenderMode::BoneWeights], `channel` selects the bone index
+    /// or -1 to color each vertex by its dominant bone.
     pub fn update_debug_settings(
         &mut self,
         queue: &wgpu::Queue,
@@ -394,6 +662,13 @@ impl Renderer {
                 channel,
             },
         );
+        queue.write_uniform_data(
+            &self.model_debug_settings_buffer,
+            &crate::shader::model::DebugSettings {
+                render_mode: render_mode as u32,
+                bone_index: channel,
+            },
+        );
     }
 
     fn opaque_pass(&self, encoder: &mut wgpu::CommandEncoder, models: &[ModelGroup]) {
@@ -725,6 +1000,29 @@ impl Renderer {
         }
     }
 
+    fn overdraw_pass(&self, encoder: &mut wgpu::CommandEncoder, models: &[ModelGroup]) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Overdraw Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.textures.gbuffer.color,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        self.model_bind_group0.set(&mut render_pass);
+
+        for model in models {
+            model.draw_overdraw(&mut render_pass, &self.camera);
+        }
+    }
+
     fn unbranch_to_depth_pass(&self, encoder: &mut wgpu::CommandEncoder) {
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Unbranch to Depth Pass"),
@@ -758,7 +1056,7 @@ impl Renderer {
                 view: &self.textures.deferred_output,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    load: wgpu::LoadOp::Clear(self.clear_color),
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -800,7 +1098,7 @@ impl Renderer {
                 view: &self.textures.deferred_output,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    load: wgpu::LoadOp::Clear(self.clear_color),
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -1049,6 +1347,17 @@ fn create_gbuffer(device: &wgpu::Device, width: u32, height: u32) -> GBuffer {
     }
 }
 
+// The group index is offset by 1 to distinguish meshes from the cleared background.
+fn pick_indices([models, model, mesh, group]: [u32; 4]) -> Option<(usize, usize, usize, usize)> {
+    let group = group.checked_sub(1)?;
+    Some((
+        group as usize,
+        models as usize,
+        model as usize,
+        mesh as usize,
+    ))
+}
+
 fn create_texture(
     device: &wgpu::Device,
     width: u32,
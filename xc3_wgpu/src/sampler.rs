@@ -2,7 +2,13 @@ pub fn create_sampler(device: &wgpu::Device, sampler: &xc3_model::Sampler) -> wg
     device.create_sampler(&sampler_descriptor(sampler))
 }
 
-fn sampler_descriptor(sampler: &xc3_model::Sampler) -> wgpu::SamplerDescriptor<'static> {
+/// Convert `sampler` to an equivalent wgpu sampler for renderers using [xc3_model].
+///
+/// Anisotropic filtering is only enabled if [xc3_model::Sampler::anisotropic_filtering]
+/// since wgpu requires linear filtering for all filters when using anisotropy.
+/// [xc3_model::AddressMode::ClampToEdge] clamps to the edge texels
+/// and never uses a border color.
+pub fn sampler_descriptor(sampler: &xc3_model::Sampler) -> wgpu::SamplerDescriptor<'static> {
     wgpu::SamplerDescriptor {
         label: None,
         address_mode_u: address_mode(sampler.address_mode_u),
@@ -279,6 +279,22 @@ fn fs_toon(in: VertexOutput) -> FragmentOutput {
     return out;
 }
 
+// Map fragment counts from 0 to 8 or more to black, blue, green, yellow, and red.
+fn overdraw_heatmap(count: f32) -> vec3<f32> {
+    if count < 0.5 {
+        return vec3(0.0);
+    }
+
+    let t = clamp((count - 1.0) / 7.0, 0.0, 1.0);
+    if t < 0.33 {
+        return mix(vec3(0.0, 0.0, 1.0), vec3(0.0, 1.0, 0.0), t / 0.33);
+    } else if t < 0.66 {
+        return mix(vec3(0.0, 1.0, 0.0), vec3(1.0, 1.0, 0.0), (t - 0.33) / 0.33);
+    } else {
+        return mix(vec3(1.0, 1.0, 0.0), vec3(1.0, 0.0, 0.0), (t - 0.66) / 0.34);
+    }
+}
+
 @fragment
 fn fs_debug(in: VertexOutput) -> FragmentOutput {
     let g_color = textureSample(g_color, shared_sampler, in.uv);
@@ -312,12 +328,27 @@ fn fs_debug(in: VertexOutput) -> FragmentOutput {
         case 7u: {
             color = g_specular_color;
         }
+        case 8u, 9u, 10u: {
+            // Bone weights and texture coordinates are written to the color texture.
+            color = vec4(g_color.rgb, 1.0);
+        }
+        case 11u: {
+            // Fragment counts are written to the color texture.
+            color = vec4(overdraw_heatmap(g_color.r * 255.0), 1.0);
+        }
         default: {
             color = vec4(0.0);
         }
     }
 
     var out: FragmentOutput;
+
+    // The channel selects a bone instead of a color channel for bone weights.
+    if debug_settings.render_mode == 8u || debug_settings.render_mode == 11u {
+        out.color = color;
+        return out;
+    }
+
     switch (debug_settings.channel) {
         case 0: {
             out.color = vec4(color.rrr, 1.0);
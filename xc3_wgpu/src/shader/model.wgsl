@@ -10,6 +10,25 @@ struct Camera {
 @group(0) @binding(0)
 var<uniform> camera: Camera;
 
+// Settings for debug modes that require vertex data.
+struct DebugSettings {
+    render_mode: u32,
+    // The selected bone or -1 to show the bone with the highest weight.
+    bone_index: i32,
+}
+
+@group(0) @binding(1)
+var<uniform> debug_settings: DebugSettings;
+
+// Settings for rendering mesh IDs when picking.
+struct PickSettings {
+    // group_index, 0, 0, 0
+    group_index: vec4<u32>
+}
+
+@group(0) @binding(2)
+var<uniform> pick_settings: PickSettings;
+
 // PerGroup values for ModelGroup.
 struct PerGroup {
     // TODO: Should this be with the model?
@@ -177,7 +196,9 @@ var<uniform> per_material: PerMaterial;
 // PerMesh values.
 struct PerMesh {
     // start_index, 0, 0, 0
-    weight_group_indices: vec4<u32>
+    weight_group_indices: vec4<u32>,
+    // models_index, model_index, mesh_index, 0
+    pick_indices: vec4<u32>
 }
 
 @group(3) @binding(2)
@@ -228,6 +249,7 @@ struct VertexOutput {
     @location(6) tex45: vec4<f32>,
     @location(7) tex67: vec4<f32>,
     @location(8) tex8: vec4<f32>,
+    @location(9) bone_weights_color: vec4<f32>,
 }
 
 struct FragmentOutput {
@@ -246,6 +268,7 @@ fn vertex_output(in0: VertexInput0, in1: VertexInput1, instance_index: u32, outl
     var position = in0.position.xyz;
     var normal_xyz = in0.normal.xyz;
     var tangent_xyz = in0.tangent.xyz;
+    var bone_weights_color = vec3(0.0);
 
     if per_group.enable_skinning.x == 1u {
         position = vec3(0.0);
@@ -268,6 +291,8 @@ fn vertex_output(in0: VertexInput0, in1: VertexInput1, instance_index: u32, outl
             tangent_xyz += skin_weight * (per_group.animated_transforms_inv_transpose[bone_index] * vec4(in0.tangent.xyz, 0.0)).xyz;
             normal_xyz += skin_weight * (per_group.animated_transforms_inv_transpose[bone_index] * vec4(in0.normal.xyz, 0.0)).xyz;
         }
+
+        bone_weights_color = bone_weight_color(bone_indices, skin_weights);
     }
 
     // Transform any direction vectors by the camera transforms.
@@ -320,9 +345,40 @@ fn vertex_output(in0: VertexInput0, in1: VertexInput1, instance_index: u32, outl
 
     out.normal = normal_xyz;
     out.tangent = vec4(tangent_xyz, in0.tangent.w);
+    out.bone_weights_color = vec4(bone_weights_color, 1.0);
     return out;
 }
 
+fn bone_weight_color(bone_indices: vec4<u32>, skin_weights: vec4<f32>) -> vec3<f32> {
+    if debug_settings.bone_index >= 0 {
+        // Show the total influence of the selected bone.
+        var weight = 0.0;
+        for (var i = 0u; i < 4u; i += 1u) {
+            if bone_indices[i] == u32(debug_settings.bone_index) {
+                weight += skin_weights[i];
+            }
+        }
+        return vec3(weight);
+    }
+
+    // Show the bone with the highest weight.
+    var max_index = 0u;
+    for (var i = 1u; i < 4u; i += 1u) {
+        if skin_weights[i] > skin_weights[max_index] {
+            max_index = i;
+        }
+    }
+    return hash_color(bone_indices[max_index]);
+}
+
+fn hash_color(value: u32) -> vec3<f32> {
+    // PCG hash to give similar bone indices distinct colors.
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    let hash = (word >> 22u) ^ word;
+    return vec3(f32(hash & 255u), f32((hash >> 8u) & 255u), f32((hash >> 16u) & 255u)) / 255.0;
+}
+
 // Adapted from shd0001 GLSL from ch11021013.pcsmt (xc3). 
 fn outline_width(vertex_color: vec4<f32>, param: f32, view_z: f32, normal: vec3<f32>) -> f32 {
     let f_line_width = vertex_color.w * param * -view_z / camera.projection[1][1];
@@ -373,6 +429,7 @@ fn vs_main_instanced_static(in0: VertexInput0, in1: VertexInput1, instance: Inst
     out.vertex_color = in1.vertex_color;
     out.normal = normal_xyz;
     out.tangent = vec4(tangent_xyz, in0.tangent.w);
+    out.bone_weights_color = vec4(0.0, 0.0, 0.0, 1.0);
     return out;
 }
 
@@ -683,12 +740,7 @@ fn blend_texture_layer(current: vec4<f32>, samplers: array<vec4<f32>, 10>, assig
     return blend_layer(current, b, ratio, n_dot_v, layers.blend_mode);
 }
 
-fn fragment_output(in: VertexOutput) -> FragmentOutput {
-    let tangent = normalize(in.tangent.xyz);
-    let vertex_normal = normalize(in.normal.xyz);
-
-    let bitangent = cross(vertex_normal, tangent) * in.tangent.w;
-
+fn sample_textures(in: VertexOutput) -> array<vec4<f32>, 10> {
     // Avoid switch statements on texcoord index that compile slowly with DX12.
     let uvs = array<vec2<f32>, 9>(
         in.tex01.xy,
@@ -704,7 +756,7 @@ fn fragment_output(in: VertexOutput) -> FragmentOutput {
 
     // Assume one access per texture and compute values ahead of time.
     // This avoids deeply nested texture lookups that compile slowly with DX12.
-    let samplers = array<vec4<f32>, 10>(
+    return array<vec4<f32>, 10>(
         textureSample(s0, s0_sampler, select_uv(in, uvs, 0)),
         textureSample(s1, s1_sampler, select_uv(in, uvs, 1)),
         textureSample(s2, s2_sampler, select_uv(in, uvs, 2)),
@@ -716,7 +768,9 @@ fn fragment_output(in: VertexOutput) -> FragmentOutput {
         textureSample(s8, s8_sampler, select_uv(in, uvs, 8)),
         textureSample(s9, s9_sampler, select_uv(in, uvs, 9)),
     );
+}
 
+fn alpha_test(samplers: array<vec4<f32>, 10>, in: VertexOutput) {
     // An index of -1 disables alpha testing.
     let alpha_texture = per_material.alpha_test_texture.x;
     let alpha_texture_channel = u32(per_material.alpha_test_texture.y);
@@ -725,6 +779,16 @@ fn fragment_output(in: VertexOutput) -> FragmentOutput {
         // TODO: incorrect reference alpha for comparison?
         discard;
     }
+}
+
+fn fragment_output(in: VertexOutput) -> FragmentOutput {
+    let tangent = normalize(in.tangent.xyz);
+    let vertex_normal = normalize(in.normal.xyz);
+
+    let bitangent = cross(vertex_normal, tangent) * in.tangent.w;
+
+    let samplers = sample_textures(in);
+    alpha_test(samplers, in);
 
     // The layout of G-Buffer textures is fixed but assignments are not.
     // Each material in game can have a unique shader program.
@@ -790,6 +854,19 @@ fn fragment_output(in: VertexOutput) -> FragmentOutput {
     out.g_velocity = g_velocity;
     out.g_depth = mrt_depth(in.position.z, g_depth.w);
     out.g_lgt_color = g_lgt_color;
+
+    // Bone weights are stored in the color texture to reuse the debug pipeline.
+    if debug_settings.render_mode == 8u {
+        out.g_color = vec4(in.bone_weights_color.rgb, 1.0);
+    }
+
+    // Texture coordinates are stored in the color texture to reuse the debug pipeline.
+    if debug_settings.render_mode == 9u {
+        out.g_color = vec4(in.tex01.xy, 0.0, 1.0);
+    } else if debug_settings.render_mode == 10u {
+        out.g_color = vec4(in.tex01.zw, 0.0, 1.0);
+    }
+
     return out;
 }
 
@@ -799,6 +876,24 @@ fn fs_alpha(in: VertexOutput) -> @location(0) vec4<f32> {
     return output.g_color;
 }
 
+// Depth only rendering still needs to discard alpha tested fragments.
+@fragment
+fn fs_depth(in: VertexOutput) {
+    alpha_test(sample_textures(in), in);
+}
+
+// Each fragment adds 1 to the 8-bit unorm count for additive blending.
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4(1.0 / 255.0);
+}
+
+// Write the mesh indices with the group index offset by 1 to distinguish from the background.
+@fragment
+fn fs_pick(in: VertexOutput) -> @location(0) vec4<u32> {
+    return vec4(per_mesh.pick_indices.xyz, pick_settings.group_index.x + 1u);
+}
+
 // TODO: Separate entry for depth prepass.
 // TODO: depth func needs to be changed if using prepass?
 
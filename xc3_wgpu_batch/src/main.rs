@@ -2,7 +2,7 @@ use std::path::Path;
 
 use clap::{Parser, ValueEnum};
 use futures::executor::block_on;
-use glam::{vec3, Mat4, Vec3};
+use glam::{vec3, Vec3};
 use image::ImageBuffer;
 use xc3_model::{load_animations, shader_database::ShaderDatabase};
 use xc3_wgpu::{CameraData, MonolibShaderTextures, Renderer};
@@ -10,6 +10,8 @@ use xc3_wgpu::{CameraData, MonolibShaderTextures, Renderer};
 const WIDTH: u32 = 1024;
 const HEIGHT: u32 = 1024;
 const FOV_Y: f32 = 0.5;
+const Z_NEAR: f32 = 0.1;
+const Z_FAR: f32 = 100000.0;
 
 #[derive(Parser)]
 #[command(author, version, about)]
@@ -33,6 +35,10 @@ struct Cli {
     /// Draw axes for each bone in the skeleton.
     #[arg(long)]
     bones: bool,
+
+    /// Save images with a transparent background instead of black.
+    #[arg(long)]
+    transparent: bool,
 }
 
 #[derive(Copy, PartialEq, Clone, Eq, ValueEnum)]
@@ -119,9 +125,14 @@ fn main() {
     // Initialize the camera transform.
     let translation = vec3(0.0, -1.0, -10.0);
     let rotation = vec3(0.0, -20f32.to_radians(), 0.0);
-    let camera_data = calculate_camera_data(WIDTH, HEIGHT, translation, rotation);
+    let camera_data =
+        CameraData::perspective(WIDTH, HEIGHT, translation, rotation, FOV_Y, Z_NEAR, Z_FAR);
     renderer.update_camera(&queue, &camera_data);
 
+    if cli.transparent {
+        renderer.set_clear_color(wgpu::Color::TRANSPARENT);
+    }
+
     let database = cli
         .shader_database
         .map(ShaderDatabase::from_file)
@@ -199,8 +210,8 @@ fn main() {
                 encoder,
                 &output,
                 &output_buffer,
-                size,
                 output_path,
+                cli.transparent,
             );
 
             // Clean up resources.
@@ -260,11 +271,14 @@ fn frame_bounds(queue: &wgpu::Queue, renderer: &mut Renderer, min_xyz: Vec3, max
     let distance = bounds_size.y.max(bounds_size.x) / FOV_Y.tan() + 2.0;
 
     let rotation = vec3(0.0, -20f32.to_radians(), 0.0);
-    let camera_data = calculate_camera_data(
+    let camera_data = CameraData::perspective(
         WIDTH,
         HEIGHT,
         vec3(center.x, -center.y, -distance),
         rotation,
+        FOV_Y,
+        Z_NEAR,
+        Z_FAR,
     );
     renderer.update_camera(queue, &camera_data);
 }
@@ -275,8 +289,8 @@ fn save_screenshot(
     mut encoder: wgpu::CommandEncoder,
     output: &wgpu::Texture,
     output_buffer: &wgpu::Buffer,
-    size: wgpu::Extent3d,
     output_path: std::path::PathBuf,
+    transparent: bool,
 ) {
     encoder.copy_texture_to_buffer(
         wgpu::TexelCopyTextureInfo {
@@ -293,7 +307,7 @@ fn save_screenshot(
                 rows_per_image: Some(HEIGHT),
             },
         },
-        size,
+        output.size(),
     );
     queue.submit([encoder.finish()]);
 
@@ -314,33 +328,11 @@ fn save_screenshot(
         let data = buffer_slice.get_mapped_range();
         let mut buffer =
             ImageBuffer::<image::Rgba<u8>, _>::from_raw(WIDTH, HEIGHT, data.to_owned()).unwrap();
-        // Force opaque.
-        buffer.pixels_mut().for_each(|p| p[3] = 255u8);
+        if !transparent {
+            // Force opaque.
+            buffer.pixels_mut().for_each(|p| p[3] = 255u8);
+        }
         buffer.save(output_path).unwrap();
     }
     output_buffer.unmap();
 }
-
-// TODO: Move to xc3_wgpu?
-fn calculate_camera_data(width: u32, height: u32, translation: Vec3, rotation: Vec3) -> CameraData {
-    let aspect = width as f32 / height as f32;
-
-    let view = Mat4::from_translation(translation)
-        * Mat4::from_rotation_x(rotation.x)
-        * Mat4::from_rotation_y(rotation.y);
-
-    let projection = Mat4::perspective_rh(FOV_Y, aspect, 0.1, 100000.0);
-
-    let view_projection = projection * view;
-
-    let position = view.inverse().col(3);
-
-    CameraData {
-        view,
-        projection,
-        view_projection,
-        position,
-        width,
-        height,
-    }
-}
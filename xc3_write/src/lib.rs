@@ -57,6 +57,8 @@ pub trait Xc3WriteOffsets {
     /// For writing in order by field recursively, simply derive [Xc3WriteOffsets].
     /// Manually implementing this trait allows flexibility for cases like placing strings
     /// for all types at the end of the file.
+    /// Derived implementations can write string fields after all other fields
+    /// for a single type using `#[xc3(strings_last)]`.
     fn write_offsets<W: Write + Seek>(
         &self,
         writer: &mut W,
@@ -145,6 +147,9 @@ impl<'a, P, T> Offset<'a, P, T> {
         }
     }
 
+    /// Write `offset` to the offset field's position using the pointer type `P`.
+    ///
+    /// Returns an error if `offset` does not fit in `P` instead of writing a truncated value.
     pub fn set_offset<W>(&self, writer: &mut W, offset: u64, endian: Endian) -> Xc3Result<()>
     where
         W: Write + Seek,
@@ -152,9 +157,19 @@ impl<'a, P, T> Offset<'a, P, T> {
         P: TryFrom<u64> + Xc3Write,
         <P as TryFrom<u64>>::Error: std::fmt::Debug,
     {
+        let value = P::try_from(offset).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "offset {offset} for {} at position {} does not fit in {}: {e:?}",
+                    std::any::type_name::<T>(),
+                    self.position,
+                    std::any::type_name::<P>()
+                ),
+            )
+        })?;
         writer.seek(SeekFrom::Start(self.position))?;
-        let offset = P::try_from(offset).unwrap();
-        offset.xc3_write(writer, endian)?;
+        value.xc3_write(writer, endian)?;
         Ok(())
     }
 
@@ -181,7 +196,16 @@ impl<'a, P, T> Offset<'a, P, T> {
         let aligned_data_pr = data_ptr.next_multiple_of(alignment);
 
         // Update the offset value.
-        self.set_offset(writer, aligned_data_pr - base_offset, endian)?;
+        let offset = aligned_data_pr.checked_sub(base_offset).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "data position {aligned_data_pr} for {} is before base offset {base_offset}",
+                    std::any::type_name::<T>()
+                ),
+            )
+        })?;
+        self.set_offset(writer, offset, endian)?;
 
         if should_write {
             // Seek to the data position.
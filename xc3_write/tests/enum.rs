@@ -1,7 +1,7 @@
 use std::io::Cursor;
 
 use hexlit::hex;
-use xc3_write::{assert_hex_eq, Endian, Xc3Write, Xc3WriteOffsets};
+use xc3_write::{assert_hex_eq, write_full, Endian, Xc3Write, Xc3WriteOffsets};
 
 #[test]
 fn write_enum_variant_magic() {
@@ -19,3 +19,88 @@ fn write_enum_variant_magic() {
 
     assert_hex_eq!(hex!(01000000 02000000 03000000), writer.into_inner());
 }
+
+#[test]
+fn write_enum_tuple_fields() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    enum A {
+        #[xc3(magic(1u32))]
+        B(u32, u8),
+    }
+
+    let value = A::B(2, 3);
+
+    let mut writer = Cursor::new(Vec::new());
+    value.xc3_write(&mut writer, Endian::Little).unwrap();
+
+    assert_hex_eq!(hex!(01000000 02000000 03), writer.into_inner());
+}
+
+#[test]
+fn write_enum_tuple_fields_offset_full() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    enum A {
+        B(Inner, Inner),
+    }
+
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    struct Inner {
+        #[xc3(offset(u32))]
+        a: u16,
+    }
+
+    let value = A::B(Inner { a: 1 }, Inner { a: 2 });
+
+    let mut writer = Cursor::new(Vec::new());
+    let mut data_ptr = 0;
+    write_full(&value, &mut writer, 0, &mut data_ptr, Endian::Little, ()).unwrap();
+
+    assert_hex_eq!(hex!(08000000 0a000000 0100 0200), writer.into_inner());
+    assert_eq!(12, data_ptr);
+}
+
+#[test]
+fn write_enum_named_fields() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    enum A {
+        #[xc3(magic(1u32))]
+        B { a: u32, b: u8 },
+    }
+
+    let value = A::B { a: 2, b: 3 };
+
+    let mut writer = Cursor::new(Vec::new());
+    value.xc3_write(&mut writer, Endian::Little).unwrap();
+
+    assert_hex_eq!(hex!(01000000 02000000 03), writer.into_inner());
+}
+
+#[test]
+fn write_enum_named_fields_offset_full() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    enum A {
+        B {
+            #[xc3(offset_count(u32, u32))]
+            a: Vec<u8>,
+            #[xc3(offset(u32))]
+            b: u16,
+            c: u32,
+        },
+    }
+
+    let value = A::B {
+        a: vec![1, 2],
+        b: 3,
+        c: 4,
+    };
+
+    let mut writer = Cursor::new(Vec::new());
+    let mut data_ptr = 0;
+    write_full(&value, &mut writer, 0, &mut data_ptr, Endian::Little, ()).unwrap();
+
+    assert_hex_eq!(
+        hex!(10000000 02000000 12000000 04000000 0102 0300),
+        writer.into_inner()
+    );
+    assert_eq!(20, data_ptr);
+}
@@ -117,3 +117,24 @@ fn write_offset_full_optional_offset_none() {
     assert_hex_eq!(hex!(00000000), writer.into_inner());
     assert_eq!(4, data_ptr);
 }
+
+#[test]
+fn write_offset_full_overflow() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    struct Test {
+        a: Vec<u8>,
+        #[xc3(offset(u8))]
+        b: u32,
+    }
+
+    let value = Test {
+        a: vec![0u8; 256],
+        b: 1,
+    };
+
+    let mut writer = Cursor::new(Vec::new());
+    let mut data_ptr = 0;
+    let result = write_full(&value, &mut writer, 0, &mut data_ptr, Endian::Little, ());
+
+    assert_eq!(std::io::ErrorKind::InvalidData, result.unwrap_err().kind());
+}
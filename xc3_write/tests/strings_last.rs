@@ -0,0 +1,58 @@
+use std::io::Cursor;
+
+use hexlit::hex;
+use xc3_write::{assert_hex_eq, write_full, Endian, Xc3Write, Xc3WriteOffsets};
+
+#[test]
+fn write_strings_in_field_order() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    struct A {
+        #[xc3(offset(u32))]
+        name: String,
+        #[xc3(offset_count(u32, u32))]
+        values: Vec<u16>,
+    }
+
+    let value = A {
+        name: "ab".to_string(),
+        values: vec![1, 2],
+    };
+
+    let mut writer = Cursor::new(Vec::new());
+    let mut data_ptr = 0;
+    write_full(&value, &mut writer, 0, &mut data_ptr, Endian::Little, ()).unwrap();
+
+    assert_hex_eq!(
+        hex!(0c000000 10000000 02000000 616200 00 01000200),
+        writer.into_inner()
+    );
+}
+
+#[test]
+fn write_strings_last() {
+    #[derive(Xc3Write, Xc3WriteOffsets)]
+    #[xc3(strings_last)]
+    struct A {
+        #[xc3(offset(u32))]
+        name: String,
+        #[xc3(offset_count(u32, u32))]
+        values: Vec<u16>,
+        #[xc3(offset(u32))]
+        name2: String,
+    }
+
+    let value = A {
+        name: "ab".to_string(),
+        values: vec![1, 2],
+        name2: "c".to_string(),
+    };
+
+    let mut writer = Cursor::new(Vec::new());
+    let mut data_ptr = 0;
+    write_full(&value, &mut writer, 0, &mut data_ptr, Endian::Little, ()).unwrap();
+
+    assert_hex_eq!(
+        hex!(14000000 10000000 02000000 17000000 01000200 616200 6300),
+        writer.into_inner()
+    );
+}
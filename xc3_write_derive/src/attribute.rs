@@ -150,6 +150,7 @@ pub struct TypeOptions {
     pub has_base_offset: bool,
     pub align: Option<u64>,
     pub align_after: Option<u64>,
+    pub strings_last: bool,
 }
 
 impl TypeOptions {
@@ -158,6 +159,7 @@ impl TypeOptions {
         let mut has_base_offset = false;
         let mut align_after = None;
         let mut align = None;
+        let mut strings_last = false;
 
         for a in attrs {
             if a.path().is_ident("xc3") {
@@ -177,6 +179,9 @@ impl TypeOptions {
                     } else if meta.path.is_ident("align") {
                         // #[xc3(align(4))]
                         align = Some(parse_u64(&meta)?);
+                    } else if meta.path.is_ident("strings_last") {
+                        // #[xc3(strings_last)]
+                        strings_last = true;
                     }
                     Ok(())
                 });
@@ -188,6 +193,7 @@ impl TypeOptions {
             has_base_offset,
             align,
             align_after,
+            strings_last,
         }
     }
 }
@@ -3,8 +3,8 @@ use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
 use syn::{
-    parse_macro_input, Data, DataEnum, DataStruct, DeriveInput, Fields, FieldsNamed, GenericParam,
-    Ident, Lifetime, LifetimeParam, Type,
+    parse_macro_input, Data, DataEnum, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed,
+    GenericParam, Ident, Lifetime, LifetimeParam, Type,
 };
 
 mod attribute;
@@ -49,7 +49,7 @@ pub fn xc3_write_derive(input: TokenStream) -> TokenStream {
             fields: Fields::Named(fields),
             ..
         }) => {
-            let fields = parse_named_fields(fields);
+            let fields = parse_named_fields(fields, FieldAccess::Struct);
 
             let offset_fields = fields.iter().map(|f| &f.offset_field);
 
@@ -57,7 +57,7 @@ pub fn xc3_write_derive(input: TokenStream) -> TokenStream {
                 #[doc(hidden)]
                 pub struct #offsets #offset_generics #where_clause {
                     #base_offset_field
-                    #(#offset_fields),*
+                    #(pub #offset_fields),*
                 }
             };
 
@@ -75,11 +75,14 @@ pub fn xc3_write_derive(input: TokenStream) -> TokenStream {
             let offset_fields = variants.iter().map(|variant| {
                 let name = &variant.ident;
                 match &variant.fields {
-                    Fields::Named(_) => todo!(),
+                    Fields::Named(named) => {
+                        let fields = parse_named_fields(named, FieldAccess::Variant);
+                        let offset_fields = fields.iter().map(|f| &f.offset_field);
+                        quote!(#name { #(#offset_fields),* })
+                    }
                     Fields::Unnamed(unnamed) => {
-                        // TODO: Don't assume just one field.
-                        let field0 = &unnamed.unnamed.first().unwrap().ty;
-                        quote!(#name(<#field0 as ::xc3_write::Xc3Write>::Offsets<'offsets>))
+                        let tys = unnamed.unnamed.iter().map(|f| &f.ty);
+                        quote!(#name(#(<#tys as ::xc3_write::Xc3Write>::Offsets<'offsets>),*))
                     }
                     Fields::Unit => quote!(#name),
                 }
@@ -100,14 +103,33 @@ pub fn xc3_write_derive(input: TokenStream) -> TokenStream {
                     .magic
                     .map(|magic| quote!(#magic.xc3_write(writer, endian)?;));
                 match &variant.fields {
-                    Fields::Named(_) => todo!(),
-                    // TODO: Don't assume one field.
-                    Fields::Unnamed(_) => quote! {
-                        Self::#name(data) => {
-                            #write_magic
-                            #offsets::#name(data.xc3_write(writer, endian)?)
+                    Fields::Named(named) => {
+                        let fields = parse_named_fields(named, FieldAccess::Variant);
+                        let field_names = fields.iter().map(|f| &f.name);
+                        let bindings = fields.iter().map(|f| variant_binding(&f.name));
+                        let write_fields = fields.iter().map(|f| &f.write_impl);
+                        let offset_field_names = fields.iter().map(|f| &f.name);
+                        quote! {
+                            // Not all fields need their values to write placeholder offsets.
+                            #[allow(unused_variables)]
+                            Self::#name { #(#field_names: #bindings),* } => {
+                                #write_magic
+                                #(#write_fields)*
+                                #offsets::#name { #(#offset_field_names),* }
+                            }
                         }
-                    },
+                    }
+                    Fields::Unnamed(unnamed) => {
+                        let fields = unnamed_fields(unnamed);
+                        quote! {
+                            Self::#name(#(#fields),*) => {
+                                #write_magic
+                                // Write each field in order.
+                                #(let #fields = #fields.xc3_write(writer, endian)?;)*
+                                #offsets::#name(#(#fields),*)
+                            }
+                        }
+                    }
                     Fields::Unit => quote!(Self::#name => #offsets::#name),
                 }
             });
@@ -200,21 +222,35 @@ pub fn xc3_write_offsets_derive(input: TokenStream) -> TokenStream {
             fields: Fields::Named(fields),
             ..
         }) => {
-            let fields = parse_named_fields(fields);
+            let fields = parse_named_fields(fields, FieldAccess::Struct);
 
-            let write_fields = fields.iter().map(|f| f.write_offset_impl.clone());
+            let write_fields = ordered_write_offsets(&fields, options.strings_last);
             quote!(#(#write_fields)*)
         }
         Data::Enum(DataEnum { variants, .. }) => {
-            // TODO: Named fields?
             let write_variants = variants.iter().map(|variant| {
                 let name = &variant.ident;
                 match &variant.fields {
-                    Fields::Named(_) => todo!(),
-                    Fields::Unnamed(_) => quote! {
-                        // TODO: Don't assume one field.
-                        Self::#name(data) => data.write_offsets(writer, base_offset, data_ptr, endian, ())?
-                    },
+                    Fields::Named(named) => {
+                        let fields = parse_named_fields(named, FieldAccess::Variant);
+                        let field_names = fields.iter().map(|f| &f.name);
+                        let write_fields = ordered_write_offsets(&fields, options.strings_last);
+                        quote! {
+                            // Some fields like saved positions have no offsets to write.
+                            #[allow(unused_variables)]
+                            Self::#name { #(#field_names),* } => {
+                                #(#write_fields)*
+                            }
+                        }
+                    }
+                    Fields::Unnamed(unnamed) => {
+                        let fields = unnamed_fields(unnamed);
+                        quote! {
+                            Self::#name(#(#fields),*) => {
+                                #(#fields.write_offsets(writer, base_offset, data_ptr, endian, ())?;)*
+                            }
+                        }
+                    }
                     Fields::Unit => quote!(Self::#name =>()),
                 }
             });
@@ -260,48 +296,137 @@ fn offsets_name(ident: &Ident) -> Ident {
     Ident::new(&(ident.to_string() + "Offsets"), Span::call_site())
 }
 
+// Generate names for the fields of tuple variants like "field0".
+fn unnamed_fields(fields: &FieldsUnnamed) -> Vec<Ident> {
+    (0..fields.unnamed.len())
+        .map(|i| Ident::new(&format!("field{i}"), Span::call_site()))
+        .collect()
+}
+
+// Avoid conflicts between the field values and the offset variables with the field name.
+fn variant_binding(name: &Ident) -> Ident {
+    Ident::new(&format!("__{name}"), Span::call_site())
+}
+
+// How to access named fields for the type and offsets type.
+#[derive(Clone, Copy)]
+enum FieldAccess {
+    /// Fields like `self.field`.
+    Struct,
+    /// Destructured enum variant fields like `Self::Variant { field }`.
+    Variant,
+}
+
+impl FieldAccess {
+    fn value(&self, name: &Ident) -> TokenStream2 {
+        match self {
+            FieldAccess::Struct => quote!(self.#name),
+            FieldAccess::Variant => {
+                let binding = variant_binding(name);
+                quote!((*#binding))
+            }
+        }
+    }
+
+    fn offset(&self, name: &Ident) -> TokenStream2 {
+        match self {
+            FieldAccess::Struct => quote!(self.#name),
+            FieldAccess::Variant => quote!(#name),
+        }
+    }
+}
+
+// Write string fields after all other fields if needed.
+fn ordered_write_offsets(fields: &[FieldData], strings_last: bool) -> Vec<&TokenStream2> {
+    if strings_last {
+        fields
+            .iter()
+            .filter(|f| !f.is_string)
+            .chain(fields.iter().filter(|f| f.is_string))
+            .map(|f| &f.write_offset_impl)
+            .collect()
+    } else {
+        fields.iter().map(|f| &f.write_offset_impl).collect()
+    }
+}
+
+// Check for types like String, Option<String>, or Vec<StringOffset32>.
+fn is_string_type(ty: &Type) -> bool {
+    contains_string(quote!(#ty))
+}
+
+fn contains_string(tokens: TokenStream2) -> bool {
+    tokens.into_iter().any(|t| match t {
+        proc_macro2::TokenTree::Ident(i) => i == "String" || i == "StringOffset32",
+        proc_macro2::TokenTree::Group(g) => contains_string(g.stream()),
+        _ => false,
+    })
+}
+
 // Collect writing related information and code for each field.
 struct FieldData {
     name: Ident,
+    is_string: bool,
     offset_field: TokenStream2,
     write_impl: TokenStream2,
     write_offset_impl: TokenStream2,
 }
 
 impl FieldData {
-    fn offset(name: &Ident, alignment: Option<Padding>, pointer: &Ident, ty: &Type) -> Self {
+    fn offset(
+        name: &Ident,
+        access: FieldAccess,
+        alignment: Option<Padding>,
+        pointer: &Ident,
+        ty: &Type,
+    ) -> Self {
+        let offset = access.offset(name);
         Self {
             name: name.clone(),
+            is_string: is_string_type(ty),
             offset_field: offset_field(name, pointer, ty),
-            write_impl: write_dummy_offset(name, alignment, pointer),
+            write_impl: write_dummy_offset(name, &access.value(name), alignment, pointer),
             write_offset_impl: quote! {
-                self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
             },
         }
     }
 
-    fn shared_offset(name: &Ident, alignment: Option<Padding>, pointer: &Type) -> Self {
+    fn shared_offset(
+        name: &Ident,
+        access: FieldAccess,
+        alignment: Option<Padding>,
+        pointer: &Type,
+    ) -> Self {
+        let offset = access.offset(name);
         Self {
             name: name.clone(),
-            offset_field: quote!(pub #name: ::xc3_write::Offset<'offsets, #pointer, ()>),
+            is_string: false,
+            offset_field: quote!(#name: ::xc3_write::Offset<'offsets, #pointer, ()>),
             write_impl: write_dummy_shared_offset(name, alignment, pointer),
             write_offset_impl: quote! {
-                self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
             },
         }
     }
 
-    fn field_position(name: &Ident, ty: &Type, should_write: bool) -> Self {
+    fn field_position(name: &Ident, access: FieldAccess, ty: &Type, should_write: bool) -> Self {
         Self {
             name: name.clone(),
-            offset_field: quote!(pub #name: ::xc3_write::FieldPosition<'offsets, #ty>),
-            write_impl: write_field_position(name, should_write),
+            is_string: is_string_type(ty),
+            offset_field: quote!(#name: ::xc3_write::FieldPosition<'offsets, #ty>),
+            write_impl: write_field_position(name, &access.value(name), should_write),
             write_offset_impl: quote!(),
         }
     }
 }
 
-fn write_dummy_offset(name: &Ident, alignment: Option<Padding>, pointer: &Ident) -> TokenStream2 {
+fn write_dummy_offset(
+    name: &Ident,
+    value: &TokenStream2,
+    alignment: Option<Padding>,
+    pointer: &Ident,
+) -> TokenStream2 {
     let align = match alignment.map(|a| a.size) {
         Some(align) => quote!(Some(#align)),
         None => quote!(None),
@@ -309,7 +434,7 @@ fn write_dummy_offset(name: &Ident, alignment: Option<Padding>, pointer: &Ident)
     let padding_byte = alignment.map(|a| a.value).unwrap_or_default();
 
     quote! {
-        let #name = ::xc3_write::Offset::new(writer.stream_position()?, &self.#name, #align, #padding_byte);
+        let #name = ::xc3_write::Offset::new(writer.stream_position()?, &#value, #align, #padding_byte);
         // Assume 0 is the default for the pointer type.
         #pointer::default().xc3_write(writer, endian)?;
     }
@@ -333,25 +458,27 @@ fn write_dummy_shared_offset(
     }
 }
 
-fn write_field_position(name: &Ident, should_write: bool) -> TokenStream2 {
+fn write_field_position(name: &Ident, value: &TokenStream2, should_write: bool) -> TokenStream2 {
     if should_write {
         quote! {
-            let #name = ::xc3_write::FieldPosition::new(writer.stream_position()?, &self.#name);
-            self.#name.xc3_write(writer, endian)?;
+            let #name = ::xc3_write::FieldPosition::new(writer.stream_position()?, &#value);
+            #value.xc3_write(writer, endian)?;
         }
     } else {
         quote! {
-            let #name = ::xc3_write::FieldPosition::new(writer.stream_position()?, &self.#name);
+            let #name = ::xc3_write::FieldPosition::new(writer.stream_position()?, &#value);
         }
     }
 }
 
-fn parse_named_fields(fields: &FieldsNamed) -> Vec<FieldData> {
+fn parse_named_fields(fields: &FieldsNamed, access: FieldAccess) -> Vec<FieldData> {
     let mut offset_fields = Vec::new();
 
     for f in fields.named.iter() {
         let name = f.ident.as_ref().unwrap();
         let ty = &f.ty;
+        let value = access.value(name);
+        let offset = access.offset(name);
 
         let options = FieldOptions::from_attrs(&f.attrs);
 
@@ -372,78 +499,82 @@ fn parse_named_fields(fields: &FieldsNamed) -> Vec<FieldData> {
         // TODO: Reduce repeated code?
         let offset_field = match options.field_type {
             Some(FieldType::Offset(offset_ty)) => {
-                FieldData::offset(name, options.align, &offset_ty, ty)
+                FieldData::offset(name, access, options.align, &offset_ty, ty)
             }
             Some(FieldType::CountOffset(count_ty, offset_ty)) => {
-                let write_offset = write_dummy_offset(name, options.align, &offset_ty);
+                let write_offset = write_dummy_offset(name, &value, options.align, &offset_ty);
 
                 FieldData {
                     name: name.clone(),
+                    is_string: is_string_type(ty),
                     offset_field: offset_field(name, &offset_ty, ty),
                     write_impl: quote! {
-                        (self.#name.len() as #count_ty).xc3_write(writer, endian)?;
+                        (#value.len() as #count_ty).xc3_write(writer, endian)?;
                         #write_offset
                     },
                     write_offset_impl: quote! {
-                        self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                        #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
                     },
                 }
             }
             Some(FieldType::OffsetCount(offset_ty, count_ty)) => {
-                let write_offset = write_dummy_offset(name, options.align, &offset_ty);
+                let write_offset = write_dummy_offset(name, &value, options.align, &offset_ty);
 
                 FieldData {
                     name: name.clone(),
+                    is_string: is_string_type(ty),
                     offset_field: offset_field(name, &offset_ty, ty),
                     write_impl: quote! {
                         #write_offset
-                        (self.#name.len() as #count_ty).xc3_write(writer, endian)?;
+                        (#value.len() as #count_ty).xc3_write(writer, endian)?;
                     },
                     write_offset_impl: quote! {
-                        self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                        #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
                     },
                 }
             }
             Some(FieldType::SharedOffset) => {
                 // Shared offsets don't actually contain any data.
                 // The pointer type is the type of the field itself.
-                FieldData::shared_offset(name, options.align, ty)
+                FieldData::shared_offset(name, access, options.align, ty)
             }
             Some(FieldType::SavePosition(should_write)) => {
                 // Store the information for later shared offsets.
-                FieldData::field_position(name, ty, should_write)
+                FieldData::field_position(name, access, ty, should_write)
             }
             Some(FieldType::OffsetSize(offset_ty, size_ty)) => {
-                let write_offset = write_dummy_offset(name, options.align, &offset_ty);
+                let write_offset = write_dummy_offset(name, &value, options.align, &offset_ty);
                 FieldData {
                     name: name.clone(),
+                    is_string: is_string_type(ty),
                     offset_field: offset_field(name, &offset_ty, ty),
                     write_impl: quote! {
                         #write_offset
                         {
                             use binrw::BinWrite;
                             let mut cur = std::io::Cursor::new(Vec::new());
-                            self.#name.xc3_write(&mut cur, endian)?;
+                            #value.xc3_write(&mut cur, endian)?;
                             cur.into_inner().len() as #size_ty
                         }.xc3_write(writer, endian)?;
                     },
                     write_offset_impl: quote! {
-                        self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                        #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
                     },
                 }
             }
             Some(FieldType::OffsetInnerCount(offset_ty, count_expr)) => {
-                let write_offset = write_dummy_offset(name, options.align, &offset_ty);
+                let write_offset = write_dummy_offset(name, &value, options.align, &offset_ty);
 
                 FieldData {
                     name: name.clone(),
+                    is_string: is_string_type(ty),
                     offset_field: offset_field(name, &offset_ty, ty),
                     write_impl: quote! {
                         #write_offset
                         (#count_expr).xc3_write(writer, endian)?;
                     },
                     write_offset_impl: quote! {
-                        self.#name.write_full(writer, base_offset, data_ptr, endian, args)?;
+                        #offset.write_full(writer, base_offset, data_ptr, endian, args)?;
                     },
                 }
             }
@@ -453,21 +584,22 @@ fn parse_named_fields(fields: &FieldsNamed) -> Vec<FieldData> {
                 let write_impl = if options.pad_size_to.is_some() {
                     quote! {
                         let before_pos = writer.stream_position()?;
-                        let #name = self.#name.xc3_write(writer, endian)?;
+                        let #name = #value.xc3_write(writer, endian)?;
                         #pad_size_to
                     }
                 } else {
                     quote! {
-                        let #name = self.#name.xc3_write(writer, endian)?;
+                        let #name = #value.xc3_write(writer, endian)?;
                     }
                 };
                 FieldData {
                     name: name.clone(),
-                    offset_field: quote!(pub #name: <#ty as ::xc3_write::Xc3Write>::Offsets<'offsets>),
+                    is_string: is_string_type(ty),
+                    offset_field: quote!(#name: <#ty as ::xc3_write::Xc3Write>::Offsets<'offsets>),
                     write_impl,
                     write_offset_impl: quote! {
                         // This field isn't an Offset<T>, so just call write_offsets.
-                        self.#name.write_offsets(writer, base_offset, data_ptr, endian, args)?;
+                        #offset.write_offsets(writer, base_offset, data_ptr, endian, args)?;
                     },
                 }
             }
@@ -479,5 +611,5 @@ fn parse_named_fields(fields: &FieldsNamed) -> Vec<FieldData> {
 }
 
 fn offset_field(name: &Ident, pointer: &Ident, ty: &Type) -> TokenStream2 {
-    quote!(pub #name: ::xc3_write::Offset<'offsets, #pointer, #ty>)
+    quote!(#name: ::xc3_write::Offset<'offsets, #pointer, #ty>)
 }